@@ -0,0 +1,178 @@
+use std::{
+    collections::HashSet,
+    env,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use cmake::Config;
+
+#[derive(Debug)]
+struct IgnoreMacros(HashSet<String>);
+
+impl bindgen::callbacks::ParseCallbacks for IgnoreMacros {
+    fn will_parse_macro(&self, name: &str) -> bindgen::callbacks::MacroParsingBehavior {
+        if self.0.contains(name) {
+            bindgen::callbacks::MacroParsingBehavior::Ignore
+        } else {
+            bindgen::callbacks::MacroParsingBehavior::Default
+        }
+    }
+}
+
+// BoringSSL does not publish releases. The snapshot of its FIPS validation is a fixed tag which
+// already supports ECH draft 13.
+const REF: &str = "fips-20220613";
+
+fn clone_boringssl(dest: &str) -> std::io::Result<()> {
+    std::fs::remove_dir_all(dest)?;
+    let status = Command::new("git")
+        .arg("clone")
+        .arg("--depth")
+        .arg("1")
+        .arg("--branch")
+        .arg(REF)
+        .arg("https://github.com/google/boringssl.git")
+        .arg(dest)
+        .status()?;
+
+    if !status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("cloning BoringSSL {} failed: {}", REF, status),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn insert_claim_interface(additional_headers: &Path) -> std::io::Result<()> {
+    let interface = security_claims::CLAIM_INTERFACE_H;
+
+    let path = additional_headers.join("claim-interface.h");
+
+    let mut file = File::create(path)?;
+    file.write_all(interface.as_bytes())?;
+
+    Ok(())
+}
+
+/// The claimer is shared with OpenSSL 3 and lives in the security-claims crate
+pub fn insert_claimer(out_dir: &Path) -> std::io::Result<PathBuf> {
+    let path = out_dir.join("openssl-claimer.c");
+
+    let mut file = File::create(&path)?;
+    file.write_all(security_claims::OPENSSL_CLAIMER_C.as_bytes())?;
+
+    Ok(path)
+}
+
+fn build_boringssl(dest: &str) -> PathBuf {
+    let mut config = Config::new(dest);
+
+    config
+        .define("CMAKE_C_COMPILER", "clang")
+        .define("CMAKE_CXX_COMPILER", "clang++")
+        .cflag("-fPIC")
+        .cxxflag("-fPIC");
+
+    if cfg!(feature = "deterministic") {
+        // We only take the deterministic RNG of the fuzzer mode (`-DFUZZ=1`). The fuzzer mode
+        // itself (BORINGSSL_UNSAFE_FUZZER_MODE) disables the record encryption and the
+        // verification of signatures, which breaks the interoperability with our agents.
+        config
+            .cflag("-DBORINGSSL_UNSAFE_DETERMINISTIC_MODE")
+            .cxxflag("-DBORINGSSL_UNSAFE_DETERMINISTIC_MODE");
+    }
+
+    if cfg!(feature = "sancov") {
+        config
+            .cflag("-fsanitize-coverage=trace-pc-guard")
+            .cxxflag("-fsanitize-coverage=trace-pc-guard");
+    }
+
+    if cfg!(feature = "asan") {
+        config
+            .cflag("-fsanitize=address")
+            .cflag("-shared-libsan")
+            .cxxflag("-fsanitize=address")
+            .cxxflag("-shared-libsan");
+        println!("cargo:rustc-link-lib=asan");
+    }
+
+    // The tests and tools of BoringSSL are not needed. `ssl` depends on `crypto`.
+    config.build_target("ssl").build()
+}
+
+fn main() -> std::io::Result<()> {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    clone_boringssl(&out_dir)?;
+    let dst = build_boringssl(&out_dir);
+    let include_dir = PathBuf::from(&out_dir).join("include");
+
+    // BoringSSL does not implement the claim-interface.h. The claimer is implemented on top of its
+    // public API.
+    let additional_headers = PathBuf::from(&out_dir).join("additional_headers");
+    std::fs::create_dir_all(&additional_headers)?;
+    insert_claim_interface(&additional_headers)?;
+
+    let mut claimer = cc::Build::new();
+    claimer
+        .compiler("clang")
+        .file(insert_claimer(Path::new(&out_dir))?)
+        .include(&include_dir)
+        .include(&additional_headers);
+    if cfg!(feature = "sancov") {
+        claimer.flag("-fsanitize-coverage=trace-pc-guard");
+    }
+    claimer.compile("puffin-boringssl");
+
+    // Block some macros:https://github.com/rust-lang/rust-bindgen/issues/687
+    let mut ignored_macros = HashSet::new();
+    for i in &["IPPORT_RESERVED"] {
+        ignored_macros.insert(i.to_string());
+    }
+    let ignored_macros = IgnoreMacros(ignored_macros);
+
+    let mut bindings = bindgen::Builder::default()
+        .header("wrapper.h")
+        .clang_arg(format!("-I{}", include_dir.display()));
+
+    if cfg!(feature = "deterministic") {
+        // Exposes RAND_reset_for_fuzzing
+        bindings = bindings.clang_arg("-DBORINGSSL_UNSAFE_DETERMINISTIC_MODE");
+    }
+
+    let bindings = bindings
+        .parse_callbacks(Box::new(ignored_macros))
+        .rustfmt_bindings(true)
+        .generate()
+        .expect("Unable to generate bindings");
+
+    bindings
+        .write_to_file(PathBuf::from(&out_dir).join("bindings.rs"))
+        .expect("Couldn't write bindings!");
+
+    // Linking Time! The libraries are placed in the root of the build directory by recent versions
+    // of BoringSSL and in subdirectories by older ones.
+    let build_dir = dst.join("build");
+    println!("cargo:rustc-link-search=native={}", build_dir.display());
+    println!(
+        "cargo:rustc-link-search=native={}",
+        build_dir.join("ssl").display()
+    );
+    println!(
+        "cargo:rustc-link-search=native={}",
+        build_dir.join("crypto").display()
+    );
+    println!("cargo:rustc-link-lib=static=ssl");
+    println!("cargo:rustc-link-lib=static=crypto");
+    // libssl of BoringSSL is written in C++
+    println!("cargo:rustc-link-lib=stdc++");
+    println!("cargo:include={}", include_dir.display());
+    println!("cargo:rerun-if-changed=wrapper.h");
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+#include <openssl/bio.h>
+#include <openssl/err.h>
+#include <openssl/hpke.h>
+#include <openssl/pem.h>
+#include <openssl/rand.h>
+#include <openssl/ssl.h>
+#include <openssl/x509.h>
@@ -0,0 +1,140 @@
+use std::{
+    collections::HashSet,
+    env,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use cmake::Config;
+
+#[derive(Debug)]
+struct IgnoreMacros(HashSet<String>);
+
+impl bindgen::callbacks::ParseCallbacks for IgnoreMacros {
+    fn will_parse_macro(&self, name: &str) -> bindgen::callbacks::MacroParsingBehavior {
+        if self.0.contains(name) {
+            bindgen::callbacks::MacroParsingBehavior::Ignore
+        } else {
+            bindgen::callbacks::MacroParsingBehavior::Default
+        }
+    }
+}
+
+// Release tags contain the generated sources, which else would need to be generated with Python
+const REF: &str = "v3.6.0";
+
+/// Changes to the default configuration of Mbed TLS
+const CONFIG: &str = "
+/* tlspuffin */
+/* All randomness comes from src/entropy.c */
+#define MBEDTLS_NO_PLATFORM_ENTROPY
+#define MBEDTLS_PSA_CRYPTO_EXTERNAL_RNG
+/* Allows fixing the time, which is part of the random values of TLS 1.2, see src/entropy.c */
+#define MBEDTLS_PLATFORM_TIME_ALT
+/* Like for the other PUTs, no ChangeCipherSpec messages are sent in TLS 1.3 */
+#undef MBEDTLS_SSL_TLS1_3_COMPATIBILITY_MODE
+";
+
+fn clone_mbedtls(dest: &str) -> std::io::Result<()> {
+    std::fs::remove_dir_all(dest)?;
+    let status = Command::new("git")
+        .arg("clone")
+        .arg("--depth")
+        .arg("1")
+        .arg("--branch")
+        .arg(REF)
+        .arg("https://github.com/Mbed-TLS/mbedtls.git")
+        .arg(dest)
+        .status()?;
+
+    if !status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("cloning Mbed TLS {} failed: {}", REF, status),
+        ));
+    }
+
+    Ok(())
+}
+
+fn configure_mbedtls(dest: &str) -> std::io::Result<()> {
+    let mut config = OpenOptions::new()
+        .append(true)
+        .open(Path::new(dest).join("include/mbedtls/mbedtls_config.h"))?;
+    config.write_all(CONFIG.as_bytes())
+}
+
+fn build_mbedtls(dest: &str) -> PathBuf {
+    let mut config = Config::new(dest);
+
+    config
+        .define("CMAKE_C_COMPILER", "clang")
+        .define("ENABLE_PROGRAMS", "OFF")
+        .define("ENABLE_TESTING", "OFF")
+        .define("GEN_FILES", "OFF")
+        .cflag("-fPIC");
+
+    if cfg!(feature = "sancov") {
+        config.cflag("-fsanitize-coverage=trace-pc-guard");
+    }
+
+    if cfg!(feature = "asan") {
+        config.cflag("-fsanitize=address").cflag("-shared-libsan");
+        println!("cargo:rustc-link-lib=asan");
+    }
+
+    config.build()
+}
+
+fn main() -> std::io::Result<()> {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    clone_mbedtls(&out_dir)?;
+    configure_mbedtls(&out_dir)?;
+    let dst = build_mbedtls(&out_dir);
+    let include_dir = dst.join("include");
+
+    let mut entropy = cc::Build::new();
+    entropy
+        .compiler("clang")
+        .file("src/entropy.c")
+        .include(&include_dir);
+    if cfg!(feature = "sancov") {
+        entropy.flag("-fsanitize-coverage=trace-pc-guard");
+    }
+    entropy.compile("puffin-mbedtls");
+
+    // Block some macros:https://github.com/rust-lang/rust-bindgen/issues/687
+    let mut ignored_macros = HashSet::new();
+    for i in &["IPPORT_RESERVED"] {
+        ignored_macros.insert(i.to_string());
+    }
+    let ignored_macros = IgnoreMacros(ignored_macros);
+
+    let bindings = bindgen::Builder::default()
+        .header("wrapper.h")
+        .clang_arg(format!("-I{}", include_dir.display()))
+        .parse_callbacks(Box::new(ignored_macros))
+        .rustfmt_bindings(true)
+        .generate()
+        .expect("Unable to generate bindings");
+
+    bindings
+        .write_to_file(PathBuf::from(&out_dir).join("bindings.rs"))
+        .expect("Couldn't write bindings!");
+
+    // Linking Time! The order matters for static linking.
+    println!(
+        "cargo:rustc-link-search=native={}",
+        dst.join("lib").display()
+    );
+    println!("cargo:rustc-link-lib=static=mbedtls");
+    println!("cargo:rustc-link-lib=static=mbedx509");
+    println!("cargo:rustc-link-lib=static=mbedcrypto");
+    println!("cargo:include={}", include_dir.display());
+    println!("cargo:rerun-if-changed=wrapper.h");
+    println!("cargo:rerun-if-changed=src/entropy.c");
+
+    Ok(())
+}
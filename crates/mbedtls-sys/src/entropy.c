@@ -0,0 +1,95 @@
+/*
+ * The only source of randomness of Mbed TLS. Mbed TLS is built without the entropy sources of
+ * the platform, and the PSA crypto core takes its random values from `mbedtls_psa_external_get_random`.
+ * Both read from `puffin_entropy_poll`, which is backed by the operating system until
+ * `puffin_mbedtls_set_deterministic` is called.
+ *
+ * The time source of Mbed TLS is fixed as well. With MBEDTLS_PLATFORM_TIME_ALT, Mbed TLS has no
+ * time until `puffin_mbedtls_init_time` is called.
+ */
+
+#include <stdint.h>
+#include <sys/random.h>
+
+#include <mbedtls/entropy.h>
+#include <mbedtls/platform.h>
+#include <mbedtls/platform_time.h>
+#include <psa/crypto.h>
+
+/*
+ * A fixed point in time, as Mbed TLS includes the time in the random values of TLS 1.2. It lies
+ * within the validity of the static certificates of tlspuffin, which expired in July 2023.
+ */
+#define FIXED_TIME 1672531200
+
+static int deterministic = 0;
+static uint64_t state = 0;
+
+/* splitmix64 */
+static uint64_t next(void) {
+    uint64_t z = (state += 0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)) * 0xbf58476d1ce4e5b9;
+    z = (z ^ (z >> 27)) * 0x94d049bb133111eb;
+    return z ^ (z >> 31);
+}
+
+static int fill(unsigned char *output, size_t len) {
+    if (deterministic) {
+        uint64_t word = 0;
+        for (size_t i = 0; i < len; i++) {
+            if (i % 8 == 0) {
+                word = next();
+            }
+            output[i] = (unsigned char) (word >> (8 * (i % 8)));
+        }
+        return 0;
+    }
+
+    size_t filled = 0;
+    while (filled < len) {
+        ssize_t result = getrandom(output + filled, len - filled, 0);
+        if (result < 0) {
+            return -1;
+        }
+        filled += (size_t) result;
+    }
+    return 0;
+}
+
+int puffin_entropy_poll(void *data, unsigned char *output, size_t len, size_t *olen) {
+    (void) data;
+
+    if (fill(output, len) != 0) {
+        return MBEDTLS_ERR_ENTROPY_SOURCE_FAILED;
+    }
+    *olen = len;
+    return 0;
+}
+
+psa_status_t mbedtls_psa_external_get_random(mbedtls_psa_external_random_context_t *context,
+                                             uint8_t *output, size_t output_size,
+                                             size_t *output_length) {
+    (void) context;
+
+    if (fill(output, output_size) != 0) {
+        return PSA_ERROR_INSUFFICIENT_ENTROPY;
+    }
+    *output_length = output_size;
+    return PSA_SUCCESS;
+}
+
+static mbedtls_time_t fixed_time(mbedtls_time_t *time) {
+    if (time != NULL) {
+        *time = FIXED_TIME;
+    }
+    return FIXED_TIME;
+}
+
+int puffin_mbedtls_init_time(void) {
+    return mbedtls_platform_set_time(fixed_time);
+}
+
+void puffin_mbedtls_set_deterministic(uint64_t seed) {
+    deterministic = 1;
+    state = seed;
+}
@@ -0,0 +1,22 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+// Implemented in src/entropy.c
+extern "C" {
+    /// The entropy source of Mbed TLS, which has the signature of `mbedtls_entropy_f_source_ptr`
+    pub fn puffin_entropy_poll(
+        data: *mut ::std::os::raw::c_void,
+        output: *mut ::std::os::raw::c_uchar,
+        len: usize,
+        olen: *mut usize,
+    ) -> ::std::os::raw::c_int;
+
+    /// Makes the entropy source of Mbed TLS deterministic
+    pub fn puffin_mbedtls_set_deterministic(seed: u64);
+
+    /// Sets the fixed time source of Mbed TLS, which is required by MBEDTLS_PLATFORM_TIME_ALT
+    pub fn puffin_mbedtls_init_time() -> ::std::os::raw::c_int;
+}
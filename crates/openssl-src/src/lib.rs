@@ -0,0 +1,8 @@
+//! The `openssl-src` crate which is built by `openssl-sys` if its `vendored` feature is enabled.
+//! OpenSSL 3 is built by the openssl3-src crate if the `openssl3xx` feature is enabled, else the
+//! openssl-src fork in `crates/openssl-src-111` builds OpenSSL 1.x or LibreSSL.
+
+#[cfg(feature = "openssl3xx")]
+pub use openssl3_src::*;
+#[cfg(not(feature = "openssl3xx"))]
+pub use openssl_src_111::*;
@@ -0,0 +1,65 @@
+/*
+ * Makes OpenSSL 3 deterministic by replacing its random number generator with a PRNG which is
+ * reset by every call to RAND_seed. OpenSSL 3 still uses a RAND_METHOD which is set explicitly,
+ * also within the default provider.
+ */
+
+#define OPENSSL_SUPPRESS_DEPRECATED
+
+#include <stdint.h>
+
+#include <openssl/rand.h>
+
+static uint64_t state = 0;
+
+/* splitmix64 */
+static uint64_t next(void) {
+    uint64_t z = (state += 0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)) * 0xbf58476d1ce4e5b9;
+    z = (z ^ (z >> 27)) * 0x94d049bb133111eb;
+    return z ^ (z >> 31);
+}
+
+static int deterministic_seed(const void *buf, int num) {
+    state = 0;
+    for (int i = 0; i < num; i++) {
+        state ^= (uint64_t) ((const unsigned char *) buf)[i] << (8 * (i % 8));
+    }
+    return 1;
+}
+
+static int deterministic_bytes(unsigned char *buf, int num) {
+    uint64_t word = 0;
+    for (int i = 0; i < num; i++) {
+        if (i % 8 == 0) {
+            word = next();
+        }
+        buf[i] = (unsigned char) (word >> (8 * (i % 8)));
+    }
+    return 1;
+}
+
+static int deterministic_add(const void *buf, int num, double randomness) {
+    // Added entropy would make the output depend on the environment
+    (void) buf;
+    (void) num;
+    (void) randomness;
+    return 1;
+}
+
+static int deterministic_status(void) {
+    return 1;
+}
+
+static RAND_METHOD deterministic_method = {
+    deterministic_seed,
+    deterministic_bytes,
+    NULL,
+    deterministic_add,
+    deterministic_bytes,
+    deterministic_status,
+};
+
+void make_openssl_deterministic(void) {
+    RAND_set_rand_method(&deterministic_method);
+}
@@ -0,0 +1,267 @@
+extern crate cc;
+
+use std::{
+    env, fs,
+    fs::{canonicalize, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The tag of OpenSSL which is built, see the build metadata of the version of this crate
+const REF: &str = "openssl-3.0.13";
+
+fn clone(dest: &PathBuf) -> std::io::Result<()> {
+    std::fs::remove_dir_all(dest)?;
+    let status = Command::new("git")
+        .arg("clone")
+        .arg("--depth")
+        .arg("1")
+        .arg("--branch")
+        .arg(REF)
+        .arg("https://github.com/openssl/openssl.git")
+        .arg(dest)
+        .status()?;
+
+    if !status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("cloning OpenSSL {} failed: {}", REF, status),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+pub struct Build {
+    out_dir: Option<PathBuf>,
+    target: Option<String>,
+    host: Option<String>,
+}
+
+pub struct Artifacts {
+    include_dir: PathBuf,
+    lib_dir: PathBuf,
+    libs: Vec<String>,
+}
+
+impl Build {
+    pub fn new() -> Build {
+        Build {
+            out_dir: env::var_os("OUT_DIR").map(|s| PathBuf::from(s).join("openssl-build")),
+            target: env::var("TARGET").ok(),
+            host: env::var("HOST").ok(),
+        }
+    }
+
+    pub fn out_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Build {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn target(&mut self, target: &str) -> &mut Build {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    pub fn host(&mut self, host: &str) -> &mut Build {
+        self.host = Some(host.to_string());
+        self
+    }
+
+    fn cmd_make(&self) -> Command {
+        let host = &self.host.as_ref().expect("HOST dir not set")[..];
+        if host.contains("dragonfly")
+            || host.contains("freebsd")
+            || host.contains("solaris")
+            || host.contains("illumos")
+        {
+            Command::new("gmake")
+        } else {
+            Command::new("make")
+        }
+    }
+
+    pub fn insert_claim_interface(additional_headers: &PathBuf) -> std::io::Result<()> {
+        let interface = security_claims::CLAIM_INTERFACE_H;
+
+        let path = additional_headers.join("claim-interface.h");
+
+        let mut file = File::create(path)?;
+        file.write_all(interface.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// The claimer is shared with BoringSSL and lives in the security-claims crate
+    fn insert_claimer(out_dir: &Path) -> std::io::Result<PathBuf> {
+        let path = out_dir.join("openssl-claimer.c");
+
+        let mut file = File::create(&path)?;
+        file.write_all(security_claims::OPENSSL_CLAIMER_C.as_bytes())?;
+
+        Ok(path)
+    }
+
+    /// The target of the `Configure` script of OpenSSL
+    fn openssl_target(target: &str) -> &'static str {
+        match target {
+            "aarch64-unknown-linux-gnu" => "linux-aarch64",
+            "x86_64-unknown-linux-gnu" => "linux-x86_64",
+            "i686-unknown-linux-gnu" => "linux-elf",
+            "aarch64-apple-darwin" => "darwin64-arm64-cc",
+            "x86_64-apple-darwin" => "darwin64-x86_64-cc",
+            _ => panic!("don't know how to configure OpenSSL for {}", target),
+        }
+    }
+
+    pub fn build(&mut self) -> Artifacts {
+        let target = &self.target.as_ref().expect("TARGET dir not set")[..];
+        let host = &self.host.as_ref().expect("HOST dir not set")[..];
+        let out_dir = self.out_dir.as_ref().expect("OUT_DIR not set");
+        let build_dir = out_dir.join("build");
+        let install_dir = out_dir.join("install");
+
+        if build_dir.exists() {
+            fs::remove_dir_all(&build_dir).unwrap();
+        }
+        if install_dir.exists() {
+            fs::remove_dir_all(&install_dir).unwrap();
+        }
+
+        let additional_headers = out_dir.join("additional_headers");
+
+        fs::create_dir_all(&additional_headers).unwrap();
+        Self::insert_claim_interface(&additional_headers).unwrap();
+
+        let inner_dir = build_dir.join("src");
+        fs::create_dir_all(&inner_dir).unwrap();
+        clone(&inner_dir).unwrap();
+
+        let mut cc = "clang".to_owned();
+
+        // Make additional headers available
+        cc.push_str(
+            format!(
+                " -I{}",
+                canonicalize(&additional_headers).unwrap().to_str().unwrap()
+            )
+            .as_str(),
+        );
+
+        if cfg!(feature = "sancov") {
+            cc.push_str(" -fsanitize-coverage=trace-pc-guard");
+        }
+
+        let mut configure = Command::new("perl");
+        configure.arg("./Configure");
+        configure.arg(format!("--prefix={}", install_dir.display()));
+        configure.arg(format!(
+            "--openssldir={}",
+            install_dir.join("ssl").display()
+        ));
+        configure.arg("--libdir=lib");
+        configure.args([
+            "no-shared",
+            "no-module",
+            "no-tests",
+            "no-dso",
+            "no-ui-console",
+        ]);
+        if cfg!(feature = "asan") {
+            configure.arg("enable-asan");
+        }
+        configure.arg(Self::openssl_target(target));
+        configure.env("CC", &cc);
+        configure.current_dir(&inner_dir);
+        self.run_command(configure, "configuring OpenSSL build");
+
+        let mut build = self.cmd_make();
+        build.arg("build_libs").current_dir(&inner_dir);
+        if let Ok(jobs) = env::var("NUM_JOBS") {
+            build.arg(format!("-j{}", jobs));
+        }
+        self.run_command(build, "building OpenSSL");
+
+        let mut install = self.cmd_make();
+        install.arg("install_dev").current_dir(&inner_dir);
+        self.run_command(install, "installing OpenSSL");
+
+        let lib_dir = install_dir.join("lib");
+        let include_dir = install_dir.join("include");
+
+        // OpenSSL 3 does not implement the claim-interface.h and cannot be made deterministic
+        // without patches. We provide both on top of its public API. openssl-sys only links
+        // libssl and libcrypto, so the library is linked through the cargo metadata of cc.
+        cc::Build::new()
+            .compiler("clang")
+            .target(target)
+            .host(host)
+            .opt_level(2)
+            .file(Self::insert_claimer(out_dir).unwrap())
+            .file(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/deterministic.c"))
+            .include(&include_dir)
+            .include(&additional_headers)
+            .out_dir(&lib_dir)
+            .compile("puffin-openssl3");
+
+        fs::remove_dir_all(&inner_dir).unwrap();
+
+        Artifacts {
+            lib_dir,
+            include_dir,
+            // The order matters for static linking: The claimer depends on libssl
+            libs: vec![
+                "puffin-openssl3".to_string(),
+                "ssl".to_string(),
+                "crypto".to_string(),
+            ],
+        }
+    }
+
+    fn run_command(&self, mut command: Command, desc: &str) {
+        println!("running {:?}", command);
+        let status = command.status().unwrap();
+        if !status.success() {
+            panic!(
+                "
+
+
+Error {}:
+    Command: {:?}
+    Exit status: {}
+
+
+    ",
+                desc, command, status
+            );
+        }
+    }
+}
+
+impl Artifacts {
+    pub fn include_dir(&self) -> &Path {
+        &self.include_dir
+    }
+
+    pub fn lib_dir(&self) -> &Path {
+        &self.lib_dir
+    }
+
+    pub fn libs(&self) -> &[String] {
+        &self.libs
+    }
+
+    pub fn print_cargo_metadata(&self) {
+        println!("cargo:rustc-link-search=native={}", self.lib_dir.display());
+        for lib in self.libs.iter() {
+            println!("cargo:rustc-link-lib=static={}", lib);
+        }
+        println!("cargo:include={}", self.include_dir.display());
+        println!("cargo:lib={}", self.lib_dir.display());
+    }
+}
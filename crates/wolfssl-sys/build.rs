@@ -85,10 +85,15 @@ fn build_wolfssl(dest: &str) -> PathBuf {
         .enable("sp", None) // FIXME: Fixes a memory leak?
         .enable("sp-asm", None)
         .enable("dtls-mtu", None)
+        .enable("oldtls", None) // TLS 1.1
+        .enable("tlsv10", None)
+        .enable("sslv3", None)
         .disable("sha3", None)
         .enable("intelasm", None)
         .enable("curve25519", None)
         .enable("secure-renegotiation", None)
+        .enable("alpn", None)
+        .enable("sni", None)
         .enable("postauth", None) // FIXME; else the session resumption crashes? SEGV?
         .enable("psk", None) // FIXME: Only 4.3.0
         .cflag("-DHAVE_EX_DATA") // FIXME: Only 4.3.0
@@ -97,6 +102,16 @@ fn build_wolfssl(dest: &str) -> PathBuf {
         //.cflag("-g")// FIXME: Reenable?
         .cflag("-fPIC");
 
+    if !cfg!(feature = "vendored-wolfssl430") {
+        // Allows overriding the seed source of the DRBG through `wc_SetSeed_Cb`
+        config.cflag("-DWC_RNG_SEED_CB");
+    }
+
+    if cfg!(feature = "vendored-wolfssl540") {
+        // DTLS 1.3 is available since 5.4.0
+        config.enable("dtls13", None);
+    }
+
     if cfg!(feature = "sancov") {
         config.cflag("-fsanitize-coverage=trace-pc-guard");
     }
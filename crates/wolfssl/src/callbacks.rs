@@ -7,7 +7,7 @@ use std::{
 };
 
 use foreign_types::ForeignTypeRef;
-use libc::{c_int, c_ulong};
+use libc::{c_int, c_uchar, c_ulong};
 use wolfssl_sys as wolf;
 
 use crate::ssl::{SslContextRef, SslRef};
@@ -141,3 +141,28 @@ pub unsafe extern "C" fn ssl_msg_callback<F>(
         write_p == 1,
     );
 }
+
+pub unsafe extern "C" fn ctx_gen_cookie_callback<F>(
+    ssl: *mut wolf::WOLFSSL,
+    buf: *mut c_uchar,
+    sz: c_int,
+    _ctx: *mut c_void,
+) -> c_int
+where
+    F: Fn(&mut SslRef, &mut [u8]) -> usize + 'static,
+{
+    let ctx = SslContextRef::from_ptr_mut(wolf::wolfSSL_get_SSL_CTX(ssl));
+
+    let callback = {
+        let callback = ctx
+            .get_user_data::<F>()
+            .expect("BUG: missing gen_cookie_callback");
+
+        callback.deref() as *const F
+    };
+
+    let ssl = SslRef::from_ptr_mut(ssl);
+    let buf = std::slice::from_raw_parts_mut(buf, sz as usize);
+
+    (*callback)(ssl, buf) as c_int
+}
@@ -19,7 +19,10 @@ use wolfssl_sys as wolf;
 
 use crate::{
     bio,
-    callbacks::{ctx_msg_callback, ssl_msg_callback, ExtraUserDataRegistry, UserData},
+    callbacks::{
+        ctx_gen_cookie_callback, ctx_msg_callback, ssl_msg_callback, ExtraUserDataRegistry,
+        UserData,
+    },
     error::{ErrorCode, ErrorStack, InnerError, SslError},
     util::{cvt, cvt_n, cvt_p},
     x509::X509Ref,
@@ -50,10 +53,43 @@ bitflags! {
     }
 }
 
+/// A TLS protocol version.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SslVersion(c_int);
+
+impl SslVersion {
+    /// SSLv3
+    pub const SSL3: SslVersion = SslVersion(0x0300);
+
+    /// TLSv1.0
+    pub const TLS1: SslVersion = SslVersion(0x0301);
+
+    /// TLSv1.1
+    pub const TLS1_1: SslVersion = SslVersion(0x0302);
+
+    /// TLSv1.2
+    pub const TLS1_2: SslVersion = SslVersion(0x0303);
+
+    /// TLSv1.3
+    pub const TLS1_3: SslVersion = SslVersion(0x0304);
+}
+
 #[derive(Copy, Clone)]
 pub struct SslMethod(*mut wolf::WOLFSSL_METHOD);
 
 impl SslMethod {
+    /// Negotiates the highest version which both peers support. The versions can be restricted
+    /// through [`SslContextRef::set_min_proto_version`] and
+    /// [`SslContextRef::set_max_proto_version`].
+    pub fn tls_client() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfSSLv23_client_method()) }
+    }
+
+    /// See [`SslMethod::tls_client`]
+    pub fn tls_server() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfSSLv23_server_method()) }
+    }
+
     pub fn tls_client_13() -> SslMethod {
         unsafe { SslMethod(wolf::wolfTLSv1_3_client_method()) }
     }
@@ -70,6 +106,54 @@ impl SslMethod {
         unsafe { SslMethod(wolf::wolfTLSv1_2_server_method()) }
     }
 
+    pub fn tls_client_11() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_1_client_method()) }
+    }
+
+    pub fn tls_server_11() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_1_server_method()) }
+    }
+
+    /// Requires wolfSSL to be configured with `--enable-tlsv10`
+    pub fn tls_client_10() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_client_method()) }
+    }
+
+    /// Requires wolfSSL to be configured with `--enable-tlsv10`
+    pub fn tls_server_10() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfTLSv1_server_method()) }
+    }
+
+    /// Requires wolfSSL to be configured with `--enable-sslv3`
+    pub fn ssl_client_3() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfSSLv3_client_method()) }
+    }
+
+    /// Requires wolfSSL to be configured with `--enable-sslv3`
+    pub fn ssl_server_3() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfSSLv3_server_method()) }
+    }
+
+    pub fn dtls_client_12() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfDTLSv1_2_client_method()) }
+    }
+
+    pub fn dtls_server_12() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfDTLSv1_2_server_method()) }
+    }
+
+    /// Requires wolfSSL to be configured with `--enable-dtls13`
+    #[cfg(feature = "dtls13")]
+    pub fn dtls_client_13() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfDTLSv1_3_client_method()) }
+    }
+
+    /// Requires wolfSSL to be configured with `--enable-dtls13`
+    #[cfg(feature = "dtls13")]
+    pub fn dtls_server_13() -> SslMethod {
+        unsafe { SslMethod(wolf::wolfDTLSv1_3_server_method()) }
+    }
+
     pub unsafe fn from_ptr(ptr: *mut wolf::WOLFSSL_METHOD) -> SslMethod {
         SslMethod(ptr)
     }
@@ -220,6 +304,21 @@ impl SslContextRef {
         }
     }
 
+    /// Sets the callback which generates the cookies of DTLS HelloVerifyRequests. The callback
+    /// fills the buffer and returns the length of the cookie.
+    ///
+    /// The default callback of wolfSSL queries the address of the peer from the socket, which
+    /// fails for custom BIOs.
+    pub fn set_gen_cookie_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&mut SslRef, &mut [u8]) -> usize + 'static,
+    {
+        unsafe {
+            self.set_user_data(callback);
+            wolf::wolfSSL_CTX_SetGenCookie(self.as_ptr(), Some(ctx_gen_cookie_callback::<F>));
+        }
+    }
+
     pub fn disable_session_cache(&mut self) -> Result<(), ErrorStack> {
         unsafe {
             cvt(wolfssl_sys::wolfSSL_CTX_set_session_cache_mode(
@@ -276,6 +375,23 @@ impl SslContextRef {
         }
     }
 
+    /// Sets the parameters to be used during ephemeral Diffie-Hellman key exchange.
+    ///
+    /// This corresponds to [`wolfSSL_CTX_SetTmpDH_buffer`].
+    ///
+    /// [`wolfSSL_CTX_SetTmpDH_buffer`]: https://www.wolfssl.com/doxygen/group__CertsKeys.html
+    pub fn set_tmp_dh_pem(&mut self, params: &[u8]) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(wolf::wolfSSL_CTX_SetTmpDH_buffer(
+                self.as_ptr(),
+                params.as_ptr() as *const u8,
+                params.len() as i64,
+                wolf::WOLFSSL_FILETYPE_PEM,
+            ))
+            .map(|_| ())
+        }
+    }
+
     /// Configures the certificate verification method for new connections.
     ///
     /// This corresponds to [`SSL_CTX_set_verify`].
@@ -291,6 +407,87 @@ impl SslContextRef {
     pub fn set_num_tickets(&mut self, n: u64) -> Result<(), ErrorStack> {
         unsafe { cvt(wolf::wolfSSL_CTX_set_num_tickets(self.as_ptr(), n)).map(|_| ()) }
     }
+
+    /// Prevents servers from issuing session tickets in TLS 1.2 and TLS 1.3.
+    ///
+    /// This corresponds to [`wolfSSL_CTX_NoTicketTLSv12`] and `wolfSSL_CTX_no_ticket_TLSv13`.
+    ///
+    /// [`wolfSSL_CTX_NoTicketTLSv12`]: https://www.wolfssl.com/documentation/manuals/wolfssl/group__Setup.html#function-wolfssl_ctx_noticketlsv12
+    pub fn disable_session_tickets(&mut self) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(wolf::wolfSSL_CTX_NoTicketTLSv12(self.as_ptr()))?;
+            cvt(wolf::wolfSSL_CTX_no_ticket_TLSv13(self.as_ptr())).map(|_| ())
+        }
+    }
+
+    /// Sets the supported groups from a colon separated list, e.g. `X25519:P-256`.
+    ///
+    /// This corresponds to [`SSL_CTX_set1_groups_list`].
+    ///
+    /// [`SSL_CTX_set1_groups_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set1_groups_list.html
+    #[cfg(not(feature = "wolfssl430"))]
+    pub fn set_groups_list(&mut self, groups: &str) -> Result<(), ErrorStack> {
+        let groups = CString::new(groups).unwrap();
+        unsafe {
+            cvt(wolf::wolfSSL_CTX_set1_groups_list(
+                self.as_ptr(),
+                groups.as_ptr() as *mut _,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// Sets the supported signature algorithms from a colon separated list, e.g.
+    /// `RSA-PSS+SHA256`.
+    ///
+    /// This corresponds to [`SSL_CTX_set1_sigalgs_list`].
+    ///
+    /// [`SSL_CTX_set1_sigalgs_list`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set1_sigalgs_list.html
+    #[cfg(not(feature = "wolfssl430"))]
+    pub fn set_sigalgs_list(&mut self, sigalgs: &str) -> Result<(), ErrorStack> {
+        let sigalgs = CString::new(sigalgs).unwrap();
+        unsafe {
+            cvt(wolf::wolfSSL_CTX_set1_sigalgs_list(
+                self.as_ptr(),
+                sigalgs.as_ptr() as *const _,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// Sets the minimum supported protocol version. Only effective for contexts of a method
+    /// which negotiates the version, like [`SslMethod::tls_client`].
+    ///
+    /// This corresponds to [`SSL_CTX_set_min_proto_version`].
+    ///
+    /// [`SSL_CTX_set_min_proto_version`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_min_proto_version.html
+    #[cfg(not(feature = "wolfssl430"))]
+    pub fn set_min_proto_version(&mut self, version: SslVersion) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(wolf::wolfSSL_CTX_set_min_proto_version(
+                self.as_ptr(),
+                version.0,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// Sets the maximum supported protocol version. Only effective for contexts of a method
+    /// which negotiates the version, like [`SslMethod::tls_client`].
+    ///
+    /// This corresponds to [`SSL_CTX_set_max_proto_version`].
+    ///
+    /// [`SSL_CTX_set_max_proto_version`]: https://www.openssl.org/docs/man1.1.1/man3/SSL_CTX_set_max_proto_version.html
+    #[cfg(not(feature = "wolfssl430"))]
+    pub fn set_max_proto_version(&mut self, version: SslVersion) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(wolf::wolfSSL_CTX_set_max_proto_version(
+                self.as_ptr(),
+                version.0,
+            ))
+            .map(|_| ())
+        }
+    }
 }
 
 /// WolfSSL library initialization (done only once statically)
@@ -429,12 +626,65 @@ impl SslRef {
         unsafe { wolf::wolfSSL_set_accept_state(self.as_ptr()) }
     }
 
+    /// Sets the MTU of DTLS. Requires wolfSSL to be configured with `--enable-dtls-mtu`.
+    pub fn dtls_set_mtu(&mut self, mtu: u16) -> Result<(), ErrorStack> {
+        unsafe { cvt(wolf::wolfSSL_dtls_set_mtu(self.as_ptr(), mtu)).map(|_| ()) }
+    }
+
+    /// Tells DTLS that the underlying stream is non-blocking, such that timeouts are not handled
+    /// by blocking reads.
+    pub fn dtls_set_using_nonblock(&mut self, nonblock: bool) {
+        unsafe { wolf::wolfSSL_dtls_set_using_nonblock(self.as_ptr(), nonblock as c_int) }
+    }
+
     pub fn use_session_ticket(&mut self) {
         unsafe {
             wolf::wolfSSL_UseSessionTicket(self.as_ptr());
         }
     }
 
+    /// Sends and accepts the renegotiation_info extension of RFC 5746, which allows renegotiating
+    /// the connection securely.
+    pub fn use_secure_renegotiation(&mut self) -> Result<(), ErrorStack> {
+        unsafe { cvt(wolf::wolfSSL_UseSecureRenegotiation(self.as_ptr())).map(|_| ()) }
+    }
+
+    /// Offers (clients) or accepts (servers) the protocols of a comma separated list through
+    /// ALPN. The handshake continues if no protocol matches.
+    ///
+    /// This corresponds to [`wolfSSL_UseALPN`].
+    ///
+    /// [`wolfSSL_UseALPN`]: https://www.wolfssl.com/documentation/manuals/wolfssl/group__IO.html#function-wolfssl_usealpn
+    pub fn use_alpn(&mut self, protocols: &str) -> Result<(), ErrorStack> {
+        let protocols = CString::new(protocols).unwrap();
+        unsafe {
+            cvt(wolf::wolfSSL_UseALPN(
+                self.as_ptr(),
+                protocols.as_ptr() as *mut _,
+                protocols.as_bytes().len() as u32,
+                wolf::WOLFSSL_ALPN_CONTINUE_ON_MISMATCH as c_uchar,
+            ))
+            .map(|_| ())
+        }
+    }
+
+    /// Sends the server name indication extension with a host name.
+    ///
+    /// This corresponds to [`wolfSSL_UseSNI`].
+    ///
+    /// [`wolfSSL_UseSNI`]: https://www.wolfssl.com/documentation/manuals/wolfssl/group__IO.html#function-wolfssl_usesni
+    pub fn use_sni(&mut self, host_name: &str) -> Result<(), ErrorStack> {
+        unsafe {
+            cvt(wolf::wolfSSL_UseSNI(
+                self.as_ptr(),
+                wolf::WOLFSSL_SNI_HOST_NAME as c_uchar,
+                host_name.as_ptr() as *const c_void,
+                host_name.len() as u16,
+            ))
+            .map(|_| ())
+        }
+    }
+
     pub fn server_state(&self) -> u32 {
         unsafe { (*self.as_ptr()).options.serverState as u32 }
     }
@@ -470,6 +720,29 @@ impl SslRef {
         }
     }
 
+    /// Copies the verify_data of the latest Finished which was sent into `buf` and returns the
+    /// length of the verify_data. The returned length may exceed the length of `buf`.
+    ///
+    /// This corresponds to `wolfSSL_get_finished`.
+    pub fn finished(&self, buf: &mut [u8]) -> usize {
+        unsafe {
+            wolf::wolfSSL_get_finished(self.as_ptr(), buf.as_mut_ptr() as *mut c_void, buf.len())
+                as usize
+        }
+    }
+
+    /// Like [`SslRef::finished`], but copies the verify_data of the latest Finished which was
+    /// received.
+    pub fn peer_finished(&self, buf: &mut [u8]) -> usize {
+        unsafe {
+            wolf::wolfSSL_get_peer_finished(
+                self.as_ptr(),
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len(),
+            ) as usize
+        }
+    }
+
     pub fn get_peer_certificate(&self) -> Option<Vec<u8>> {
         unsafe {
             let cert = wolf::wolfSSL_get_peer_certificate(self.as_ptr());
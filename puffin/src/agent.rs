@@ -76,6 +76,18 @@ pub struct AgentDescriptor {
     ///
     /// Default: true
     pub server_authentication: bool,
+    /// If agent is a server:
+    ///   Accept 0-RTT data in TLS 1.3 without protecting against replays.
+    /// If agent is a client:
+    ///   No effect.
+    ///
+    /// Default: false
+    pub early_data: bool,
+    /// Send and accept the renegotiation_info extension before TLS 1.3, such that the connection
+    /// can be renegotiated securely. Only libraries which disable this by default are affected.
+    ///
+    /// Default: false
+    pub renegotiation: bool,
 }
 
 impl Default for AgentDescriptor {
@@ -87,6 +99,8 @@ impl Default for AgentDescriptor {
             try_reuse: false,
             client_authentication: false,
             server_authentication: true,
+            early_data: false,
+            renegotiation: false,
         }
     }
 }
@@ -135,6 +149,10 @@ impl AgentDescriptor {
 pub enum TLSVersion {
     V1_3,
     V1_2,
+    V1_1,
+    V1_0,
+    /// SSLv3, which is only available in PUTs which are built with support for it
+    SSL3,
 }
 
 /// An [`Agent`] holds a non-cloneable reference to a Stream.
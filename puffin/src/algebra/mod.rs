@@ -131,6 +131,7 @@ pub mod test_signature {
         codec::{Codec, Reader},
         define_signature,
         error::Error,
+        oracle::{OracleFamily, StateObserver},
         protocol::{
             MessageResult, OpaqueProtocolMessage, ProtocolBehavior, ProtocolMessage,
             ProtocolMessageDeframer,
@@ -509,6 +510,7 @@ pub mod test_signature {
         type ProtocolMessage = TestMessage;
         type OpaqueProtocolMessage = TestOpaqueMessage;
         type Matcher = AnyMatcher;
+        type OracleObserver = StateObserver;
 
         fn signature() -> &'static Signature {
             panic!("Not implemented for test stub");
@@ -521,6 +523,10 @@ pub mod test_signature {
         fn create_corpus() -> Vec<(Trace<Self::Matcher>, &'static str)> {
             panic!("Not implemented for test stub");
         }
+
+        fn create_oracle_families() -> Vec<OracleFamily<Self::Matcher>> {
+            panic!("Not implemented for test stub");
+        }
     }
 
     pub struct TestFactory;
@@ -4,6 +4,7 @@ use std::{
     io::{Read, Write},
     path::PathBuf,
     process::ExitCode,
+    time::Duration,
 };
 
 use clap::{arg, crate_authors, crate_name, crate_version, Command};
@@ -19,7 +20,9 @@ use crate::{
     },
     graphviz::write_graphviz,
     log::create_stdout_config,
+    oracle::{analyze_family, OracleConfig},
     protocol::ProtocolBehavior,
+    put::{PutName, PutOptions},
     put_registry::PutRegistry,
     trace::{Trace, TraceContext},
 };
@@ -36,6 +39,10 @@ fn create_app() -> Command<'static> {
         .arg(arg!(--minimizer "Use a minimizer"))
         .arg(arg!(--monitor "Use a monitor"))
         .arg(arg!(--"no-launcher" "Do not use the convenient launcher"))
+        .arg(arg!(--"check-violations-per-step" "Evaluate the security policy after each step of a trace"))
+        .arg(arg!(--"external-coverage" "Collect coverage of external PUT processes through __AFL_SHM_ID"))
+        .arg(arg!(--put [name] "Replaces the default PUT, e.g. DYLIB to load a TLS library through --put-option lib=<path>"))
+        .arg(arg!(--"put-option" [option] ... "Configures the default PUT with an option of the form key=value"))
         .subcommands(vec![
             Command::new("quick-experiment").about("Starts a new experiment and writes the results out"),
             Command::new("experiment").about("Starts a new experiment and writes the results out")
@@ -52,7 +59,11 @@ fn create_app() -> Command<'static> {
                 .arg(arg!(--tree "Whether want to use tree mode in the combined view")),
             Command::new("execute")
                 .about("Executes a trace stored in a file")
-                .arg(arg!(<input> "The file which stores a trace"))
+                .arg(arg!(<input> "The file which stores a trace")),
+            Command::new("oracle")
+                .about("Checks whether the PUT distinguishes the variants of the oracle families. Fails if it does.")
+                .arg(arg!(-r --repetitions [n] "How often each variant is executed"))
+                .arg(arg!(--"timing-bucket" [us] "Considers the execution time in buckets of this many microseconds"))
         ])
 }
 
@@ -76,6 +87,26 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
     let minimizer = matches.is_present("minimizer");
     let monitor = matches.is_present("monitor");
     let no_launcher = matches.is_present("no-launcher");
+    let check_violations_per_step = matches.is_present("check-violations-per-step");
+    let external_coverage = matches.is_present("external-coverage");
+    let default_put = match matches.value_of("put") {
+        Some(name) => match put_registry.find_put_name(name) {
+            Some(put_name) => Some(put_name),
+            None => {
+                error!("Unknown PUT {}", name);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+    let put_options = matches.values_of("put-option").into_iter().flatten();
+    let put_options = match PutOptions::parse(put_options) {
+        Ok(put_options) => put_options,
+        Err(err) => {
+            error!("Failed to parse PUT options: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
 
     info!("Version: {}", crate::GIT_REF);
     info!("Put Versions:");
@@ -111,10 +142,46 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
         // Parse arguments
         let input = matches.value_of("input").unwrap();
 
-        if let Err(err) = execute(input, put_registry) {
+        if let Err(err) = execute(input, put_registry, default_put, put_options) {
             error!("Failed to execute trace: {:?}", err);
             return ExitCode::FAILURE;
         }
+    } else if let Some(matches) = matches.subcommand_matches("oracle") {
+        let timing_bucket = match matches
+            .value_of("timing-bucket")
+            .map(str::parse::<u64>)
+            .transpose()
+        {
+            Ok(timing_bucket) => timing_bucket.map(Duration::from_micros),
+            Err(err) => {
+                error!("Invalid timing bucket: {}", err);
+                return ExitCode::FAILURE;
+            }
+        };
+        let repetitions = match matches.value_of("repetitions").map(str::parse::<usize>) {
+            None => 1,
+            Some(Ok(repetitions)) if repetitions > 0 => repetitions,
+            Some(Ok(_)) => {
+                error!("Invalid number of repetitions: must be at least 1");
+                return ExitCode::FAILURE;
+            }
+            Some(Err(err)) => {
+                error!("Invalid number of repetitions: {}", err);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let config = OracleConfig {
+            timing_bucket,
+            repetitions,
+            default_put,
+            put_options,
+            ..OracleConfig::default()
+        };
+
+        if oracle(put_registry, &config) {
+            return ExitCode::FAILURE;
+        }
     } else {
         let experiment_path = if let Some(matches) = matches.subcommand_matches("experiment") {
             let title = matches.value_of("title").unwrap();
@@ -179,6 +246,10 @@ pub fn main<PB: ProtocolBehavior + Clone + 'static>(
             mutation_config: Default::default(),
             monitor,
             no_launcher,
+            check_violations_per_step,
+            external_coverage,
+            default_put,
+            put_options,
         };
 
         if let Err(err) = start::<PB>(config, handle) {
@@ -246,15 +317,35 @@ fn seed<PB: ProtocolBehavior>(
     Ok(())
 }
 
+/// Analyzes all oracle families of the protocol. Returns whether an oracle was found.
+fn oracle<PB: ProtocolBehavior>(
+    put_registry: &'static PutRegistry<PB>,
+    config: &OracleConfig,
+) -> bool {
+    let mut found = false;
+
+    for family in PB::create_oracle_families() {
+        let report = analyze_family::<PB, PB::OracleObserver>(&family, put_registry, config);
+        info!("{}", report);
+        found |= report.is_oracle();
+    }
+
+    found
+}
+
 fn execute<PB: ProtocolBehavior>(
     input: &str,
     put_registry: &'static PutRegistry<PB>,
+    default_put: Option<PutName>,
+    put_options: PutOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let trace = Trace::<PB::Matcher>::from_file(input)?;
 
     info!("Agents: {:?}", &trace.descriptors);
 
     let mut ctx = TraceContext::new(put_registry);
+    ctx.set_default_put(default_put);
+    ctx.set_default_put_options(put_options);
     trace.execute(&mut ctx)?;
     Ok(())
 }
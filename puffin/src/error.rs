@@ -16,7 +16,12 @@ pub enum Error {
     /// Error while operating on a [`Stream`]
     Stream(String),
     Extraction(),
-    SecurityClaim(&'static str),
+    /// The PUT crashed, e.g. because of a signal or a sanitizer report. Contains the captured
+    /// output of the PUT.
+    Crash(String),
+    /// A security violation occurred. If the policy is checked after each step, then the index of
+    /// the step after which the violation was detected is included.
+    SecurityClaim(&'static str, Option<usize>),
 }
 
 impl std::error::Error for Error {}
@@ -35,11 +40,17 @@ impl fmt::Display for Error {
             Error::Agent(err) => write!(f, "error regarding an agent: {}", err),
             Error::Stream(err) => write!(f, "error in the stream: {}", err),
             Error::Extraction() => write!(f, "error while extracting variable",),
-            Error::SecurityClaim(msg) => write!(
+            Error::Crash(output) => write!(f, "the PUT crashed:\n{}", output),
+            Error::SecurityClaim(msg, None) => write!(
                 f,
                 "error because a security violation occurred. msg: {}",
                 msg
             ),
+            Error::SecurityClaim(msg, Some(step)) => write!(
+                f,
+                "error because a security violation occurred after step #{}. msg: {}",
+                step, msg
+            ),
         }
     }
 }
@@ -0,0 +1,106 @@
+//! Attaches the output of crashed PUT processes to objectives. The harness stores the output of
+//! an [`Error::Crash`](crate::error::Error::Crash) and the [`CrashOutputFeedback`] moves it into
+//! the metadata of the testcase.
+
+use std::cell::RefCell;
+
+use libafl::{
+    bolts::tuples::Named, corpus::Testcase, events::EventFirer, executors::ExitKind,
+    feedbacks::Feedback, impl_serdeany, inputs::Input, observers::ObserversTuple,
+    state::HasClientPerfMonitor, Error,
+};
+use serde::{Deserialize, Serialize};
+
+thread_local! {
+    static CRASH_OUTPUT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Remembers the `output` of a crashed PUT for the current execution.
+pub fn set_crash_output(output: String) {
+    CRASH_OUTPUT.with(|crash_output| *crash_output.borrow_mut() = Some(output));
+}
+
+fn take_crash_output() -> Option<String> {
+    CRASH_OUTPUT.with(|crash_output| crash_output.borrow_mut().take())
+}
+
+/// The captured stdout and stderr of a crashed PUT process
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrashOutputMetadata {
+    pub output: String,
+}
+
+impl_serdeany!(CrashOutputMetadata);
+
+/// A feedback which is never interesting on its own. It only adds the [`CrashOutputMetadata`] to
+/// objectives, therefore it has to be combined eagerly with the other objective feedbacks.
+#[derive(Debug, Default)]
+pub struct CrashOutputFeedback {
+    output: Option<String>,
+}
+
+impl CrashOutputFeedback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Named for CrashOutputFeedback {
+    fn name(&self) -> &str {
+        "CrashOutputFeedback"
+    }
+}
+
+impl<I, S> Feedback<I, S> for CrashOutputFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let output = take_crash_output();
+        self.output = if *exit_kind == ExitKind::Crash {
+            output
+        } else {
+            None
+        };
+        Ok(false)
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(output) = self.output.take() {
+            testcase.add_metadata(CrashOutputMetadata { output });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.output = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_crash_output() {
+        set_crash_output("==1==ERROR: AddressSanitizer".to_string());
+        assert_eq!(
+            take_crash_output().as_deref(),
+            Some("==1==ERROR: AddressSanitizer")
+        );
+        assert_eq!(take_crash_output(), None);
+    }
+}
@@ -0,0 +1,208 @@
+//! Coverage of PUTs which run in external processes. Instrumented processes find the shared
+//! memory map through the `__AFL_SHM_ID` environment variable, like processes spawned by AFL.
+//! After each execution the map is merged into a separate map, which is observed next to the
+//! edges map of the fuzzer. Processes which outlive an execution are stopped before, see
+//! [`stop_before_merge`].
+
+use std::cell::RefCell;
+
+use libafl::bolts::shmem::{ShMem, ShMemProvider, UnixShMem, UnixShMemProvider};
+use log::{info, warn};
+
+/// The environment variable through which AFL instrumented processes find the coverage map
+pub const SHM_ENV_VAR: &str = "__AFL_SHM_ID";
+/// The map size which AFL instrumented processes expect
+pub const EXTERNAL_MAP_SIZE: usize = 65536;
+
+/// The map which is observed by the fuzzer. It is separate from the edges map of the fuzzer, such
+/// that the indices of external edges do not collide with the edges of in-process PUTs.
+static mut EXTERNAL_EDGES_MAP: [u8; EXTERNAL_MAP_SIZE] = [0; EXTERNAL_MAP_SIZE];
+
+thread_local! {
+    static EXTERNAL_MAP: RefCell<Option<UnixShMem>> = RefCell::new(None);
+    static STOP_BEFORE_MERGE: RefCell<Vec<Box<dyn FnOnce()>>> = RefCell::new(Vec::new());
+}
+
+/// Creates the shared memory map for the current fuzzer client. Processes which are spawned
+/// afterwards should receive [`shm_id`] through [`SHM_ENV_VAR`].
+pub fn enable() {
+    let map =
+        UnixShMemProvider::new().and_then(|mut provider| provider.new_shmem(EXTERNAL_MAP_SIZE));
+
+    match map {
+        Ok(map) => {
+            info!(
+                "Created coverage map for external processes: {}",
+                map.id().as_str()
+            );
+            EXTERNAL_MAP.with(|external_map| *external_map.borrow_mut() = Some(map));
+        }
+        Err(err) => warn!(
+            "Failed to create coverage map for external processes: {}",
+            err
+        ),
+    }
+}
+
+/// Returns the ID of the shared memory map, if external coverage is enabled
+pub fn shm_id() -> Option<String> {
+    EXTERNAL_MAP.with(|external_map| {
+        external_map
+            .borrow()
+            .as_ref()
+            .map(|map| map.id().as_str().to_owned())
+    })
+}
+
+/// Clears the map before the next execution
+pub fn reset() {
+    EXTERNAL_MAP.with(|external_map| {
+        if let Some(map) = external_map.borrow_mut().as_mut() {
+            map.as_mut_slice().fill(0);
+        }
+    });
+}
+
+/// Adds the hit counts of the external processes to `edges`. If `edges` is smaller than the map,
+/// then the remaining hit counts are dropped.
+pub fn merge_into(edges: &mut [u8]) {
+    EXTERNAL_MAP.with(|external_map| {
+        if let Some(map) = external_map.borrow().as_ref() {
+            for (edge, hits) in edges.iter_mut().zip(map.as_slice()) {
+                *edge = edge.saturating_add(*hits);
+            }
+        }
+    });
+}
+
+/// Returns the map which is observed by the fuzzer, see [`merge_into_external_edges_map`]
+///
+/// # Safety
+///
+/// The map must only be used by the observer of the fuzzer.
+pub(crate) unsafe fn external_edges_map() -> &'static mut [u8] {
+    &mut EXTERNAL_EDGES_MAP[..]
+}
+
+/// Registers `stop`, which stops an external process that would outlive the current execution,
+/// e.g. a process which is shared between executions. The coverage of a running process is not
+/// final, so `stop` is called once before the coverage of the execution is merged.
+pub fn stop_before_merge(stop: Box<dyn FnOnce()>) {
+    STOP_BEFORE_MERGE.with(|hooks| hooks.borrow_mut().push(stop));
+}
+
+/// Merges the external coverage into the map which is observed by the fuzzer. The processes
+/// registered through [`stop_before_merge`] are stopped first.
+pub(crate) fn merge_into_external_edges_map() {
+    let hooks = STOP_BEFORE_MERGE.with(|hooks| hooks.take());
+    for stop in hooks {
+        stop();
+    }
+    merge_into(unsafe { external_edges_map() });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, path::PathBuf, process::Command, rc::Rc};
+
+    use super::*;
+
+    /// Compiles `assets/external_coverage_child.c`, which counts its edges like an AFL
+    /// instrumented process. Returns `None` if no C compiler with `-fsanitize-coverage` support is
+    /// available.
+    fn compile_child() -> Option<PathBuf> {
+        let source =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/external_coverage_child.c");
+        let binary = std::env::temp_dir().join(format!(
+            "puffin-external-coverage-child-{}",
+            std::process::id()
+        ));
+
+        match Command::new("cc")
+            .arg("-fsanitize-coverage=trace-pc")
+            .arg(&source)
+            .arg("-o")
+            .arg(&binary)
+            .status()
+        {
+            Ok(status) if status.success() => Some(binary),
+            _ => None,
+        }
+    }
+
+    fn run_child(child: &PathBuf, args: &[&str]) -> usize {
+        reset();
+        Command::new(child)
+            .args(args)
+            .env(SHM_ENV_VAR, shm_id().unwrap())
+            .status()
+            .unwrap();
+
+        let mut edges = vec![0u8; EXTERNAL_MAP_SIZE];
+        merge_into(&mut edges);
+        edges.iter().map(|hits| *hits as usize).sum()
+    }
+
+    #[test]
+    fn test_merge_into() {
+        enable();
+        let id = shm_id().unwrap();
+        assert!(id.parse::<i32>().is_ok());
+
+        EXTERNAL_MAP.with(|external_map| {
+            let mut external_map = external_map.borrow_mut();
+            let map = external_map.as_mut().unwrap().as_mut_slice();
+            map[1] = 2;
+            map[3] = 255;
+        });
+
+        let mut edges = [1u8; 4];
+        merge_into(&mut edges);
+        assert_eq!(edges, [1, 3, 1, 255]);
+
+        reset();
+        let mut edges = [0u8; 4];
+        merge_into(&mut edges);
+        assert_eq!(edges, [0; 4]);
+    }
+
+    #[test]
+    fn test_instrumented_child() {
+        enable();
+        let child = match compile_child() {
+            Some(child) => child,
+            None => {
+                warn!("Skipping test: cc does not support -fsanitize-coverage");
+                return;
+            }
+        };
+
+        let hits_without_matches = run_child(&child, &[]);
+        let hits_with_matches = run_child(&child, &["x", "y", "x"]);
+        std::fs::remove_file(&child).unwrap();
+
+        assert!(hits_without_matches > 0);
+        assert!(hits_with_matches > hits_without_matches);
+
+        reset();
+        let mut edges = vec![0u8; EXTERNAL_MAP_SIZE];
+        merge_into(&mut edges);
+        assert!(edges.iter().all(|hits| *hits == 0));
+    }
+
+    #[test]
+    fn test_stop_before_merge() {
+        let stopped = Rc::new(Cell::new(0));
+
+        let counter = stopped.clone();
+        stop_before_merge(Box::new(move || counter.set(counter.get() + 1)));
+        assert_eq!(stopped.get(), 0);
+
+        merge_into_external_edges_map();
+        assert_eq!(stopped.get(), 1);
+
+        // Hooks are only called once
+        merge_into_external_edges_map();
+        assert_eq!(stopped.get(), 1);
+    }
+}
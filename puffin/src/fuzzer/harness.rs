@@ -4,13 +4,30 @@ use rand::Rng;
 
 use crate::{
     error::Error,
-    fuzzer::stats_stage::*,
+    fuzzer::{crash_output, external_coverage, stats_stage::*},
     protocol::ProtocolBehavior,
+    put::{PutName, PutOptions},
     trace::{Action, Trace, TraceContext},
 };
 
 pub fn harness<PB: ProtocolBehavior + 'static>(input: &Trace<PB::Matcher>) -> ExitKind {
+    harness_with_options::<PB>(input, false, None, &PutOptions::default())
+}
+
+/// Like [`harness`], but the security policy is optionally evaluated after each step and the
+/// default PUT is replaced by `default_put` and configured with `put_options`.
+/// See [`TraceContext::set_check_violations_per_step`], [`TraceContext::set_default_put`] and
+/// [`TraceContext::set_default_put_options`].
+pub fn harness_with_options<PB: ProtocolBehavior + 'static>(
+    input: &Trace<PB::Matcher>,
+    check_violations_per_step: bool,
+    default_put: Option<PutName>,
+    put_options: &PutOptions,
+) -> ExitKind {
     let mut ctx = TraceContext::new(PB::registry());
+    ctx.set_check_violations_per_step(check_violations_per_step);
+    ctx.set_default_put(default_put);
+    ctx.set_default_put_options(put_options.clone());
 
     TRACE_LENGTH.update(input.steps.len());
 
@@ -23,7 +40,14 @@ pub fn harness<PB: ProtocolBehavior + 'static>(input: &Trace<PB::Matcher>) -> Ex
         }
     }
 
-    if let Err(err) = input.execute(&mut ctx) {
+    external_coverage::reset();
+    let result = input.execute(&mut ctx);
+    // Dropping the context stops the processes of external PUTs, such that their coverage is final.
+    // Processes which outlive the PUTs are stopped while merging.
+    drop(ctx);
+    external_coverage::merge_into_external_edges_map();
+
+    if let Err(err) = result {
         match &err {
             Error::Fn(_) => FN_ERROR.increment(),
             Error::Term(_e) => TERM.increment(),
@@ -32,10 +56,15 @@ pub fn harness<PB: ProtocolBehavior + 'static>(input: &Trace<PB::Matcher>) -> Ex
             Error::Agent(_) => AGENT.increment(),
             Error::Stream(_) => STREAM.increment(),
             Error::Extraction() => EXTRACTION.increment(),
-            Error::SecurityClaim(msg) => {
-                warn!("{}", msg);
+            Error::SecurityClaim(..) => {
+                warn!("{}", err);
                 std::process::abort()
             }
+            Error::Crash(output) => {
+                warn!("{}", err);
+                crash_output::set_crash_output(output.clone());
+                return ExitKind::Crash;
+            }
         }
 
         trace!("{}", err);
@@ -35,6 +35,8 @@ use log4rs::Handle;
 use super::harness;
 use crate::{
     fuzzer::{
+        crash_output::CrashOutputFeedback,
+        external_coverage,
         mutations::{trace_mutations, util::TermConstraints},
         stages::{PuffinMutationalStage, PuffinScheduledMutator},
         stats_monitor::StatsMonitor,
@@ -42,11 +44,14 @@ use crate::{
     },
     log::create_file_config,
     protocol::ProtocolBehavior,
+    put::{PutName, PutOptions},
     trace::Trace,
 };
 
 pub const MAP_FEEDBACK_NAME: &str = "edges";
 const EDGES_OBSERVER_NAME: &str = "edges_observer";
+const EXTERNAL_MAP_FEEDBACK_NAME: &str = "external_edges";
+const EXTERNAL_EDGES_OBSERVER_NAME: &str = "external_edges_observer";
 
 type ConcreteExecutor<'harness, H, OT, S, I> =
     TimeoutExecutor<InProcessExecutor<'harness, H, I, OT, S>>;
@@ -69,6 +74,18 @@ pub struct FuzzerConfig {
     pub monitor: bool,
     pub no_launcher: bool,
     pub log_file: PathBuf,
+    /// Evaluate the security policy after each step instead of only after the whole trace
+    pub check_violations_per_step: bool,
+    /// Collect the coverage of external PUT processes through an AFL compatible shared memory map
+    pub external_coverage: bool,
+    /// Replaces the default PUT of the registry, see [`TraceContext::set_default_put`]
+    ///
+    /// [`TraceContext::set_default_put`]: crate::trace::TraceContext::set_default_put
+    pub default_put: Option<PutName>,
+    /// Options of the default PUT, see [`TraceContext::set_default_put_options`]
+    ///
+    /// [`TraceContext::set_default_put_options`]: crate::trace::TraceContext::set_default_put_options
+    pub put_options: PutOptions,
 }
 
 #[derive(Clone, Copy)]
@@ -334,19 +351,30 @@ type ConcreteMinimizer<C, R, SC, I> =
 
 type ConcreteObservers<'a> = (
     TimeObserver,
-    (HitcountsMapObserver<StdMapObserver<'a, u8>>, ()),
+    (
+        HitcountsMapObserver<StdMapObserver<'a, u8>>,
+        (HitcountsMapObserver<StdMapObserver<'a, u8>>, ()),
+    ),
 );
 
+type ConcreteMapFeedback<'a, C, R, SC, I> = MapFeedback<
+    I,
+    DifferentIsNovel,
+    HitcountsMapObserver<StdMapObserver<'a, u8>>,
+    MaxReducer,
+    ConcreteState<C, R, SC, I>,
+    u8,
+>;
+
 type ConcreteFeedback<'a, C, R, SC, I> = CombinedFeedback<
-    MapFeedback<
+    ConcreteMapFeedback<'a, C, R, SC, I>,
+    CombinedFeedback<
+        ConcreteMapFeedback<'a, C, R, SC, I>,
+        TimeFeedback,
+        LogicEagerOr,
         I,
-        DifferentIsNovel,
-        HitcountsMapObserver<StdMapObserver<'a, u8>>,
-        MaxReducer,
         ConcreteState<C, R, SC, I>,
-        u8,
     >,
-    TimeFeedback,
     LogicEagerOr,
     I,
     ConcreteState<C, R, SC, I>,
@@ -407,26 +435,49 @@ where
             &mut EDGES_MAP[0..MAX_EDGES_NUM]
         };
 
+        // The coverage of external processes is observed in a separate map
+        let external_map = unsafe {
+            let external_map = external_coverage::external_edges_map();
+            if self.config.external_coverage {
+                external_map
+            } else {
+                &mut external_map[0..0]
+            }
+        };
+
         let map_feedback = MaxMapFeedback::with_names_tracking(
             MAP_FEEDBACK_NAME,
             EDGES_OBSERVER_NAME,
             true,
             false,
         );
+        // Only the in-process edges are tracked for the minimizer
+        let external_map_feedback = MaxMapFeedback::with_names_tracking(
+            EXTERNAL_MAP_FEEDBACK_NAME,
+            EXTERNAL_EDGES_OBSERVER_NAME,
+            false,
+            false,
+        );
 
         let (feedback, observers) = {
             let time_observer = TimeObserver::new("time");
             let edges_observer =
                 HitcountsMapObserver::new(StdMapObserver::new(EDGES_OBSERVER_NAME, map));
+            let external_edges_observer = HitcountsMapObserver::new(StdMapObserver::new(
+                EXTERNAL_EDGES_OBSERVER_NAME,
+                external_map,
+            ));
             let feedback = feedback_or!(
                 // New maximization map feedback linked to the edges observer and the feedback state
                 // `track_indexes` needed because of IndexesLenTimeMinimizerCorpusScheduler
                 map_feedback,
+                // Maximization map feedback for the coverage of external processes
+                external_map_feedback,
                 // Time feedback, this one does not need a feedback state
                 // needed for IndexesLenTimeMinimizerCorpusScheduler
                 TimeFeedback::new_with_observer(&time_observer)
             );
-            let observers = tuple_list!(time_observer, edges_observer);
+            let observers = tuple_list!(time_observer, edges_observer, external_edges_observer);
             (feedback, observers)
         };
         self.with_feedback(feedback)
@@ -450,6 +501,10 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
         broker_port,
         monitor,
         no_launcher,
+        check_violations_per_step,
+        external_coverage,
+        default_put,
+        put_options,
         mutation_config:
             MutationConfig {
                 fresh_zoo_after,
@@ -469,7 +524,19 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
          -> Result<(), Error> {
             let seed = static_seed.unwrap_or(event_manager.mgr_id().id as u64);
             info!("Seed is {}", seed);
-            let harness_fn = &mut harness::harness::<PB>;
+
+            if *external_coverage {
+                external_coverage::enable();
+            }
+
+            let harness_fn = &mut |input: &Trace<PB::Matcher>| {
+                harness::harness_with_options::<PB>(
+                    input,
+                    *check_violations_per_step,
+                    *default_put,
+                    put_options,
+                )
+            };
 
             let mut builder =
                 RunClientBuilder::new(config.clone(), harness_fn, state, event_manager);
@@ -498,7 +565,12 @@ pub fn start<PB: ProtocolBehavior + Clone + 'static>(
                     )
                     .unwrap(),
                 )
-                .with_objective(feedback_or!(CrashFeedback::new(), TimeoutFeedback::new()));
+                .with_objective(feedback_or!(
+                    CrashFeedback::new(),
+                    TimeoutFeedback::new(),
+                    // Stores the output of crashed PUT processes in the objective metadata
+                    CrashOutputFeedback::new()
+                ));
 
             #[cfg(feature = "sancov_libafl")]
             {
@@ -5,6 +5,8 @@ use libafl::{bolts::HasLen, inputs::Input};
 
 use crate::trace::Trace;
 
+mod crash_output;
+pub mod external_coverage;
 mod harness;
 mod libafl_setup;
 pub mod sanitizer;
@@ -13,6 +13,7 @@ pub mod experiment;
 pub mod fuzzer;
 pub mod graphviz;
 pub mod log;
+pub mod oracle;
 pub mod protocol;
 pub mod put;
 pub mod put_registry;
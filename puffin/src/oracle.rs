@@ -0,0 +1,321 @@
+//! Detection of oracles like padding oracles. Some vulnerabilities are neither crashes nor
+//! violations of the security policy, but observable differences in how an agent reacts to inputs
+//! which should be indistinguishable. For example, a server which responds differently to
+//! ciphertexts with invalid paddings and ciphertexts with valid paddings, but invalid MACs, is a
+//! padding oracle.
+//!
+//! An [`OracleFamily`] is a set of [`Trace`]s which only differ in a single subterm. Each variant
+//! belongs to a class. All variants of a family are executed and the reaction of the agent is
+//! recorded as [`Outcome`]. If the outcomes differ, then the agent distinguishes the variants.
+
+use std::{
+    fmt,
+    fmt::{Debug, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use log::{debug, warn};
+
+use crate::{
+    agent::AgentName,
+    algebra::{Matcher, Term},
+    error::Error,
+    protocol::ProtocolBehavior,
+    put::{PutName, PutOptions},
+    put_registry::PutRegistry,
+    trace::{Trace, TraceContext},
+};
+
+/// Observes the reaction of an agent after a trace has been executed. What is observable is
+/// protocol specific, e.g. the alerts which a TLS agent sent.
+pub trait Observer<PB: ProtocolBehavior> {
+    type Observation: Clone + Debug + PartialEq;
+
+    fn observe(ctx: &TraceContext<PB>, agent: AgentName) -> Self::Observation;
+}
+
+/// Observes only the state of an agent, see [`crate::put::Put::describe_state`]. This is a
+/// fallback for protocols which do not observe more.
+pub struct StateObserver;
+
+impl<PB: ProtocolBehavior> Observer<PB> for StateObserver {
+    type Observation = String;
+
+    fn observe(ctx: &TraceContext<PB>, agent: AgentName) -> String {
+        ctx.find_agent(agent)
+            .map(|agent| agent.put().describe_state().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// The reaction of an agent to a variant of an [`OracleFamily`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome<O> {
+    pub observation: O,
+    /// The execution time divided by [`OracleConfig::timing_bucket`]. Always zero if timing is
+    /// not considered.
+    pub timing_bucket: u128,
+}
+
+impl<O: Debug> Display for Outcome<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} (timing bucket {})",
+            self.observation, self.timing_bucket
+        )
+    }
+}
+
+pub struct OracleVariant<M: Matcher> {
+    /// The class of the variant. Variants of different classes should be indistinguishable,
+    /// e.g. an invalid padding and a valid padding with an invalid MAC.
+    pub class: String,
+    pub trace: Trace<M>,
+}
+
+/// A family of traces which only differ in a single subterm. The reactions of the `agent` to
+/// the variants are compared.
+pub struct OracleFamily<M: Matcher> {
+    pub name: &'static str,
+    pub agent: AgentName,
+    pub variants: Vec<OracleVariant<M>>,
+}
+
+impl<M: Matcher> OracleFamily<M> {
+    /// Creates a variant for each of the `subterms` by inserting it into the `template`.
+    pub fn from_subterms<F>(
+        name: &'static str,
+        agent: AgentName,
+        template: F,
+        subterms: Vec<(String, Term<M>)>,
+    ) -> Self
+    where
+        F: Fn(Term<M>) -> Trace<M>,
+    {
+        Self {
+            name,
+            agent,
+            variants: subterms
+                .into_iter()
+                .map(|(class, subterm)| OracleVariant {
+                    class,
+                    trace: template(subterm),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OracleConfig {
+    /// The width of the timing buckets. If `None`, then timing is not considered because it is
+    /// noisy.
+    pub timing_bucket: Option<Duration>,
+    /// How often each variant is executed. The median of the execution times is used.
+    pub repetitions: usize,
+    pub deterministic: bool,
+    /// Replaces the default PUT of the registry for executing the variants
+    pub default_put: Option<PutName>,
+    /// The options of the PUTs which execute the variants
+    pub put_options: PutOptions,
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        Self {
+            timing_bucket: None,
+            repetitions: 1,
+            deterministic: true,
+            default_put: None,
+            put_options: PutOptions::default(),
+        }
+    }
+}
+
+/// The outcomes of an [`OracleFamily`] and the classes of the variants which led to them.
+#[derive(Clone, Debug)]
+pub struct OracleReport<O> {
+    pub family: &'static str,
+    pub outcomes: Vec<(Outcome<O>, Vec<String>)>,
+    /// Classes of variants which could not be executed because the attacker failed to evaluate
+    /// a term. These do not tell anything about the agent.
+    pub skipped: Vec<String>,
+}
+
+impl<O: Clone + Debug + PartialEq> OracleReport<O> {
+    fn new(family: &'static str) -> Self {
+        Self {
+            family,
+            outcomes: vec![],
+            skipped: vec![],
+        }
+    }
+
+    fn add(&mut self, outcome: Outcome<O>, class: &str) {
+        let index = match self
+            .outcomes
+            .iter()
+            .position(|(existing, _)| *existing == outcome)
+        {
+            Some(index) => index,
+            None => {
+                self.outcomes.push((outcome, vec![]));
+                self.outcomes.len() - 1
+            }
+        };
+
+        let classes = &mut self.outcomes[index].1;
+        if !classes.iter().any(|existing| existing == class) {
+            classes.push(class.to_string());
+        }
+    }
+
+    /// Whether the agent distinguished variants of different classes. This is the case if an
+    /// outcome was not observed for all classes, such that observing it tells something about
+    /// the class. Differences between variants of the same class are not an oracle on their own.
+    pub fn is_oracle(&self) -> bool {
+        let classes: Vec<&String> = self
+            .outcomes
+            .iter()
+            .flat_map(|(_, classes)| classes)
+            .collect();
+
+        self.outcomes.iter().any(|(_, outcome_classes)| {
+            classes
+                .iter()
+                .any(|class| !outcome_classes.contains(*class))
+        })
+    }
+}
+
+impl<O: Clone + Debug + PartialEq> Display for OracleReport<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_oracle() {
+            writeln!(f, "{}: found {} outcomes", self.family, self.outcomes.len())?;
+        } else {
+            writeln!(f, "{}: no oracle", self.family)?;
+        }
+
+        for (outcome, classes) in &self.outcomes {
+            writeln!(f, "  {} <- {}", outcome, classes.join(", "))?;
+        }
+
+        if !self.skipped.is_empty() {
+            writeln!(f, "  skipped: {}", self.skipped.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Executes all variants of the `family` and groups the outcomes which the observer `O`
+/// reports.
+pub fn analyze_family<PB, O>(
+    family: &OracleFamily<PB::Matcher>,
+    put_registry: &'static PutRegistry<PB>,
+    config: &OracleConfig,
+) -> OracleReport<O::Observation>
+where
+    PB: ProtocolBehavior,
+    O: Observer<PB>,
+{
+    let mut report = OracleReport::new(family.name);
+
+    'variants: for variant in &family.variants {
+        let mut observation = None;
+        let mut durations = Vec::with_capacity(config.repetitions.max(1));
+
+        for _ in 0..config.repetitions.max(1) {
+            let mut ctx = TraceContext::new(put_registry);
+            ctx.set_deterministic(config.deterministic);
+            ctx.set_default_put(config.default_put);
+            ctx.set_default_put_options(config.put_options.clone());
+
+            let start = Instant::now();
+            let result = variant.trace.execute(&mut ctx);
+            durations.push(start.elapsed());
+
+            match result {
+                Err(err @ Error::Fn(_)) | Err(err @ Error::Term(_)) => {
+                    warn!(
+                        "Skipping variant {} of {}: {}",
+                        variant.class, family.name, err
+                    );
+                    report.skipped.push(variant.class.clone());
+                    continue 'variants;
+                }
+                Err(err) => debug!("Variant {} of {}: {}", variant.class, family.name, err),
+                Ok(()) => {}
+            }
+
+            let current = O::observe(&ctx, family.agent);
+            if observation.as_ref().map_or(false, |last| *last != current) {
+                warn!(
+                    "Variant {} of {} is not deterministic",
+                    variant.class, family.name
+                );
+            }
+            observation = Some(current);
+        }
+
+        durations.sort();
+        let timing_bucket = config.timing_bucket.map_or(0, |bucket| {
+            durations[durations.len() / 2].as_nanos() / bucket.as_nanos().max(1)
+        });
+
+        if let Some(observation) = observation {
+            report.add(
+                Outcome {
+                    observation,
+                    timing_bucket,
+                },
+                &variant.class,
+            );
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::oracle::{OracleReport, Outcome};
+
+    fn outcome(observation: &'static str) -> Outcome<&'static str> {
+        Outcome {
+            observation,
+            timing_bucket: 0,
+        }
+    }
+
+    #[test]
+    fn test_report_groups_classes() {
+        let mut report = OracleReport::new("test");
+        report.add(outcome("bad_record_mac"), "invalid padding");
+        report.add(outcome("bad_record_mac"), "invalid mac");
+        report.add(outcome("bad_record_mac"), "invalid padding");
+        assert!(!report.is_oracle());
+        assert_eq!(report.outcomes[0].1, vec!["invalid padding", "invalid mac"]);
+
+        report.add(outcome("decryption_failed"), "invalid padding");
+        assert!(report.is_oracle());
+        assert_eq!(report.outcomes[1].1, vec!["invalid padding"]);
+    }
+
+    #[test]
+    fn test_report_ignores_differences_within_class() {
+        let mut report = OracleReport::new("test");
+        report.add(outcome("bad_record_mac"), "invalid padding");
+        report.add(outcome("decryption_failed"), "invalid padding");
+        assert!(!report.is_oracle());
+
+        // Both outcomes are observed for both classes
+        report.add(outcome("bad_record_mac"), "valid padding");
+        report.add(outcome("decryption_failed"), "valid padding");
+        assert!(!report.is_oracle());
+
+        report.add(outcome("unexpected_message"), "valid padding");
+        assert!(report.is_oracle());
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
     claims::{Claim, SecurityViolationPolicy},
     codec::Codec,
     error::Error,
+    oracle::{Observer, OracleFamily},
     put_registry::PutRegistry,
     trace::Trace,
     variable_data::VariableData,
@@ -26,6 +27,12 @@ pub trait OpaqueProtocolMessage: Clone + Debug + Codec {
     fn extract_knowledge(&self) -> Result<Vec<Box<dyn VariableData>>, Error>;
 }
 
+/// A sequence of [opaque messages](OpaqueProtocolMessage) which is sent to an agent in a single
+/// [`InputAction`](crate::trace::InputAction). This allows terms to choose how messages are
+/// framed, e.g. to split a message across several records or to pack several messages into one.
+#[derive(Clone, Debug)]
+pub struct OpaqueMessageSequence<O: OpaqueProtocolMessage>(pub Vec<O>);
+
 /// Deframes a stream of bytes into distinct [OpaqueProtocolMessages](OpaqueProtocolMessage).
 /// A deframer is usually state-ful. This means it produces as many messages from the input bytes
 /// and stores them.
@@ -45,7 +52,7 @@ pub trait ProtocolMessageDeframer {
 /// (security policy)[SecurityViolationPolicy] over
 /// sequences of them. Finally, there is a [matcher](Matcher) which allows traces to include
 /// queries for [knowledge](crate::trace::Knowledge).
-pub trait ProtocolBehavior: 'static {
+pub trait ProtocolBehavior: 'static + Sized {
     type Claim: Claim;
     type SecurityViolationPolicy: SecurityViolationPolicy<Self::Claim>;
 
@@ -55,6 +62,9 @@ pub trait ProtocolBehavior: 'static {
     type Matcher: Matcher
         + for<'a> TryFrom<&'a MessageResult<Self::ProtocolMessage, Self::OpaqueProtocolMessage>>;
 
+    /// Observes the reaction of agents to the variants of [oracle families](OracleFamily).
+    type OracleObserver: Observer<Self>;
+
     /// Get the signature which is used in the protocol
     fn signature() -> &'static Signature;
 
@@ -65,6 +75,9 @@ pub trait ProtocolBehavior: 'static {
 
     /// Creates a sane initial seed corpus.
     fn create_corpus() -> Vec<(Trace<Self::Matcher>, &'static str)>;
+
+    /// Creates the families of traces which are checked for oracles, see [`crate::oracle`].
+    fn create_oracle_families() -> Vec<OracleFamily<Self::Matcher>>;
 }
 
 pub struct MessageResult<M: ProtocolMessage<O>, O: OpaqueProtocolMessage>(pub Option<M>, pub O);
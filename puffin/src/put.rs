@@ -57,6 +57,45 @@ impl PutOptions {
             .find(|(found_key, _value)| -> bool { found_key == key })
             .map(|(_key, value)| value.as_str())
     }
+
+    /// Parses options of the form `key=value`, like they are passed on the command line
+    pub fn parse<'a>(options: impl IntoIterator<Item = &'a str>) -> Result<Self, Error> {
+        let options = options
+            .into_iter()
+            .map(|option| {
+                option.split_once('=').ok_or_else(|| {
+                    Error::Put(format!(
+                        "PUT option \"{}\" is not of the form key=value",
+                        option
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(options))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.options
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /// Fails if an option is not one of the `keys` which the PUT consumes, so that a misspelled
+    /// option is not silently ignored
+    pub fn check_keys(&self, keys: &[&str]) -> Result<(), Error> {
+        match self.iter().find(|(key, _value)| !keys.contains(key)) {
+            Some((key, _value)) => Err(Error::Put(format!(
+                "Unknown PUT option {}, expected one of {}",
+                key,
+                keys.join(", ")
+            ))),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq, Hash, Default)]
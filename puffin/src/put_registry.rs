@@ -38,6 +38,19 @@ impl<PB: ProtocolBehavior> PutRegistry<PB> {
             .map(|func| func())
             .find(|factory: &Box<dyn Factory<PB>>| factory.name() == put_name)
     }
+
+    /// Finds the PUT whose name matches `name` without the trailing underscores, e.g. `TCP`
+    pub fn find_put_name(&self, name: &str) -> Option<PutName> {
+        self.factories
+            .iter()
+            .map(|func| func().name())
+            .find(|put_name| {
+                put_name
+                    .to_string()
+                    .trim_end_matches('_')
+                    .eq_ignore_ascii_case(name.trim_end_matches('_'))
+            })
+    }
 }
 
 /// Factory for instantiating programs-under-test.
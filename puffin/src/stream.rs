@@ -35,7 +35,7 @@ use crate::{
 };
 
 pub trait Stream<M: ProtocolMessage<O>, O: OpaqueProtocolMessage> {
-    fn add_to_inbound(&mut self, opaque_message: &O);
+    fn add_to_inbound(&mut self, opaque_message: &O) -> Result<(), Error>;
 
     /// Takes a single TLS message from the outbound channel
     fn take_message_from_outbound(&mut self) -> Result<Option<MessageResult<M, O>>, Error>;
@@ -78,8 +78,9 @@ where
     E: Into<Error>,
     M: TryInto<M>,
 {
-    fn add_to_inbound(&mut self, opaque_message: &D::OpaqueProtocolMessage) {
+    fn add_to_inbound(&mut self, opaque_message: &D::OpaqueProtocolMessage) -> Result<(), Error> {
         opaque_message.encode(self.inbound.get_mut());
+        Ok(())
     }
 
     fn take_message_from_outbound(
@@ -32,8 +32,11 @@ use crate::{
     algebra::{dynamic_function::TypeShape, error::FnError, remove_prefix, Matcher, Term},
     claims::{Claim, GlobalClaimList, SecurityViolationPolicy},
     error::Error,
-    protocol::{MessageResult, OpaqueProtocolMessage, ProtocolBehavior, ProtocolMessage},
-    put::PutDescriptor,
+    protocol::{
+        MessageResult, OpaqueMessageSequence, OpaqueProtocolMessage, ProtocolBehavior,
+        ProtocolMessage,
+    },
+    put::{PutDescriptor, PutName, PutOptions},
     put_registry::{Factory, PutRegistry},
     variable_data::VariableData,
 };
@@ -102,8 +105,15 @@ pub struct TraceContext<PB: ProtocolBehavior + 'static> {
     agents: Vec<Agent<PB>>,
     claims: GlobalClaimList<PB::Claim>,
     put_descriptors: HashMap<AgentName, PutDescriptor>,
+    /// Replaces the default PUT of the registry, see [`TraceContext::set_default_put`]
+    default_put: Option<PutName>,
+    /// The options of the default PUT, which is used by agents without a non-default PUT
+    default_put_options: PutOptions,
     put_registry: &'static PutRegistry<PB>,
     deterministic: bool,
+    /// Whether the security policy is evaluated after each step instead of only after
+    /// the whole trace has been executed.
+    check_violations_per_step: bool,
     phantom: PhantomData<PB>,
 }
 
@@ -118,8 +128,11 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
             agents: vec![],
             claims,
             put_descriptors: Default::default(),
+            default_put: None,
+            default_put_options: Default::default(),
             put_registry,
             deterministic: false,
+            check_violations_per_step: false,
             phantom: Default::default(),
         }
     }
@@ -133,11 +146,16 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
     }
 
     pub fn verify_security_violations(&self) -> Result<(), Error> {
+        self.verify_security_violations_at(None)
+    }
+
+    /// Checks the claims for violations. The `step` is recorded in the error if a violation is found.
+    /// See [`TraceContext::set_check_violations_per_step`] and the "violations" benchmark for the
+    /// efficiency loss of doing this at each step.
+    fn verify_security_violations_at(&self, step: Option<usize>) -> Result<(), Error> {
         let claims = self.claims.deref_borrow();
         if let Some(msg) = PB::SecurityViolationPolicy::check_violation(claims.slice()) {
-            // [TODO] Lucca: versus checking at each step ? Could detect violation earlier, before a blocking state is reached ? [BENCH] benchmark the efficiency loss of doing so
-            // Max: We only check for Finished claims right now, so its fine to check only at the end
-            return Err(Error::SecurityClaim(msg));
+            return Err(Error::SecurityClaim(msg, step));
         }
         Ok(())
     }
@@ -210,7 +228,7 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
         message: &PB::OpaqueProtocolMessage,
     ) -> Result<(), Error> {
         self.find_agent_mut(agent_name)
-            .map(|agent| agent.put_mut().add_to_inbound(message))
+            .and_then(|agent| agent.put_mut().add_to_inbound(message))
     }
 
     pub fn next_state(&mut self, agent_name: AgentName) -> Result<(), Error> {
@@ -265,12 +283,11 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
         self.put_descriptors
             .get(&agent_descriptor.name)
             .cloned()
-            .unwrap_or_else(|| {
-                let factory = (self.put_registry.default)();
-                PutDescriptor {
-                    name: factory.name(),
-                    options: Default::default(),
-                }
+            .unwrap_or_else(|| PutDescriptor {
+                name: self
+                    .default_put
+                    .unwrap_or_else(|| self.put_registry.default_factory().name()),
+                options: self.default_put_options.clone(),
             })
     }
 
@@ -283,6 +300,16 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
         self.put_descriptors.extend(descriptors.iter().cloned());
     }
 
+    /// Replaces the default PUT of the registry, e.g. with the PUT passed through `--put`
+    pub fn set_default_put(&mut self, put_name: Option<PutName>) {
+        self.default_put = put_name;
+    }
+
+    /// Configures the default PUT, e.g. with the options passed through `--put-option`
+    pub fn set_default_put_options(&mut self, options: PutOptions) {
+        self.default_put_options = options;
+    }
+
     pub fn reset_agents(&mut self) -> Result<(), Error> {
         for agent in &mut self.agents {
             agent.reset(agent.name())?;
@@ -299,6 +326,12 @@ impl<PB: ProtocolBehavior> TraceContext<PB> {
     pub fn set_deterministic(&mut self, deterministic: bool) {
         self.deterministic = deterministic;
     }
+
+    /// Evaluate the security policy after each step. This detects violations which would
+    /// otherwise be masked by a later step failing, e.g. with an [`Error::Put`].
+    pub fn set_check_violations_per_step(&mut self, check_violations_per_step: bool) {
+        self.check_violations_per_step = check_violations_per_step;
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize, Hash)]
@@ -355,26 +388,45 @@ impl<M: Matcher> Trace<M> {
         for (i, step) in steps.iter().enumerate() {
             debug!("Executing step #{}", i);
 
-            step.action.execute(step, ctx)?;
+            let result = Self::execute_step(step, ctx);
 
-            // Output after each InputAction step
-            match step.action {
-                Action::Input(_) => {
-                    let output_step = &OutputAction::<M>::new_step(step.agent);
+            ctx.claims.deref_borrow().log();
 
-                    output_step.action.execute(output_step, ctx)?;
-                }
-                Action::Output(_) => {}
+            // A violation takes precedence over the error of the step, as the step might have
+            // failed after the claims were made
+            if ctx.check_violations_per_step {
+                ctx.verify_security_violations_at(Some(i))?;
+            } else if result.is_err() {
+                ctx.verify_security_violations()?;
             }
 
-            ctx.claims.deref_borrow().log();
+            result?;
         }
 
-        ctx.verify_security_violations()?;
+        if !ctx.check_violations_per_step {
+            ctx.verify_security_violations()?;
+        }
 
         Ok(())
     }
 
+    fn execute_step<PB>(step: &Step<M>, ctx: &mut TraceContext<PB>) -> Result<(), Error>
+    where
+        PB: ProtocolBehavior<Matcher = M>,
+    {
+        step.action.execute(step, ctx)?;
+
+        // Output after each InputAction step
+        match step.action {
+            Action::Input(_) => {
+                let output_step = &OutputAction::<M>::new_step(step.agent);
+
+                output_step.action.execute(output_step, ctx)
+            }
+            Action::Output(_) => Ok(()),
+        }
+    }
+
     pub fn execute_deterministic<PB>(
         &self,
         put_registry: &'static PutRegistry<PB>,
@@ -553,8 +605,8 @@ pub struct InputAction<M: Matcher> {
     pub recipe: Term<M>,
 }
 
-/// Processes messages in the inbound channel. Uses the recipe field to evaluate to a rustls Message
-/// or a MultiMessage.
+/// Processes messages in the inbound channel. Uses the recipe field to evaluate to a rustls Message,
+/// an OpaqueMessage or an OpaqueMessageSequence.
 impl<M: Matcher> InputAction<M> {
     pub fn new_step(agent: AgentName, recipe: Term<M>) -> Step<M> {
         Step {
@@ -584,9 +636,17 @@ impl<M: Matcher> InputAction<M> {
         {
             opaque_message.debug("Input opaque message");
             ctx.add_to_inbound(step.agent, opaque_message)?;
+        } else if let Some(sequence) = evaluated
+            .as_ref()
+            .downcast_ref::<OpaqueMessageSequence<PB::OpaqueProtocolMessage>>()
+        {
+            for opaque_message in &sequence.0 {
+                opaque_message.debug("Input opaque message of sequence");
+                ctx.add_to_inbound(step.agent, opaque_message)?;
+            }
         } else {
             return Err(FnError::Unknown(String::from(
-                "Recipe is not a `Message`, `OpaqueMessage` or `MultiMessage`!",
+                "Recipe is not a `Message`, `OpaqueMessage` or `OpaqueMessageSequence`!",
             ))
             .into());
         }
@@ -600,3 +660,221 @@ impl<M: Matcher> fmt::Display for InputAction<M> {
         write!(f, "InputAction:\n{}", self.recipe)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::{
+        agent::{AgentDescriptor, AgentName, AgentType},
+        algebra::{
+            dynamic_function::TypeShape,
+            signature::Signature,
+            test_signature::{TestMessage, TestOpaqueMessage},
+            AnyMatcher,
+        },
+        claims::{Claim, GlobalClaimList, SecurityViolationPolicy},
+        error::Error,
+        oracle::{OracleFamily, StateObserver},
+        protocol::{MessageResult, ProtocolBehavior},
+        put::{Put, PutName},
+        put_registry::{Factory, PutRegistry},
+        stream::Stream,
+        trace::{OutputAction, Trace, TraceContext},
+    };
+
+    const VIOLATING_PUT: PutName = PutName(['V', 'I', 'O', 'L', 'A', 'T', 'I', 'N', 'G', '_']);
+
+    const VIOLATING_PUT_REGISTRY: PutRegistry<ViolatingProtocolBehavior> = PutRegistry {
+        factories: &[new_violating_factory],
+        default: new_violating_factory,
+    };
+
+    #[derive(Debug, Clone)]
+    struct ViolatingClaim(AgentName);
+
+    impl Claim for ViolatingClaim {
+        fn agent_name(&self) -> AgentName {
+            self.0
+        }
+
+        fn id(&self) -> TypeShape {
+            TypeShape::of::<ViolatingClaim>()
+        }
+
+        fn inner(&self) -> Box<dyn Any> {
+            Box::new(self.clone())
+        }
+    }
+
+    /// Every claim is a violation
+    struct ViolatingPolicy;
+
+    impl SecurityViolationPolicy<ViolatingClaim> for ViolatingPolicy {
+        fn check_violation(claims: &[ViolatingClaim]) -> Option<&'static str> {
+            (!claims.is_empty()).then_some("Claim made")
+        }
+    }
+
+    struct ViolatingProtocolBehavior;
+
+    impl ProtocolBehavior for ViolatingProtocolBehavior {
+        type Claim = ViolatingClaim;
+        type SecurityViolationPolicy = ViolatingPolicy;
+        type ProtocolMessage = TestMessage;
+        type OpaqueProtocolMessage = TestOpaqueMessage;
+        type Matcher = AnyMatcher;
+        type OracleObserver = StateObserver;
+
+        fn signature() -> &'static Signature {
+            panic!("Not implemented for test stub");
+        }
+
+        fn registry() -> &'static PutRegistry<Self> {
+            panic!("Not implemented for test stub");
+        }
+
+        fn create_corpus() -> Vec<(Trace<Self::Matcher>, &'static str)> {
+            panic!("Not implemented for test stub");
+        }
+
+        fn create_oracle_families() -> Vec<OracleFamily<Self::Matcher>> {
+            panic!("Not implemented for test stub");
+        }
+    }
+
+    /// Servers make a claim before failing to progress, clients just fail
+    struct ViolatingPut {
+        descriptor: AgentDescriptor,
+        claims: GlobalClaimList<ViolatingClaim>,
+    }
+
+    impl Stream<TestMessage, TestOpaqueMessage> for ViolatingPut {
+        fn add_to_inbound(&mut self, _opaque_message: &TestOpaqueMessage) -> Result<(), Error> {
+            panic!("Not implemented for test stub");
+        }
+
+        fn take_message_from_outbound(
+            &mut self,
+        ) -> Result<Option<MessageResult<TestMessage, TestOpaqueMessage>>, Error> {
+            Ok(None)
+        }
+    }
+
+    impl Put<ViolatingProtocolBehavior> for ViolatingPut {
+        fn progress(&mut self, agent_name: &AgentName) -> Result<(), Error> {
+            if self.descriptor.typ == AgentType::Server {
+                self.claims
+                    .deref_borrow_mut()
+                    .claim_sized(ViolatingClaim(*agent_name));
+            }
+            Err(Error::Put("progress failed".to_string()))
+        }
+
+        fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn descriptor(&self) -> &AgentDescriptor {
+            &self.descriptor
+        }
+
+        #[cfg(feature = "claims")]
+        fn register_claimer(&mut self, _agent_name: AgentName) {}
+
+        #[cfg(feature = "claims")]
+        fn deregister_claimer(&mut self) {}
+
+        fn rename_agent(&mut self, _agent_name: AgentName) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn describe_state(&self) -> &str {
+            "violating"
+        }
+
+        fn is_state_successful(&self) -> bool {
+            false
+        }
+
+        fn set_deterministic(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn shutdown(&mut self) -> String {
+            String::new()
+        }
+
+        fn version() -> String {
+            String::new()
+        }
+    }
+
+    struct ViolatingFactory;
+
+    impl Factory<ViolatingProtocolBehavior> for ViolatingFactory {
+        fn create(
+            &self,
+            context: &TraceContext<ViolatingProtocolBehavior>,
+            agent_descriptor: &AgentDescriptor,
+        ) -> Result<Box<dyn Put<ViolatingProtocolBehavior>>, Error> {
+            Ok(Box::new(ViolatingPut {
+                descriptor: agent_descriptor.clone(),
+                claims: context.claims().clone(),
+            }))
+        }
+
+        fn name(&self) -> PutName {
+            VIOLATING_PUT
+        }
+
+        fn version(&self) -> String {
+            String::new()
+        }
+    }
+
+    fn new_violating_factory() -> Box<dyn Factory<ViolatingProtocolBehavior>> {
+        Box::new(ViolatingFactory)
+    }
+
+    fn execute(typ: AgentType, check_violations_per_step: bool) -> Result<(), Error> {
+        let agent = AgentName::first();
+        let trace = Trace::<AnyMatcher> {
+            descriptors: vec![AgentDescriptor {
+                name: agent,
+                typ,
+                ..AgentDescriptor::default()
+            }],
+            steps: vec![OutputAction::new_step(agent)],
+            prior_traces: vec![],
+        };
+
+        let mut ctx = TraceContext::new(&VIOLATING_PUT_REGISTRY);
+        ctx.set_check_violations_per_step(check_violations_per_step);
+        trace.execute(&mut ctx)
+    }
+
+    #[test]
+    fn test_violation_in_failing_step() {
+        assert!(matches!(
+            execute(AgentType::Server, false),
+            Err(Error::SecurityClaim("Claim made", None))
+        ));
+        assert!(matches!(
+            execute(AgentType::Server, true),
+            Err(Error::SecurityClaim("Claim made", Some(0)))
+        ));
+    }
+
+    #[test]
+    fn test_failing_step_without_violation() {
+        assert!(matches!(
+            execute(AgentType::Client, false),
+            Err(Error::Put(_))
+        ));
+        assert!(matches!(
+            execute(AgentType::Client, true),
+            Err(Error::Put(_))
+        ));
+    }
+}
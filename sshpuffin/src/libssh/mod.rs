@@ -166,11 +166,12 @@ pub struct LibSSL {
 impl LibSSL {}
 
 impl Stream<SshMessage, RawSshMessage> for LibSSL {
-    fn add_to_inbound(&mut self, result: &RawSshMessage) {
+    fn add_to_inbound(&mut self, result: &RawSshMessage) -> Result<(), Error> {
         let mut buffer = Vec::new();
         Codec::encode(result, &mut buffer);
 
-        self.fuzz_stream.write_all(&mut buffer).unwrap();
+        self.fuzz_stream.write_all(&mut buffer)?;
+        Ok(())
     }
 
     fn take_message_from_outbound(
@@ -3,6 +3,7 @@ use std::io::Read;
 use puffin::{
     algebra::{signature::Signature, AnyMatcher},
     error::Error,
+    oracle::{OracleFamily, StateObserver},
     protocol::{
         MessageResult, OpaqueProtocolMessage, ProtocolBehavior, ProtocolMessage,
         ProtocolMessageDeframer,
@@ -34,6 +35,7 @@ impl ProtocolBehavior for SshProtocolBehavior {
     type ProtocolMessage = SshMessage;
     type OpaqueProtocolMessage = RawSshMessage;
     type Matcher = AnyMatcher;
+    type OracleObserver = StateObserver;
 
     fn signature() -> &'static Signature {
         &SSH_SIGNATURE
@@ -49,4 +51,8 @@ impl ProtocolBehavior for SshProtocolBehavior {
     fn create_corpus() -> Vec<(Trace<Self::Matcher>, &'static str)> {
         vec![] // TODO
     }
+
+    fn create_oracle_families() -> Vec<OracleFamily<Self::Matcher>> {
+        vec![]
+    }
 }
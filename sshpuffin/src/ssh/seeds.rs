@@ -18,6 +18,8 @@ pub fn seed_successful(client: AgentName, server: AgentName) -> Trace<AnyMatcher
                 try_reuse: false,             // FIXME: Remove?
                 client_authentication: false, // FIXME: Remove?
                 server_authentication: false, // FIXME: Remove?
+                early_data: false,            // FIXME: Remove?
+                renegotiation: false,         // FIXME: Remove?
             },
             AgentDescriptor {
                 name: server,
@@ -26,6 +28,8 @@ pub fn seed_successful(client: AgentName, server: AgentName) -> Trace<AnyMatcher
                 try_reuse: false,             // FIXME: Remove?
                 client_authentication: false, // FIXME: Remove?
                 server_authentication: false, // FIXME: Remove?
+                early_data: false,            // FIXME: Remove?
+                renegotiation: false,         // FIXME: Remove?
             },
         ],
         steps: vec![
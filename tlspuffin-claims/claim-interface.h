@@ -1,9 +1,23 @@
 #ifndef TLSPUFFIN_CLAIM_INTERFACE_H
 #define TLSPUFFIN_CLAIM_INTERFACE_H
 
+/*
+ * Version 2 added the peer authentication, version 3 the unknown fields. Claimers must
+ * zero-initialize each Claim and set interface_version to the version whose fields they fill in.
+ */
+#define CLAIM_INTERFACE_VERSION 3
+
+/* Fields which a claimer can not supply, see unknown_fields */
+#define CLAIM_UNKNOWN_RANDOMS 1
+#define CLAIM_UNKNOWN_SESSION_ID 2
+#define CLAIM_UNKNOWN_MASTER_SECRET 4
+#define CLAIM_UNKNOWN_AVAILABLE_CIPHERS 8
+#define CLAIM_UNKNOWN_SIGNATURE_ALGORITHMS 16
+
 #define CLAIM_MAX_AVAILABLE_CIPHERS 128
 #define CLAIM_MAX_SECRET_SIZE 64 /* longest known is SHA512 */
 #define CLAIM_SESSION_ID_LENGTH 32
+#define CLAIM_MAX_CERTIFICATE_SIZE 4096
 
 typedef enum ClaimTLSVersion {
     CLAIM_TLS_VERSION_UNDEFINED = 0,
@@ -92,6 +106,11 @@ typedef struct ClaimSessionId {
     unsigned char data[CLAIM_SESSION_ID_LENGTH];
 } ClaimSessionId;
 
+typedef struct ClaimCertificate {
+    int length;
+    unsigned char data[CLAIM_MAX_CERTIFICATE_SIZE]; // DER encoded leaf certificate
+} ClaimCertificate;
+
 typedef struct ClaimTranscript {
     int length;
     unsigned char data[CLAIM_MAX_SECRET_SIZE]; // it contains a hash -> use CLAIM_MAX_SECRET_SIZE
@@ -152,6 +171,18 @@ typedef struct Claim {
 
     // Transcript
     ClaimTranscript transcript;
+
+    // Peer authentication, only set if interface_version >= 2
+    int authenticate_peer; // whether the peer was required to authenticate
+    ClaimCertificate peer_certificate;
+
+    // The version of the fields which the claimer filled in. Claims of claimers which leave it
+    // zero only contain the fields of version 1.
+    int interface_version;
+
+    // The CLAIM_UNKNOWN_* fields which the claimer can not supply, only set if
+    // interface_version >= 3. They are not compared with the claims of other agents.
+    int unknown_fields;
 } Claim;
 
 typedef void (*claim_t)(Claim claim, void *ctx);
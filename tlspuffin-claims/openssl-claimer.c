@@ -0,0 +1,473 @@
+/*
+ * Implements the claim-interface.h for OpenSSL 3 and BoringSSL on top of their public API. Claims
+ * are made when handshake messages are written or read, which is observed through the message
+ * callback.
+ *
+ * The transcripts are hashed from the handshake messages which pass the message callback, with
+ * the handshake digest of the cipher suite in the ServerHello. The TLS 1.3 secrets are taken from
+ * the key log callback, which exposes the application traffic and exporter secrets once they are
+ * derived, e.g. the client application traffic secret only after the Finished of the client. The
+ * early, handshake, master and resumption secrets are not exposed and stay zeroed, like the
+ * transcripts of DTLS. The TLS 1.3 master secret is claimed as unknown, such that it is not
+ * compared with the claims of other PUTs.
+ */
+
+#include <stdlib.h>
+#include <string.h>
+
+#include <openssl/evp.h>
+#include <openssl/ssl.h>
+#include <openssl/x509.h>
+
+#include "claim-interface.h"
+
+#define HANDSHAKE_HEADER_LENGTH 4
+#define RANDOM_LENGTH 32
+#define MESSAGE_HASH 254
+
+static const unsigned char HELLO_RETRY_REQUEST_RANDOM[RANDOM_LENGTH] = {
+    0xcf, 0x21, 0xad, 0x74, 0xe5, 0x9a, 0x61, 0x11, 0xbe, 0x1d, 0x8c, 0x02, 0x1e, 0x65, 0xb8, 0x91,
+    0xc2, 0xa2, 0x11, 0x16, 0x7a, 0xbb, 0x8c, 0x5e, 0x07, 0x9e, 0x09, 0xe2, 0xc8, 0xa8, 0x33, 0x9c,
+};
+
+/* The handshake messages of the current handshake, from the ClientHello on */
+typedef struct Transcript {
+    unsigned char *data;
+    size_t length;
+    size_t capacity;
+
+    /* The handshake digest of the negotiated cipher suite, NULL before the ServerHello */
+    const EVP_MD *md;
+    int hello_retry_request;
+    int finished_messages;
+} Transcript;
+
+typedef struct Claimer {
+    claim_t claimer;
+    void *ctx;
+
+    Transcript transcript;
+
+    ClaimSecret client_app_traffic_secret;
+    ClaimSecret server_app_traffic_secret;
+    ClaimSecret exporter_master_secret;
+    ClaimSecret early_exporter_master_secret;
+} Claimer;
+
+static int claimer_index = -1;
+
+static int get_claimer_index(void) {
+    if (claimer_index < 0) {
+        claimer_index = SSL_get_ex_new_index(0, NULL, NULL, NULL, NULL);
+    }
+    return claimer_index;
+}
+
+static TLSVersion claim_version(const SSL *ssl) {
+    switch (SSL_version(ssl)) {
+        case TLS1_3_VERSION:
+            return CLAIM_TLS_VERSION_V1_3;
+        case TLS1_2_VERSION:
+            return CLAIM_TLS_VERSION_V1_2;
+        default:
+            return CLAIM_TLS_VERSION_UNDEFINED;
+    }
+}
+
+/* Claims whether the peer is required to authenticate and the DER encoded peer certificate */
+static void fill_peer_authentication(SSL *ssl, Claim *claim) {
+    int verify_mode = SSL_get_verify_mode(ssl);
+    if (SSL_is_server(ssl)) {
+        claim->authenticate_peer = (verify_mode & SSL_VERIFY_PEER) &&
+                                   (verify_mode & SSL_VERIFY_FAIL_IF_NO_PEER_CERT);
+    } else {
+        claim->authenticate_peer = (verify_mode & SSL_VERIFY_PEER) != 0;
+    }
+
+    X509 *peer = SSL_get_peer_certificate(ssl);
+    if (peer == NULL) {
+        return;
+    }
+    unsigned char *der = NULL;
+    int length = i2d_X509(peer, &der);
+    if (length > 0) {
+        if (length > CLAIM_MAX_CERTIFICATE_SIZE) {
+            length = CLAIM_MAX_CERTIFICATE_SIZE;
+        }
+        memcpy(claim->peer_certificate.data, der, (size_t) length);
+        claim->peer_certificate.length = length;
+        OPENSSL_free(der);
+    }
+    X509_free(peer);
+}
+
+static void fill_claim(SSL *ssl, Claimer *claimer, Claim *claim) {
+    claim->interface_version = CLAIM_INTERFACE_VERSION;
+    claim->version.data = claim_version(ssl);
+    claim->server = SSL_is_server(ssl);
+
+    SSL_get_client_random(ssl, claim->client_random.data, CLAIM_SESSION_ID_LENGTH);
+    SSL_get_server_random(ssl, claim->server_random.data, CLAIM_SESSION_ID_LENGTH);
+
+    const SSL_SESSION *session = SSL_get_session(ssl);
+    if (session != NULL) {
+        unsigned int length = 0;
+        const unsigned char *id = SSL_SESSION_get_id(session, &length);
+        if (length > CLAIM_SESSION_ID_LENGTH) {
+            length = CLAIM_SESSION_ID_LENGTH;
+        }
+        memcpy(claim->session_id.data, id, length);
+        claim->session_id.length = (int) length;
+
+        // In TLS 1.3 this is the resumption secret instead of the master secret
+        if (SSL_version(ssl) != TLS1_3_VERSION) {
+            SSL_SESSION_get_master_key(session, claim->master_secret_12.secret,
+                                       CLAIM_MAX_SECRET_SIZE);
+        }
+    }
+    if (SSL_version(ssl) == TLS1_3_VERSION) {
+        claim->unknown_fields |= CLAIM_UNKNOWN_MASTER_SECRET;
+    }
+
+    const SSL_CIPHER *cipher = SSL_get_current_cipher(ssl);
+    if (cipher != NULL) {
+        claim->chosen_cipher.data = SSL_CIPHER_get_protocol_id(cipher);
+    }
+
+    STACK_OF(SSL_CIPHER) *ciphers = SSL_get_ciphers(ssl);
+    size_t length = ciphers == NULL ? 0 : (size_t) sk_SSL_CIPHER_num(ciphers);
+    if (length > CLAIM_MAX_AVAILABLE_CIPHERS) {
+        length = CLAIM_MAX_AVAILABLE_CIPHERS;
+    }
+    for (size_t i = 0; i < length; i++) {
+        claim->available_ciphers.ciphers[i].data =
+            SSL_CIPHER_get_protocol_id(sk_SSL_CIPHER_value(ciphers, i));
+    }
+    claim->available_ciphers.length = (int) length;
+
+#ifndef OPENSSL_IS_BORINGSSL
+    // BoringSSL only exposes the signature algorithm of the peer, which can not be compared to the
+    // one of the other agent
+    int nid;
+    if (SSL_get_signature_nid(ssl, &nid)) {
+        claim->signature_algorithm = nid;
+    }
+    if (SSL_get_peer_signature_nid(ssl, &nid)) {
+        claim->peer_signature_algorithm = nid;
+    }
+#else
+    claim->unknown_fields |= CLAIM_UNKNOWN_SIGNATURE_ALGORITHMS;
+#endif
+
+    fill_peer_authentication(ssl, claim);
+
+    claim->client_app_traffic_secret = claimer->client_app_traffic_secret;
+    claim->server_app_traffic_secret = claimer->server_app_traffic_secret;
+    claim->exporter_master_secret = claimer->exporter_master_secret;
+    claim->early_exporter_master_secret = claimer->early_exporter_master_secret;
+}
+
+static void make_claim(SSL *ssl, Claimer *claimer, ClaimType typ, int write_p,
+                       const ClaimTranscript *transcript) {
+    Claim claim;
+    memset(&claim, 0, sizeof(claim));
+
+    claim.typ = typ;
+    claim.write = write_p;
+    fill_claim(ssl, claimer, &claim);
+    if (transcript != NULL) {
+        claim.transcript = *transcript;
+    }
+
+    claimer->claimer(claim, claimer->ctx);
+}
+
+/* Looks up the handshake digest of the cipher suite in a ServerHello */
+static const EVP_MD *server_hello_md(SSL *ssl, const unsigned char *msg, size_t len) {
+    size_t offset = HANDSHAKE_HEADER_LENGTH + 2 + RANDOM_LENGTH;
+    if (len < offset + 1) {
+        return NULL;
+    }
+    offset += 1 + msg[offset];
+    if (len < offset + 2) {
+        return NULL;
+    }
+
+#ifdef OPENSSL_IS_BORINGSSL
+    const SSL_CIPHER *cipher = SSL_get_cipher_by_value((uint16_t) (msg[offset] << 8 | msg[offset + 1]));
+#else
+    const SSL_CIPHER *cipher = SSL_CIPHER_find(ssl, msg + offset);
+#endif
+    return cipher == NULL ? NULL : SSL_CIPHER_get_handshake_digest(cipher);
+}
+
+static int is_hello_retry_request(const unsigned char *msg, size_t len) {
+    return len >= HANDSHAKE_HEADER_LENGTH + 2 + RANDOM_LENGTH &&
+           memcmp(msg + HANDSHAKE_HEADER_LENGTH + 2, HELLO_RETRY_REQUEST_RANDOM, RANDOM_LENGTH) == 0;
+}
+
+static int transcript_append(Transcript *transcript, const unsigned char *data, size_t len) {
+    if (transcript->length + len > transcript->capacity) {
+        size_t capacity = transcript->capacity == 0 ? 4096 : transcript->capacity;
+        while (transcript->length + len > capacity) {
+            capacity *= 2;
+        }
+        unsigned char *grown = realloc(transcript->data, capacity);
+        if (grown == NULL) {
+            return 0;
+        }
+        transcript->data = grown;
+        transcript->capacity = capacity;
+    }
+
+    memcpy(transcript->data + transcript->length, data, len);
+    transcript->length += len;
+    return 1;
+}
+
+static void transcript_reset(Transcript *transcript) {
+    transcript->length = 0;
+    transcript->md = NULL;
+    transcript->hello_retry_request = 0;
+    transcript->finished_messages = 0;
+}
+
+static int transcript_hash(const Transcript *transcript, const unsigned char *data, size_t len,
+                           unsigned char *hash, unsigned int *hash_length) {
+    return transcript->md != NULL && EVP_MD_size(transcript->md) <= CLAIM_MAX_SECRET_SIZE &&
+           EVP_Digest(data, len, hash, hash_length, transcript->md, NULL);
+}
+
+/*
+ * Replaces the first ClientHello with a message_hash message after a HelloRetryRequest, see
+ * https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.1
+ */
+static int transcript_hash_client_hello(Transcript *transcript) {
+    unsigned char message_hash[HANDSHAKE_HEADER_LENGTH + CLAIM_MAX_SECRET_SIZE] = {MESSAGE_HASH};
+    unsigned int hash_length = 0;
+    if (!transcript_hash(transcript, transcript->data, transcript->length,
+                         message_hash + HANDSHAKE_HEADER_LENGTH, &hash_length)) {
+        return 0;
+    }
+    message_hash[3] = (unsigned char) hash_length;
+
+    transcript->length = 0;
+    return transcript_append(transcript, message_hash, HANDSHAKE_HEADER_LENGTH + hash_length);
+}
+
+static void claim_transcript(SSL *ssl, Claimer *claimer, ClaimType typ, int write_p) {
+    ClaimTranscript claim_transcript;
+    memset(&claim_transcript, 0, sizeof(claim_transcript));
+
+    unsigned int hash_length = 0;
+    if (transcript_hash(&claimer->transcript, claimer->transcript.data,
+                        claimer->transcript.length, claim_transcript.data, &hash_length)) {
+        claim_transcript.length = (int) hash_length;
+        make_claim(ssl, claimer, typ, write_p, &claim_transcript);
+    }
+}
+
+/* Adds a handshake message to the transcript and claims the transcript if it ends a phase */
+static void update_transcript(SSL *ssl, Claimer *claimer, int write_p, const unsigned char *msg,
+                              size_t len) {
+    Transcript *transcript = &claimer->transcript;
+    // The peer which sent the message
+    int from_server = write_p == SSL_is_server(ssl);
+
+    // The transcript of DTLS includes the fragmentation headers, which we do not see
+    if (SSL_is_dtls(ssl)) {
+        return;
+    }
+
+    switch (msg[0]) {
+        case SSL3_MT_CLIENT_HELLO:
+            // A new handshake starts, unless the ClientHello answers a HelloRetryRequest
+            if (!transcript->hello_retry_request) {
+                transcript_reset(transcript);
+            }
+            transcript->hello_retry_request = 0;
+            break;
+        case SSL3_MT_SERVER_HELLO:
+            transcript->md = server_hello_md(ssl, msg, len);
+            if (is_hello_retry_request(msg, len)) {
+                transcript->hello_retry_request = 1;
+                transcript_hash_client_hello(transcript);
+            }
+            break;
+        default:
+            // Messages after the handshake, e.g. NewSessionTicket in TLS 1.3, are not part of it
+            if (transcript->finished_messages >= 2) {
+                return;
+            }
+    }
+
+    if (!transcript_append(transcript, msg, len)) {
+        return;
+    }
+
+    switch (msg[0]) {
+        case SSL3_MT_CLIENT_HELLO:
+            claim_transcript(ssl, claimer, CLAIM_TRANSCRIPT_CH, write_p);
+            break;
+        case SSL3_MT_SERVER_HELLO:
+            if (!transcript->hello_retry_request) {
+                claim_transcript(ssl, claimer, CLAIM_TRANSCRIPT_CH_SH, write_p);
+            }
+            break;
+        case SSL3_MT_CERTIFICATE:
+            // The transcript which is signed by the CertificateVerify of the client
+            if (!from_server) {
+                claim_transcript(ssl, claimer, CLAIM_TRANSCRIPT_CH_CERT, write_p);
+            }
+            break;
+        case SSL3_MT_CERTIFICATE_VERIFY:
+            // The transcript which is covered by the Finished of an authenticated client
+            if (!from_server) {
+                claim_transcript(ssl, claimer, CLAIM_CERTIFICATE_VERIFY, write_p);
+            }
+            break;
+        case SSL3_MT_FINISHED:
+            transcript->finished_messages++;
+            claim_transcript(ssl, claimer,
+                             from_server ? CLAIM_TRANSCRIPT_CH_SERVER_FIN
+                                         : CLAIM_TRANSCRIPT_CH_CLIENT_FIN,
+                             write_p);
+            break;
+        default:
+            break;
+    }
+}
+
+static void msg_callback(int write_p, int version, int content_type, const void *buf, size_t len,
+                         SSL *ssl, void *arg) {
+    (void) version;
+    (void) arg;
+
+    Claimer *claimer = SSL_get_ex_data(ssl, get_claimer_index());
+    if (claimer == NULL || claimer->claimer == NULL) {
+        return;
+    }
+
+    if (content_type != SSL3_RT_HANDSHAKE || len < HANDSHAKE_HEADER_LENGTH) {
+        return;
+    }
+
+    const unsigned char *msg = buf;
+    update_transcript(ssl, claimer, write_p, msg, len);
+
+    switch (msg[0]) {
+        case SSL3_MT_CLIENT_HELLO:
+            make_claim(ssl, claimer, CLAIM_CLIENT_HELLO, write_p, NULL);
+            break;
+        case SSL3_MT_SERVER_HELLO:
+            make_claim(ssl, claimer, CLAIM_SERVER_HELLO, write_p, NULL);
+            break;
+        case SSL3_MT_FINISHED:
+            make_claim(ssl, claimer, CLAIM_FINISHED, write_p, NULL);
+            break;
+        default:
+            break;
+    }
+}
+
+static int hex_value(char c) {
+    if (c >= '0' && c <= '9') {
+        return c - '0';
+    }
+    if (c >= 'a' && c <= 'f') {
+        return c - 'a' + 10;
+    }
+    if (c >= 'A' && c <= 'F') {
+        return c - 'A' + 10;
+    }
+    return -1;
+}
+
+/* Decodes the hex encoded secret at the end of a key log line */
+static void parse_secret(const char *line, ClaimSecret *secret) {
+    const char *hex = strrchr(line, ' ');
+    if (hex == NULL) {
+        return;
+    }
+    hex++;
+
+    ClaimSecret parsed;
+    memset(&parsed, 0, sizeof(parsed));
+    size_t length = strlen(hex) / 2;
+    if (length > CLAIM_MAX_SECRET_SIZE) {
+        return;
+    }
+    for (size_t i = 0; i < length; i++) {
+        int high = hex_value(hex[2 * i]);
+        int low = hex_value(hex[2 * i + 1]);
+        if (high < 0 || low < 0) {
+            return;
+        }
+        parsed.secret[i] = (unsigned char) (high << 4 | low);
+    }
+
+    *secret = parsed;
+}
+
+static int has_label(const char *line, const char *label) {
+    size_t length = strlen(label);
+    return strncmp(line, label, length) == 0 && line[length] == ' ';
+}
+
+static void keylog_callback(const SSL *ssl, const char *line) {
+    Claimer *claimer = SSL_get_ex_data(ssl, get_claimer_index());
+    if (claimer == NULL) {
+        return;
+    }
+
+    if (has_label(line, "CLIENT_TRAFFIC_SECRET_0")) {
+        parse_secret(line, &claimer->client_app_traffic_secret);
+    } else if (has_label(line, "SERVER_TRAFFIC_SECRET_0")) {
+        parse_secret(line, &claimer->server_app_traffic_secret);
+    } else if (has_label(line, "EXPORTER_SECRET")) {
+        parse_secret(line, &claimer->exporter_master_secret);
+    } else if (has_label(line, "EARLY_EXPORTER_SECRET")) {
+        parse_secret(line, &claimer->early_exporter_master_secret);
+    }
+}
+
+static void free_claimer(Claimer *claimer) {
+    if (claimer != NULL) {
+        free(claimer->transcript.data);
+        free(claimer);
+    }
+}
+
+void register_claimer(const void *tls_like, claim_t claimer, void *ctx) {
+    SSL *ssl = (SSL *) tls_like;
+
+    free_claimer(SSL_get_ex_data(ssl, get_claimer_index()));
+
+    Claimer *new_claimer = calloc(1, sizeof(Claimer));
+    if (new_claimer == NULL) {
+        SSL_set_ex_data(ssl, get_claimer_index(), NULL);
+        return;
+    }
+    new_claimer->claimer = claimer;
+    new_claimer->ctx = ctx;
+
+    SSL_set_ex_data(ssl, get_claimer_index(), new_claimer);
+    SSL_set_msg_callback(ssl, msg_callback);
+    SSL_CTX_set_keylog_callback(SSL_get_SSL_CTX(ssl), keylog_callback);
+}
+
+void *deregister_claimer(const void *tls_like) {
+    SSL *ssl = (SSL *) tls_like;
+
+    Claimer *claimer = SSL_get_ex_data(ssl, get_claimer_index());
+    if (claimer == NULL) {
+        return NULL;
+    }
+
+    void *ctx = claimer->ctx;
+    SSL_set_ex_data(ssl, get_claimer_index(), NULL);
+    SSL_set_msg_callback(ssl, NULL);
+    free_claimer(claimer);
+
+    return ctx;
+}
@@ -9,8 +9,19 @@ pub type TLSLike = *const c_void;
 pub const CLAIM_INTERFACE_H: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/claim-interface.h"));
 
+/// Implements the claim-interface.h for OpenSSL 3 and BoringSSL on top of their public API
+pub const OPENSSL_CLAIMER_C: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/openssl-claimer.c"));
+
 include!(concat!(env!("OUT_DIR"), "/claim-interface.rs"));
 
+/// Returns the first `length` elements of `data`. The length is supplied by the PUT and is
+/// clamped to the bounds of `data`.
+pub fn bounded<T>(data: &[T], length: i32) -> &[T] {
+    let length = usize::try_from(length).unwrap_or(0).min(data.len());
+    &data[..length]
+}
+
 impl fmt::Display for Claim {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -47,6 +58,10 @@ impl fmt::Display for Claim {
             available_ciphers: {},\
             chosen_cipher: {},\
             transcript: {},\
+            authenticate_peer: {},\
+            peer_certificate: {},\
+            interface_version: {},\
+            unknown_fields: {},\
             ",
             self.typ,
             self.write,
@@ -79,6 +94,10 @@ impl fmt::Display for Claim {
             self.available_ciphers,
             self.chosen_cipher,
             self.transcript,
+            self.authenticate_peer,
+            self.peer_certificate,
+            self.interface_version,
+            self.unknown_fields,
         )
     }
 }
@@ -95,6 +114,12 @@ impl fmt::Display for ClaimTranscript {
     }
 }
 
+impl fmt::Display for ClaimCertificate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(bounded(&self.data, self.length)))
+    }
+}
+
 impl fmt::Display for ClaimCipher {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", hex::encode(self.data.to_be_bytes()))
@@ -103,7 +128,7 @@ impl fmt::Display for ClaimCipher {
 
 impl fmt::Display for ClaimSessionId {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", hex::encode(&self.data[0..self.length as usize]),)
+        write!(f, "{}", hex::encode(bounded(&self.data, self.length)),)
     }
 }
 
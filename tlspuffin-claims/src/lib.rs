@@ -4,7 +4,9 @@
 #![allow(unused)]
 
 pub use ffi::*;
-pub use register::{deregister_claimer, register_claimer};
+pub use register::{
+    deregister_claimer, deregister_claimer_with, register_claimer, register_claimer_with,
+};
 
 mod ffi;
 pub mod register;
@@ -2,7 +2,7 @@ use std::{ffi::c_void, mem};
 
 use crate::{
     ffi,
-    ffi::{Claim, TLSLike},
+    ffi::{claim_t, Claim, TLSLike},
 };
 
 extern "C" fn handle_claim_c(x: Claim, ctx: *mut c_void) {
@@ -12,24 +12,48 @@ extern "C" fn handle_claim_c(x: Claim, ctx: *mut c_void) {
 
 pub type Claimer = dyn FnMut(Claim);
 
+/// The `register_claimer` function of the claim-interface.h
+pub type RegisterClaimerFn = unsafe extern "C" fn(TLSLike, claim_t, *mut c_void);
+/// The `deregister_claimer` function of the claim-interface.h
+pub type DeregisterClaimerFn = unsafe extern "C" fn(TLSLike) -> *mut c_void;
+
 pub unsafe fn register_claimer<F>(ssl_like_ptr: TLSLike, claimer: F)
 where
     F: FnMut(Claim),
     F: 'static,
+{
+    register_claimer_with(ffi::register_claimer, ssl_like_ptr, claimer)
+}
+
+pub unsafe fn deregister_claimer(ssl_like_ptr: TLSLike) {
+    deregister_claimer_with(ffi::deregister_claimer, ssl_like_ptr)
+}
+
+/// Like [`register_claimer`], but with the claim-interface.h of a library which is not linked
+/// statically, e.g. one which was loaded at runtime.
+pub unsafe fn register_claimer_with<F>(
+    register: RegisterClaimerFn,
+    ssl_like_ptr: TLSLike,
+    claimer: F,
+) where
+    F: FnMut(Claim),
+    F: 'static,
 {
     let cb: Box<Box<Claimer>> = Box::new(Box::new(claimer));
 
-    ffi::register_claimer(
+    register(
         ssl_like_ptr,
         Some(handle_claim_c),
         Box::into_raw(cb) as *mut _,
     );
 }
 
-pub unsafe fn deregister_claimer(ssl_like_ptr: TLSLike) {
+/// Like [`deregister_claimer`], but with the claim-interface.h of a library which is not linked
+/// statically.
+pub unsafe fn deregister_claimer_with(deregister: DeregisterClaimerFn, ssl_like_ptr: TLSLike) {
     // drop the callback
     let _: Box<Box<Claimer>> = {
-        let ptr = ffi::deregister_claimer(ssl_like_ptr);
+        let ptr = deregister(ssl_like_ptr);
         let raw: Box<Box<Claimer>> = Box::from_raw(ptr as *mut _);
         raw
     };
@@ -11,9 +11,10 @@ use puffin::{
         state::StdState,
     },
     term,
-    trace::Trace,
+    trace::{Trace, TraceContext},
 };
 use tlspuffin::{
+    put_registry::TLS_PUT_REGISTRY,
     query::TlsQueryMatcher,
     tls::{fn_impl::*, seeds::*},
 };
@@ -159,11 +160,43 @@ fn benchmark_seeds(c: &mut Criterion) {
     group.finish()
 }
 
+fn benchmark_violations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("violations");
+
+    let mut bench_seed = |name: &str, trace: Trace<TlsQueryMatcher>| {
+        for check_violations_per_step in [false, true] {
+            let id = if check_violations_per_step {
+                format!("{} per step", name)
+            } else {
+                format!("{} at end", name)
+            };
+
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+                    ctx.set_deterministic(true);
+                    ctx.set_check_violations_per_step(check_violations_per_step);
+                    trace.execute(&mut ctx).unwrap();
+                })
+            });
+        }
+    };
+
+    bench_seed("seed_successful", seed_successful.build_trace());
+    bench_seed(
+        "seed_successful12_with_tickets",
+        seed_successful12_with_tickets.build_trace(),
+    );
+
+    group.finish()
+}
+
 criterion_group!(
     benches,
     benchmark_dynamic,
     benchmark_trace,
     benchmark_mutations,
     benchmark_seeds,
+    benchmark_violations,
 );
 criterion_main!(benches);
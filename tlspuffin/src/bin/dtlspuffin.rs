@@ -0,0 +1,7 @@
+use std::process::ExitCode;
+
+use tlspuffin::dtls::put_registry::DTLS_PUT_REGISTRY;
+
+pub fn main() -> ExitCode {
+    puffin::cli::main(&DTLS_PUT_REGISTRY)
+}
@@ -0,0 +1,34 @@
+use log::warn;
+
+/// BoringSSL is built with the deterministic RNG of its fuzzer mode. Resetting it makes the
+/// following executions produce the same random values.
+pub fn set_boringssl_deterministic() {
+    warn!("BoringSSL is no longer random!");
+    unsafe {
+        boringssl_sys::RAND_reset_for_fuzzing();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::boringssl::deterministic::set_boringssl_deterministic;
+
+    fn rand_bytes() -> [u8; 16] {
+        let mut buf = [0; 16];
+        assert_eq!(
+            unsafe { boringssl_sys::RAND_bytes(buf.as_mut_ptr(), buf.len()) },
+            1
+        );
+        buf
+    }
+
+    #[test]
+    fn test_boringssl_no_randomness() {
+        set_boringssl_deterministic();
+        let buf1 = rand_bytes();
+
+        set_boringssl_deterministic();
+        let buf2 = rand_bytes();
+        assert_eq!(buf1, buf2);
+    }
+}
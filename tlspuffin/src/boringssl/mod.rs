@@ -0,0 +1,540 @@
+//! A PUT for BoringSSL. BoringSSL is driven through a BIO pair: Inbound data is written to the
+//! external BIO, and the data which BoringSSL writes to the internal BIO is moved to the
+//! outbound channel of a [`MemoryStream`].
+
+use std::{
+    cell::RefCell,
+    ffi::CStr,
+    io::{Read, Write},
+    os::raw::{c_char, c_int},
+    ptr,
+    rc::Rc,
+};
+
+use boringssl_sys::{
+    BIO_ctrl_get_write_guarantee, BIO_free, BIO_new_bio_pair, BIO_new_mem_buf, BIO_read, BIO_write,
+    ERR_error_string_n, ERR_get_error, EVP_HPKE_KEY_free, EVP_HPKE_KEY_init, EVP_HPKE_KEY_new,
+    EVP_PKEY_free, EVP_hpke_x25519_hkdf_sha256, OpenSSL_version, PEM_read_bio_PrivateKey,
+    PEM_read_bio_X509, SSL_CTX_free, SSL_CTX_get_cert_store, SSL_CTX_new, SSL_CTX_set1_ech_keys,
+    SSL_CTX_set_max_proto_version, SSL_CTX_set_verify, SSL_CTX_use_PrivateKey,
+    SSL_CTX_use_certificate, SSL_ECH_KEYS_add, SSL_ECH_KEYS_free, SSL_ECH_KEYS_new, SSL_clear,
+    SSL_do_handshake, SSL_free, SSL_get_error, SSL_is_init_finished, SSL_new, SSL_read,
+    SSL_set_accept_state, SSL_set_bio, SSL_set_connect_state, SSL_state_string_long, TLS_method,
+    X509_STORE_add_cert, X509_free, BIO, OPENSSL_VERSION, SSL, SSL_CTX, SSL_ERROR_NONE,
+    SSL_ERROR_WANT_READ, SSL_ERROR_WANT_WRITE, SSL_ERROR_ZERO_RETURN,
+    SSL_VERIFY_FAIL_IF_NO_PEER_CERT, SSL_VERIFY_NONE, SSL_VERIFY_PEER, TLS1_2_VERSION,
+    TLS1_3_VERSION, X509,
+};
+use puffin::{
+    agent::{AgentDescriptor, AgentName, AgentType, TLSVersion},
+    codec::Codec,
+    error::Error,
+    protocol::MessageResult,
+    put::{Put, PutName},
+    put_registry::Factory,
+    stream::{MemoryStream, Stream},
+    trace::TraceContext,
+};
+
+use crate::{
+    protocol::TLSProtocolBehavior,
+    put::{TlsPutConfig, TlsPutOptions},
+    put_registry::BORINGSSL_PUT,
+    static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT},
+    tls::{
+        fn_impl::{fn_new_ech_config, ECH_PRIVATE_KEY},
+        rustls::msgs::{
+            deframer::MessageDeframer,
+            message::{Message, OpaqueMessage},
+        },
+    },
+};
+
+#[cfg(feature = "deterministic")]
+mod deterministic;
+
+/// The size of the buffers of the BIO pair. This is large enough to hold all the messages which
+/// are added to the inbound channel before the agent progresses.
+const BIO_PAIR_SIZE: usize = 1 << 20;
+
+pub fn new_boringssl_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
+    struct BoringSSLFactory;
+    impl Factory<TLSProtocolBehavior> for BoringSSLFactory {
+        fn create(
+            &self,
+            context: &TraceContext<TLSProtocolBehavior>,
+            agent_descriptor: &AgentDescriptor,
+        ) -> Result<Box<dyn Put<TLSProtocolBehavior>>, Error> {
+            let options = TlsPutOptions::unsupported(
+                &context.put_descriptor(agent_descriptor).options,
+                "BoringSSL",
+            )?;
+
+            let config = TlsPutConfig {
+                descriptor: agent_descriptor.clone(),
+                claims: context.claims().clone(),
+                authenticate_peer: agent_descriptor.typ == AgentType::Client
+                    && agent_descriptor.server_authentication
+                    || agent_descriptor.typ == AgentType::Server
+                        && agent_descriptor.client_authentication,
+                extract_deferred: Rc::new(RefCell::new(None)),
+                options,
+            };
+            Ok(Box::new(BoringSSL::new(config)?))
+        }
+
+        fn name(&self) -> PutName {
+            BORINGSSL_PUT
+        }
+
+        fn version(&self) -> String {
+            BoringSSL::version()
+        }
+    }
+
+    Box::new(BoringSSLFactory)
+}
+
+pub struct BoringSSL {
+    ctx: *mut SSL_CTX,
+    ssl: *mut SSL,
+    /// The end of the BIO pair which is not owned by `ssl`
+    external_bio: *mut BIO,
+    stream: MemoryStream<MessageDeframer>,
+    config: TlsPutConfig,
+}
+
+impl Drop for BoringSSL {
+    fn drop(&mut self) {
+        #[cfg(feature = "claims")]
+        self.deregister_claimer();
+
+        unsafe {
+            SSL_free(self.ssl);
+            BIO_free(self.external_bio);
+            SSL_CTX_free(self.ctx);
+        }
+    }
+}
+
+impl Stream<Message, OpaqueMessage> for BoringSSL {
+    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) -> Result<(), Error> {
+        <MemoryStream<MessageDeframer> as Stream<Message, OpaqueMessage>>::add_to_inbound(
+            &mut self.stream,
+            opaque_message,
+        )
+    }
+
+    fn take_message_from_outbound(
+        &mut self,
+    ) -> Result<Option<MessageResult<Message, OpaqueMessage>>, Error> {
+        MemoryStream::take_message_from_outbound(&mut self.stream)
+    }
+}
+
+impl Put<TLSProtocolBehavior> for BoringSSL {
+    fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
+        self.transfer_inbound()?;
+
+        let result = unsafe {
+            if self.is_state_successful() {
+                // Trigger another read
+                let mut buffer = [0u8; 128];
+                SSL_read(self.ssl, buffer.as_mut_ptr().cast(), buffer.len() as c_int)
+            } else {
+                SSL_do_handshake(self.ssl)
+            }
+        };
+        let result = self.check_result(result);
+
+        // BoringSSL writes an alert before failing, which should be sent in any case
+        self.transfer_outbound()?;
+        result
+    }
+
+    fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
+        unsafe {
+            if SSL_clear(self.ssl) != 1 {
+                return Err(Error::Put(last_error()));
+            }
+
+            // Discard the data which is still buffered in the BIO pair
+            let external_bio = attach_bio_pair(self.ssl)?;
+            BIO_free(self.external_bio);
+            self.external_bio = external_bio;
+
+            set_handshake_state(self.ssl, self.config.descriptor.typ);
+        }
+
+        Ok(())
+    }
+
+    fn descriptor(&self) -> &AgentDescriptor {
+        &self.config.descriptor
+    }
+
+    #[cfg(feature = "claims")]
+    fn register_claimer(&mut self, agent_name: AgentName) {
+        use crate::claims::{to_claim_data, TlsClaim};
+
+        let claims = self.config.claims.clone();
+        let protocol_version = self.config.descriptor.tls_version;
+        let origin = self.config.descriptor.typ;
+
+        unsafe {
+            security_claims::register_claimer(
+                self.ssl.cast(),
+                move |claim: security_claims::Claim| {
+                    if let Some(data) = to_claim_data(protocol_version, claim) {
+                        claims.deref_borrow_mut().claim_sized(TlsClaim {
+                            agent_name,
+                            origin,
+                            protocol_version,
+                            data,
+                        })
+                    }
+                },
+            );
+        }
+    }
+
+    #[cfg(feature = "claims")]
+    fn deregister_claimer(&mut self) {
+        unsafe {
+            security_claims::deregister_claimer(self.ssl.cast());
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn rename_agent(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        {
+            self.deregister_claimer();
+            self.register_claimer(agent_name);
+        }
+        Ok(())
+    }
+
+    fn describe_state(&self) -> &str {
+        // The states are static strings
+        unsafe { CStr::from_ptr(SSL_state_string_long(self.ssl)) }
+            .to_str()
+            .unwrap_or("Unknown")
+    }
+
+    fn is_state_successful(&self) -> bool {
+        unsafe { SSL_is_init_finished(self.ssl) == 1 }
+    }
+
+    fn set_deterministic(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "deterministic")]
+        {
+            deterministic::set_boringssl_deterministic();
+            Ok(())
+        }
+        #[cfg(not(feature = "deterministic"))]
+        {
+            Err(Error::Agent(
+                "Unable to make BoringSSL deterministic!".to_string(),
+            ))
+        }
+    }
+
+    fn shutdown(&mut self) -> String {
+        panic!("Unsupported with BoringSSL PUT")
+    }
+
+    fn version() -> String {
+        unsafe { CStr::from_ptr(OpenSSL_version(OPENSSL_VERSION as c_int)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl BoringSSL {
+    fn new(config: TlsPutConfig) -> Result<BoringSSL, Error> {
+        let descriptor = &config.descriptor;
+
+        let (ctx, ssl, external_bio) = unsafe {
+            let ctx = Self::create_context(descriptor)?;
+
+            let ssl = SSL_new(ctx);
+            if ssl.is_null() {
+                SSL_CTX_free(ctx);
+                return Err(Error::Put(last_error()));
+            }
+
+            let external_bio = match attach_bio_pair(ssl) {
+                Ok(external_bio) => external_bio,
+                Err(err) => {
+                    SSL_free(ssl);
+                    SSL_CTX_free(ctx);
+                    return Err(err);
+                }
+            };
+
+            set_handshake_state(ssl, descriptor.typ);
+            (ctx, ssl, external_bio)
+        };
+
+        #[cfg(feature = "claims")]
+        let agent_name = descriptor.name;
+
+        let mut boringssl = BoringSSL {
+            ctx,
+            ssl,
+            external_bio,
+            stream: MemoryStream::new(MessageDeframer::new()),
+            config,
+        };
+
+        #[cfg(feature = "claims")]
+        boringssl.register_claimer(agent_name);
+
+        Ok(boringssl)
+    }
+
+    /// Creates a context with the same certificates as the other PUTs
+    unsafe fn create_context(descriptor: &AgentDescriptor) -> Result<*mut SSL_CTX, Error> {
+        let ctx = SSL_CTX_new(TLS_method());
+        if ctx.is_null() {
+            return Err(Error::Put(last_error()));
+        }
+
+        let result = Self::configure_context(ctx, descriptor);
+        if result.is_err() {
+            SSL_CTX_free(ctx);
+        }
+        result.map(|_| ctx)
+    }
+
+    unsafe fn configure_context(
+        ctx: *mut SSL_CTX,
+        descriptor: &AgentDescriptor,
+    ) -> Result<(), Error> {
+        let max_version = match descriptor.tls_version {
+            TLSVersion::V1_3 => TLS1_3_VERSION,
+            TLSVersion::V1_2 => TLS1_2_VERSION,
+            version => {
+                return Err(Error::Put(format!(
+                    "{:?} is not supported by BoringSSL",
+                    version
+                )))
+            }
+        };
+        check(SSL_CTX_set_max_proto_version(ctx, max_version as u16))?;
+
+        let (cert_and_key, trusted_certs, authenticate_peer) = match descriptor.typ {
+            AgentType::Server => (
+                Some((ALICE_CERT.0, ALICE_PRIVATE_KEY.0)),
+                [BOB_CERT.0, EVE_CERT.0],
+                descriptor.client_authentication,
+            ),
+            AgentType::Client => (
+                if descriptor.client_authentication {
+                    Some((BOB_CERT.0, BOB_PRIVATE_KEY.0))
+                } else {
+                    None
+                },
+                [ALICE_CERT.0, EVE_CERT.0],
+                descriptor.server_authentication,
+            ),
+        };
+
+        if let Some((cert, key)) = cert_and_key {
+            let cert = read_pem(cert, |bio| {
+                PEM_read_bio_X509(bio, ptr::null_mut(), None, ptr::null_mut())
+            })?;
+            let result = check(SSL_CTX_use_certificate(ctx, cert));
+            X509_free(cert);
+            result?;
+
+            let key = read_pem(key, |bio| {
+                PEM_read_bio_PrivateKey(bio, ptr::null_mut(), None, ptr::null_mut())
+            })?;
+            let result = check(SSL_CTX_use_PrivateKey(ctx, key));
+            EVP_PKEY_free(key);
+            result?;
+        }
+
+        if authenticate_peer {
+            let store = SSL_CTX_get_cert_store(ctx);
+            for trusted_cert in trusted_certs {
+                let cert: *mut X509 = read_pem(trusted_cert, |bio| {
+                    PEM_read_bio_X509(bio, ptr::null_mut(), None, ptr::null_mut())
+                })?;
+                let result = check(X509_STORE_add_cert(store, cert));
+                X509_free(cert);
+                result?;
+            }
+
+            SSL_CTX_set_verify(
+                ctx,
+                (SSL_VERIFY_PEER | SSL_VERIFY_FAIL_IF_NO_PEER_CERT) as c_int,
+                None,
+            );
+        } else {
+            SSL_CTX_set_verify(ctx, SSL_VERIFY_NONE as c_int, None);
+        }
+
+        if descriptor.typ == AgentType::Server && descriptor.tls_version == TLSVersion::V1_3 {
+            Self::set_ech_keys(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Configures the ECH key pair of the attacker, such that the server accepts the
+    /// ClientHelloInners of [`crate::tls::seeds::seed_client_attacker_ech`]
+    unsafe fn set_ech_keys(ctx: *mut SSL_CTX) -> Result<(), Error> {
+        let ech_config = fn_new_ech_config()?.get_encoding();
+
+        let key = EVP_HPKE_KEY_new();
+        if key.is_null() {
+            return Err(Error::Put(last_error()));
+        }
+        let keys = SSL_ECH_KEYS_new();
+        if keys.is_null() {
+            EVP_HPKE_KEY_free(key);
+            return Err(Error::Put(last_error()));
+        }
+
+        let result = check(EVP_HPKE_KEY_init(
+            key,
+            EVP_hpke_x25519_hkdf_sha256(),
+            ECH_PRIVATE_KEY.as_ptr(),
+            ECH_PRIVATE_KEY.len(),
+        ))
+        .and_then(|_| {
+            check(SSL_ECH_KEYS_add(
+                keys,
+                1,
+                ech_config.as_ptr(),
+                ech_config.len(),
+                key,
+            ))
+        })
+        .and_then(|_| check(SSL_CTX_set1_ech_keys(ctx, keys)));
+
+        // The context holds its own references
+        SSL_ECH_KEYS_free(keys);
+        EVP_HPKE_KEY_free(key);
+        result
+    }
+
+    /// Moves the inbound data to the external BIO, from which BoringSSL reads
+    fn transfer_inbound(&mut self) -> Result<(), Error> {
+        let mut buffer = [0u8; 4096];
+        loop {
+            let space = unsafe { BIO_ctrl_get_write_guarantee(self.external_bio) };
+            if space == 0 {
+                return Ok(());
+            }
+
+            let length = space.min(buffer.len());
+            let length = match self.stream.read(&mut buffer[..length]) {
+                Ok(length) => length,
+                // No more inbound data
+                Err(_) => return Ok(()),
+            };
+
+            let written =
+                unsafe { BIO_write(self.external_bio, buffer.as_ptr().cast(), length as c_int) };
+            if written != length as c_int {
+                return Err(Error::Put("Failed to write to the BIO pair".to_string()));
+            }
+        }
+    }
+
+    /// Moves the data which BoringSSL wrote to the outbound channel
+    fn transfer_outbound(&mut self) -> Result<(), Error> {
+        let mut buffer = [0u8; 4096];
+        loop {
+            let length = unsafe {
+                BIO_read(
+                    self.external_bio,
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len() as c_int,
+                )
+            };
+            if length <= 0 {
+                return Ok(());
+            }
+            self.stream.write_all(&buffer[..length as usize])?;
+        }
+    }
+
+    fn check_result(&self, result: c_int) -> Result<(), Error> {
+        match unsafe { SSL_get_error(self.ssl, result) } as u32 {
+            // Reaching the end of the inbound data is not an error
+            SSL_ERROR_NONE | SSL_ERROR_WANT_READ | SSL_ERROR_WANT_WRITE | SSL_ERROR_ZERO_RETURN => {
+                Ok(())
+            }
+            // BoringSSL threw an error, that means that there should be an Alert message in the
+            // outbound channel
+            _ => Err(Error::Put(last_error())),
+        }
+    }
+}
+
+/// Creates a BIO pair, of which one end is owned by `ssl`. The other end is returned.
+unsafe fn attach_bio_pair(ssl: *mut SSL) -> Result<*mut BIO, Error> {
+    let mut internal_bio = ptr::null_mut();
+    let mut external_bio = ptr::null_mut();
+    check(BIO_new_bio_pair(
+        &mut internal_bio,
+        BIO_PAIR_SIZE,
+        &mut external_bio,
+        BIO_PAIR_SIZE,
+    ))?;
+
+    // Takes the ownership of the internal BIO, which is used for reading and writing. The
+    // previous BIO of `ssl` is freed.
+    SSL_set_bio(ssl, internal_bio, internal_bio);
+    Ok(external_bio)
+}
+
+unsafe fn set_handshake_state(ssl: *mut SSL, typ: AgentType) {
+    match typ {
+        AgentType::Server => SSL_set_accept_state(ssl),
+        AgentType::Client => SSL_set_connect_state(ssl),
+    }
+}
+
+unsafe fn read_pem<T>(pem: &str, read: impl FnOnce(*mut BIO) -> *mut T) -> Result<*mut T, Error> {
+    let bio = BIO_new_mem_buf(pem.as_ptr().cast(), pem.len() as _);
+    if bio.is_null() {
+        return Err(Error::Put(last_error()));
+    }
+    let value = read(bio);
+    BIO_free(bio);
+
+    if value.is_null() {
+        Err(Error::Put(last_error()))
+    } else {
+        Ok(value)
+    }
+}
+
+fn check(result: c_int) -> Result<(), Error> {
+    if result == 1 {
+        Ok(())
+    } else {
+        Err(Error::Put(last_error()))
+    }
+}
+
+/// Formats the earliest error in the error queue of BoringSSL
+fn last_error() -> String {
+    let mut buffer = [0 as c_char; 256];
+    unsafe {
+        let error = ERR_get_error();
+        if error == 0 {
+            return "Unknown BoringSSL error".to_string();
+        }
+        ERR_error_string_n(error, buffer.as_mut_ptr(), buffer.len());
+        CStr::from_ptr(buffer.as_ptr())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
@@ -6,57 +6,58 @@ use puffin::{
     claims::Claim,
     variable_data::VariableData,
 };
+use security_claims::bounded;
 use smallvec::SmallVec;
 
 #[derive(Debug, Clone)]
 pub struct TlsTranscript(pub [u8; 64], pub i32);
 
+impl TlsTranscript {
+    fn as_slice(&self) -> &[u8] {
+        bounded(&self.0, self.1)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TranscriptClientHello(pub TlsTranscript);
 impl Transcript for TranscriptClientHello {
     fn as_slice(&self) -> &[u8] {
-        let transcript = &self.0;
-        &transcript.0[..transcript.1 as usize]
+        self.0.as_slice()
     }
 }
 #[derive(Debug, Clone)]
 pub struct TranscriptPartialClientHello(pub TlsTranscript);
 impl Transcript for TranscriptPartialClientHello {
     fn as_slice(&self) -> &[u8] {
-        let transcript = &self.0;
-        &transcript.0[..transcript.1 as usize]
+        self.0.as_slice()
     }
 }
 #[derive(Debug, Clone)]
 pub struct TranscriptServerHello(pub TlsTranscript);
 impl Transcript for TranscriptServerHello {
     fn as_slice(&self) -> &[u8] {
-        let transcript = &self.0;
-        &transcript.0[..transcript.1 as usize]
+        self.0.as_slice()
     }
 }
 #[derive(Debug, Clone)]
 pub struct TranscriptServerFinished(pub TlsTranscript);
 impl Transcript for TranscriptServerFinished {
     fn as_slice(&self) -> &[u8] {
-        let transcript = &self.0;
-        &transcript.0[..transcript.1 as usize]
+        self.0.as_slice()
     }
 }
 #[derive(Debug, Clone)]
 pub struct TranscriptClientFinished(pub TlsTranscript);
 impl Transcript for TranscriptClientFinished {
     fn as_slice(&self) -> &[u8] {
-        let transcript = &self.0;
-        &transcript.0[..transcript.1 as usize]
+        self.0.as_slice()
     }
 }
 #[derive(Debug, Clone)]
 pub struct TranscriptCertificate(pub TlsTranscript);
 impl Transcript for TranscriptCertificate {
     fn as_slice(&self) -> &[u8] {
-        let transcript = &self.0;
-        &transcript.0[..transcript.1 as usize]
+        self.0.as_slice()
     }
 }
 
@@ -72,13 +73,15 @@ pub struct ServerHello;
 pub struct Certificate;
 #[derive(Debug, Clone)]
 pub struct CertificateVerify;
+/// The fields which are `None` are unknown, because the PUT can not supply them. They are not
+/// compared with the claims of other agents.
 #[derive(Debug, Clone)]
 pub struct Finished {
     pub outbound: bool,
 
-    pub client_random: SmallVec<[u8; 32]>,
-    pub server_random: SmallVec<[u8; 32]>,
-    pub session_id: SmallVec<[u8; 32]>,
+    pub client_random: Option<SmallVec<[u8; 32]>>,
+    pub server_random: Option<SmallVec<[u8; 32]>>,
+    pub session_id: Option<SmallVec<[u8; 32]>>,
 
     pub authenticate_peer: bool,
     /// DER encoded certificate. DER works, because:
@@ -86,13 +89,13 @@ pub struct Finished {
     ///     (https://en.wikipedia.org/wiki/X.690#DER_encoding)
     pub peer_certificate: SmallVec<[u8; 32]>,
 
-    pub master_secret: SmallVec<[u8; 32]>,
+    pub master_secret: Option<SmallVec<[u8; 32]>>,
 
     pub chosen_cipher: u16,
-    pub available_ciphers: SmallVec<[u16; 20]>,
+    pub available_ciphers: Option<SmallVec<[u16; 20]>>,
 
-    pub signature_algorithm: i32,
-    pub peer_signature_algorithm: i32,
+    pub signature_algorithm: Option<i32>,
+    pub peer_signature_algorithm: Option<i32>,
     /* TODO: tmp_skey_type peer_tmp_skey_type
                    // TLS 1.2
                    if let Some(server_kex) = claims.iter().find(|(_agent, claim)| {
@@ -119,6 +122,25 @@ pub struct Finished {
     */
 }
 
+/// Claimed when an EndOfEarlyData is sent or processed, i.e. 0-RTT data was accepted.
+#[derive(Debug, Clone)]
+pub struct EndOfEarlyData {
+    pub outbound: bool,
+
+    /// The early secret which is derived from the PSK
+    pub early_secret: SmallVec<[u8; 32]>,
+}
+
+/// Claimed when a handshake completes which renegotiated a previous handshake on the same
+/// connection.
+#[derive(Debug, Clone)]
+pub struct Renegotiation {
+    /// The verify_data of the Finished of the client in the previous handshake
+    pub previous_client_verify_data: SmallVec<[u8; 32]>,
+    /// The verify_data of the Finished of the server in the previous handshake
+    pub previous_server_verify_data: SmallVec<[u8; 32]>,
+}
+
 #[derive(Debug, Clone)]
 pub enum ClaimDataTranscript {
     ClientHello(TranscriptClientHello),
@@ -136,6 +158,8 @@ pub enum ClaimDataMessage {
     Certificate(Certificate),
     CertificateVerify(CertificateVerify),
     Finished(Finished),
+    EndOfEarlyData(EndOfEarlyData),
+    Renegotiation(Renegotiation),
 }
 
 #[derive(Debug, Clone)]
@@ -144,6 +168,151 @@ pub enum ClaimData {
     Message(ClaimDataMessage),
 }
 
+/// Whether the claimer of the library filled in the peer authentication of `claim`, which was
+/// added in version 2 of the claim-interface.h
+pub fn reports_peer_authentication(claim: &security_claims::Claim) -> bool {
+    claim.interface_version >= 2
+}
+
+/// Whether the claimer of the library supplied the `CLAIM_UNKNOWN_*` field of `claim`. Unknown
+/// fields were added in version 3 of the claim-interface.h.
+pub fn is_known(claim: &security_claims::Claim, field: u32) -> bool {
+    claim.interface_version < 3 || claim.unknown_fields as u32 & field == 0
+}
+
+/// Converts a claim of a library which implements the claim-interface.h into [`ClaimData`]
+pub fn to_claim_data(
+    protocol_version: TLSVersion,
+    claim: security_claims::Claim,
+) -> Option<ClaimData> {
+    match claim.typ {
+        // Transcripts
+        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH => Some(ClaimData::Transcript(
+            ClaimDataTranscript::ClientHello(TranscriptClientHello(TlsTranscript(
+                claim.transcript.data,
+                claim.transcript.length,
+            ))),
+        )),
+        security_claims::ClaimType::CLAIM_TRANSCRIPT_PARTIAL_CH => Some(ClaimData::Transcript(
+            ClaimDataTranscript::PartialClientHello(TranscriptPartialClientHello(TlsTranscript(
+                claim.transcript.data,
+                claim.transcript.length,
+            ))),
+        )),
+        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_SH => Some(ClaimData::Transcript(
+            ClaimDataTranscript::ServerHello(TranscriptServerHello(TlsTranscript(
+                claim.transcript.data,
+                claim.transcript.length,
+            ))),
+        )),
+        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_SERVER_FIN => Some(ClaimData::Transcript(
+            ClaimDataTranscript::ServerFinished(TranscriptServerFinished(TlsTranscript(
+                claim.transcript.data,
+                claim.transcript.length,
+            ))),
+        )),
+        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_CLIENT_FIN => Some(ClaimData::Transcript(
+            ClaimDataTranscript::ClientFinished(TranscriptClientFinished(TlsTranscript(
+                claim.transcript.data,
+                claim.transcript.length,
+            ))),
+        )),
+        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_CERT => Some(ClaimData::Transcript(
+            ClaimDataTranscript::Certificate(TranscriptCertificate(TlsTranscript(
+                claim.transcript.data,
+                claim.transcript.length,
+            ))),
+        )),
+        // Messages
+        // Transcripts in these messages are not up-to-date. They get updated after the Message has
+        // been processed
+        security_claims::ClaimType::CLAIM_FINISHED => {
+            let known = |field| is_known(&claim, field);
+            Some(ClaimData::Message(ClaimDataMessage::Finished(Finished {
+                outbound: claim.write > 0,
+                client_random: known(security_claims::CLAIM_UNKNOWN_RANDOMS)
+                    .then(|| SmallVec::from(claim.client_random.data)),
+                server_random: known(security_claims::CLAIM_UNKNOWN_RANDOMS)
+                    .then(|| SmallVec::from(claim.server_random.data)),
+                session_id: known(security_claims::CLAIM_UNKNOWN_SESSION_ID).then(|| {
+                    SmallVec::from_slice(bounded(&claim.session_id.data, claim.session_id.length))
+                }),
+                authenticate_peer: reports_peer_authentication(&claim)
+                    && claim.authenticate_peer > 0,
+                peer_certificate: if reports_peer_authentication(&claim) {
+                    SmallVec::from_slice(bounded(
+                        &claim.peer_certificate.data,
+                        claim.peer_certificate.length,
+                    ))
+                } else {
+                    SmallVec::new()
+                },
+                master_secret: known(security_claims::CLAIM_UNKNOWN_MASTER_SECRET).then(|| {
+                    match protocol_version {
+                        TLSVersion::V1_3 => SmallVec::from_slice(&claim.master_secret.secret),
+                        TLSVersion::V1_2
+                        | TLSVersion::V1_1
+                        | TLSVersion::V1_0
+                        | TLSVersion::SSL3 => SmallVec::from_slice(&claim.master_secret_12.secret),
+                    }
+                }),
+                chosen_cipher: claim.chosen_cipher.data,
+                available_ciphers: known(security_claims::CLAIM_UNKNOWN_AVAILABLE_CIPHERS).then(
+                    || {
+                        SmallVec::from_iter(
+                            bounded(
+                                &claim.available_ciphers.ciphers,
+                                claim.available_ciphers.length,
+                            )
+                            .iter()
+                            .map(|cipher| cipher.data),
+                        )
+                    },
+                ),
+                signature_algorithm: known(security_claims::CLAIM_UNKNOWN_SIGNATURE_ALGORITHMS)
+                    .then(|| claim.signature_algorithm),
+                peer_signature_algorithm: known(
+                    security_claims::CLAIM_UNKNOWN_SIGNATURE_ALGORITHMS,
+                )
+                .then(|| claim.peer_signature_algorithm),
+            })))
+        }
+        security_claims::ClaimType::CLAIM_CLIENT_HELLO => None,
+        security_claims::ClaimType::CLAIM_CCS => None,
+        security_claims::ClaimType::CLAIM_END_OF_EARLY_DATA => Some(ClaimData::Message(
+            ClaimDataMessage::EndOfEarlyData(EndOfEarlyData {
+                outbound: claim.write > 0,
+                early_secret: SmallVec::from_slice(&claim.early_secret.secret),
+            }),
+        )),
+        security_claims::ClaimType::CLAIM_CERTIFICATE => None,
+        security_claims::ClaimType::CLAIM_KEY_EXCHANGE => None,
+        // FIXME it is weird that this returns the correct transcript
+        security_claims::ClaimType::CLAIM_CERTIFICATE_VERIFY => {
+            if claim.write == 0 {
+                Some(ClaimData::Transcript(ClaimDataTranscript::ServerFinished(
+                    TranscriptServerFinished(TlsTranscript(
+                        claim.transcript.data,
+                        claim.transcript.length,
+                    )),
+                )))
+            } else {
+                None
+            }
+        }
+        security_claims::ClaimType::CLAIM_KEY_UPDATE => None,
+        security_claims::ClaimType::CLAIM_HELLO_REQUEST => None,
+        security_claims::ClaimType::CLAIM_SERVER_HELLO => None,
+        security_claims::ClaimType::CLAIM_CERTIFICATE_REQUEST => None,
+        security_claims::ClaimType::CLAIM_SERVER_DONE => None,
+        security_claims::ClaimType::CLAIM_SESSION_TICKET => None,
+        security_claims::ClaimType::CLAIM_CERTIFICATE_STATUS => None,
+        security_claims::ClaimType::CLAIM_EARLY_DATA => None,
+        security_claims::ClaimType::CLAIM_ENCRYPTED_EXTENSIONS => None,
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TlsClaim {
     pub agent_name: AgentName,
@@ -168,6 +337,8 @@ impl Claim for TlsClaim {
                 Message::Certificate(_) => Type::of::<Certificate>(),
                 Message::CertificateVerify(_) => Type::of::<CertificateVerify>(),
                 Message::Finished(_) => Type::of::<Finished>(),
+                Message::EndOfEarlyData(_) => Type::of::<EndOfEarlyData>(),
+                Message::Renegotiation(_) => Type::of::<Renegotiation>(),
             },
             ClaimData::Transcript(transcript) => match transcript {
                 Transcript::ClientHello(_) => Type::of::<TranscriptClientHello>(),
@@ -191,6 +362,8 @@ impl Claim for TlsClaim {
                 Message::Certificate(claim) => claim.boxed_any(),
                 Message::CertificateVerify(claim) => claim.boxed_any(),
                 Message::Finished(claim) => claim.boxed_any(),
+                Message::EndOfEarlyData(claim) => claim.boxed_any(),
+                Message::Renegotiation(claim) => claim.boxed_any(),
             },
             ClaimData::Transcript(transcript) => match transcript {
                 Transcript::ClientHello(claim) => claim.boxed_any(),
@@ -203,3 +376,102 @@ impl Claim for TlsClaim {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use puffin::agent::TLSVersion;
+
+    use crate::claims::{
+        to_claim_data, ClaimData, ClaimDataMessage, ClaimDataTranscript, Finished, Transcript,
+    };
+
+    fn finished_claim(length: i32) -> security_claims::Claim {
+        let mut claim = security_claims::Claim {
+            typ: security_claims::ClaimType::CLAIM_FINISHED,
+            interface_version: security_claims::CLAIM_INTERFACE_VERSION as i32,
+            ..security_claims::Claim::default()
+        };
+        claim.session_id.data = [1; 32];
+        claim.session_id.length = length;
+        claim.peer_certificate.data[0] = 2;
+        claim.peer_certificate.length = length;
+        claim.available_ciphers.ciphers[0].data = 0x1301;
+        claim.available_ciphers.length = length;
+        claim
+    }
+
+    fn to_finished(claim: security_claims::Claim) -> Finished {
+        match to_claim_data(TLSVersion::V1_3, claim) {
+            Some(ClaimData::Message(ClaimDataMessage::Finished(finished))) => finished,
+            data => panic!("Expected a Finished claim, got {:?}", data),
+        }
+    }
+
+    #[test]
+    fn test_to_claim_data_finished() {
+        let finished = to_finished(finished_claim(1));
+        assert_eq!(finished.session_id.unwrap().as_slice(), &[1]);
+        assert_eq!(finished.peer_certificate.as_slice(), &[2]);
+        assert_eq!(finished.available_ciphers.unwrap().as_slice(), &[0x1301]);
+        assert!(finished.master_secret.is_some());
+    }
+
+    #[test]
+    fn test_to_claim_data_unknown_fields() {
+        let mut claim = finished_claim(1);
+        claim.unknown_fields = (security_claims::CLAIM_UNKNOWN_MASTER_SECRET
+            | security_claims::CLAIM_UNKNOWN_SIGNATURE_ALGORITHMS)
+            as i32;
+
+        let finished = to_finished(claim);
+        assert!(finished.master_secret.is_none());
+        assert!(finished.signature_algorithm.is_none());
+        assert!(finished.peer_signature_algorithm.is_none());
+        assert!(finished.client_random.is_some());
+        assert!(finished.session_id.is_some());
+        assert!(finished.available_ciphers.is_some());
+
+        // Claimers of older versions do not know about unknown fields
+        claim.interface_version = 2;
+        assert!(to_finished(claim).master_secret.is_some());
+    }
+
+    #[test]
+    fn test_to_claim_data_version_1() {
+        let mut claim = finished_claim(1);
+        claim.interface_version = 1;
+        claim.authenticate_peer = 1;
+
+        let finished = to_finished(claim);
+        assert!(!finished.authenticate_peer);
+        assert!(finished.peer_certificate.is_empty());
+    }
+
+    #[test]
+    fn test_to_claim_data_oversized_length() {
+        let finished = to_finished(finished_claim(i32::MAX));
+        assert_eq!(finished.session_id.unwrap().len(), 32);
+        assert_eq!(finished.peer_certificate.len(), 4096);
+        assert_eq!(finished.available_ciphers.unwrap().len(), 128);
+
+        let finished = to_finished(finished_claim(-1));
+        assert!(finished.session_id.unwrap().is_empty());
+        assert!(finished.peer_certificate.is_empty());
+        assert!(finished.available_ciphers.unwrap().is_empty());
+
+        let claim = security_claims::Claim {
+            typ: security_claims::ClaimType::CLAIM_TRANSCRIPT_CH,
+            transcript: security_claims::ClaimTranscript {
+                length: 1000,
+                ..security_claims::ClaimTranscript::default()
+            },
+            ..security_claims::Claim::default()
+        };
+        match to_claim_data(TLSVersion::V1_3, claim) {
+            Some(ClaimData::Transcript(ClaimDataTranscript::ClientHello(transcript))) => {
+                assert_eq!(transcript.as_slice().len(), 64)
+            }
+            data => panic!("Expected a ClientHello transcript, got {:?}", data),
+        }
+    }
+}
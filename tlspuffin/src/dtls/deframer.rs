@@ -0,0 +1,119 @@
+use std::{collections::VecDeque, io};
+
+use puffin::codec;
+
+use crate::dtls::message::DtlsOpaqueMessage;
+
+/// The maximum size of a UDP datagram
+const MAX_DATAGRAM_SIZE: usize = u16::MAX as usize;
+
+/// This deframer works to reconstruct DTLS records from datagrams.
+/// The input is `read()`, the output is the `frames` deque.
+///
+/// In contrast to [`crate::tls::rustls::msgs::deframer::MessageDeframer`], records are not
+/// buffered across reads. A datagram can contain several records, but a record never spans
+/// several datagrams. Decoding each datagram on its own also bounds DTLS 1.3 records, which omit
+/// their length, to the end of their datagram.
+pub struct DtlsMessageDeframer {
+    /// Completed frames for output.
+    pub frames: VecDeque<DtlsOpaqueMessage>,
+
+    /// Set to true if a datagram contained data which is not a DTLS record. The remaining data of
+    /// the datagram is dropped.
+    pub desynced: bool,
+}
+
+impl Default for DtlsMessageDeframer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DtlsMessageDeframer {
+    pub fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            desynced: false,
+        }
+    }
+
+    /// Read a single datagram from `rd` and decode all of its records.
+    pub fn read(&mut self, rd: &mut dyn io::Read) -> io::Result<usize> {
+        let mut datagram = vec![0u8; MAX_DATAGRAM_SIZE];
+        let new_bytes = rd.read(&mut datagram)?;
+
+        self.read_datagram(&datagram[..new_bytes]);
+
+        Ok(new_bytes)
+    }
+
+    /// Decode all records of a datagram.
+    pub fn read_datagram(&mut self, datagram: &[u8]) {
+        let mut rd = codec::Reader::init(datagram);
+
+        while rd.any_left() {
+            match DtlsOpaqueMessage::read(&mut rd) {
+                Ok(m) => self.frames.push_back(m),
+                Err(_) => {
+                    self.desynced = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Returns true if we have messages for the caller to process.
+    pub fn has_pending(&self) -> bool {
+        !self.frames.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::DtlsMessageDeframer;
+
+    /// Two records which are written back to back into the same datagram
+    const TWO_RECORDS: &[u8] = &[
+        0x15, 0xfe, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x01, 0x00,
+        0x14, 0xfe, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x01,
+    ];
+
+    #[test]
+    fn test_two_records() {
+        let mut deframer = DtlsMessageDeframer::new();
+        deframer.read(&mut io::Cursor::new(TWO_RECORDS)).unwrap();
+        assert!(!deframer.desynced);
+        assert_eq!(deframer.frames.len(), 2);
+        assert_eq!(deframer.frames[0].sequence_number, 1);
+        assert_eq!(deframer.frames[1].sequence_number, 2);
+    }
+
+    #[test]
+    fn test_truncated_record() {
+        let mut deframer = DtlsMessageDeframer::new();
+        deframer
+            .read(&mut io::Cursor::new(&TWO_RECORDS[..8]))
+            .unwrap();
+        assert!(deframer.desynced);
+        assert!(deframer.frames.is_empty());
+        assert!(!deframer.has_pending());
+    }
+
+    #[test]
+    fn test_unified_header_without_length() {
+        // C=0, S=0, L=0, epoch bits = 2, followed by a DTLSPlaintext in the next datagram
+        let ciphertext = [0x22, 0x07, 0xaa, 0xbb];
+
+        let mut deframer = DtlsMessageDeframer::new();
+        deframer.read_datagram(&ciphertext);
+        deframer.read_datagram(&TWO_RECORDS[..15]);
+        assert!(!deframer.desynced);
+        assert_eq!(deframer.frames.len(), 2);
+        assert_eq!(deframer.frames[0].epoch, 2);
+        assert_eq!(deframer.frames[0].sequence_number, 7);
+        assert_eq!(deframer.frames[0].payload.0, vec![0xaa, 0xbb]);
+        assert_eq!(deframer.frames[1].sequence_number, 1);
+    }
+}
@@ -0,0 +1,65 @@
+#![allow(clippy::ptr_arg)]
+#![allow(dead_code)]
+
+use puffin::algebra::error::FnError;
+
+use crate::{
+    dtls::message::{DtlsCookie, RecordNumber},
+    tls::rustls::msgs::{
+        enums::ProtocolVersion,
+        handshake::{ClientExtension, ProtocolVersions, ServerExtension},
+    },
+};
+
+pub fn fn_dtls_protocol_version10() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::DTLSv1_0)
+}
+
+pub fn fn_dtls_protocol_version12() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::DTLSv1_2)
+}
+
+pub fn fn_dtls_protocol_version13() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::DTLSv1_3)
+}
+
+pub fn fn_dtls_empty_cookie() -> Result<DtlsCookie, FnError> {
+    Ok(DtlsCookie(vec![]))
+}
+
+pub fn fn_dtls_new_cookie(cookie: &Vec<u8>) -> Result<DtlsCookie, FnError> {
+    Ok(DtlsCookie(cookie.clone()))
+}
+
+pub fn fn_dtls_record_number(epoch: &u64, sequence_number: &u64) -> Result<RecordNumber, FnError> {
+    Ok(RecordNumber {
+        epoch: *epoch,
+        sequence_number: *sequence_number,
+    })
+}
+
+pub fn fn_dtls_record_numbers_new() -> Result<Vec<RecordNumber>, FnError> {
+    Ok(vec![])
+}
+
+pub fn fn_dtls_record_numbers_append(
+    record_numbers: &Vec<RecordNumber>,
+    record_number: &RecordNumber,
+) -> Result<Vec<RecordNumber>, FnError> {
+    let mut new_record_numbers = record_numbers.clone();
+    new_record_numbers.push(*record_number);
+    Ok(new_record_numbers)
+}
+
+/// SupportedVersions => 0x002b, offering DTLS 1.3
+pub fn fn_dtls_supported_versions13_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::SupportedVersions(ProtocolVersions(vec![
+        ProtocolVersion::DTLSv1_3,
+    ])))
+}
+
+pub fn fn_dtls_supported_versions13_server_extension() -> Result<ServerExtension, FnError> {
+    Ok(ServerExtension::SupportedVersions(
+        ProtocolVersion::DTLSv1_3,
+    ))
+}
@@ -0,0 +1,187 @@
+#![allow(clippy::ptr_arg)]
+#![allow(dead_code)]
+
+//! DTLS records and handshake messages. Records are constructed explicitly with an epoch and a
+//! record sequence number, handshake messages with a message sequence number.
+
+use puffin::algebra::error::FnError;
+
+use crate::{
+    dtls::message::{
+        AckPayload, DtlsClientHelloPayload, DtlsCookie, DtlsHandshakeFragment,
+        DtlsHandshakeMessagePayload, DtlsHandshakePayload, DtlsMessage, DtlsMessagePayload,
+        DtlsOpaqueMessage, HelloVerifyRequestPayload, RecordNumber,
+    },
+    tls::rustls::msgs::{
+        alert::AlertMessagePayload,
+        base::Payload,
+        ccs::ChangeCipherSpecPayload,
+        enums::*,
+        handshake::{ClientExtension, Random, SessionID},
+        message::{Message, MessagePayload},
+    },
+};
+
+/// Records of DTLS 1.3 also use the version of DTLS 1.2, because the `legacy_record_version` of a
+/// DTLSPlaintext is fixed to {254, 253} (RFC 9147, Section 4). Encrypted records of DTLS 1.3 use
+/// the unified header, which has no version, and are forwarded with [`fn_dtls_opaque_record`].
+fn new_record(epoch: &u64, sequence_number: &u64, payload: DtlsMessagePayload) -> DtlsMessage {
+    DtlsMessage {
+        version: ProtocolVersion::DTLSv1_2,
+        epoch: *epoch as u16,
+        sequence_number: *sequence_number,
+        unified_header: None,
+        payload,
+    }
+}
+
+pub fn fn_dtls_opaque_record(message: &DtlsOpaqueMessage) -> Result<DtlsOpaqueMessage, FnError> {
+    Ok(message.clone())
+}
+
+// ----
+// Records
+// ----
+
+pub fn fn_dtls_handshake_record(
+    epoch: &u64,
+    sequence_number: &u64,
+    handshake: &DtlsHandshakeMessagePayload,
+) -> Result<DtlsMessage, FnError> {
+    Ok(new_record(
+        epoch,
+        sequence_number,
+        DtlsMessagePayload::Handshake(handshake.clone()),
+    ))
+}
+
+pub fn fn_dtls_change_cipher_spec(
+    epoch: &u64,
+    sequence_number: &u64,
+) -> Result<DtlsMessage, FnError> {
+    Ok(new_record(
+        epoch,
+        sequence_number,
+        DtlsMessagePayload::ChangeCipherSpec(ChangeCipherSpecPayload),
+    ))
+}
+
+pub fn fn_dtls_alert_close_notify(
+    epoch: &u64,
+    sequence_number: &u64,
+) -> Result<DtlsMessage, FnError> {
+    Ok(new_record(
+        epoch,
+        sequence_number,
+        DtlsMessagePayload::Alert(AlertMessagePayload {
+            level: AlertLevel::Warning,
+            description: AlertDescription::CloseNotify,
+        }),
+    ))
+}
+
+pub fn fn_dtls_application_data(
+    epoch: &u64,
+    sequence_number: &u64,
+    data: &Vec<u8>,
+) -> Result<DtlsMessage, FnError> {
+    Ok(new_record(
+        epoch,
+        sequence_number,
+        DtlsMessagePayload::ApplicationData(Payload::new(data.clone())),
+    ))
+}
+
+pub fn fn_dtls_ack(
+    epoch: &u64,
+    sequence_number: &u64,
+    record_numbers: &Vec<RecordNumber>,
+) -> Result<DtlsMessage, FnError> {
+    Ok(new_record(
+        epoch,
+        sequence_number,
+        DtlsMessagePayload::Ack(AckPayload(record_numbers.clone())),
+    ))
+}
+
+// ----
+// Handshake messages
+// ----
+
+pub fn fn_dtls_client_hello(
+    message_seq: &u64,
+    client_version: &ProtocolVersion,
+    random: &Random,
+    session_id: &SessionID,
+    cookie: &DtlsCookie,
+    cipher_suites: &Vec<CipherSuite>,
+    compression_methods: &Vec<Compression>,
+    extensions: &Vec<ClientExtension>,
+) -> Result<DtlsHandshakeMessagePayload, FnError> {
+    Ok(DtlsHandshakeMessagePayload {
+        typ: HandshakeType::ClientHello,
+        message_seq: *message_seq as u16,
+        payload: DtlsHandshakePayload::ClientHello(DtlsClientHelloPayload {
+            client_version: *client_version,
+            random: *random,
+            session_id: *session_id,
+            cookie: cookie.clone(),
+            cipher_suites: cipher_suites.clone(),
+            compression_methods: compression_methods.clone(),
+            extensions: extensions.clone(),
+        }),
+    })
+}
+
+pub fn fn_dtls_hello_verify_request(
+    message_seq: &u64,
+    server_version: &ProtocolVersion,
+    cookie: &DtlsCookie,
+) -> Result<DtlsHandshakeMessagePayload, FnError> {
+    Ok(DtlsHandshakeMessagePayload {
+        typ: HandshakeType::HelloVerifyRequest,
+        message_seq: *message_seq as u16,
+        payload: DtlsHandshakePayload::HelloVerifyRequest(HelloVerifyRequestPayload {
+            server_version: *server_version,
+            cookie: cookie.clone(),
+        }),
+    })
+}
+
+/// Wraps the handshake message of a TLS [`Message`] in a DTLS handshake message.
+pub fn fn_dtls_handshake(
+    message_seq: &u64,
+    message: &Message,
+) -> Result<DtlsHandshakeMessagePayload, FnError> {
+    match &message.payload {
+        MessagePayload::Handshake(handshake) => Ok(DtlsHandshakeMessagePayload {
+            typ: handshake.typ,
+            message_seq: *message_seq as u16,
+            payload: DtlsHandshakePayload::Tls(handshake.payload.clone()),
+        }),
+        _ => Err(FnError::Unknown(
+            "Only handshake messages can be wrapped in a DTLS handshake message".to_string(),
+        )),
+    }
+}
+
+/// Cuts the fragment `[offset, offset + length)` out of the body of a handshake message.
+pub fn fn_dtls_fragment(
+    handshake: &DtlsHandshakeMessagePayload,
+    offset: &u64,
+    length: &u64,
+) -> Result<DtlsHandshakeMessagePayload, FnError> {
+    let (typ, body) = handshake.encode_body();
+    let start = (*offset as usize).min(body.len());
+    let end = start.saturating_add(*length as usize).min(body.len());
+
+    Ok(DtlsHandshakeMessagePayload {
+        typ,
+        message_seq: handshake.message_seq,
+        payload: DtlsHandshakePayload::Fragment(DtlsHandshakeFragment {
+            length: body.len() as u32,
+            fragment_offset: start as u32,
+            body: Payload::new(&body[start..end]),
+        }),
+    })
+}
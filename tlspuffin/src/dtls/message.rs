@@ -0,0 +1,695 @@
+//! DTLS records and handshake messages according to
+//! [RFC 6347](https://datatracker.ietf.org/doc/html/rfc6347) and
+//! [RFC 9147](https://datatracker.ietf.org/doc/html/rfc9147).
+//!
+//! Compared to TLS, each record carries an epoch and a sequence number and each handshake message
+//! carries a message sequence number and fragmentation information. Most handshake bodies are
+//! shared with TLS and are therefore decoded using the codec in [`crate::tls::rustls::msgs`].
+
+use std::convert::TryFrom;
+
+use puffin::{
+    codec,
+    codec::{Codec, Reader},
+};
+
+use crate::tls::rustls::{
+    error::Error,
+    msgs::{
+        alert::AlertMessagePayload,
+        base::Payload,
+        ccs::ChangeCipherSpecPayload,
+        enums::{CipherSuite, Compression, ContentType, HandshakeType, ProtocolVersion},
+        handshake::{
+            ClientExtension, HandshakeMessagePayload, HandshakePayload, Random, SessionID,
+        },
+        message::MessageError,
+    },
+};
+
+/// Bits of the first byte of a DTLS 1.3 unified header: `0|0|1|C|S|L|E|E`
+const UNIFIED_HEADER_MASK: u8 = 0b1110_0000;
+const UNIFIED_HEADER_FIXED_BITS: u8 = 0b0010_0000;
+const UNIFIED_HEADER_CID_BIT: u8 = 0b0001_0000;
+const UNIFIED_HEADER_SEQ16_BIT: u8 = 0b0000_1000;
+const UNIFIED_HEADER_LENGTH_BIT: u8 = 0b0000_0100;
+const UNIFIED_HEADER_EPOCH_BITS: u8 = 0b0000_0011;
+
+fn read_u48(r: &mut Reader) -> Option<u64> {
+    let bytes = r.take(6)?;
+    let mut buf = [0u8; 8];
+    buf[2..].copy_from_slice(bytes);
+    Some(u64::from_be_bytes(buf))
+}
+
+fn encode_u48(v: u64, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&v.to_be_bytes()[2..]);
+}
+
+/// A DTLS record, named DTLSPlaintext or DTLSCiphertext in the standards.
+///
+/// In contrast to [`crate::tls::rustls::msgs::message::OpaqueMessage`] this includes the epoch and
+/// the 48-bit sequence number of the record.
+#[derive(Clone, Debug)]
+pub struct DtlsOpaqueMessage {
+    pub typ: ContentType,
+    pub version: ProtocolVersion,
+    pub epoch: u16,
+    pub sequence_number: u64,
+    /// The first byte of the unified header of a DTLS 1.3 DTLSCiphertext. If set, then only the
+    /// low bits of the epoch and the sequence number are written, and neither the type nor the
+    /// version are written.
+    pub unified_header: Option<u8>,
+    pub payload: Payload,
+}
+
+impl Codec for DtlsOpaqueMessage {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&DtlsOpaqueMessage::encode(self.clone()));
+    }
+
+    fn read(reader: &mut Reader) -> Option<Self> {
+        Self::read(reader).ok()
+    }
+}
+
+impl DtlsOpaqueMessage {
+    /// `MessageError` allows callers to distinguish between valid prefixes (might
+    /// become valid if we read more data) and invalid data.
+    pub fn read(r: &mut Reader) -> Result<Self, MessageError> {
+        let first = *r
+            .peek(1)
+            .and_then(|first| first.first())
+            .ok_or(MessageError::TooShortForHeader)?;
+
+        if first & UNIFIED_HEADER_MASK == UNIFIED_HEADER_FIXED_BITS {
+            return Self::read_ciphertext(r);
+        }
+
+        let typ = ContentType::read(r).ok_or(MessageError::TooShortForHeader)?;
+        let version = ProtocolVersion::read(r).ok_or(MessageError::TooShortForHeader)?;
+        let epoch = u16::read(r).ok_or(MessageError::TooShortForHeader)?;
+        let sequence_number = read_u48(r).ok_or(MessageError::TooShortForHeader)?;
+        let len = u16::read(r).ok_or(MessageError::TooShortForHeader)?;
+
+        // Reject undersize messages
+        if typ != ContentType::ApplicationData && len == 0 {
+            return Err(MessageError::IllegalLength);
+        }
+
+        // Reject oversize messages
+        if len >= Self::MAX_PAYLOAD {
+            return Err(MessageError::IllegalLength);
+        }
+
+        // Don't accept any new content-types.
+        if let ContentType::Unknown(_) = typ {
+            return Err(MessageError::IllegalContentType);
+        }
+
+        // Accept only versions 0xFEXX for any XX.
+        match version {
+            ProtocolVersion::Unknown(ref v) if (v & 0xff00) != 0xfe00 => {
+                return Err(MessageError::IllegalProtocolVersion);
+            }
+            _ => {}
+        };
+
+        let mut sub = r.sub(len as usize).ok_or(MessageError::TooShortForLength)?;
+        let payload = Payload::read(&mut sub);
+
+        Ok(Self {
+            typ,
+            version,
+            epoch,
+            sequence_number,
+            unified_header: None,
+            payload,
+        })
+    }
+
+    /// Reads a DTLS 1.3 DTLSCiphertext. Connection IDs are not supported. If the length is omitted,
+    /// then the record spans the rest of its datagram. Therefore, `r` must be bounded to a single
+    /// datagram, see [`crate::dtls::deframer::DtlsMessageDeframer`].
+    fn read_ciphertext(r: &mut Reader) -> Result<Self, MessageError> {
+        let flags = u8::read(r).ok_or(MessageError::TooShortForHeader)?;
+
+        if flags & UNIFIED_HEADER_CID_BIT != 0 {
+            return Err(MessageError::IllegalContentType);
+        }
+
+        let sequence_number = if flags & UNIFIED_HEADER_SEQ16_BIT != 0 {
+            u16::read(r).ok_or(MessageError::TooShortForHeader)? as u64
+        } else {
+            u8::read(r).ok_or(MessageError::TooShortForHeader)? as u64
+        };
+
+        let payload = if flags & UNIFIED_HEADER_LENGTH_BIT != 0 {
+            let len = u16::read(r).ok_or(MessageError::TooShortForHeader)?;
+
+            if len >= Self::MAX_PAYLOAD {
+                return Err(MessageError::IllegalLength);
+            }
+
+            let mut sub = r.sub(len as usize).ok_or(MessageError::TooShortForLength)?;
+            Payload::read(&mut sub)
+        } else {
+            Payload::read(r)
+        };
+
+        Ok(Self {
+            typ: ContentType::ApplicationData,
+            version: ProtocolVersion::DTLSv1_2,
+            epoch: (flags & UNIFIED_HEADER_EPOCH_BITS) as u16,
+            sequence_number,
+            unified_header: Some(flags),
+            payload,
+        })
+    }
+
+    pub fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self.unified_header {
+            Some(flags) => {
+                flags.encode(&mut buf);
+                if flags & UNIFIED_HEADER_SEQ16_BIT != 0 {
+                    (self.sequence_number as u16).encode(&mut buf);
+                } else {
+                    (self.sequence_number as u8).encode(&mut buf);
+                }
+                if flags & UNIFIED_HEADER_LENGTH_BIT != 0 {
+                    (self.payload.0.len() as u16).encode(&mut buf);
+                }
+            }
+            None => {
+                self.typ.encode(&mut buf);
+                self.version.encode(&mut buf);
+                self.epoch.encode(&mut buf);
+                encode_u48(self.sequence_number, &mut buf);
+                (self.payload.0.len() as u16).encode(&mut buf);
+            }
+        }
+        self.payload.encode(&mut buf);
+        buf
+    }
+
+    /// This is the maximum on-the-wire size of a DTLSCiphertext.
+    /// That's 2^14 payload bytes, a header, and a 2KB allowance
+    /// for ciphertext overheads.
+    const MAX_PAYLOAD: u16 = 16384 + 2048;
+
+    /// Content type, version, epoch, sequence number and size.
+    const HEADER_SIZE: u16 = 1 + 2 + 2 + 6 + 2;
+
+    /// Maximum on-wire message size.
+    pub const MAX_WIRE_SIZE: usize = (Self::MAX_PAYLOAD + Self::HEADER_SIZE) as usize;
+}
+
+/// A DTLS record with decoded payload
+#[derive(Debug, Clone)]
+pub struct DtlsMessage {
+    pub version: ProtocolVersion,
+    pub epoch: u16,
+    pub sequence_number: u64,
+    /// See [`DtlsOpaqueMessage::unified_header`]
+    pub unified_header: Option<u8>,
+    pub payload: DtlsMessagePayload,
+}
+
+impl Codec for DtlsMessage {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&DtlsOpaqueMessage::from(self.clone()).encode());
+    }
+
+    fn read(reader: &mut Reader) -> Option<Self> {
+        DtlsOpaqueMessage::read(reader)
+            .ok()
+            .and_then(|opaque| DtlsMessage::try_from(opaque).ok())
+    }
+}
+
+impl From<DtlsMessage> for DtlsOpaqueMessage {
+    fn from(msg: DtlsMessage) -> Self {
+        let typ = msg.payload.content_type();
+        let payload = match msg.payload {
+            DtlsMessagePayload::ApplicationData(payload) => payload,
+            _ => {
+                let mut buf = Vec::new();
+                msg.payload.encode(&mut buf);
+                Payload(buf)
+            }
+        };
+
+        Self {
+            typ,
+            version: msg.version,
+            epoch: msg.epoch,
+            sequence_number: msg.sequence_number,
+            unified_header: msg.unified_header,
+            payload,
+        }
+    }
+}
+
+/// Parses a DTLS record into a well-typed [`DtlsMessage`]. Handshake records of an epoch greater
+/// than zero are encrypted and can not be parsed.
+impl TryFrom<DtlsOpaqueMessage> for DtlsMessage {
+    type Error = Error;
+
+    fn try_from(opaque: DtlsOpaqueMessage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            version: opaque.version,
+            epoch: opaque.epoch,
+            sequence_number: opaque.sequence_number,
+            unified_header: opaque.unified_header,
+            payload: DtlsMessagePayload::new(
+                opaque.typ,
+                opaque.version,
+                opaque.epoch,
+                opaque.payload,
+            )?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DtlsMessagePayload {
+    Alert(AlertMessagePayload),
+    Handshake(DtlsHandshakeMessagePayload),
+    // this type is for DTLS 1.2 handshake messages of an epoch greater than zero
+    EncryptedHandshake(Payload),
+    ChangeCipherSpec(ChangeCipherSpecPayload),
+    ApplicationData(Payload),
+    Ack(AckPayload),
+}
+
+impl DtlsMessagePayload {
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        match *self {
+            Self::Alert(ref x) => x.encode(bytes),
+            Self::Handshake(ref x) => x.encode(bytes),
+            Self::EncryptedHandshake(ref x) => x.encode(bytes),
+            Self::ChangeCipherSpec(ref x) => x.encode(bytes),
+            Self::ApplicationData(ref x) => x.encode(bytes),
+            Self::Ack(ref x) => x.encode(bytes),
+        }
+    }
+
+    pub fn new(
+        typ: ContentType,
+        vers: ProtocolVersion,
+        epoch: u16,
+        payload: Payload,
+    ) -> Result<Self, Error> {
+        let mut r = Reader::init(&payload.0);
+        let parsed = match typ {
+            ContentType::ApplicationData => return Ok(Self::ApplicationData(payload)),
+            ContentType::Handshake if epoch > 0 => {
+                return Ok(Self::EncryptedHandshake(payload));
+            }
+            ContentType::Alert => AlertMessagePayload::read(&mut r).map(Self::Alert),
+            ContentType::Handshake => {
+                DtlsHandshakeMessagePayload::read_version(&mut r, vers).map(Self::Handshake)
+            }
+            ContentType::ChangeCipherSpec => {
+                ChangeCipherSpecPayload::read(&mut r).map(Self::ChangeCipherSpec)
+            }
+            ContentType::Ack => AckPayload::read(&mut r).map(Self::Ack),
+            _ => None,
+        };
+
+        parsed.ok_or_else(|| Error::corrupt_message(typ))
+    }
+
+    pub fn content_type(&self) -> ContentType {
+        match self {
+            Self::Alert(_) => ContentType::Alert,
+            Self::Handshake(_) => ContentType::Handshake,
+            Self::EncryptedHandshake(_) => ContentType::Handshake,
+            Self::ChangeCipherSpec(_) => ContentType::ChangeCipherSpec,
+            Self::ApplicationData(_) => ContentType::ApplicationData,
+            Self::Ack(_) => ContentType::Ack,
+        }
+    }
+}
+
+/// A DTLS handshake message. The `length`, `fragment_offset` and `fragment_length` fields of the
+/// header are derived from the payload while encoding.
+#[derive(Debug, Clone)]
+pub struct DtlsHandshakeMessagePayload {
+    pub typ: HandshakeType,
+    pub message_seq: u16,
+    pub payload: DtlsHandshakePayload,
+}
+
+#[derive(Debug, Clone)]
+pub enum DtlsHandshakePayload {
+    ClientHello(DtlsClientHelloPayload),
+    HelloVerifyRequest(HelloVerifyRequestPayload),
+    /// Handshake messages which have the same body as in TLS
+    Tls(HandshakePayload),
+    /// A fragment of a handshake message which has not been reassembled
+    Fragment(DtlsHandshakeFragment),
+}
+
+impl Codec for DtlsHandshakeMessagePayload {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let (typ, length, fragment_offset, body) = match &self.payload {
+            DtlsHandshakePayload::Fragment(fragment) => (
+                self.typ,
+                fragment.length,
+                fragment.fragment_offset,
+                fragment.body.0.clone(),
+            ),
+            _ => {
+                let (typ, body) = self.encode_body();
+                (typ, body.len() as u32, 0, body)
+            }
+        };
+
+        typ.encode(bytes);
+        codec::u24(length).encode(bytes);
+        self.message_seq.encode(bytes);
+        codec::u24(fragment_offset).encode(bytes);
+        codec::u24(body.len() as u32).encode(bytes);
+        bytes.extend_from_slice(&body);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        Self::read_version(r, ProtocolVersion::DTLSv1_2)
+    }
+}
+
+impl DtlsHandshakeMessagePayload {
+    pub fn read_version(r: &mut Reader, vers: ProtocolVersion) -> Option<Self> {
+        let typ = HandshakeType::read(r)?;
+        let length = codec::u24::read(r)?.0;
+        let message_seq = u16::read(r)?;
+        let fragment_offset = codec::u24::read(r)?.0;
+        let fragment_length = codec::u24::read(r)?.0;
+        let mut sub = r.sub(fragment_length as usize)?;
+
+        if fragment_offset != 0 || fragment_length != length {
+            return Some(Self {
+                typ,
+                message_seq,
+                payload: DtlsHandshakePayload::Fragment(DtlsHandshakeFragment {
+                    length,
+                    fragment_offset,
+                    body: Payload::read(&mut sub),
+                }),
+            });
+        }
+
+        let (typ, payload) = match typ {
+            HandshakeType::ClientHello => (
+                typ,
+                DtlsHandshakePayload::ClientHello(DtlsClientHelloPayload::read(&mut sub)?),
+            ),
+            HandshakeType::HelloVerifyRequest => (
+                typ,
+                DtlsHandshakePayload::HelloVerifyRequest(HelloVerifyRequestPayload::read(
+                    &mut sub,
+                )?),
+            ),
+            _ => {
+                // Reuse the TLS codec by replacing the DTLS header with a TLS header
+                let mut tls_encoding = Vec::new();
+                typ.encode(&mut tls_encoding);
+                codec::u24(length).encode(&mut tls_encoding);
+                tls_encoding.extend_from_slice(sub.rest());
+
+                let tls_version = match vers {
+                    ProtocolVersion::DTLSv1_3 => ProtocolVersion::TLSv1_3,
+                    _ => ProtocolVersion::TLSv1_2,
+                };
+                let handshake = HandshakeMessagePayload::read_version(
+                    &mut Reader::init(&tls_encoding),
+                    tls_version,
+                )?;
+                (handshake.typ, DtlsHandshakePayload::Tls(handshake.payload))
+            }
+        };
+
+        if sub.any_left() {
+            None
+        } else {
+            Some(Self {
+                typ,
+                message_seq,
+                payload,
+            })
+        }
+    }
+
+    /// Encodes the body of a complete handshake message and returns the handshake type which
+    /// appears on the wire.
+    pub fn encode_body(&self) -> (HandshakeType, Vec<u8>) {
+        match &self.payload {
+            DtlsHandshakePayload::ClientHello(x) => (self.typ, x.get_encoding()),
+            DtlsHandshakePayload::HelloVerifyRequest(x) => (self.typ, x.get_encoding()),
+            DtlsHandshakePayload::Tls(x) => {
+                let tls_encoding = HandshakeMessagePayload {
+                    typ: self.typ,
+                    payload: x.clone(),
+                }
+                .get_encoding();
+                // Strip the TLS handshake header
+                (
+                    HandshakeType::from(tls_encoding[0]),
+                    tls_encoding[4..].to_vec(),
+                )
+            }
+            DtlsHandshakePayload::Fragment(x) => (self.typ, x.body.0.clone()),
+        }
+    }
+}
+
+/// A fragment of a handshake message. `length` is the length of the complete message.
+#[derive(Debug, Clone)]
+pub struct DtlsHandshakeFragment {
+    pub length: u32,
+    pub fragment_offset: u32,
+    pub body: Payload,
+}
+
+/// The cookie of a HelloVerifyRequest which is echoed in the second ClientHello
+#[derive(Debug, Clone, PartialEq)]
+pub struct DtlsCookie(pub Vec<u8>);
+
+impl Codec for DtlsCookie {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (self.0.len() as u8).encode(bytes);
+        bytes.extend_from_slice(&self.0);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let len = u8::read(r)? as usize;
+        let mut sub = r.sub(len)?;
+        Some(Self(sub.rest().to_vec()))
+    }
+}
+
+/// The DTLS ClientHello, which has an additional cookie compared to the TLS ClientHello
+#[derive(Debug, Clone)]
+pub struct DtlsClientHelloPayload {
+    pub client_version: ProtocolVersion,
+    pub random: Random,
+    pub session_id: SessionID,
+    pub cookie: DtlsCookie,
+    pub cipher_suites: Vec<CipherSuite>,
+    pub compression_methods: Vec<Compression>,
+    pub extensions: Vec<ClientExtension>,
+}
+
+impl Codec for DtlsClientHelloPayload {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.client_version.encode(bytes);
+        self.random.encode(bytes);
+        self.session_id.encode(bytes);
+        self.cookie.encode(bytes);
+        codec::encode_vec_u16(bytes, &self.cipher_suites);
+        codec::encode_vec_u8(bytes, &self.compression_methods);
+
+        if !self.extensions.is_empty() {
+            codec::encode_vec_u16(bytes, &self.extensions);
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let mut ret = Self {
+            client_version: ProtocolVersion::read(r)?,
+            random: Random::read(r)?,
+            session_id: SessionID::read(r)?,
+            cookie: DtlsCookie::read(r)?,
+            cipher_suites: codec::read_vec_u16::<CipherSuite>(r)?,
+            compression_methods: codec::read_vec_u8::<Compression>(r)?,
+            extensions: Vec::new(),
+        };
+
+        if r.any_left() {
+            ret.extensions = codec::read_vec_u16::<ClientExtension>(r)?;
+        }
+
+        if r.any_left() {
+            None
+        } else {
+            Some(ret)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HelloVerifyRequestPayload {
+    pub server_version: ProtocolVersion,
+    pub cookie: DtlsCookie,
+}
+
+impl Codec for HelloVerifyRequestPayload {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.server_version.encode(bytes);
+        self.cookie.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            server_version: ProtocolVersion::read(r)?,
+            cookie: DtlsCookie::read(r)?,
+        })
+    }
+}
+
+/// Identifies a record which is acknowledged in an [`AckPayload`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordNumber {
+    pub epoch: u64,
+    pub sequence_number: u64,
+}
+
+impl Codec for RecordNumber {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.epoch.encode(bytes);
+        self.sequence_number.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            epoch: u64::read(r)?,
+            sequence_number: u64::read(r)?,
+        })
+    }
+}
+
+/// The ACK message of DTLS 1.3
+#[derive(Debug, Clone)]
+pub struct AckPayload(pub Vec<RecordNumber>);
+
+impl Codec for AckPayload {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        codec::encode_vec_u16(bytes, &self.0);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        codec::read_vec_u16::<RecordNumber>(r).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use puffin::codec::{Codec, Reader};
+
+    use super::{
+        DtlsHandshakePayload, DtlsMessage, DtlsMessagePayload, DtlsOpaqueMessage, RecordNumber,
+    };
+    use crate::tls::rustls::msgs::enums::{ContentType, HandshakeType, ProtocolVersion};
+
+    /// A HelloVerifyRequest with the cookie `01 02 03 04`
+    const HELLO_VERIFY_REQUEST: &[u8] = &[
+        0x16, 0xfe, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13, 0x03, 0x00,
+        0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0xfe, 0xff, 0x04, 0x01, 0x02,
+        0x03, 0x04,
+    ];
+
+    #[test]
+    fn test_hello_verify_request_roundtrip() {
+        let opaque = DtlsOpaqueMessage::read(&mut Reader::init(HELLO_VERIFY_REQUEST)).unwrap();
+        assert_eq!(opaque.typ, ContentType::Handshake);
+        assert_eq!(opaque.version, ProtocolVersion::DTLSv1_0);
+
+        let message = DtlsMessage::try_from(opaque).unwrap();
+        match &message.payload {
+            DtlsMessagePayload::Handshake(handshake) => {
+                assert_eq!(handshake.typ, HandshakeType::HelloVerifyRequest);
+                match &handshake.payload {
+                    DtlsHandshakePayload::HelloVerifyRequest(hvr) => {
+                        assert_eq!(hvr.cookie.0, vec![1, 2, 3, 4]);
+                    }
+                    _ => panic!("expected HelloVerifyRequest"),
+                }
+            }
+            _ => panic!("expected handshake message"),
+        }
+
+        assert_eq!(message.get_encoding(), HELLO_VERIFY_REQUEST);
+    }
+
+    #[test]
+    fn test_fragment_is_not_reassembled() {
+        let mut fragment = HELLO_VERIFY_REQUEST.to_vec();
+        // Declare a total length of 8 bytes while the fragment contains 7 bytes
+        fragment[16] = 0x08;
+
+        let message =
+            DtlsMessage::read(&mut Reader::init(&fragment)).expect("fragment should be decoded");
+        match &message.payload {
+            DtlsMessagePayload::Handshake(handshake) => match &handshake.payload {
+                DtlsHandshakePayload::Fragment(fragment) => {
+                    assert_eq!(fragment.length, 8);
+                    assert_eq!(fragment.fragment_offset, 0);
+                    assert_eq!(fragment.body.0.len(), 7);
+                }
+                _ => panic!("expected fragment"),
+            },
+            _ => panic!("expected handshake message"),
+        }
+
+        assert_eq!(message.get_encoding(), fragment);
+    }
+
+    #[test]
+    fn test_unified_header_roundtrip() {
+        // C=0, S=1, L=1, epoch bits = 3
+        let record = [0x2f, 0x00, 0x05, 0x00, 0x03, 0xaa, 0xbb, 0xcc];
+        let opaque = DtlsOpaqueMessage::read(&mut Reader::init(&record)).unwrap();
+        assert_eq!(opaque.epoch, 3);
+        assert_eq!(opaque.sequence_number, 5);
+        assert_eq!(opaque.payload.0, vec![0xaa, 0xbb, 0xcc]);
+        assert_eq!(opaque.encode(), record);
+    }
+
+    #[test]
+    fn test_ack_roundtrip() {
+        // An ACK of the record with epoch 2 and sequence number 1
+        let record = [
+            0x1a, 0xfe, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x12, 0x00,
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x01,
+        ];
+
+        let message = DtlsMessage::read(&mut Reader::init(&record)).unwrap();
+        match &message.payload {
+            DtlsMessagePayload::Ack(ack) => assert_eq!(
+                ack.0,
+                vec![RecordNumber {
+                    epoch: 2,
+                    sequence_number: 1
+                }]
+            ),
+            _ => panic!("expected ACK"),
+        }
+
+        assert_eq!(message.get_encoding(), record);
+    }
+}
@@ -0,0 +1,123 @@
+//! The *dtls* module provides the [`ProtocolBehavior`](puffin::protocol::ProtocolBehavior) for
+//! DTLS 1.2 and DTLS 1.3. Records and the handshake headers are specific to DTLS, while most
+//! handshake bodies, extensions and fields are shared with the *tls* module.
+
+use fn_impl::*;
+use puffin::define_signature;
+
+use crate::tls::fn_impl::*;
+
+pub mod deframer;
+pub mod message;
+pub mod protocol;
+pub mod put_registry;
+pub mod seeds;
+pub mod stream;
+
+/// This modules contains all the concrete implementations of DTLS specific function symbols.
+#[path = "."]
+pub mod fn_impl {
+    pub mod fn_fields;
+    pub mod fn_messages;
+
+    pub use fn_fields::*;
+    pub use fn_messages::*;
+}
+
+define_signature!(
+    DTLS_SIGNATURE,
+    // constants
+    fn_true
+    fn_false
+    fn_seq_0
+    fn_seq_1
+    fn_seq_2
+    fn_seq_3
+    fn_seq_4
+    fn_seq_5
+    fn_seq_6
+    fn_seq_7
+    fn_seq_8
+    fn_seq_9
+    fn_seq_10
+    fn_seq_11
+    fn_seq_12
+    fn_seq_13
+    fn_seq_14
+    fn_seq_15
+    fn_seq_16
+    fn_large_length
+    fn_empty_bytes_vec
+    // records
+    fn_dtls_opaque_record
+    fn_dtls_handshake_record
+    fn_dtls_change_cipher_spec
+    fn_dtls_alert_close_notify
+    fn_dtls_application_data
+    fn_dtls_ack
+    // handshake messages
+    fn_dtls_client_hello
+    fn_dtls_hello_verify_request
+    fn_dtls_handshake
+    fn_dtls_fragment
+    fn_certificate
+    fn_certificate13
+    fn_certificate_request
+    fn_certificate_request13
+    fn_certificate_verify
+    fn_client_key_exchange
+    fn_encrypted_extensions
+    fn_finished
+    fn_hello_request
+    fn_hello_retry_request
+    fn_key_update
+    fn_key_update_not_requested
+    fn_new_session_ticket
+    fn_new_session_ticket13
+    fn_server_hello
+    fn_server_hello_done
+    fn_server_key_exchange
+    // extensions
+    fn_client_extensions_new
+    fn_client_extensions_append
+    fn_server_extensions_new
+    fn_server_extensions_append
+    fn_hello_retry_extensions_new
+    fn_hello_retry_extensions_append
+    fn_server_name_extension
+    fn_support_group_extension
+    fn_ec_point_formats_extension
+    fn_signature_algorithm_extension
+    fn_extended_master_secret_extension
+    fn_session_ticket_request_extension
+    fn_renegotiation_info_extension
+    fn_cookie_extension
+    fn_cookie_hello_retry_extension
+    fn_key_share_deterministic_extension
+    fn_key_share_extension
+    fn_key_share_server_extension
+    fn_key_share_hello_retry_extension
+    fn_unknown_client_extension
+    fn_dtls_supported_versions13_extension
+    fn_dtls_supported_versions13_server_extension
+    // fields
+    fn_dtls_protocol_version10
+    fn_dtls_protocol_version12
+    fn_dtls_protocol_version13
+    fn_dtls_empty_cookie
+    fn_dtls_new_cookie
+    fn_dtls_record_number
+    fn_dtls_record_numbers_new
+    fn_dtls_record_numbers_append
+    fn_new_session_id
+    fn_empty_session_id
+    fn_new_random
+    fn_compressions
+    fn_compression
+    fn_new_cipher_suites
+    fn_append_cipher_suite
+    fn_cipher_suite12
+    fn_cipher_suite13_aes_128_gcm_sha256
+    fn_named_group_secp384r1
+    fn_named_group_x25519
+);
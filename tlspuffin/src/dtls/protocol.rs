@@ -0,0 +1,215 @@
+use log::debug;
+use puffin::{
+    algebra::signature::Signature,
+    error::Error,
+    oracle::{OracleFamily, StateObserver},
+    protocol::{
+        MessageResult, OpaqueProtocolMessage, ProtocolBehavior, ProtocolMessage,
+        ProtocolMessageDeframer,
+    },
+    put_registry::PutRegistry,
+    trace::Trace,
+    variable_data::VariableData,
+};
+
+use crate::{
+    claims::TlsClaim,
+    dtls::{
+        deframer::DtlsMessageDeframer,
+        message::{DtlsHandshakePayload, DtlsMessage, DtlsMessagePayload, DtlsOpaqueMessage},
+        put_registry::DTLS_PUT_REGISTRY,
+        seeds::create_corpus,
+        DTLS_SIGNATURE,
+    },
+    query::TlsQueryMatcher,
+    tls::{
+        rustls::msgs::{
+            enums::{ContentType, ProtocolVersion},
+            handshake::HandshakeMessagePayload,
+            message::{Message, MessagePayload},
+        },
+        violation::TlsSecurityViolationPolicy,
+    },
+};
+
+impl ProtocolMessage<DtlsOpaqueMessage> for DtlsMessage {
+    fn create_opaque(&self) -> DtlsOpaqueMessage {
+        DtlsOpaqueMessage::from(self.clone())
+    }
+
+    fn debug(&self, info: &str) {
+        debug!(
+            "{} | DTLS Message (epoch {}, seq {}): {:?}",
+            info, self.epoch, self.sequence_number, self.payload
+        );
+    }
+
+    /// Extracts knowledge from a [`DtlsMessage`]. Handshake messages which are shared with TLS
+    /// additionally yield the knowledge of the equivalent TLS [`Message`]. Encrypted handshake
+    /// messages and application data only yield the message itself and the binary payload.
+    fn extract_knowledge(&self) -> Result<Vec<Box<dyn VariableData>>, Error> {
+        Ok(match &self.payload {
+            DtlsMessagePayload::Alert(alert) => {
+                vec![
+                    Box::new(self.clone()),
+                    Box::new(alert.description),
+                    Box::new(alert.level),
+                ]
+            }
+            DtlsMessagePayload::Handshake(hs) => match &hs.payload {
+                DtlsHandshakePayload::ClientHello(ch) => {
+                    let vars: Vec<Box<dyn VariableData>> = vec![
+                        Box::new(self.clone()),
+                        Box::new(hs.typ),
+                        Box::new(ch.random),
+                        Box::new(ch.session_id),
+                        Box::new(ch.client_version),
+                        Box::new(ch.cookie.clone()),
+                        Box::new(ch.extensions.clone()),
+                        Box::new(ch.compression_methods.clone()),
+                        Box::new(ch.cipher_suites.clone()),
+                    ];
+
+                    let extensions = ch
+                        .extensions
+                        .iter()
+                        .map(|extension| Box::new(extension.clone()) as Box<dyn VariableData>);
+                    let compression_methods = ch
+                        .compression_methods
+                        .iter()
+                        .map(|compression| Box::new(*compression) as Box<dyn VariableData>);
+                    let cipher_suites = ch
+                        .cipher_suites
+                        .iter()
+                        .map(|cipher_suite| Box::new(*cipher_suite) as Box<dyn VariableData>);
+
+                    vars.into_iter()
+                        .chain(extensions) // also add all extensions individually
+                        .chain(compression_methods)
+                        .chain(cipher_suites)
+                        .collect::<Vec<Box<dyn VariableData>>>()
+                }
+                DtlsHandshakePayload::HelloVerifyRequest(hvr) => {
+                    vec![
+                        Box::new(self.clone()),
+                        Box::new(hs.typ),
+                        Box::new(hvr.server_version),
+                        Box::new(hvr.cookie.clone()),
+                    ]
+                }
+                DtlsHandshakePayload::Tls(payload) => {
+                    let tls_message = Message {
+                        version: ProtocolVersion::TLSv1_2,
+                        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+                            typ: hs.typ,
+                            payload: payload.clone(),
+                        }),
+                    };
+
+                    let vars: Vec<Box<dyn VariableData>> = vec![Box::new(self.clone())];
+                    vars.into_iter()
+                        .chain(tls_message.extract_knowledge()?)
+                        .collect::<Vec<Box<dyn VariableData>>>()
+                }
+                DtlsHandshakePayload::Fragment(fragment) => {
+                    vec![
+                        Box::new(self.clone()),
+                        Box::new(hs.typ),
+                        Box::new(fragment.body.0.clone()),
+                    ]
+                }
+            },
+            DtlsMessagePayload::EncryptedHandshake(encrypted) => {
+                vec![Box::new(self.clone()), Box::new(encrypted.0.clone())]
+            }
+            DtlsMessagePayload::ChangeCipherSpec(_ccs) => {
+                vec![]
+            }
+            DtlsMessagePayload::ApplicationData(opaque) => {
+                vec![Box::new(self.clone()), Box::new(opaque.0.clone())]
+            }
+            DtlsMessagePayload::Ack(ack) => {
+                vec![Box::new(self.clone()), Box::new(ack.0.clone())]
+            }
+        })
+    }
+}
+
+impl ProtocolMessageDeframer for DtlsMessageDeframer {
+    type OpaqueProtocolMessage = DtlsOpaqueMessage;
+
+    fn pop_frame(&mut self) -> Option<DtlsOpaqueMessage> {
+        self.frames.pop_front()
+    }
+    fn read(&mut self, rd: &mut dyn std::io::Read) -> std::io::Result<usize> {
+        self.read(rd)
+    }
+}
+
+impl OpaqueProtocolMessage for DtlsOpaqueMessage {
+    fn debug(&self, info: &str) {
+        debug!(
+            "{} | DTLS Opaque Message (epoch {}, seq {}) ({:?}): {:?}",
+            info, self.epoch, self.sequence_number, self.version, self.typ,
+        );
+    }
+
+    fn extract_knowledge(&self) -> Result<Vec<Box<dyn VariableData>>, Error> {
+        Ok(vec![Box::new(self.clone())])
+    }
+}
+
+impl TryFrom<&MessageResult<DtlsMessage, DtlsOpaqueMessage>> for TlsQueryMatcher {
+    type Error = Error;
+
+    fn try_from(
+        message_result: &MessageResult<DtlsMessage, DtlsOpaqueMessage>,
+    ) -> Result<Self, Self::Error> {
+        let dtls_opaque_type = message_result.1.typ;
+        match (dtls_opaque_type, message_result) {
+            (ContentType::Handshake, MessageResult(Some(message), _)) => match &message.payload {
+                DtlsMessagePayload::Handshake(handshake_payload) => {
+                    Ok(TlsQueryMatcher::Handshake(Some(handshake_payload.typ)))
+                }
+                DtlsMessagePayload::EncryptedHandshake(_) => Ok(TlsQueryMatcher::Handshake(None)),
+                _ => Err(Error::Extraction()),
+            },
+            (ContentType::Handshake, _) => Ok(TlsQueryMatcher::Handshake(None)),
+            (ContentType::ApplicationData, _) => Ok(TlsQueryMatcher::ApplicationData),
+            (ContentType::Alert, _) => Ok(TlsQueryMatcher::Alert),
+            (ContentType::ChangeCipherSpec, _) => Ok(TlsQueryMatcher::ChangeCipherSpec),
+            (ContentType::Ack, _) => Ok(TlsQueryMatcher::Ack),
+            (ContentType::Heartbeat, _) | (ContentType::Unknown(_), _) => Err(Error::Extraction()),
+        }
+    }
+}
+
+/// Like [`crate::protocol::TLSProtocolBehavior`], but for DTLS. The claims and the security
+/// policy are shared with TLS.
+#[derive(Clone)]
+pub struct DtlsProtocolBehavior;
+
+impl ProtocolBehavior for DtlsProtocolBehavior {
+    type Claim = TlsClaim;
+    type SecurityViolationPolicy = TlsSecurityViolationPolicy;
+    type ProtocolMessage = DtlsMessage;
+    type OpaqueProtocolMessage = DtlsOpaqueMessage;
+    type Matcher = TlsQueryMatcher;
+    type OracleObserver = StateObserver;
+
+    fn signature() -> &'static Signature {
+        &DTLS_SIGNATURE
+    }
+
+    fn registry() -> &'static PutRegistry<Self> {
+        &DTLS_PUT_REGISTRY
+    }
+
+    fn create_corpus() -> Vec<(Trace<Self::Matcher>, &'static str)> {
+        create_corpus()
+    }
+
+    fn create_oracle_families() -> Vec<OracleFamily<Self::Matcher>> {
+        vec![]
+    }
+}
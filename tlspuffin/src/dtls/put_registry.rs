@@ -0,0 +1,25 @@
+use puffin::put_registry::{Factory, PutRegistry};
+
+use crate::dtls::protocol::DtlsProtocolBehavior;
+
+pub const DTLS_PUT_REGISTRY: PutRegistry<DtlsProtocolBehavior> = PutRegistry {
+    factories: &[
+        #[cfg(feature = "openssl-binding")]
+        crate::openssl::dtls::new_openssl_dtls_factory,
+        #[cfg(feature = "wolfssl-binding")]
+        crate::wolfssl::dtls::new_wolfssl_dtls_factory,
+    ],
+    default: DEFAULT_PUT_FACTORY,
+};
+
+pub const DEFAULT_PUT_FACTORY: fn() -> Box<dyn Factory<DtlsProtocolBehavior>> = {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "openssl-binding")] {
+            crate::openssl::dtls::new_openssl_dtls_factory
+        } else if #[cfg(feature = "wolfssl-binding")] {
+            crate::wolfssl::dtls::new_wolfssl_dtls_factory
+        } else {
+            compile_error!("DTLS requires the OpenSSL or wolfSSL bindings")
+        }
+    }
+};
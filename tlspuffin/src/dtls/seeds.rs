@@ -0,0 +1,461 @@
+//! Implementation of special DTLS traces. The [`TLSVersion`] of the agents selects the DTLS
+//! version: [`TLSVersion::V1_2`] is DTLS 1.2 and [`TLSVersion::V1_3`] is DTLS 1.3.
+#![allow(dead_code)]
+
+use puffin::{
+    agent::{AgentDescriptor, AgentName, TLSVersion},
+    term,
+    trace::{InputAction, OutputAction, Trace},
+};
+
+use crate::{
+    dtls::{fn_impl::*, message::DtlsMessage},
+    query::TlsQueryMatcher,
+    tls::{fn_impl::*, rustls::msgs::enums::HandshakeType},
+};
+
+/// A full DTLS 1.2 handshake with a HelloVerifyRequest. The server flight is forwarded as
+/// [`DtlsMessage`]s, while the messages of the client in epoch 0 are rebuilt from their fields.
+pub fn seed_dtls12_successful(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![
+            AgentDescriptor::new_client(client, TLSVersion::V1_2),
+            AgentDescriptor::new_server(server, TLSVersion::V1_2),
+        ],
+        steps: vec![
+            OutputAction::new_step(client),
+            // Client Hello, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_dtls_handshake_record(
+                        fn_seq_0,
+                        fn_seq_0,
+                        (fn_dtls_client_hello(
+                            fn_seq_0,
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0))
+                        ))
+                    )
+                },
+            ),
+            // Hello Verify Request, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_dtls_handshake_record(
+                        fn_seq_0,
+                        fn_seq_0,
+                        (fn_dtls_hello_verify_request(
+                            fn_seq_0,
+                            ((server, 0)),
+                            ((server, 0))
+                        ))
+                    )
+                },
+            ),
+            // Client Hello with Cookie, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_dtls_handshake_record(
+                        fn_seq_0,
+                        fn_seq_1,
+                        (fn_dtls_client_hello(
+                            fn_seq_1,
+                            ((client, 1)),
+                            ((client, 1)),
+                            ((client, 1)),
+                            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::HelloVerifyRequest)))]),
+                            ((client, 1)),
+                            ((client, 1)),
+                            ((client, 1))
+                        ))
+                    )
+                },
+            ),
+            // Server Hello, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]/DtlsMessage
+                },
+            ),
+            // Server Certificate, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::Certificate)))]/DtlsMessage
+                },
+            ),
+            // Server Key Exchange, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/DtlsMessage
+                },
+            ),
+            // Server Hello Done, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHelloDone)))]/DtlsMessage
+                },
+            ),
+            // Client Key Exchange, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_dtls_handshake_record(
+                        fn_seq_0,
+                        fn_seq_2,
+                        (fn_dtls_handshake(
+                            fn_seq_2,
+                            (fn_client_key_exchange(
+                                ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>)
+                            ))
+                        ))
+                    )
+                },
+            ),
+            // Client Change Cipher Spec, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_dtls_change_cipher_spec(
+                        fn_seq_0,
+                        fn_seq_3
+                    )
+                },
+            ),
+            // Client Handshake Finished, Client -> Server
+            // The record is encrypted in epoch 1 and therefore forwarded as is. Before, the client
+            // sent two ClientHellos, the ClientKeyExchange and the ChangeCipherSpec.
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_dtls_opaque_record(
+                        ((client, 4)[None])
+                    )
+                },
+            ),
+            // Server Change Cipher Spec, Server -> Client
+            // Before, the server sent the HelloVerifyRequest and four messages of its first flight.
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_dtls_change_cipher_spec(
+                        fn_seq_0,
+                        fn_seq_5
+                    )
+                },
+            ),
+            // Server Handshake Finished, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_dtls_opaque_record(
+                        ((server, 6)[None])
+                    )
+                },
+            ),
+        ],
+    }
+}
+
+/// Like [`seed_dtls12_successful`], but the second ClientHello is split into two fragments which
+/// are sent in separate records.
+pub fn seed_dtls12_fragmented_client_hello(
+    client: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let mut trace = seed_dtls12_successful(client, server);
+
+    let client_hello_cookie = term! {
+        fn_dtls_client_hello(
+            fn_seq_1,
+            ((client, 1)),
+            ((client, 1)),
+            ((client, 1)),
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::HelloVerifyRequest)))]),
+            ((client, 1)),
+            ((client, 1)),
+            ((client, 1))
+        )
+    };
+
+    trace.steps[3] = InputAction::new_step(
+        server,
+        term! {
+            fn_dtls_handshake_record(
+                fn_seq_0,
+                fn_seq_1,
+                (fn_dtls_fragment((@client_hello_cookie), fn_seq_0, fn_seq_16))
+            )
+        },
+    );
+    trace.steps.insert(
+        4,
+        InputAction::new_step(
+            server,
+            term! {
+                fn_dtls_handshake_record(
+                    fn_seq_0,
+                    fn_seq_2,
+                    (fn_dtls_fragment((@client_hello_cookie), fn_seq_16, fn_large_length))
+                )
+            },
+        ),
+    );
+
+    // The following records of the client in epoch 0 are shifted by one
+    trace.steps[9] = InputAction::new_step(
+        server,
+        term! {
+            fn_dtls_handshake_record(
+                fn_seq_0,
+                fn_seq_3,
+                (fn_dtls_handshake(
+                    fn_seq_2,
+                    (fn_client_key_exchange(
+                        ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>)
+                    ))
+                ))
+            )
+        },
+    );
+    trace.steps[10] = InputAction::new_step(
+        server,
+        term! {
+            fn_dtls_change_cipher_spec(
+                fn_seq_0,
+                fn_seq_4
+            )
+        },
+    );
+
+    trace
+}
+
+/// A full DTLS 1.3 handshake. The server answers the first ClientHello with a HelloRetryRequest,
+/// which carries a cookie like the HelloVerifyRequest of DTLS 1.2. Starting with the
+/// EncryptedExtensions, all handshake messages are encrypted and sent as DTLSCiphertext with a
+/// unified header, which is decoded as application data and forwarded as is. Finally, the server
+/// acknowledges the Finished of the client with an ACK.
+pub fn seed_dtls13_successful(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![
+            AgentDescriptor::new_client(client, TLSVersion::V1_3),
+            AgentDescriptor::new_server(server, TLSVersion::V1_3),
+        ],
+        steps: vec![
+            OutputAction::new_step(client),
+            // Client Hello, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    (client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))]/DtlsMessage
+                },
+            ),
+            // Hello Retry Request, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::HelloRetryRequest)))]/DtlsMessage
+                },
+            ),
+            // Client Hello with Cookie, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    (client, 1)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))]/DtlsMessage
+                },
+            ),
+            // Server Hello, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]/DtlsMessage
+                },
+            ),
+            // Encrypted Extensions, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 0)[Some(TlsQueryMatcher::ApplicationData)]/DtlsMessage
+                },
+            ),
+            // Certificate, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 1)[Some(TlsQueryMatcher::ApplicationData)]/DtlsMessage
+                },
+            ),
+            // Certificate Verify, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 2)[Some(TlsQueryMatcher::ApplicationData)]/DtlsMessage
+                },
+            ),
+            // Server Handshake Finished, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 3)[Some(TlsQueryMatcher::ApplicationData)]/DtlsMessage
+                },
+            ),
+            // Client Handshake Finished, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    (client, 0)[Some(TlsQueryMatcher::ApplicationData)]/DtlsMessage
+                },
+            ),
+            // ACK of the Client Handshake Finished, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    (server, 4)[Some(TlsQueryMatcher::ApplicationData)]/DtlsMessage
+                },
+            ),
+        ],
+    }
+}
+
+/// Like [`seed_dtls13_successful`], but both ClientHellos are rebuilt from their fields. The
+/// cookie of the HelloRetryRequest is echoed in an extension of the second ClientHello, while the
+/// DTLS cookie field is empty.
+pub fn seed_dtls13_client_hello_fields(
+    client: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let mut trace = seed_dtls13_successful(client, server);
+
+    trace.steps[1] = InputAction::new_step(
+        server,
+        term! {
+            fn_dtls_handshake_record(
+                fn_seq_0,
+                fn_seq_0,
+                (fn_dtls_client_hello(
+                    fn_seq_0,
+                    ((client, 0)),
+                    ((client, 0)),
+                    ((client, 0)),
+                    ((client, 0)),
+                    ((client, 0)),
+                    ((client, 0)),
+                    ((client, 0))
+                ))
+            )
+        },
+    );
+    trace.steps[3] = InputAction::new_step(
+        server,
+        term! {
+            fn_dtls_handshake_record(
+                fn_seq_0,
+                fn_seq_1,
+                (fn_dtls_client_hello(
+                    fn_seq_1,
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1))
+                ))
+            )
+        },
+    );
+
+    trace
+}
+
+pub fn create_corpus() -> Vec<(Trace<TlsQueryMatcher>, &'static str)> {
+    let client = AgentName::first();
+    let server = client.next();
+
+    let mut corpus = Vec::new();
+
+    #[cfg(feature = "dtls12")]
+    {
+        corpus.push((
+            seed_dtls12_successful(client, server),
+            "seed_dtls12_successful",
+        ));
+        corpus.push((
+            seed_dtls12_fragmented_client_hello(client, server),
+            "seed_dtls12_fragmented_client_hello",
+        ));
+    }
+
+    #[cfg(feature = "dtls13")]
+    {
+        corpus.push((
+            seed_dtls13_successful(client, server),
+            "seed_dtls13_successful",
+        ));
+        corpus.push((
+            seed_dtls13_client_hello_fields(client, server),
+            "seed_dtls13_client_hello_fields",
+        ));
+    }
+
+    corpus
+}
+
+#[cfg(test)]
+pub mod tests {
+    use puffin::agent::AgentName;
+    use test_log::test;
+
+    use super::*;
+    use crate::dtls::put_registry::DTLS_PUT_REGISTRY;
+
+    #[test]
+    #[cfg(feature = "dtls12")]
+    fn test_seed_dtls12_successful() {
+        let client = AgentName::first();
+        let ctx =
+            seed_dtls12_successful(client, client.next()).execute_deterministic(&DTLS_PUT_REGISTRY);
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "dtls12")]
+    fn test_seed_dtls12_fragmented_client_hello() {
+        let client = AgentName::first();
+        let ctx = seed_dtls12_fragmented_client_hello(client, client.next())
+            .execute_deterministic(&DTLS_PUT_REGISTRY);
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "dtls13")]
+    fn test_seed_dtls13_successful() {
+        let client = AgentName::first();
+        let ctx =
+            seed_dtls13_successful(client, client.next()).execute_deterministic(&DTLS_PUT_REGISTRY);
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "dtls13")]
+    fn test_seed_dtls13_client_hello_fields() {
+        let client = AgentName::first();
+        let ctx = seed_dtls13_client_hello_fields(client, client.next())
+            .execute_deterministic(&DTLS_PUT_REGISTRY);
+        assert!(ctx.agents_successful());
+    }
+}
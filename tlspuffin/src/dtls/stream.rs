@@ -0,0 +1,130 @@
+//! In-memory channels for DTLS. In contrast to [`puffin::stream::MemoryStream`], the boundaries
+//! of datagrams are preserved. Each write of the PUT is a datagram and each read of the PUT
+//! receives a single datagram. Every message which is added to the inbound channel is sent in its
+//! own datagram.
+
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    io,
+    io::{Read, Write},
+};
+
+use log::error;
+use puffin::{codec::Codec, error::Error, protocol::MessageResult, stream::Stream};
+
+use crate::dtls::{
+    deframer::DtlsMessageDeframer,
+    message::{DtlsMessage, DtlsOpaqueMessage},
+};
+
+#[derive(Default)]
+pub struct DatagramStream {
+    inbound: VecDeque<Vec<u8>>,
+    outbound: VecDeque<Vec<u8>>,
+    deframer: DtlsMessageDeframer,
+}
+
+impl DatagramStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Stream<DtlsMessage, DtlsOpaqueMessage> for DatagramStream {
+    fn add_to_inbound(&mut self, opaque_message: &DtlsOpaqueMessage) -> Result<(), Error> {
+        self.inbound.push_back(opaque_message.get_encoding());
+        Ok(())
+    }
+
+    fn take_message_from_outbound(
+        &mut self,
+    ) -> Result<Option<MessageResult<DtlsMessage, DtlsOpaqueMessage>>, Error> {
+        while !self.deframer.has_pending() {
+            match self.outbound.pop_front() {
+                Some(datagram) => self.deframer.read_datagram(&datagram),
+                None => return Ok(None),
+            }
+        }
+
+        let opaque_message = match self.deframer.frames.pop_front() {
+            Some(opaque_message) => opaque_message,
+            None => return Ok(None),
+        };
+
+        let message = match DtlsMessage::try_from(opaque_message.clone()) {
+            Ok(message) => Some(message),
+            Err(err) => {
+                error!("Failed to decode DTLS message! {}", err);
+                None
+            }
+        };
+
+        Ok(Some(MessageResult(message, opaque_message)))
+    }
+}
+
+impl Read for DatagramStream {
+    /// Receives the next datagram. Like with UDP sockets, the datagram is truncated if `buf` is
+    /// too small.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.inbound.pop_front() {
+            Some(datagram) => {
+                let n = datagram.len().min(buf.len());
+                buf[..n].copy_from_slice(&datagram[..n]);
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "no data available",
+            )),
+        }
+    }
+}
+
+impl Write for DatagramStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outbound.push_back(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use puffin::{protocol::MessageResult, stream::Stream};
+
+    use super::DatagramStream;
+
+    #[test]
+    fn test_datagram_boundaries() {
+        let mut stream = DatagramStream::new();
+
+        // A DTLS 1.3 ciphertext without length followed by a DTLSPlaintext in a second datagram
+        stream.write_all(&[0x22, 0x07, 0xaa, 0xbb]).unwrap();
+        stream
+            .write_all(&[
+                0x15, 0xfe, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x01,
+                0x00,
+            ])
+            .unwrap();
+
+        let MessageResult(_, ciphertext) = stream.take_message_from_outbound().unwrap().unwrap();
+        assert_eq!(ciphertext.payload.0, vec![0xaa, 0xbb]);
+        let MessageResult(alert, _) = stream.take_message_from_outbound().unwrap().unwrap();
+        assert!(alert.is_some());
+        assert!(stream.take_message_from_outbound().unwrap().is_none());
+
+        stream.add_to_inbound(&ciphertext).unwrap();
+        stream.add_to_inbound(&ciphertext).unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(stream.read(&mut buf).unwrap(), 4);
+        assert_eq!(stream.read(&mut buf).unwrap(), 4);
+        assert!(stream.read(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! Bindings of the `put-interface.h` which are resolved at runtime.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_void, CStr, CString},
+    mem,
+    os::raw::{c_char, c_int},
+    rc::Rc,
+};
+
+use puffin::{
+    agent::{AgentType, TLSVersion},
+    error::Error,
+};
+use security_claims::register::{DeregisterClaimerFn, RegisterClaimerFn};
+
+/// The version of the `put-interface.h` which we implement
+const PUT_INTERFACE_VERSION: u32 = 1;
+
+pub const PUT_OK: c_int = 0;
+
+#[repr(C)]
+pub struct PutDescriptor {
+    pub typ: c_int,
+    pub tls_version: c_int,
+    pub server_authentication: c_int,
+    pub client_authentication: c_int,
+    pub cert: *const c_char,
+    pub key: *const c_char,
+    pub trusted_certs: *const *const c_char,
+    pub trusted_certs_length: usize,
+}
+
+pub fn agent_type(typ: AgentType) -> c_int {
+    match typ {
+        AgentType::Client => 0,
+        AgentType::Server => 1,
+    }
+}
+
+pub fn tls_version(version: TLSVersion) -> c_int {
+    match version {
+        TLSVersion::V1_3 => 0,
+        TLSVersion::V1_2 => 1,
+        TLSVersion::V1_1 => 2,
+        TLSVersion::V1_0 => 3,
+        TLSVersion::SSL3 => 4,
+    }
+}
+
+thread_local! {
+    /// Libraries by their path. Libraries are never unloaded, as TLS libraries usually do not
+    /// clean up their global state.
+    static LIBRARIES: RefCell<HashMap<String, Rc<PutLibrary>>> = RefCell::new(HashMap::new());
+}
+
+/// The functions of a shared object which implements the `put-interface.h`
+pub struct PutLibrary {
+    pub path: String,
+    pub version: unsafe extern "C" fn() -> *const c_char,
+    pub create: unsafe extern "C" fn(*const PutDescriptor) -> *mut c_void,
+    pub destroy: unsafe extern "C" fn(*mut c_void),
+    pub add_inbound: unsafe extern "C" fn(*mut c_void, *const u8, usize) -> c_int,
+    pub take_outbound: unsafe extern "C" fn(*mut c_void, *mut u8, usize) -> usize,
+    pub progress: unsafe extern "C" fn(*mut c_void) -> c_int,
+    pub reset: unsafe extern "C" fn(*mut c_void) -> c_int,
+    pub describe_state: unsafe extern "C" fn(*mut c_void) -> *const c_char,
+    pub is_state_successful: unsafe extern "C" fn(*mut c_void) -> c_int,
+    pub tls_like: Option<unsafe extern "C" fn(*mut c_void) -> *const c_void>,
+    pub set_deterministic: Option<unsafe extern "C" fn(u64) -> c_int>,
+    /// The claim-interface.h of the library
+    pub claimer: Option<(RegisterClaimerFn, DeregisterClaimerFn)>,
+}
+
+impl PutLibrary {
+    /// Loads the shared object at `path`, or returns it if it was already loaded by this thread.
+    pub fn load(path: &str) -> Result<Rc<PutLibrary>, Error> {
+        if let Some(library) = LIBRARIES.with(|libraries| libraries.borrow().get(path).cloned()) {
+            return Ok(library);
+        }
+
+        let library = Rc::new(Self::open(path)?);
+        LIBRARIES.with(|libraries| {
+            libraries
+                .borrow_mut()
+                .insert(path.to_owned(), library.clone())
+        });
+        Ok(library)
+    }
+
+    fn open(path: &str) -> Result<PutLibrary, Error> {
+        let c_path = CString::new(path).map_err(|err| Error::Put(err.to_string()))?;
+        let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(Error::Put(format!(
+                "Failed to load {}: {}",
+                path,
+                last_dl_error()
+            )));
+        }
+
+        let library = Self::resolve(path, handle);
+        if library.is_err() {
+            // The library is not usable, so it is unloaded again
+            unsafe { libc::dlclose(handle) };
+        }
+        library
+    }
+
+    /// Resolves the functions of the library which was loaded as `handle`
+    fn resolve(path: &str, handle: *mut c_void) -> Result<PutLibrary, Error> {
+        let required = |name: &str| {
+            symbol(handle, name)
+                .ok_or_else(|| Error::Put(format!("{} does not export {}", path, name)))
+        };
+
+        unsafe {
+            let interface_version: unsafe extern "C" fn() -> u32 =
+                mem::transmute(required("put_interface_version")?);
+            let found_version = interface_version();
+            if found_version != PUT_INTERFACE_VERSION {
+                return Err(Error::Put(format!(
+                    "{} implements version {} of the PUT interface, but version {} is required",
+                    path, found_version, PUT_INTERFACE_VERSION
+                )));
+            }
+
+            let claimer = symbol(handle, "register_claimer")
+                .zip(symbol(handle, "deregister_claimer"))
+                .map(|(register, deregister)| {
+                    (mem::transmute(register), mem::transmute(deregister))
+                });
+
+            Ok(PutLibrary {
+                path: path.to_owned(),
+                version: mem::transmute(required("put_version")?),
+                create: mem::transmute(required("put_create")?),
+                destroy: mem::transmute(required("put_destroy")?),
+                add_inbound: mem::transmute(required("put_add_inbound")?),
+                take_outbound: mem::transmute(required("put_take_outbound")?),
+                progress: mem::transmute(required("put_progress")?),
+                reset: mem::transmute(required("put_reset")?),
+                describe_state: mem::transmute(required("put_describe_state")?),
+                is_state_successful: mem::transmute(required("put_is_state_successful")?),
+                tls_like: symbol(handle, "put_tls_like").map(|tls_like| mem::transmute(tls_like)),
+                set_deterministic: symbol(handle, "put_set_deterministic")
+                    .map(|set_deterministic| mem::transmute(set_deterministic)),
+                claimer,
+            })
+        }
+    }
+
+    pub fn version(&self) -> String {
+        unsafe { to_string((self.version)()) }
+    }
+}
+
+/// Looks up the address of the function `name`
+fn symbol(handle: *mut c_void, name: &str) -> Option<*mut c_void> {
+    let name = CString::new(name).ok()?;
+    let address = unsafe { libc::dlsym(handle, name.as_ptr()) };
+    if address.is_null() {
+        None
+    } else {
+        Some(address)
+    }
+}
+
+fn last_dl_error() -> String {
+    unsafe { to_string(libc::dlerror()) }
+}
+
+/// Copies a string which is owned by the library
+pub unsafe fn to_string(string: *const c_char) -> String {
+    if string.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(string).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use puffin::error::Error;
+
+    use crate::dylib::library::PutLibrary;
+
+    #[test]
+    fn test_load_missing_library() {
+        let result = PutLibrary::load("/nonexistent/put.so");
+        assert!(matches!(result, Err(Error::Put(message)) if message.contains("Failed to load")));
+    }
+
+    #[test]
+    fn test_load_library_without_interface() {
+        let result = PutLibrary::load("libc.so.6");
+        assert!(
+            matches!(result, Err(Error::Put(message)) if message.ends_with("does not export put_interface_version"))
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! A PUT which loads a TLS library at runtime. The library is a shared object which implements
+//! the `put-interface.h` and is selected through the `lib` option of the PUT, e.g.
+//! `--put DYLIB --put-option lib=/path/to/put.so`. This allows fuzzing TLS libraries without
+//! adding bindings and a factory for them to tlspuffin.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_void, CString},
+    io::Write,
+    os::raw::c_char,
+    ptr,
+    rc::Rc,
+};
+
+use puffin::{
+    agent::{AgentDescriptor, AgentName, AgentType},
+    error::Error,
+    protocol::MessageResult,
+    put::{Put, PutName},
+    put_registry::Factory,
+    stream::{MemoryStream, Stream},
+    trace::TraceContext,
+};
+
+use crate::{
+    dylib::library::{agent_type, tls_version, to_string, PutLibrary, PUT_OK},
+    protocol::TLSProtocolBehavior,
+    put::TlsPutConfig,
+    put_registry::DYLIB_PUT,
+    static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT},
+    tls::rustls::msgs::{
+        deframer::MessageDeframer,
+        message::{Message, OpaqueMessage},
+    },
+};
+
+mod library;
+
+/// The seed which is passed to `put_set_deterministic`
+const DETERMINISTIC_SEED: u64 = 42;
+
+pub fn new_dylib_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
+    struct DylibFactory;
+    impl Factory<TLSProtocolBehavior> for DylibFactory {
+        fn create(
+            &self,
+            context: &TraceContext<TLSProtocolBehavior>,
+            agent_descriptor: &AgentDescriptor,
+        ) -> Result<Box<dyn Put<TLSProtocolBehavior>>, Error> {
+            let put_descriptor = context.put_descriptor(agent_descriptor);
+            put_descriptor.options.check_keys(&["lib"])?;
+
+            let path = put_descriptor
+                .options
+                .get_option("lib")
+                .ok_or_else(|| Error::Agent("Unable to find lib".to_string()))?;
+            let library = PutLibrary::load(path)?;
+
+            let config = TlsPutConfig {
+                descriptor: agent_descriptor.clone(),
+                claims: context.claims().clone(),
+                authenticate_peer: agent_descriptor.typ == AgentType::Client
+                    && agent_descriptor.server_authentication
+                    || agent_descriptor.typ == AgentType::Server
+                        && agent_descriptor.client_authentication,
+                extract_deferred: Rc::new(RefCell::new(None)),
+                // The options of this PUT configure the loading of the library
+                options: Default::default(),
+            };
+            Ok(Box::new(DynamicPut::new(library, config)?))
+        }
+
+        fn name(&self) -> PutName {
+            DYLIB_PUT
+        }
+
+        fn version(&self) -> String {
+            DynamicPut::version()
+        }
+    }
+
+    Box::new(DylibFactory)
+}
+
+pub struct DynamicPut {
+    library: Rc<PutLibrary>,
+    put: *mut c_void,
+    /// Only the outbound channel is used. Inbound bytes are passed directly to the library.
+    stream: MemoryStream<MessageDeframer>,
+    config: TlsPutConfig,
+    /// The state of the PUT after the latest call to the library
+    state: String,
+}
+
+impl DynamicPut {
+    fn new(library: Rc<PutLibrary>, config: TlsPutConfig) -> Result<Self, Error> {
+        let put = Self::create(&library, &config.descriptor)?;
+
+        #[cfg(feature = "claims")]
+        let agent_name = config.descriptor.name;
+
+        let mut dynamic_put = Self {
+            library,
+            put,
+            stream: MemoryStream::new(MessageDeframer::new()),
+            config,
+            state: String::new(),
+        };
+        dynamic_put.update_state();
+
+        #[cfg(feature = "claims")]
+        dynamic_put.register_claimer(agent_name);
+
+        Ok(dynamic_put)
+    }
+
+    /// Creates a client or server with the same certificates as the other PUTs
+    fn create(library: &PutLibrary, descriptor: &AgentDescriptor) -> Result<*mut c_void, Error> {
+        let (cert_and_key, trusted_certs) = match descriptor.typ {
+            AgentType::Server => (
+                Some((ALICE_CERT.0, ALICE_PRIVATE_KEY.0)),
+                if descriptor.client_authentication {
+                    vec![BOB_CERT.0, EVE_CERT.0]
+                } else {
+                    vec![]
+                },
+            ),
+            AgentType::Client => (
+                if descriptor.client_authentication {
+                    Some((BOB_CERT.0, BOB_PRIVATE_KEY.0))
+                } else {
+                    None
+                },
+                if descriptor.server_authentication {
+                    vec![ALICE_CERT.0, EVE_CERT.0]
+                } else {
+                    vec![]
+                },
+            ),
+        };
+
+        let to_c_string = |pem: &str| CString::new(pem).map_err(|err| Error::Put(err.to_string()));
+        let cert = cert_and_key
+            .map(|(cert, _)| to_c_string(cert))
+            .transpose()?;
+        let key = cert_and_key.map(|(_, key)| to_c_string(key)).transpose()?;
+        let trusted_certs = trusted_certs
+            .into_iter()
+            .map(to_c_string)
+            .collect::<Result<Vec<_>, _>>()?;
+        let trusted_cert_ptrs: Vec<*const c_char> =
+            trusted_certs.iter().map(|cert| cert.as_ptr()).collect();
+        let as_ptr = |string: &Option<CString>| string.as_ref().map_or(ptr::null(), |s| s.as_ptr());
+
+        let c_descriptor = library::PutDescriptor {
+            typ: agent_type(descriptor.typ),
+            tls_version: tls_version(descriptor.tls_version),
+            server_authentication: descriptor.server_authentication as i32,
+            client_authentication: descriptor.client_authentication as i32,
+            cert: as_ptr(&cert),
+            key: as_ptr(&key),
+            trusted_certs: trusted_cert_ptrs.as_ptr(),
+            trusted_certs_length: trusted_cert_ptrs.len(),
+        };
+
+        let put = unsafe { (library.create)(&c_descriptor) };
+        if put.is_null() {
+            return Err(Error::Put(format!(
+                "Failed to create client/server with {}",
+                library.path
+            )));
+        }
+        Ok(put)
+    }
+
+    fn update_state(&mut self) {
+        self.state = unsafe { to_string((self.library.describe_state)(self.put)) };
+    }
+
+    fn check_result(&self, result: i32) -> Result<(), Error> {
+        if result == PUT_OK {
+            Ok(())
+        } else {
+            // The library should have written an alert to the outbound buffer
+            Err(Error::Put(format!(
+                "The PUT failed in state: {}",
+                self.state
+            )))
+        }
+    }
+
+    /// The pointer which identifies the PUT in the claim-interface.h
+    #[cfg(feature = "claims")]
+    fn tls_like(&self) -> *const c_void {
+        match self.library.tls_like {
+            Some(tls_like) => unsafe { tls_like(self.put) },
+            None => self.put,
+        }
+    }
+}
+
+impl Drop for DynamicPut {
+    fn drop(&mut self) {
+        #[cfg(feature = "claims")]
+        self.deregister_claimer();
+
+        unsafe { (self.library.destroy)(self.put) };
+    }
+}
+
+impl Stream<Message, OpaqueMessage> for DynamicPut {
+    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) -> Result<(), Error> {
+        let data = opaque_message.clone().encode();
+        let result = unsafe { (self.library.add_inbound)(self.put, data.as_ptr(), data.len()) };
+        if result != PUT_OK {
+            return Err(Error::Put(format!(
+                "Failed to add inbound data to the PUT {}",
+                self.library.path
+            )));
+        }
+        Ok(())
+    }
+
+    fn take_message_from_outbound(
+        &mut self,
+    ) -> Result<Option<MessageResult<Message, OpaqueMessage>>, Error> {
+        let mut buffer = [0u8; 4096];
+        loop {
+            let length = unsafe {
+                (self.library.take_outbound)(self.put, buffer.as_mut_ptr(), buffer.len())
+            }
+            .min(buffer.len());
+            if length == 0 {
+                break;
+            }
+            self.stream.write_all(&buffer[..length])?;
+        }
+
+        MemoryStream::take_message_from_outbound(&mut self.stream)
+    }
+}
+
+impl Put<TLSProtocolBehavior> for DynamicPut {
+    fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
+        let result = unsafe { (self.library.progress)(self.put) };
+        self.update_state();
+        self.check_result(result)
+    }
+
+    fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
+        let result = unsafe { (self.library.reset)(self.put) };
+        self.stream = MemoryStream::new(MessageDeframer::new());
+        self.update_state();
+        self.check_result(result)
+    }
+
+    fn descriptor(&self) -> &AgentDescriptor {
+        &self.config.descriptor
+    }
+
+    #[cfg(feature = "claims")]
+    fn register_claimer(&mut self, agent_name: AgentName) {
+        use crate::claims::{to_claim_data, TlsClaim};
+
+        if let Some((register, _)) = self.library.claimer {
+            let claims = self.config.claims.clone();
+            let protocol_version = self.config.descriptor.tls_version;
+            let origin = self.config.descriptor.typ;
+
+            unsafe {
+                security_claims::register_claimer_with(
+                    register,
+                    self.tls_like(),
+                    move |claim: security_claims::Claim| {
+                        if let Some(data) = to_claim_data(protocol_version, claim) {
+                            claims.deref_borrow_mut().claim_sized(TlsClaim {
+                                agent_name,
+                                origin,
+                                protocol_version,
+                                data,
+                            })
+                        }
+                    },
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "claims")]
+    fn deregister_claimer(&mut self) {
+        if let Some((_, deregister)) = self.library.claimer {
+            unsafe {
+                security_claims::deregister_claimer_with(deregister, self.tls_like());
+            }
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn rename_agent(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        {
+            self.deregister_claimer();
+            self.register_claimer(agent_name);
+        }
+        Ok(())
+    }
+
+    fn describe_state(&self) -> &str {
+        &self.state
+    }
+
+    fn is_state_successful(&self) -> bool {
+        unsafe { (self.library.is_state_successful)(self.put) != 0 }
+    }
+
+    fn set_deterministic(&mut self) -> Result<(), Error> {
+        let result = self
+            .library
+            .set_deterministic
+            .map(|set_deterministic| unsafe { set_deterministic(DETERMINISTIC_SEED) });
+
+        if result == Some(PUT_OK) {
+            Ok(())
+        } else {
+            Err(Error::Agent(format!(
+                "Unable to make {} deterministic!",
+                self.library.path
+            )))
+        }
+    }
+
+    fn shutdown(&mut self) -> String {
+        format!(
+            "{} ({}): {}",
+            self.library.path,
+            self.library.version(),
+            self.state
+        )
+    }
+
+    fn version() -> String
+    where
+        Self: Sized,
+    {
+        // The version depends on the library which is loaded for an agent
+        "Undefined".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    use log::warn;
+    use puffin::{
+        algebra::dynamic_function::TypeShape,
+        put::{PutDescriptor, PutOptions},
+        trace::Query,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        put_registry::{DYLIB_PUT, TLS_PUT_REGISTRY},
+        query::TlsQueryMatcher,
+        tls::{
+            rustls::msgs::message::Message,
+            seeds::{seed_client_attacker_full, SeedHelper},
+        },
+    };
+
+    /// Compiles `test-put.c` into a shared object in `dir`. Returns `None` if no C compiler is
+    /// available.
+    fn compile_test_put(dir: &Path) -> Option<PathBuf> {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let library = dir.join("libtestput.so");
+
+        let status = match Command::new("cc")
+            .args(["-shared", "-fPIC"])
+            .arg("-I")
+            .arg(manifest_dir.join("src/dylib"))
+            .arg("-I")
+            .arg(manifest_dir.join("../tlspuffin-claims"))
+            .arg(manifest_dir.join("src/dylib/test-put.c"))
+            .arg("-o")
+            .arg(&library)
+            .status()
+        {
+            Ok(status) => status,
+            Err(err) => {
+                warn!("Skipping test: failed to run cc: {}", err);
+                return None;
+            }
+        };
+        assert!(status.success(), "failed to compile test-put.c");
+
+        Some(library)
+    }
+
+    #[test]
+    fn test_dylib_put() {
+        let temp_dir = tempdir().unwrap();
+        let library = match compile_test_put(temp_dir.path()) {
+            Some(library) => library,
+            None => return,
+        };
+        let put = PutDescriptor {
+            name: DYLIB_PUT,
+            options: PutOptions::new(vec![("lib", library.to_str().unwrap())]),
+        };
+
+        // Only the ClientHello of the attacker and the response of the server
+        let mut trace = seed_client_attacker_full.build_trace();
+        trace.steps.truncate(2);
+        let server = trace.descriptors[0].name;
+        let context = trace.execute_with_puts(&TLS_PUT_REGISTRY, &[(server, put)]);
+
+        let agent = context.find_agent(server).unwrap();
+        assert!(agent.put().describe_state().starts_with("alert after"));
+        assert!(!agent.put().is_state_successful());
+
+        let alert = context.find_variable(
+            TypeShape::of::<Message>(),
+            &Query {
+                agent_name: server,
+                matcher: Some(TlsQueryMatcher::Alert),
+                counter: 0,
+            },
+        );
+        assert!(alert.is_some());
+
+        // The length of the session ID which the library claimed is clamped
+        #[cfg(feature = "claims")]
+        {
+            use crate::claims::Finished;
+
+            let finished = context
+                .find_claim(server, TypeShape::of::<Finished>())
+                .unwrap();
+            let finished = finished.downcast_ref::<Finished>().unwrap();
+            assert_eq!(finished.session_id.as_deref(), Some(&[1; 32][..]));
+        }
+    }
+}
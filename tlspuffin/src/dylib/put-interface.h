@@ -0,0 +1,125 @@
+#ifndef TLSPUFFIN_PUT_INTERFACE_H
+#define TLSPUFFIN_PUT_INTERFACE_H
+
+/*
+ * Interface of TLS libraries which are loaded at runtime by the DYLIB PUT of tlspuffin. The path
+ * of the shared object is set through the `lib` option of the PUT, e.g.
+ * `--put DYLIB --put-option lib=/path/to/put.so`.
+ *
+ * The shared object exports the functions below. Functions which are marked as optional may be
+ * missing. Additionally, the shared object may export `register_claimer` and `deregister_claimer`
+ * of the claim-interface.h. Their `tls_like` argument is the value returned by `put_tls_like`,
+ * or the PUT itself if `put_tls_like` is missing.
+ *
+ * tlspuffin exchanges TLS records with the PUT through two buffers: It feeds inbound bytes with
+ * `put_add_inbound`, makes the PUT progress and drains the bytes which the PUT wants to send
+ * with `put_take_outbound`.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define PUT_INTERFACE_VERSION 1
+
+typedef enum PutAgentType {
+    PUT_AGENT_CLIENT = 0,
+    PUT_AGENT_SERVER = 1,
+} PutAgentType;
+
+typedef enum PutTLSVersion {
+    PUT_TLS_VERSION_V1_3 = 0,
+    PUT_TLS_VERSION_V1_2 = 1,
+    PUT_TLS_VERSION_V1_1 = 2,
+    PUT_TLS_VERSION_V1_0 = 3,
+    PUT_TLS_VERSION_SSL3 = 4,
+} PutTLSVersion;
+
+typedef enum PutResult {
+    PUT_OK = 0,
+    // The TLS library rejected the input. It may have written an alert to the outbound buffer.
+    PUT_ERROR = 1,
+} PutResult;
+
+typedef struct PutDescriptor {
+    PutAgentType typ;
+    // The maximum version which the PUT supports
+    PutTLSVersion tls_version;
+    // Whether a client verifies the certificate of the server
+    int server_authentication;
+    // Whether a server requests and verifies a certificate of the client
+    int client_authentication;
+
+    // PEM encoded certificate and private key of the PUT, NULL if the PUT has none
+    const char *cert;
+    const char *key;
+    // PEM encoded certificates which the PUT trusts when verifying its peer
+    const char *const *trusted_certs;
+    size_t trusted_certs_length;
+} PutDescriptor;
+
+/**
+ * Returns the value of PUT_INTERFACE_VERSION which the shared object implements
+ */
+uint32_t put_interface_version(void);
+
+/**
+ * Returns a textual representation of the version of the TLS library
+ */
+const char *put_version(void);
+
+/**
+ * Creates a client or server. The descriptor and its strings are only valid during the call.
+ *
+ * @return the new PUT, or NULL on error
+ */
+void *put_create(const PutDescriptor *descriptor);
+
+void put_destroy(void *put);
+
+/**
+ * Appends bytes which the peer sent to the inbound buffer of the PUT
+ */
+PutResult put_add_inbound(void *put, const uint8_t *data, size_t length);
+
+/**
+ * Moves up to `capacity` bytes from the outbound buffer of the PUT to `data`
+ *
+ * @return the number of moved bytes, 0 if the outbound buffer is empty
+ */
+size_t put_take_outbound(void *put, uint8_t *data, size_t capacity);
+
+/**
+ * Processes the inbound buffer and makes the handshake or the connection progress
+ */
+PutResult put_progress(void *put);
+
+/**
+ * Resets the PUT in place, such that it starts a new handshake
+ */
+PutResult put_reset(void *put);
+
+/**
+ * Returns a textual representation of the state of the PUT. It stays valid until the next call
+ * to a function of this interface with the same PUT.
+ */
+const char *put_describe_state(void *put);
+
+/**
+ * Returns 1 if the handshake completed successfully, else 0
+ */
+int put_is_state_successful(void *put);
+
+/**
+ * Optional: Returns the pointer which is passed as `tls_like` to the claim-interface.h
+ */
+const void *put_tls_like(void *put);
+
+/**
+ * Optional: Makes the random number generator of the TLS library deterministic, e.g. by seeding
+ * it with `seed`
+ *
+ * @return PUT_OK if the TLS library is deterministic from now on
+ */
+PutResult put_set_deterministic(uint64_t seed);
+
+#endif // TLSPUFFIN_PUT_INTERFACE_H
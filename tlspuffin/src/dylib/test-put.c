@@ -0,0 +1,118 @@
+/*
+ * A PUT for the tests of the DYLIB PUT. It answers every input with a fatal handshake_failure
+ * alert and claims a Finished whose session ID length exceeds the bounds of the claim.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "claim-interface.h"
+#include "put-interface.h"
+
+static const uint8_t ALERT[] = {0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28};
+
+typedef struct TestPut {
+    PutAgentType typ;
+    size_t received;
+    uint8_t outbound[sizeof(ALERT)];
+    size_t outbound_length;
+    char state[64];
+
+    claim_t claimer;
+    void *claimer_ctx;
+} TestPut;
+
+uint32_t put_interface_version(void) {
+    return PUT_INTERFACE_VERSION;
+}
+
+const char *put_version(void) {
+    return "test-put 1.0";
+}
+
+void *put_create(const PutDescriptor *descriptor) {
+    TestPut *put = calloc(1, sizeof(TestPut));
+    if (put == NULL) {
+        return NULL;
+    }
+
+    put->typ = descriptor->typ;
+    strcpy(put->state, "created");
+    return put;
+}
+
+void put_destroy(void *put) {
+    free(put);
+}
+
+PutResult put_add_inbound(void *put, const uint8_t *data, size_t length) {
+    (void) data;
+    ((TestPut *) put)->received += length;
+    return PUT_OK;
+}
+
+size_t put_take_outbound(void *put, uint8_t *data, size_t capacity) {
+    TestPut *test_put = put;
+
+    size_t length = test_put->outbound_length < capacity ? test_put->outbound_length : capacity;
+    memcpy(data, test_put->outbound, length);
+    memmove(test_put->outbound, test_put->outbound + length, test_put->outbound_length - length);
+    test_put->outbound_length -= length;
+    return length;
+}
+
+PutResult put_progress(void *put) {
+    TestPut *test_put = put;
+    if (test_put->received == 0) {
+        return PUT_OK;
+    }
+
+    memcpy(test_put->outbound, ALERT, sizeof(ALERT));
+    test_put->outbound_length = sizeof(ALERT);
+    snprintf(test_put->state, sizeof(test_put->state), "alert after %zu bytes", test_put->received);
+
+    if (test_put->claimer != NULL) {
+        Claim claim;
+        memset(&claim, 0, sizeof(claim));
+        claim.typ = CLAIM_FINISHED;
+        claim.write = 1;
+        claim.server = test_put->typ == PUT_AGENT_SERVER;
+        memset(claim.session_id.data, 1, sizeof(claim.session_id.data));
+        claim.session_id.length = 1000;
+        test_put->claimer(claim, test_put->claimer_ctx);
+    }
+
+    return PUT_OK;
+}
+
+PutResult put_reset(void *put) {
+    TestPut *test_put = put;
+    test_put->received = 0;
+    test_put->outbound_length = 0;
+    strcpy(test_put->state, "reset");
+    return PUT_OK;
+}
+
+const char *put_describe_state(void *put) {
+    return ((TestPut *) put)->state;
+}
+
+int put_is_state_successful(void *put) {
+    (void) put;
+    return 0;
+}
+
+void register_claimer(const void *tls_like, claim_t claimer, void *ctx) {
+    TestPut *test_put = (TestPut *) tls_like;
+    test_put->claimer = claimer;
+    test_put->claimer_ctx = ctx;
+}
+
+void *deregister_claimer(const void *tls_like) {
+    TestPut *test_put = (TestPut *) tls_like;
+    void *ctx = test_put->claimer_ctx;
+    test_put->claimer = NULL;
+    test_put->claimer_ctx = NULL;
+    return ctx;
+}
@@ -1,3 +1,5 @@
+#![feature(unix_socket_abstract)]
+
 //! TODO: Write intro: https://github.com/tlspuffin/tlspuffin/issues/94
 //!
 //! ### Used protocol and cryptographic libraries
@@ -102,13 +104,22 @@
 //! ```
 //!
 
+#[cfg(feature = "boringssl-binding")]
+pub mod boringssl;
 pub mod claims;
 pub mod debug;
+#[cfg(any(feature = "dtls12", feature = "dtls13"))]
+pub mod dtls;
+pub mod dylib;
+#[cfg(feature = "mbedtls-binding")]
+pub mod mbedtls;
 #[cfg(feature = "openssl-binding")]
 pub mod openssl;
 pub mod put;
 pub mod put_registry;
 pub mod query;
+#[cfg(feature = "rustls-binding")]
+pub mod rustls;
 pub mod static_certs;
 pub mod tcp;
 pub mod tls;
@@ -0,0 +1,46 @@
+use log::warn;
+
+/// The seed of the entropy source
+const SEED: u64 = 42;
+
+pub fn set_mbedtls_deterministic() {
+    warn!("Mbed TLS is no longer random!");
+    unsafe {
+        mbedtls_sys::puffin_mbedtls_set_deterministic(SEED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use crate::mbedtls::deterministic::set_mbedtls_deterministic;
+
+    fn poll_entropy() -> [u8; 16] {
+        let mut buf = [0; 16];
+        let mut length = 0;
+        assert_eq!(
+            unsafe {
+                mbedtls_sys::puffin_entropy_poll(
+                    ptr::null_mut(),
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    &mut length,
+                )
+            },
+            0
+        );
+        assert_eq!(length, buf.len());
+        buf
+    }
+
+    #[test]
+    fn test_mbedtls_no_randomness() {
+        set_mbedtls_deterministic();
+        let buf1 = poll_entropy();
+
+        set_mbedtls_deterministic();
+        let buf2 = poll_entropy();
+        assert_eq!(buf1, buf2);
+    }
+}
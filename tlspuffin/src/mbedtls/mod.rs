@@ -0,0 +1,730 @@
+//! A PUT for Mbed TLS. Mbed TLS reads from and writes to the [`MemoryStream`] through the send and
+//! receive callbacks of `mbedtls_ssl_set_bio`.
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    io::{ErrorKind, Read, Write},
+    mem,
+    os::raw::{c_char, c_int, c_uchar, c_void},
+    ptr,
+    rc::Rc,
+    slice,
+};
+
+use mbedtls_sys::{
+    mbedtls_cipher_type_t_MBEDTLS_CIPHER_AES_256_GCM, mbedtls_ctr_drbg_context,
+    mbedtls_ctr_drbg_free, mbedtls_ctr_drbg_init, mbedtls_ctr_drbg_random, mbedtls_ctr_drbg_seed,
+    mbedtls_entropy_add_source, mbedtls_entropy_context, mbedtls_entropy_free,
+    mbedtls_entropy_func, mbedtls_entropy_init, mbedtls_pk_context, mbedtls_pk_free,
+    mbedtls_pk_init, mbedtls_pk_parse_key, mbedtls_ssl_conf_authmode, mbedtls_ssl_conf_ca_chain,
+    mbedtls_ssl_conf_own_cert, mbedtls_ssl_conf_renegotiation, mbedtls_ssl_conf_rng,
+    mbedtls_ssl_conf_session_tickets_cb, mbedtls_ssl_config, mbedtls_ssl_config_defaults,
+    mbedtls_ssl_config_free, mbedtls_ssl_config_init, mbedtls_ssl_context, mbedtls_ssl_free,
+    mbedtls_ssl_handshake, mbedtls_ssl_init,
+    mbedtls_ssl_protocol_version_MBEDTLS_SSL_VERSION_TLS1_2,
+    mbedtls_ssl_protocol_version_MBEDTLS_SSL_VERSION_TLS1_3, mbedtls_ssl_read,
+    mbedtls_ssl_session_reset, mbedtls_ssl_set_bio, mbedtls_ssl_set_hostname, mbedtls_ssl_setup,
+    mbedtls_ssl_states_MBEDTLS_SSL_CERTIFICATE_REQUEST,
+    mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_CERTIFICATE,
+    mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_FINISHED, mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_HELLO,
+    mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_KEY_EXCHANGE,
+    mbedtls_ssl_states_MBEDTLS_SSL_ENCRYPTED_EXTENSIONS,
+    mbedtls_ssl_states_MBEDTLS_SSL_HANDSHAKE_OVER, mbedtls_ssl_states_MBEDTLS_SSL_HELLO_REQUEST,
+    mbedtls_ssl_states_MBEDTLS_SSL_SERVER_CERTIFICATE,
+    mbedtls_ssl_states_MBEDTLS_SSL_SERVER_FINISHED, mbedtls_ssl_states_MBEDTLS_SSL_SERVER_HELLO,
+    mbedtls_ssl_states_MBEDTLS_SSL_SERVER_HELLO_DONE,
+    mbedtls_ssl_states_MBEDTLS_SSL_SERVER_KEY_EXCHANGE, mbedtls_ssl_ticket_context,
+    mbedtls_ssl_ticket_free, mbedtls_ssl_ticket_init, mbedtls_ssl_ticket_parse,
+    mbedtls_ssl_ticket_setup, mbedtls_ssl_ticket_write, mbedtls_strerror,
+    mbedtls_version_get_string_full, mbedtls_x509_crt, mbedtls_x509_crt_free,
+    mbedtls_x509_crt_init, mbedtls_x509_crt_parse, psa_crypto_init, puffin_entropy_poll,
+    puffin_mbedtls_init_time, MBEDTLS_ENTROPY_SOURCE_STRONG, MBEDTLS_ERR_SSL_INTERNAL_ERROR,
+    MBEDTLS_ERR_SSL_PEER_CLOSE_NOTIFY, MBEDTLS_ERR_SSL_RECEIVED_NEW_SESSION_TICKET,
+    MBEDTLS_ERR_SSL_WANT_READ, MBEDTLS_ERR_SSL_WANT_WRITE, MBEDTLS_SSL_IS_CLIENT,
+    MBEDTLS_SSL_IS_SERVER, MBEDTLS_SSL_PRESET_DEFAULT, MBEDTLS_SSL_RENEGOTIATION_ENABLED,
+    MBEDTLS_SSL_TRANSPORT_STREAM, MBEDTLS_SSL_VERIFY_NONE, MBEDTLS_SSL_VERIFY_REQUIRED,
+};
+#[cfg(feature = "claims")]
+use mbedtls_sys::{
+    mbedtls_ssl_get_ciphersuite_id_from_ssl, mbedtls_ssl_get_peer_cert,
+    mbedtls_ssl_key_export_type,
+    mbedtls_ssl_key_export_type_MBEDTLS_SSL_KEY_EXPORT_TLS12_MASTER_SECRET,
+    mbedtls_ssl_key_export_type_MBEDTLS_SSL_KEY_EXPORT_TLS1_3_CLIENT_APPLICATION_TRAFFIC_SECRET,
+    mbedtls_ssl_set_export_keys_cb, mbedtls_tls_prf_types,
+};
+use puffin::{
+    agent::{AgentDescriptor, AgentName, AgentType, TLSVersion},
+    error::Error,
+    protocol::MessageResult,
+    put::{Put, PutName},
+    put_registry::Factory,
+    stream::{MemoryStream, Stream},
+    trace::TraceContext,
+};
+#[cfg(feature = "claims")]
+use smallvec::SmallVec;
+
+#[cfg(feature = "claims")]
+use crate::claims::{ClaimData, ClaimDataMessage, Finished, TlsClaim};
+use crate::{
+    protocol::TLSProtocolBehavior,
+    put::{TlsPutConfig, TlsPutOptions},
+    put_registry::MBEDTLS_PUT,
+    static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT},
+    tls::rustls::msgs::{
+        deframer::MessageDeframer,
+        message::{Message, OpaqueMessage},
+    },
+};
+
+#[cfg(feature = "deterministic")]
+mod deterministic;
+
+/// The lifetime of session tickets in seconds
+const TICKET_LIFETIME: u32 = 86400;
+
+pub fn new_mbedtls_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
+    struct MbedTLSFactory;
+    impl Factory<TLSProtocolBehavior> for MbedTLSFactory {
+        fn create(
+            &self,
+            context: &TraceContext<TLSProtocolBehavior>,
+            agent_descriptor: &AgentDescriptor,
+        ) -> Result<Box<dyn Put<TLSProtocolBehavior>>, Error> {
+            let options = TlsPutOptions::unsupported(
+                &context.put_descriptor(agent_descriptor).options,
+                "Mbed TLS",
+            )?;
+
+            let config = TlsPutConfig {
+                descriptor: agent_descriptor.clone(),
+                claims: context.claims().clone(),
+                authenticate_peer: agent_descriptor.typ == AgentType::Client
+                    && agent_descriptor.server_authentication
+                    || agent_descriptor.typ == AgentType::Server
+                        && agent_descriptor.client_authentication,
+                extract_deferred: Rc::new(RefCell::new(None)),
+                options,
+            };
+            Ok(Box::new(MbedTLS::new(config)?))
+        }
+
+        fn name(&self) -> PutName {
+            MBEDTLS_PUT
+        }
+
+        fn version(&self) -> String {
+            MbedTLS::version()
+        }
+    }
+
+    Box::new(MbedTLSFactory)
+}
+
+/// The contexts of Mbed TLS reference each other. Therefore, they are boxed so that their
+/// addresses do not change.
+pub struct MbedTLS {
+    ssl: Box<mbedtls_ssl_context>,
+    conf: Box<mbedtls_ssl_config>,
+    cert: Box<mbedtls_x509_crt>,
+    key: Box<mbedtls_pk_context>,
+    trusted_certs: Box<mbedtls_x509_crt>,
+    entropy: Box<mbedtls_entropy_context>,
+    ctr_drbg: Box<mbedtls_ctr_drbg_context>,
+    ticket: Box<mbedtls_ssl_ticket_context>,
+    /// Passed to the send and receive callbacks of Mbed TLS
+    stream: Box<MemoryStream<MessageDeframer>>,
+    /// Passed to the callback which exports the keys of Mbed TLS
+    #[cfg(feature = "claims")]
+    exported_keys: Box<ExportedKeys>,
+    /// The agent for which claims are made
+    #[cfg(feature = "claims")]
+    claimer: Option<AgentName>,
+    /// Whether the completed handshake of the connection has already been claimed
+    #[cfg(feature = "claims")]
+    claimed_finished: bool,
+    config: TlsPutConfig,
+}
+
+/// The randoms and the master secret of the latest handshake
+#[cfg(feature = "claims")]
+#[derive(Default)]
+struct ExportedKeys {
+    client_random: SmallVec<[u8; 32]>,
+    server_random: SmallVec<[u8; 32]>,
+    /// Mbed TLS only exports the master secret in TLS 1.2
+    master_secret: Option<SmallVec<[u8; 32]>>,
+}
+
+/// Allocates a context of Mbed TLS, which is initialized with `init`
+unsafe fn new_context<T>(init: unsafe extern "C" fn(*mut T)) -> Box<T> {
+    let mut context: Box<T> = Box::new(mem::zeroed());
+    init(&mut *context);
+    context
+}
+
+impl Drop for MbedTLS {
+    fn drop(&mut self) {
+        unsafe {
+            mbedtls_ssl_free(&mut *self.ssl);
+            mbedtls_ssl_config_free(&mut *self.conf);
+            mbedtls_ssl_ticket_free(&mut *self.ticket);
+            mbedtls_x509_crt_free(&mut *self.cert);
+            mbedtls_pk_free(&mut *self.key);
+            mbedtls_x509_crt_free(&mut *self.trusted_certs);
+            mbedtls_ctr_drbg_free(&mut *self.ctr_drbg);
+            mbedtls_entropy_free(&mut *self.entropy);
+        }
+    }
+}
+
+impl Stream<Message, OpaqueMessage> for MbedTLS {
+    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) -> Result<(), Error> {
+        <MemoryStream<MessageDeframer> as Stream<Message, OpaqueMessage>>::add_to_inbound(
+            &mut self.stream,
+            opaque_message,
+        )
+    }
+
+    fn take_message_from_outbound(
+        &mut self,
+    ) -> Result<Option<MessageResult<Message, OpaqueMessage>>, Error> {
+        MemoryStream::take_message_from_outbound(&mut self.stream)
+    }
+}
+
+unsafe extern "C" fn send(ctx: *mut c_void, buf: *const c_uchar, len: usize) -> c_int {
+    let stream = &mut *(ctx as *mut MemoryStream<MessageDeframer>);
+    match stream.write(slice::from_raw_parts(buf, len)) {
+        Ok(length) => length as c_int,
+        Err(_) => MBEDTLS_ERR_SSL_INTERNAL_ERROR,
+    }
+}
+
+unsafe extern "C" fn recv(ctx: *mut c_void, buf: *mut c_uchar, len: usize) -> c_int {
+    let stream = &mut *(ctx as *mut MemoryStream<MessageDeframer>);
+    match stream.read(slice::from_raw_parts_mut(buf, len)) {
+        Ok(length) => length as c_int,
+        // Not actually an error, we just reached the end of the inbound channel
+        Err(err) if err.kind() == ErrorKind::WouldBlock => MBEDTLS_ERR_SSL_WANT_READ,
+        Err(_) => MBEDTLS_ERR_SSL_INTERNAL_ERROR,
+    }
+}
+
+/// Exports the secrets from which the claims are made, see `mbedtls_ssl_export_keys_t`
+#[cfg(feature = "claims")]
+unsafe extern "C" fn export_keys(
+    p_expkey: *mut c_void,
+    typ: mbedtls_ssl_key_export_type,
+    secret: *const c_uchar,
+    secret_len: usize,
+    client_random: *const c_uchar,
+    server_random: *const c_uchar,
+    _tls_prf_type: mbedtls_tls_prf_types,
+) {
+    let exported_keys = &mut *(p_expkey as *mut ExportedKeys);
+
+    #[allow(non_upper_case_globals)]
+    let master_secret = match typ {
+        mbedtls_ssl_key_export_type_MBEDTLS_SSL_KEY_EXPORT_TLS12_MASTER_SECRET => Some(
+            SmallVec::from_slice(slice::from_raw_parts(secret, secret_len)),
+        ),
+        // The traffic secrets can not be compared with the master secrets of other PUTs
+        mbedtls_ssl_key_export_type_MBEDTLS_SSL_KEY_EXPORT_TLS1_3_CLIENT_APPLICATION_TRAFFIC_SECRET => {
+            None
+        }
+        _ => return,
+    };
+
+    exported_keys.client_random = SmallVec::from_slice(slice::from_raw_parts(client_random, 32));
+    exported_keys.server_random = SmallVec::from_slice(slice::from_raw_parts(server_random, 32));
+    exported_keys.master_secret = master_secret;
+}
+
+impl Put<TLSProtocolBehavior> for MbedTLS {
+    fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
+        unsafe {
+            if !self.is_state_successful() {
+                #[cfg(feature = "claims")]
+                {
+                    // Renegotiations start a new handshake
+                    self.claimed_finished = false;
+                }
+
+                check_progress(mbedtls_ssl_handshake(&mut *self.ssl))?;
+            }
+
+            if self.is_state_successful() {
+                #[cfg(feature = "claims")]
+                if !self.claimed_finished {
+                    self.claim_finished();
+                }
+
+                // Processes the messages which follow the handshake, e.g. session tickets
+                let mut buffer = [0u8; 128];
+                check_progress(mbedtls_ssl_read(
+                    &mut *self.ssl,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                ))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        {
+            self.claimed_finished = false;
+            *self.exported_keys = ExportedKeys::default();
+        }
+
+        check(unsafe { mbedtls_ssl_session_reset(&mut *self.ssl) })
+    }
+
+    fn descriptor(&self) -> &AgentDescriptor {
+        &self.config.descriptor
+    }
+
+    #[cfg(feature = "claims")]
+    fn register_claimer(&mut self, agent_name: AgentName) {
+        self.claimer = Some(agent_name);
+    }
+
+    #[cfg(feature = "claims")]
+    fn deregister_claimer(&mut self) {
+        self.claimer = None;
+    }
+
+    #[allow(unused_variables)]
+    fn rename_agent(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        {
+            self.deregister_claimer();
+            self.register_claimer(agent_name);
+        }
+        Ok(())
+    }
+
+    fn describe_state(&self) -> &str {
+        // The state is private, but there is no function which exposes it
+        #[allow(non_upper_case_globals)]
+        match self.ssl.private_state as u32 {
+            mbedtls_ssl_states_MBEDTLS_SSL_HELLO_REQUEST => "Hello request",
+            mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_HELLO => "Client hello",
+            mbedtls_ssl_states_MBEDTLS_SSL_SERVER_HELLO => "Server hello",
+            mbedtls_ssl_states_MBEDTLS_SSL_ENCRYPTED_EXTENSIONS => "Encrypted extensions",
+            mbedtls_ssl_states_MBEDTLS_SSL_SERVER_CERTIFICATE => "Server certificate",
+            mbedtls_ssl_states_MBEDTLS_SSL_SERVER_KEY_EXCHANGE => "Server key exchange",
+            mbedtls_ssl_states_MBEDTLS_SSL_CERTIFICATE_REQUEST => "Certificate request",
+            mbedtls_ssl_states_MBEDTLS_SSL_SERVER_HELLO_DONE => "Server hello done",
+            mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_CERTIFICATE => "Client certificate",
+            mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_KEY_EXCHANGE => "Client key exchange",
+            mbedtls_ssl_states_MBEDTLS_SSL_CLIENT_FINISHED => "Client finished",
+            mbedtls_ssl_states_MBEDTLS_SSL_SERVER_FINISHED => "Server finished",
+            mbedtls_ssl_states_MBEDTLS_SSL_HANDSHAKE_OVER => "Handshake over",
+            _ => "Handshake in progress",
+        }
+    }
+
+    fn is_state_successful(&self) -> bool {
+        // Like the inline function mbedtls_ssl_is_handshake_over. The states of TLS 1.3 which
+        // follow the handshake are sorted after MBEDTLS_SSL_HANDSHAKE_OVER.
+        self.ssl.private_state as u32 >= mbedtls_ssl_states_MBEDTLS_SSL_HANDSHAKE_OVER
+    }
+
+    fn set_deterministic(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "deterministic")]
+        {
+            deterministic::set_mbedtls_deterministic();
+            // The random number generator and the ticket keys have been seeded with random
+            // values when creating the PUT
+            unsafe { self.seed() }
+        }
+        #[cfg(not(feature = "deterministic"))]
+        {
+            Err(Error::Agent(
+                "Unable to make Mbed TLS deterministic!".to_string(),
+            ))
+        }
+    }
+
+    fn shutdown(&mut self) -> String {
+        panic!("Unsupported with Mbed TLS PUT")
+    }
+
+    fn version() -> String {
+        // The buffer needs to hold at least 18 bytes
+        let mut buffer = [0 as c_char; 64];
+        unsafe {
+            mbedtls_version_get_string_full(buffer.as_mut_ptr());
+            CStr::from_ptr(buffer.as_ptr())
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+}
+
+impl MbedTLS {
+    fn new(config: TlsPutConfig) -> Result<MbedTLS, Error> {
+        // TLS 1.3 is implemented on top of the PSA crypto API. This can be called multiple times.
+        if unsafe { psa_crypto_init() } != 0 {
+            return Err(Error::Put("Failed to initialize PSA crypto".to_string()));
+        }
+        // The time is part of the random values of TLS 1.2 and checked against the validity of
+        // the certificates
+        check(unsafe { puffin_mbedtls_init_time() })?;
+
+        let mut mbedtls = unsafe {
+            MbedTLS {
+                ssl: new_context(mbedtls_ssl_init),
+                conf: new_context(mbedtls_ssl_config_init),
+                cert: new_context(mbedtls_x509_crt_init),
+                key: new_context(mbedtls_pk_init),
+                trusted_certs: new_context(mbedtls_x509_crt_init),
+                entropy: new_context(mbedtls_entropy_init),
+                ctr_drbg: new_context(mbedtls_ctr_drbg_init),
+                ticket: new_context(mbedtls_ssl_ticket_init),
+                stream: Box::new(MemoryStream::new(MessageDeframer::new())),
+                #[cfg(feature = "claims")]
+                exported_keys: Box::new(ExportedKeys::default()),
+                #[cfg(feature = "claims")]
+                claimer: Some(config.descriptor.name),
+                #[cfg(feature = "claims")]
+                claimed_finished: false,
+                config,
+            }
+        };
+
+        unsafe {
+            check(mbedtls_entropy_add_source(
+                &mut *mbedtls.entropy,
+                Some(puffin_entropy_poll),
+                ptr::null_mut(),
+                32,
+                MBEDTLS_ENTROPY_SOURCE_STRONG as c_int,
+            ))?;
+            mbedtls.seed()?;
+            mbedtls.configure()?;
+
+            check(mbedtls_ssl_setup(&mut *mbedtls.ssl, &*mbedtls.conf))?;
+            if mbedtls.config.descriptor.typ == AgentType::Client {
+                // Our certificates are not issued for a hostname
+                check(mbedtls_ssl_set_hostname(&mut *mbedtls.ssl, ptr::null()))?;
+            }
+            let stream: *mut MemoryStream<MessageDeframer> = &mut *mbedtls.stream;
+            mbedtls_ssl_set_bio(
+                &mut *mbedtls.ssl,
+                stream.cast(),
+                Some(send),
+                Some(recv),
+                None,
+            );
+
+            #[cfg(feature = "claims")]
+            {
+                let exported_keys: *mut ExportedKeys = &mut *mbedtls.exported_keys;
+                mbedtls_ssl_set_export_keys_cb(
+                    &mut *mbedtls.ssl,
+                    Some(export_keys),
+                    exported_keys.cast(),
+                );
+            }
+        }
+
+        Ok(mbedtls)
+    }
+
+    /// Claims the completed handshake. Mbed TLS does not expose the session ID, the available
+    /// ciphers and the signature algorithms. In TLS 1.3, it exports the client application
+    /// traffic secret instead of the master secret. The traffic secret is derived from the master
+    /// secret and therefore is equal for both peers if their secrets are. This is why claims of
+    /// TLS 1.3 can only be compared to other claims of Mbed TLS.
+    #[cfg(feature = "claims")]
+    fn claim_finished(&mut self) {
+        self.claimed_finished = true;
+
+        let agent_name = match self.claimer {
+            Some(agent_name) => agent_name,
+            None => return,
+        };
+
+        #[allow(non_upper_case_globals)]
+        let protocol_version = match self.ssl.private_tls_version {
+            mbedtls_ssl_protocol_version_MBEDTLS_SSL_VERSION_TLS1_3 => TLSVersion::V1_3,
+            mbedtls_ssl_protocol_version_MBEDTLS_SSL_VERSION_TLS1_2 => TLSVersion::V1_2,
+            _ => self.config.descriptor.tls_version,
+        };
+
+        let peer_certificate = unsafe {
+            let certificate = mbedtls_ssl_get_peer_cert(&*self.ssl);
+            if certificate.is_null() {
+                SmallVec::new()
+            } else {
+                let raw = &(*certificate).raw;
+                SmallVec::from_slice(slice::from_raw_parts(raw.p, raw.len))
+            }
+        };
+
+        let chosen_cipher = unsafe { mbedtls_ssl_get_ciphersuite_id_from_ssl(&*self.ssl) } as u16;
+
+        self.config.claims.deref_borrow_mut().claim_sized(TlsClaim {
+            agent_name,
+            origin: self.config.descriptor.typ,
+            protocol_version,
+            data: ClaimData::Message(ClaimDataMessage::Finished(Finished {
+                outbound: false,
+                client_random: Some(self.exported_keys.client_random.clone()),
+                server_random: Some(self.exported_keys.server_random.clone()),
+                // Mbed TLS does not expose the session ID, the offered ciphers and the signature
+                // algorithms
+                session_id: None,
+                authenticate_peer: self.config.authenticate_peer,
+                peer_certificate,
+                master_secret: self.exported_keys.master_secret.clone(),
+                chosen_cipher,
+                available_ciphers: None,
+                signature_algorithm: None,
+                peer_signature_algorithm: None,
+            })),
+        });
+    }
+
+    /// Seeds the random number generator from the entropy source and creates the keys of the
+    /// session tickets
+    unsafe fn seed(&mut self) -> Result<(), Error> {
+        mbedtls_ctr_drbg_free(&mut *self.ctr_drbg);
+        mbedtls_ctr_drbg_init(&mut *self.ctr_drbg);
+        let entropy: *mut mbedtls_entropy_context = &mut *self.entropy;
+        check(mbedtls_ctr_drbg_seed(
+            &mut *self.ctr_drbg,
+            Some(mbedtls_entropy_func),
+            entropy.cast(),
+            ptr::null(),
+            0,
+        ))?;
+
+        if self.config.descriptor.typ == AgentType::Server {
+            mbedtls_ssl_ticket_free(&mut *self.ticket);
+            mbedtls_ssl_ticket_init(&mut *self.ticket);
+            let ctr_drbg: *mut mbedtls_ctr_drbg_context = &mut *self.ctr_drbg;
+            check(mbedtls_ssl_ticket_setup(
+                &mut *self.ticket,
+                Some(mbedtls_ctr_drbg_random),
+                ctr_drbg.cast(),
+                mbedtls_cipher_type_t_MBEDTLS_CIPHER_AES_256_GCM,
+                TICKET_LIFETIME,
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Configures a client or server with the same certificates as the other PUTs
+    unsafe fn configure(&mut self) -> Result<(), Error> {
+        let descriptor = &self.config.descriptor;
+        let conf: *mut mbedtls_ssl_config = &mut *self.conf;
+
+        let endpoint = match descriptor.typ {
+            AgentType::Server => MBEDTLS_SSL_IS_SERVER,
+            AgentType::Client => MBEDTLS_SSL_IS_CLIENT,
+        };
+        check(mbedtls_ssl_config_defaults(
+            conf,
+            endpoint as c_int,
+            MBEDTLS_SSL_TRANSPORT_STREAM as c_int,
+            MBEDTLS_SSL_PRESET_DEFAULT as c_int,
+        ))?;
+
+        // The setter mbedtls_ssl_conf_max_tls_version is an inline function
+        (*conf).private_max_tls_version = match descriptor.tls_version {
+            TLSVersion::V1_3 => mbedtls_ssl_protocol_version_MBEDTLS_SSL_VERSION_TLS1_3,
+            TLSVersion::V1_2 => mbedtls_ssl_protocol_version_MBEDTLS_SSL_VERSION_TLS1_2,
+            version => {
+                return Err(Error::Put(format!(
+                    "{:?} is not supported by Mbed TLS",
+                    version
+                )))
+            }
+        };
+
+        let ctr_drbg: *mut mbedtls_ctr_drbg_context = &mut *self.ctr_drbg;
+        mbedtls_ssl_conf_rng(conf, Some(mbedtls_ctr_drbg_random), ctr_drbg.cast());
+        mbedtls_ssl_conf_renegotiation(conf, MBEDTLS_SSL_RENEGOTIATION_ENABLED as c_int);
+
+        let (cert_and_key, trusted_certs, authenticate_peer) = match descriptor.typ {
+            AgentType::Server => {
+                let ticket: *mut mbedtls_ssl_ticket_context = &mut *self.ticket;
+                mbedtls_ssl_conf_session_tickets_cb(
+                    conf,
+                    Some(mbedtls_ssl_ticket_write),
+                    Some(mbedtls_ssl_ticket_parse),
+                    ticket.cast(),
+                );
+
+                (
+                    Some((ALICE_CERT.0, ALICE_PRIVATE_KEY.0)),
+                    [BOB_CERT.0, EVE_CERT.0],
+                    descriptor.client_authentication,
+                )
+            }
+            AgentType::Client => (
+                if descriptor.client_authentication {
+                    Some((BOB_CERT.0, BOB_PRIVATE_KEY.0))
+                } else {
+                    None
+                },
+                [ALICE_CERT.0, EVE_CERT.0],
+                descriptor.server_authentication,
+            ),
+        };
+
+        if let Some((cert, key)) = cert_and_key {
+            let cert = to_c_string(cert)?;
+            let key = to_c_string(key)?;
+            // The length of PEM data includes the null byte
+            check(mbedtls_x509_crt_parse(
+                &mut *self.cert,
+                cert.as_ptr().cast(),
+                cert.as_bytes_with_nul().len(),
+            ))?;
+            check(mbedtls_pk_parse_key(
+                &mut *self.key,
+                key.as_ptr().cast(),
+                key.as_bytes_with_nul().len(),
+                ptr::null(),
+                0,
+                Some(mbedtls_ctr_drbg_random),
+                ctr_drbg.cast(),
+            ))?;
+            check(mbedtls_ssl_conf_own_cert(
+                conf,
+                &mut *self.cert,
+                &mut *self.key,
+            ))?;
+        }
+
+        if authenticate_peer {
+            for trusted_cert in trusted_certs {
+                let trusted_cert = to_c_string(trusted_cert)?;
+                check(mbedtls_x509_crt_parse(
+                    &mut *self.trusted_certs,
+                    trusted_cert.as_ptr().cast(),
+                    trusted_cert.as_bytes_with_nul().len(),
+                ))?;
+            }
+            mbedtls_ssl_conf_ca_chain(conf, &mut *self.trusted_certs, ptr::null_mut());
+            mbedtls_ssl_conf_authmode(conf, MBEDTLS_SSL_VERIFY_REQUIRED as c_int);
+        } else {
+            mbedtls_ssl_conf_authmode(conf, MBEDTLS_SSL_VERIFY_NONE as c_int);
+        }
+
+        Ok(())
+    }
+}
+
+fn to_c_string(pem: &str) -> Result<CString, Error> {
+    CString::new(pem).map_err(|err| Error::Put(err.to_string()))
+}
+
+fn check(result: c_int) -> Result<(), Error> {
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(Error::Put(error_string(result)))
+    }
+}
+
+/// Checks the result of a function which progresses the connection
+fn check_progress(result: c_int) -> Result<(), Error> {
+    match result {
+        // mbedtls_ssl_read returns the number of bytes read
+        result if result >= 0 => Ok(()),
+        // Reaching the end of the inbound channel is not an error
+        MBEDTLS_ERR_SSL_WANT_READ
+        | MBEDTLS_ERR_SSL_WANT_WRITE
+        | MBEDTLS_ERR_SSL_RECEIVED_NEW_SESSION_TICKET
+        | MBEDTLS_ERR_SSL_PEER_CLOSE_NOTIFY => Ok(()),
+        // Mbed TLS failed, that means that there should be an Alert message in the outbound
+        // channel
+        error => Err(Error::Put(error_string(error))),
+    }
+}
+
+fn error_string(error: c_int) -> String {
+    let mut buffer = [0 as c_char; 256];
+    unsafe {
+        mbedtls_strerror(error, buffer.as_mut_ptr(), buffer.len());
+        CStr::from_ptr(buffer.as_ptr())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "claims")]
+mod tests {
+    use puffin::{
+        agent::{AgentName, TLSVersion},
+        claims::SecurityViolationPolicy,
+        put::PutDescriptor,
+        trace::Trace,
+    };
+    use test_log::test;
+
+    use crate::{
+        put_registry::{MBEDTLS_PUT, TLS_PUT_REGISTRY},
+        query::TlsQueryMatcher,
+        tls::{
+            seeds::{seed_successful, seed_successful12},
+            violation::{find_two_finished_messages, TlsSecurityViolationPolicy},
+        },
+    };
+
+    /// Both peers of a handshake between Mbed TLS agents claim the same randoms and secrets. The
+    /// master secret is only known in TLS 1.2.
+    fn check_finished_claims(seed: fn(AgentName, AgentName) -> Trace<TlsQueryMatcher>) {
+        let client = AgentName::first();
+        let server = client.next();
+        let put = PutDescriptor {
+            name: MBEDTLS_PUT,
+            ..PutDescriptor::default()
+        };
+
+        let context = seed(client, server)
+            .execute_with_puts(&TLS_PUT_REGISTRY, &[(client, put.clone()), (server, put)]);
+        let claims = context.claims().deref_borrow();
+
+        let ((client_claim, client), (_, server)) =
+            find_two_finished_messages(claims.slice()).expect("no Finished claims");
+        match client_claim.protocol_version {
+            TLSVersion::V1_3 => assert!(client.master_secret.is_none()),
+            _ => assert!(!client.master_secret.as_ref().unwrap().is_empty()),
+        }
+        assert_eq!(client.master_secret, server.master_secret);
+        assert!(client.session_id.is_none());
+        assert!(client.signature_algorithm.is_none());
+        assert_eq!(client.client_random.as_ref().unwrap().len(), 32);
+        assert_eq!(client.client_random, server.client_random);
+        assert_eq!(client.server_random, server.server_random);
+        assert_ne!(client.chosen_cipher, 0);
+        assert_eq!(
+            TlsSecurityViolationPolicy::check_violation(claims.slice()),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "tls13")]
+    fn test_mbedtls_finished_claims() {
+        check_finished_claims(seed_successful);
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_mbedtls_finished_claims12() {
+        check_finished_claims(seed_successful12);
+    }
+}
@@ -1,7 +1,7 @@
-use std::ffi::c_void;
+use std::ffi::{c_void, CString};
 
 use foreign_types_openssl::ForeignTypeRef;
-use libc::{c_int, c_long, c_ulong};
+use libc::{c_int, c_long, c_ulong, size_t};
 use openssl::{
     error::ErrorStack,
     pkey::Private,
@@ -11,7 +11,11 @@ use openssl::{
 use openssl_sys::{SSL_CTX_ctrl, SSL_CTX_set_options, RSA, SSL, SSL_CTX};
 
 const SSL_OP_ALLOW_NO_DHE_KEX: c_ulong = 0x00000400;
+#[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+const SSL_OP_NO_ANTI_REPLAY: c_ulong = 0x01000000;
 const SSL_CTRL_SET_TMP_RSA: c_int = 2;
+const SSL_CTRL_SET_GROUPS_LIST: c_int = 92;
+const SSL_CTRL_SET_SIGALGS_LIST: c_int = 98;
 
 unsafe fn SSL_CTX_set_tmp_rsa(ctx: *mut SSL_CTX, key: *mut RSA) -> c_long {
     SSL_CTX_ctrl(ctx, SSL_CTRL_SET_TMP_RSA, 0, key as *mut c_void)
@@ -20,7 +24,13 @@ unsafe fn SSL_CTX_set_tmp_rsa(ctx: *mut SSL_CTX, key: *mut RSA) -> c_long {
 extern "C" {
     fn SSL_clear(ssl: *mut SSL) -> c_int;
 
-    #[cfg(not(feature = "openssl111"))]
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+    fn SSL_CTX_set_post_handshake_auth(ctx: *mut SSL_CTX, val: c_int);
+
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+    fn SSL_CTX_set_num_tickets(ctx: *mut SSL_CTX, num_tickets: size_t) -> c_int;
+
+    #[cfg(not(any(feature = "openssl111", feature = "openssl3xx")))]
     fn SSL_CTX_set_tmp_rsa_callback(
         ctx: *mut SSL_CTX,
         ecdh: unsafe extern "C" fn(ssl: *mut SSL, is_export: c_int, keylength: c_int) -> *mut RSA,
@@ -29,7 +39,7 @@ extern "C" {
 
 #[cfg(all(
     any(feature = "openssl101f", feature = "openssl102u"),
-    not(feature = "openssl111")
+    not(any(feature = "openssl111", feature = "openssl3xx"))
 ))]
 unsafe extern "C" fn raw_tmp_rsa<F>(ssl: *mut SSL, is_export: c_int, keylength: c_int) -> *mut RSA
 where
@@ -56,7 +66,7 @@ where
 
 #[cfg(all(
     any(feature = "openssl101f", feature = "openssl102u"),
-    not(feature = "openssl111")
+    not(any(feature = "openssl111", feature = "openssl3xx"))
 ))]
 pub fn set_tmp_rsa_callback<F>(ctx: &mut SslContextBuilder, callback: F)
 where
@@ -89,6 +99,61 @@ pub fn set_allow_no_dhe_kex(ctx: &mut SslContextBuilder) {
     unsafe { SSL_CTX_set_options(ctx.as_ptr(), SSL_OP_ALLOW_NO_DHE_KEX) };
 }
 
+/// Disables the TLS 1.3 anti-replay protection, such that a server accepts 0-RTT data of a
+/// ticket more than once.
+#[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+pub fn set_no_anti_replay(ctx: &mut SslContextBuilder) {
+    unsafe { SSL_CTX_set_options(ctx.as_ptr(), SSL_OP_NO_ANTI_REPLAY) };
+}
+
+/// Allows a TLS 1.3 client to authenticate after the handshake if the server requests it.
+///
+/// This corresponds to `SSL_CTX_set_post_handshake_auth`.
+#[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+pub fn set_post_handshake_auth(ctx: &mut SslContextBuilder, enabled: bool) {
+    unsafe { SSL_CTX_set_post_handshake_auth(ctx.as_ptr(), enabled as c_int) };
+}
+
+/// Sets the supported groups from a colon separated list, e.g. `X25519:P-256`.
+///
+/// This corresponds to `SSL_CTX_set1_groups_list`.
+pub fn set_groups_list(ctx: &mut SslContextBuilder, groups: &str) -> Result<(), ErrorStack> {
+    let groups = CString::new(groups).unwrap();
+    unsafe {
+        cvt(SSL_CTX_ctrl(
+            ctx.as_ptr(),
+            SSL_CTRL_SET_GROUPS_LIST,
+            0,
+            groups.as_ptr() as *mut c_void,
+        ) as c_int)
+        .map(|_| ())
+    }
+}
+
+/// Sets the supported signature algorithms from a colon separated list, e.g. `RSA-PSS+SHA256`.
+///
+/// This corresponds to `SSL_CTX_set1_sigalgs_list`.
+pub fn set_sigalgs_list(ctx: &mut SslContextBuilder, sigalgs: &str) -> Result<(), ErrorStack> {
+    let sigalgs = CString::new(sigalgs).unwrap();
+    unsafe {
+        cvt(SSL_CTX_ctrl(
+            ctx.as_ptr(),
+            SSL_CTRL_SET_SIGALGS_LIST,
+            0,
+            sigalgs.as_ptr() as *mut c_void,
+        ) as c_int)
+        .map(|_| ())
+    }
+}
+
+/// Sets the number of TLS 1.3 tickets which a server sends after the handshake.
+///
+/// This corresponds to `SSL_CTX_set_num_tickets`.
+#[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+pub fn set_num_tickets(ctx: &mut SslContextBuilder, num_tickets: usize) -> Result<(), ErrorStack> {
+    unsafe { cvt(SSL_CTX_set_num_tickets(ctx.as_ptr(), num_tickets)).map(|_| ()) }
+}
+
 pub fn clear(ssl: &SslRef) -> u32 {
     unsafe { SSL_clear(ssl.as_ptr()) as u32 }
 }
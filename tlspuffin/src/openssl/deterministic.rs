@@ -32,4 +32,25 @@ mod tests {
         rand_bytes(&mut buf1).unwrap();
         assert_eq!(buf1, [70, 100]);
     }
+
+    /// openssl-sys builds the OpenSSL which is selected by the features of openssl-src
+    #[test]
+    #[cfg(feature = "openssl3xx")]
+    fn test_openssl3_linked() {
+        assert!(openssl::version::number() >= 0x3000_0000);
+    }
+
+    #[test]
+    #[cfg(feature = "openssl3xx")]
+    fn test_openssl3_no_randomness() {
+        use crate::openssl::deterministic::set_openssl_deterministic;
+        set_openssl_deterministic();
+        let mut buf1 = [0; 16];
+        rand_bytes(&mut buf1).unwrap();
+
+        set_openssl_deterministic();
+        let mut buf2 = [0; 16];
+        rand_bytes(&mut buf2).unwrap();
+        assert_eq!(buf1, buf2);
+    }
 }
@@ -0,0 +1,240 @@
+//! DTLS PUT for OpenSSL. OpenSSL 1.1.1 supports DTLS up to version 1.2.
+
+use std::{cell::RefCell, rc::Rc};
+
+use openssl::{
+    error::ErrorStack,
+    ssl::{Ssl, SslMethod, SslOptions, SslStream},
+};
+use puffin::{
+    agent::{AgentDescriptor, AgentName, AgentType, TLSVersion},
+    error::Error,
+    protocol::MessageResult,
+    put::{Put, PutName},
+    put_registry::Factory,
+    stream::Stream,
+    trace::TraceContext,
+};
+
+use crate::{
+    dtls::{
+        message::{DtlsMessage, DtlsOpaqueMessage},
+        protocol::DtlsProtocolBehavior,
+        stream::DatagramStream,
+    },
+    openssl::{bindings, check_options, MaybeError, OpenSSL},
+    put::{TlsPutConfig, TlsPutOptions},
+    put_registry::OPENSSL111_PUT,
+};
+
+/// The MTU which is announced to OpenSSL. The in-memory stream does not limit the size of
+/// datagrams, therefore this only needs to be large enough to fit the flights of the seeds.
+const DTLS_MTU: u32 = 1500;
+
+/// The cookie which is sent in the HelloVerifyRequest. It is static such that traces are
+/// deterministic.
+const DTLS_COOKIE: &[u8] = b"tlspuffin-dtls-cookie";
+
+pub fn new_openssl_dtls_factory() -> Box<dyn Factory<DtlsProtocolBehavior>> {
+    struct OpenSSLDtlsFactory;
+    impl Factory<DtlsProtocolBehavior> for OpenSSLDtlsFactory {
+        fn create(
+            &self,
+            context: &TraceContext<DtlsProtocolBehavior>,
+            agent_descriptor: &AgentDescriptor,
+        ) -> Result<Box<dyn Put<DtlsProtocolBehavior>>, Error> {
+            if agent_descriptor.tls_version != TLSVersion::V1_2 {
+                return Err(Error::Put(format!(
+                    "Unsupported DTLS version for OpenSSL: {:?}",
+                    agent_descriptor.tls_version
+                )));
+            }
+
+            let options = TlsPutOptions::parse(&context.put_descriptor(agent_descriptor).options)?;
+            options.check_dtls()?;
+            check_options(&options)?;
+
+            let config = TlsPutConfig {
+                descriptor: agent_descriptor.clone(),
+                claims: context.claims().clone(),
+                authenticate_peer: options.authenticate_peer(agent_descriptor),
+                extract_deferred: Rc::new(RefCell::new(None)),
+                options,
+            };
+            Ok(Box::new(OpenSSLDtls::new(config).map_err(|err| {
+                Error::Put(format!("Failed to create client/server: {}", err))
+            })?))
+        }
+
+        fn name(&self) -> PutName {
+            OPENSSL111_PUT
+        }
+
+        fn version(&self) -> String {
+            OpenSSLDtls::version()
+        }
+    }
+
+    Box::new(OpenSSLDtlsFactory)
+}
+
+pub struct OpenSSLDtls {
+    stream: SslStream<DatagramStream>,
+    config: TlsPutConfig,
+}
+
+impl Drop for OpenSSLDtls {
+    fn drop(&mut self) {
+        #[cfg(feature = "claims")]
+        self.deregister_claimer();
+    }
+}
+
+impl Stream<DtlsMessage, DtlsOpaqueMessage> for OpenSSLDtls {
+    fn add_to_inbound(&mut self, opaque_message: &DtlsOpaqueMessage) -> Result<(), Error> {
+        self.stream.get_mut().add_to_inbound(opaque_message)
+    }
+
+    fn take_message_from_outbound(
+        &mut self,
+    ) -> Result<Option<MessageResult<DtlsMessage, DtlsOpaqueMessage>>, Error> {
+        self.stream.get_mut().take_message_from_outbound()
+    }
+}
+
+impl Put<DtlsProtocolBehavior> for OpenSSLDtls {
+    fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
+        if self.is_state_successful() {
+            // Trigger another read
+            let mut vec: Vec<u8> = Vec::from([1; 128]);
+            let maybe_error: MaybeError = self.stream.ssl_read(&mut vec).into();
+            maybe_error.into()
+        } else {
+            let maybe_error: MaybeError = self.stream.do_handshake().into();
+            maybe_error.into()
+        }
+    }
+
+    fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
+        bindings::clear(self.stream.ssl());
+        Ok(())
+    }
+
+    fn descriptor(&self) -> &AgentDescriptor {
+        &self.config.descriptor
+    }
+
+    #[cfg(feature = "claims")]
+    fn register_claimer(&mut self, agent_name: AgentName) {
+        super::register_claimer(self.stream.ssl(), &self.config, agent_name);
+    }
+
+    #[cfg(feature = "claims")]
+    fn deregister_claimer(&mut self) {
+        unsafe {
+            use foreign_types_openssl::ForeignTypeRef;
+            security_claims::deregister_claimer(self.stream.ssl().as_ptr().cast());
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn rename_agent(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        {
+            self.deregister_claimer();
+            self.register_claimer(agent_name);
+        }
+        Ok(())
+    }
+
+    fn describe_state(&self) -> &str {
+        self.stream.ssl().state_string_long()
+    }
+
+    fn is_state_successful(&self) -> bool {
+        self.describe_state()
+            .contains("SSL negotiation finished successfully")
+    }
+
+    fn set_deterministic(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "deterministic")]
+        {
+            super::deterministic::set_openssl_deterministic();
+            Ok(())
+        }
+        #[cfg(not(feature = "deterministic"))]
+        {
+            Err(Error::Agent(
+                "Unable to make OpenSSL deterministic!".to_string(),
+            ))
+        }
+    }
+
+    fn shutdown(&mut self) -> String {
+        panic!("Unsupported with OpenSSL PUT")
+    }
+
+    fn version() -> String {
+        openssl::version::version().to_string()
+    }
+}
+
+impl OpenSSLDtls {
+    fn new(config: TlsPutConfig) -> Result<Self, ErrorStack> {
+        let agent_descriptor = &config.descriptor;
+        let mut ctx_builder = match agent_descriptor.typ {
+            AgentType::Server => OpenSSL::server_context_builder(
+                agent_descriptor,
+                &config.options,
+                SslMethod::dtls(),
+            )?,
+            AgentType::Client => OpenSSL::client_context_builder(
+                agent_descriptor,
+                &config.options,
+                SslMethod::dtls(),
+            )?,
+        };
+
+        // The MTU is set explicitly as the in-memory stream can not be queried for it
+        ctx_builder.set_options(SslOptions::NO_QUERY_MTU);
+
+        if config.options.tickets != Some(true) {
+            ctx_builder.set_options(SslOptions::NO_TICKET);
+        }
+
+        if agent_descriptor.typ == AgentType::Server {
+            // Always send a HelloVerifyRequest like servers which are exposed to the internet
+            ctx_builder.set_options(SslOptions::COOKIE_EXCHANGE);
+            ctx_builder.set_cookie_generate_cb(|_ssl, cookie| {
+                cookie[..DTLS_COOKIE.len()].copy_from_slice(DTLS_COOKIE);
+                Ok(DTLS_COOKIE.len())
+            });
+            ctx_builder.set_cookie_verify_cb(|_ssl, cookie| cookie == DTLS_COOKIE);
+        }
+
+        let mut ssl = Ssl::new(&ctx_builder.build())?;
+        ssl.set_mtu(DTLS_MTU)?;
+
+        match agent_descriptor.typ {
+            AgentType::Server => ssl.set_accept_state(),
+            AgentType::Client => ssl.set_connect_state(),
+        }
+
+        if let (AgentType::Client, Some(sni)) = (agent_descriptor.typ, &config.options.sni) {
+            ssl.set_hostname(sni)?;
+        }
+
+        let stream = SslStream::new(ssl, DatagramStream::new())?;
+
+        #[cfg(feature = "claims")]
+        let agent_name = agent_descriptor.name;
+
+        #[allow(unused_mut)]
+        let mut openssl = OpenSSLDtls { config, stream };
+
+        #[cfg(feature = "claims")]
+        openssl.register_claimer(agent_name);
+
+        Ok(openssl)
+    }
+}
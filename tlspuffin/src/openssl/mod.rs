@@ -8,9 +8,12 @@ use std::{
 };
 
 use openssl::{
+    dh::Dh,
     error::ErrorStack,
     pkey::{PKeyRef, Private},
-    ssl::{Ssl, SslContext, SslMethod, SslOptions, SslStream, SslVerifyMode},
+    ssl::{
+        Ssl, SslContext, SslContextBuilder, SslMethod, SslOptions, SslRef, SslStream, SslVerifyMode,
+    },
     stack::Stack,
     x509::{
         store::{X509Store, X509StoreBuilder},
@@ -26,19 +29,19 @@ use puffin::{
     stream::{MemoryStream, Stream},
     trace::TraceContext,
 };
+#[cfg(feature = "claims")]
 use smallvec::SmallVec;
 
+#[cfg(feature = "claims")]
+use crate::claims::{to_claim_data, ClaimData, ClaimDataMessage, Renegotiation, TlsClaim};
 use crate::{
-    claims::{
-        ClaimData, ClaimDataMessage, ClaimDataTranscript, ClientHello, Finished, TlsClaim,
-        TlsTranscript, TranscriptCertificate, TranscriptClientFinished, TranscriptClientHello,
-        TranscriptPartialClientHello, TranscriptServerFinished, TranscriptServerHello,
-    },
-    openssl::util::{set_max_protocol_version, static_rsa_cert},
+    openssl::util::{set_max_protocol_version, set_min_protocol_version, static_rsa_cert},
     protocol::TLSProtocolBehavior,
-    put::TlsPutConfig,
+    put::{check_tls_version, TlsPutConfig, TlsPutOptions, VerifyMode},
     put_registry::OPENSSL111_PUT,
-    static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT},
+    static_certs::{
+        ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT, FFDHE2048_PARAMS,
+    },
     tls::rustls::msgs::{
         deframer::MessageDeframer,
         message::{Message, OpaqueMessage},
@@ -48,6 +51,8 @@ use crate::{
 mod bindings;
 #[cfg(feature = "deterministic")]
 mod deterministic;
+#[cfg(any(feature = "dtls12", feature = "dtls13"))]
+pub mod dtls;
 mod util;
 
 /*
@@ -65,14 +70,16 @@ pub fn new_openssl_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
             context: &TraceContext<TLSProtocolBehavior>,
             agent_descriptor: &AgentDescriptor,
         ) -> Result<Box<dyn Put<TLSProtocolBehavior>>, Error> {
+            check_tls_version(agent_descriptor)?;
+            let options = TlsPutOptions::parse(&context.put_descriptor(agent_descriptor).options)?;
+            check_options(&options)?;
+
             let config = TlsPutConfig {
                 descriptor: agent_descriptor.clone(),
                 claims: context.claims().clone(),
-                authenticate_peer: agent_descriptor.typ == AgentType::Client
-                    && agent_descriptor.server_authentication
-                    || agent_descriptor.typ == AgentType::Server
-                        && agent_descriptor.client_authentication,
+                authenticate_peer: options.authenticate_peer(agent_descriptor),
                 extract_deferred: Rc::new(RefCell::new(None)),
+                options,
             };
             Ok(Box::new(OpenSSL::new(config).map_err(|err| {
                 Error::Put(format!("Failed to create client/server: {}", err))
@@ -91,9 +98,41 @@ pub fn new_openssl_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
     Box::new(OpenSSLFactory)
 }
 
+/// Rejects the options which the linked OpenSSL version can not apply. Shared between the TLS and
+/// DTLS PUTs.
+#[allow(unused_variables)]
+fn check_options(options: &TlsPutOptions) -> Result<(), Error> {
+    #[cfg(not(any(feature = "openssl111", feature = "openssl3xx")))]
+    if options.ciphersuites.is_some() || options.min_version == Some(TLSVersion::V1_3) {
+        return Err(Error::Put(
+            "TLS 1.3 options require OpenSSL 1.1.1 or newer".to_string(),
+        ));
+    }
+
+    #[cfg(feature = "openssl101f")]
+    if options.groups.is_some() || options.sigalgs.is_some() || options.alpn.is_some() {
+        return Err(Error::Put(
+            "The options groups, sigalgs and alpn require OpenSSL 1.0.2 or newer".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Maximum amount of 0-RTT data which TLS 1.3 servers accept
+#[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+const MAX_EARLY_DATA: u32 = 16384;
+
 pub struct OpenSSL {
     stream: SslStream<MemoryStream<MessageDeframer>>,
     config: TlsPutConfig,
+    /// Whether the server still has to read 0-RTT data before continuing the handshake
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+    reading_early_data: bool,
+    /// The verify_data of the Finished messages of the client and server in the latest completed
+    /// handshake
+    #[cfg(feature = "claims")]
+    verify_data: Option<(SmallVec<[u8; 32]>, SmallVec<[u8; 32]>)>,
 }
 
 impl Drop for OpenSSL {
@@ -104,7 +143,7 @@ impl Drop for OpenSSL {
 }
 
 impl Stream<Message, OpaqueMessage> for OpenSSL {
-    fn add_to_inbound(&mut self, result: &OpaqueMessage) {
+    fn add_to_inbound(&mut self, result: &OpaqueMessage) -> Result<(), Error> {
         <MemoryStream<MessageDeframer> as Stream<Message, OpaqueMessage>>::add_to_inbound(
             self.stream.get_mut(),
             result,
@@ -121,120 +160,104 @@ impl Stream<Message, OpaqueMessage> for OpenSSL {
     }
 }
 
-fn to_claim_data(protocol_version: TLSVersion, claim: security_claims::Claim) -> Option<ClaimData> {
-    match claim.typ {
-        // Transcripts
-        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH => Some(ClaimData::Transcript(
-            ClaimDataTranscript::ClientHello(TranscriptClientHello(TlsTranscript(
-                claim.transcript.data,
-                claim.transcript.length,
-            ))),
-        )),
-        security_claims::ClaimType::CLAIM_TRANSCRIPT_PARTIAL_CH => Some(ClaimData::Transcript(
-            ClaimDataTranscript::PartialClientHello(TranscriptPartialClientHello(TlsTranscript(
-                claim.transcript.data,
-                claim.transcript.length,
-            ))),
-        )),
-        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_SH => Some(ClaimData::Transcript(
-            ClaimDataTranscript::ServerHello(TranscriptServerHello(TlsTranscript(
-                claim.transcript.data,
-                claim.transcript.length,
-            ))),
-        )),
-        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_SERVER_FIN => Some(ClaimData::Transcript(
-            ClaimDataTranscript::ServerFinished(TranscriptServerFinished(TlsTranscript(
-                claim.transcript.data,
-                claim.transcript.length,
-            ))),
-        )),
-        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_CLIENT_FIN => Some(ClaimData::Transcript(
-            ClaimDataTranscript::ClientFinished(TranscriptClientFinished(TlsTranscript(
-                claim.transcript.data,
-                claim.transcript.length,
-            ))),
-        )),
-        security_claims::ClaimType::CLAIM_TRANSCRIPT_CH_CERT => Some(ClaimData::Transcript(
-            ClaimDataTranscript::Certificate(TranscriptCertificate(TlsTranscript(
-                claim.transcript.data,
-                claim.transcript.length,
-            ))),
-        )),
-        // Messages
-        // Transcripts in these messages are not up-to-date. They get updated after the Message has
-        // been processed
-        security_claims::ClaimType::CLAIM_FINISHED => {
-            Some(ClaimData::Message(ClaimDataMessage::Finished(Finished {
-                outbound: claim.write > 0,
-                client_random: SmallVec::from(claim.client_random.data),
-                server_random: SmallVec::from(claim.server_random.data),
-                session_id: SmallVec::from_slice(
-                    &claim.session_id.data[..claim.session_id.length as usize],
-                ),
-                authenticate_peer: false,             // FIXME
-                peer_certificate: Default::default(), // FIXME
-                master_secret: match protocol_version {
-                    TLSVersion::V1_3 => SmallVec::from_slice(&claim.master_secret.secret),
-                    TLSVersion::V1_2 => SmallVec::from_slice(&claim.master_secret_12.secret),
-                },
-                chosen_cipher: claim.chosen_cipher.data,
-                available_ciphers: SmallVec::from_iter(
-                    claim.available_ciphers.ciphers[..claim.available_ciphers.length as usize]
-                        .iter()
-                        .map(|cipher| cipher.data),
-                ),
-                signature_algorithm: claim.signature_algorithm,
-                peer_signature_algorithm: claim.peer_signature_algorithm,
-            })))
-        }
-        security_claims::ClaimType::CLAIM_CLIENT_HELLO => None,
-        security_claims::ClaimType::CLAIM_CCS => None,
-        security_claims::ClaimType::CLAIM_END_OF_EARLY_DATA => None,
-        security_claims::ClaimType::CLAIM_CERTIFICATE => None,
-        security_claims::ClaimType::CLAIM_KEY_EXCHANGE => None,
-        // FIXME it is weird that this returns the correct transcript
-        security_claims::ClaimType::CLAIM_CERTIFICATE_VERIFY => {
-            if claim.write == 0 {
-                Some(ClaimData::Transcript(ClaimDataTranscript::ServerFinished(
-                    TranscriptServerFinished(TlsTranscript(
-                        claim.transcript.data,
-                        claim.transcript.length,
-                    )),
-                )))
-            } else {
-                None
+/// Fills in whether the peer is required to authenticate and the DER encoded leaf certificate
+/// which the peer presented. This upgrades `claim` to the current claim-interface.h.
+#[cfg(feature = "claims")]
+fn claim_peer_authentication(ssl: &SslRef, claim: &mut security_claims::Claim) {
+    let verify_mode = ssl.verify_mode();
+    let authenticate_peer = if ssl.is_server() {
+        verify_mode.contains(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT)
+    } else {
+        verify_mode.contains(SslVerifyMode::PEER)
+    };
+    claim.authenticate_peer = authenticate_peer as i32;
+    claim.peer_certificate.length = 0;
+    claim.interface_version = security_claims::CLAIM_INTERFACE_VERSION as i32;
+
+    if let Some(der) = ssl
+        .peer_certificate()
+        .and_then(|certificate| certificate.to_der().ok())
+    {
+        let peer_certificate = &mut claim.peer_certificate;
+        let length = der.len().min(peer_certificate.data.len());
+        peer_certificate.data[..length].copy_from_slice(&der[..length]);
+        peer_certificate.length = length as i32;
+    }
+}
+
+/// Registers a claimer for `ssl` which forwards the claims of the library to the claims of the
+/// trace. Shared between the TLS and DTLS PUTs.
+#[cfg(feature = "claims")]
+fn register_claimer(ssl: &SslRef, config: &TlsPutConfig, agent_name: AgentName) {
+    unsafe {
+        use foreign_types_openssl::ForeignTypeRef;
+
+        let claims = config.claims.clone();
+        let protocol_version = config.descriptor.tls_version;
+        let origin = config.descriptor.typ;
+        let ssl = ssl.as_ptr();
+
+        security_claims::register_claimer(ssl.cast(), move |mut claim: security_claims::Claim| {
+            if claim.typ == security_claims::ClaimType::CLAIM_FINISHED
+                && !crate::claims::reports_peer_authentication(&claim)
+            {
+                // The claimer of the library did not report the peer authentication
+                claim_peer_authentication(SslRef::from_ptr(ssl), &mut claim);
             }
-        }
-        security_claims::ClaimType::CLAIM_KEY_UPDATE => None,
-        security_claims::ClaimType::CLAIM_HELLO_REQUEST => None,
-        security_claims::ClaimType::CLAIM_SERVER_HELLO => None,
-        security_claims::ClaimType::CLAIM_CERTIFICATE_REQUEST => None,
-        security_claims::ClaimType::CLAIM_SERVER_DONE => None,
-        security_claims::ClaimType::CLAIM_SESSION_TICKET => None,
-        security_claims::ClaimType::CLAIM_CERTIFICATE_STATUS => None,
-        security_claims::ClaimType::CLAIM_EARLY_DATA => None,
-        security_claims::ClaimType::CLAIM_ENCRYPTED_EXTENSIONS => None,
-        _ => None,
+
+            if let Some(data) = to_claim_data(protocol_version, claim) {
+                claims.deref_borrow_mut().claim_sized(TlsClaim {
+                    agent_name,
+                    origin,
+                    protocol_version,
+                    data,
+                })
+            }
+        });
     }
 }
 
 impl Put<TLSProtocolBehavior> for OpenSSL {
-    fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
+    #[cfg_attr(not(feature = "claims"), allow(unused_variables))]
+    fn progress(&mut self, agent_name: &AgentName) -> Result<(), Error> {
         let result = if self.is_state_successful() {
             // Trigger another read
             let mut vec: Vec<u8> = Vec::from([1; 128]);
             let maybe_error: MaybeError = self.stream.ssl_read(&mut vec).into();
             maybe_error.into()
         } else {
+            #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+            if self.reading_early_data {
+                self.read_early_data()?;
+
+                if self.reading_early_data {
+                    return Ok(());
+                }
+            }
+
             let maybe_error: MaybeError = self.stream.do_handshake().into();
             maybe_error.into()
         };
 
+        #[cfg(feature = "claims")]
+        self.claim_renegotiation(*agent_name);
+
         result
     }
 
-    fn reset(&mut self, agent_name: AgentName) -> Result<(), Error> {
+    fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
         bindings::clear(self.stream.ssl());
+
+        #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+        {
+            self.reading_early_data = Self::accepts_early_data(&self.config.descriptor);
+        }
+
+        #[cfg(feature = "claims")]
+        {
+            self.verify_data = None;
+        }
+
         Ok(())
     }
 
@@ -244,27 +267,7 @@ impl Put<TLSProtocolBehavior> for OpenSSL {
 
     #[cfg(feature = "claims")]
     fn register_claimer(&mut self, agent_name: AgentName) {
-        unsafe {
-            use foreign_types_openssl::ForeignTypeRef;
-
-            let claims = self.config.claims.clone();
-            let protocol_version = self.config.descriptor.tls_version;
-            let origin = self.config.descriptor.typ;
-
-            security_claims::register_claimer(
-                self.stream.ssl().as_ptr().cast(),
-                move |claim: security_claims::Claim| {
-                    if let Some(data) = to_claim_data(protocol_version, claim) {
-                        claims.deref_borrow_mut().claim_sized(TlsClaim {
-                            agent_name,
-                            origin,
-                            protocol_version,
-                            data,
-                        })
-                    }
-                },
-            );
-        }
+        register_claimer(self.stream.ssl(), &self.config, agent_name);
     }
 
     #[cfg(feature = "claims")]
@@ -326,8 +329,8 @@ impl OpenSSL {
     fn new(config: TlsPutConfig) -> Result<OpenSSL, ErrorStack> {
         let agent_descriptor = &config.descriptor;
         let mut ssl = match agent_descriptor.typ {
-            AgentType::Server => Self::create_server(agent_descriptor)?,
-            AgentType::Client => Self::create_client(agent_descriptor)?,
+            AgentType::Server => Self::create_server(agent_descriptor, &config.options)?,
+            AgentType::Client => Self::create_client(agent_descriptor, &config.options)?,
         };
 
         let stream = SslStream::new(ssl, MemoryStream::new(MessageDeframer::new()))?;
@@ -335,7 +338,15 @@ impl OpenSSL {
         #[cfg(feature = "claims")]
         let agent_name = agent_descriptor.name;
 
-        let mut openssl = OpenSSL { config, stream };
+        #[allow(unused_mut)]
+        let mut openssl = OpenSSL {
+            #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+            reading_early_data: Self::accepts_early_data(&config.descriptor),
+            #[cfg(feature = "claims")]
+            verify_data: None,
+            config,
+            stream,
+        };
 
         #[cfg(feature = "claims")]
         openssl.register_claimer(agent_name);
@@ -343,33 +354,135 @@ impl OpenSSL {
         Ok(openssl)
     }
 
-    fn create_server(descriptor: &AgentDescriptor) -> Result<Ssl, ErrorStack> {
-        let mut ctx_builder = SslContext::builder(SslMethod::tls())?;
+    fn create_server(
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+    ) -> Result<Ssl, ErrorStack> {
+        let mut ctx_builder = Self::server_context_builder(descriptor, options, SslMethod::tls())?;
+
+        Self::set_protocol_versions(&mut ctx_builder, descriptor, options)?;
+
+        #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+        if Self::accepts_early_data(descriptor) {
+            ctx_builder.set_max_early_data(MAX_EARLY_DATA)?;
+            bindings::set_no_anti_replay(&mut ctx_builder);
+        }
+
+        let mut ssl = Ssl::new(&ctx_builder.build())?;
+        ssl.set_accept_state();
+
+        Ok(ssl)
+    }
+
+    /// TLS 1.3 servers accept 0-RTT data if the descriptor asks for it. The anti-replay
+    /// protection of OpenSSL is disabled, such that replays are left to the security policy.
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+    fn accepts_early_data(descriptor: &AgentDescriptor) -> bool {
+        descriptor.early_data
+            && descriptor.typ == AgentType::Server
+            && descriptor.tls_version == TLSVersion::V1_3
+    }
+
+    /// Reads the 0-RTT data until the EndOfEarlyData has been processed or the early data has
+    /// been rejected. Afterwards, the handshake continues as usual.
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+    fn read_early_data(&mut self) -> Result<(), Error> {
+        let mut early_data = [0u8; 128];
+        loop {
+            match self.stream.read_early_data(&mut early_data) {
+                Ok(0) => {
+                    self.reading_early_data = false;
+                    return Ok(());
+                }
+                Ok(_) => continue,
+                Err(err) => {
+                    let maybe_error: MaybeError = Err::<(), _>(err).into();
+                    return maybe_error.into();
+                }
+            }
+        }
+    }
+
+    /// Claims a [`Renegotiation`] once a handshake completes which replaced a previous handshake
+    /// on this connection. OpenSSL only keeps the Finished messages of the latest handshake,
+    /// therefore the ones of the previous handshake are remembered here.
+    #[cfg(feature = "claims")]
+    fn claim_renegotiation(&mut self, agent_name: AgentName) {
+        if !self.is_state_successful() {
+            return;
+        }
+
+        let ssl = self.stream.ssl();
+        let mut finished = [0u8; 64];
+        let mut peer_finished = [0u8; 64];
+        let finished_len = ssl.finished(&mut finished).min(finished.len());
+        let peer_finished_len = ssl
+            .peer_finished(&mut peer_finished)
+            .min(peer_finished.len());
+
+        let finished = SmallVec::from_slice(&finished[..finished_len]);
+        let peer_finished = SmallVec::from_slice(&peer_finished[..peer_finished_len]);
+        let verify_data = if ssl.is_server() {
+            (peer_finished, finished)
+        } else {
+            (finished, peer_finished)
+        };
+
+        if let Some(previous_verify_data) = self.verify_data.take() {
+            if previous_verify_data != verify_data {
+                let (previous_client_verify_data, previous_server_verify_data) =
+                    previous_verify_data;
+                self.config.claims.deref_borrow_mut().claim_sized(TlsClaim {
+                    agent_name,
+                    origin: self.config.descriptor.typ,
+                    protocol_version: self.config.descriptor.tls_version,
+                    data: ClaimData::Message(ClaimDataMessage::Renegotiation(Renegotiation {
+                        previous_client_verify_data,
+                        previous_server_verify_data,
+                    })),
+                });
+            }
+        }
+
+        self.verify_data = Some(verify_data);
+    }
+
+    /// Creates a context for a server which is shared between the TLS and DTLS PUTs. The maximum
+    /// protocol version is not restricted yet.
+    fn server_context_builder(
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+        method: SslMethod,
+    ) -> Result<SslContextBuilder, ErrorStack> {
+        let mut ctx_builder = SslContext::builder(method)?;
 
         let (cert, key) = static_rsa_cert(ALICE_PRIVATE_KEY.0.as_bytes(), ALICE_CERT.0.as_bytes())?;
         ctx_builder.set_certificate(&cert)?;
         ctx_builder.set_private_key(&key)?;
 
-        if descriptor.client_authentication {
+        let verify_mode = options.verify_mode(descriptor);
+        if verify_mode == VerifyMode::Disabled {
+            ctx_builder.set_verify(SslVerifyMode::NONE);
+        } else {
             let mut store = X509StoreBuilder::new()?;
             store.add_cert(X509::from_pem(BOB_CERT.0.as_bytes())?)?;
             store.add_cert(X509::from_pem(EVE_CERT.0.as_bytes())?)?;
             let store = store.build();
 
-            ctx_builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+            if verify_mode == VerifyMode::Required {
+                ctx_builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+            } else {
+                ctx_builder.set_verify(SslVerifyMode::PEER);
+            }
             ctx_builder.set_cert_store(store);
-        } else {
-            ctx_builder.set_verify(SslVerifyMode::NONE);
         }
 
-        #[cfg(feature = "openssl111")]
+        #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
         ctx_builder.clear_options(openssl::ssl::SslOptions::ENABLE_MIDDLEBOX_COMPAT);
 
-        #[cfg(feature = "openssl111")]
+        #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
         bindings::set_allow_no_dhe_kex(&mut ctx_builder);
 
-        set_max_protocol_version(&mut ctx_builder, descriptor.tls_version)?;
-
         #[cfg(any(feature = "openssl101f", feature = "openssl102u"))]
         {
             ctx_builder.set_tmp_ecdh(
@@ -379,28 +492,142 @@ impl OpenSSL {
             bindings::set_tmp_rsa(&ctx_builder, &openssl::rsa::Rsa::generate(512)?)?;
         }
 
+        // Enables the DHE cipher suites
+        ctx_builder.set_tmp_dh(&Dh::params_from_pem(FFDHE2048_PARAMS.0.as_bytes())?)?;
+
         // Allow EXPORT in server
-        ctx_builder.set_cipher_list("ALL:EXPORT:!LOW:!aNULL:!eNULL:!SSLv2")?;
+        ctx_builder.set_cipher_list(
+            options
+                .cipher_list
+                .as_deref()
+                .unwrap_or("ALL:EXPORT:!LOW:!aNULL:!eNULL:!SSLv2"),
+        )?;
+
+        Self::configure_context(&mut ctx_builder, options)?;
+
+        #[cfg(not(feature = "openssl101f"))]
+        if let Some(server_protocols) = Self::alpn_protocols(options) {
+            ctx_builder.set_alpn_select_callback(move |_ssl, client_protocols| {
+                openssl::ssl::select_next_proto(&server_protocols, client_protocols)
+                    .ok_or(openssl::ssl::AlpnError::NOACK)
+            });
+        }
+
+        Ok(ctx_builder)
+    }
+
+    fn create_client(
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+    ) -> Result<Ssl, ErrorStack> {
+        let mut ctx_builder = Self::client_context_builder(descriptor, options, SslMethod::tls())?;
+
+        Self::set_protocol_versions(&mut ctx_builder, descriptor, options)?;
 
         let mut ssl = Ssl::new(&ctx_builder.build())?;
-        ssl.set_accept_state();
+        ssl.set_connect_state();
+
+        if let Some(sni) = &options.sni {
+            ssl.set_hostname(sni)?;
+        }
 
         Ok(ssl)
     }
 
-    fn create_client(descriptor: &AgentDescriptor) -> Result<Ssl, ErrorStack> {
-        let mut ctx_builder = SslContext::builder(SslMethod::tls())?;
+    /// Limits the protocol versions to the version of the agent, unless the options set other
+    /// bounds
+    fn set_protocol_versions(
+        ctx_builder: &mut SslContextBuilder,
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+    ) -> Result<(), ErrorStack> {
+        set_max_protocol_version(
+            ctx_builder,
+            options.max_version.unwrap_or(descriptor.tls_version),
+        )?;
+
+        if let Some(min_version) = options.min_version {
+            set_min_protocol_version(ctx_builder, min_version)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the options which are the same for clients and servers. Shared between the TLS
+    /// and DTLS PUTs.
+    fn configure_context(
+        ctx_builder: &mut SslContextBuilder,
+        options: &TlsPutOptions,
+    ) -> Result<(), ErrorStack> {
+        #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+        if let Some(ciphersuites) = &options.ciphersuites {
+            ctx_builder.set_ciphersuites(ciphersuites)?;
+        }
+
+        if let Some(groups) = &options.groups {
+            bindings::set_groups_list(ctx_builder, groups)?;
+        }
+
+        if let Some(sigalgs) = &options.sigalgs {
+            bindings::set_sigalgs_list(ctx_builder, sigalgs)?;
+        }
+
+        match options.tickets {
+            Some(true) => {
+                ctx_builder.clear_options(SslOptions::NO_TICKET);
+            }
+            Some(false) => {
+                ctx_builder.set_options(SslOptions::NO_TICKET);
+                // TLS 1.3 servers would still send stateful tickets
+                #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+                bindings::set_num_tickets(ctx_builder, 0)?;
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    /// Encodes the ALPN protocols of the options as a list of length prefixed strings
+    #[cfg(not(feature = "openssl101f"))]
+    fn alpn_protocols(options: &TlsPutOptions) -> Option<Vec<u8>> {
+        options.alpn.as_ref().map(|protocols| {
+            protocols
+                .iter()
+                .flat_map(|protocol| std::iter::once(protocol.len() as u8).chain(protocol.bytes()))
+                .collect()
+        })
+    }
+
+    /// Creates a context for a client which is shared between the TLS and DTLS PUTs. The maximum
+    /// protocol version is not restricted yet.
+    fn client_context_builder(
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+        method: SslMethod,
+    ) -> Result<SslContextBuilder, ErrorStack> {
+        let mut ctx_builder = SslContext::builder(method)?;
         // Not sure whether we want this disabled or enabled: https://github.com/tlspuffin/tlspuffin/issues/67
         // The tests become simpler if disabled to maybe that's what we want. Lets leave it default
         // for now.
         // https://wiki.openssl.org/index.php/TLS1.3#Middlebox_Compatibility_Mode
-        #[cfg(feature = "openssl111")]
+        #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
         ctx_builder.clear_options(openssl::ssl::SslOptions::ENABLE_MIDDLEBOX_COMPAT);
 
-        set_max_protocol_version(&mut ctx_builder, descriptor.tls_version)?;
-
         // Disallow EXPORT in client
-        ctx_builder.set_cipher_list("ALL:!EXPORT:!LOW:!aNULL:!eNULL:!SSLv2")?;
+        ctx_builder.set_cipher_list(
+            options
+                .cipher_list
+                .as_deref()
+                .unwrap_or("ALL:!EXPORT:!LOW:!aNULL:!eNULL:!SSLv2"),
+        )?;
+
+        Self::configure_context(&mut ctx_builder, options)?;
+
+        #[cfg(not(feature = "openssl101f"))]
+        if let Some(protocols) = Self::alpn_protocols(options) {
+            ctx_builder.set_alpn_protos(&protocols)?;
+        }
 
         ctx_builder.set_verify(SslVerifyMode::NONE);
 
@@ -408,9 +635,12 @@ impl OpenSSL {
             let (cert, key) = static_rsa_cert(BOB_PRIVATE_KEY.0.as_bytes(), BOB_CERT.0.as_bytes())?;
             ctx_builder.set_certificate(&cert)?;
             ctx_builder.set_private_key(&key)?;
+
+            #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+            bindings::set_post_handshake_auth(&mut ctx_builder, true);
         }
 
-        if descriptor.server_authentication {
+        if options.verify_mode(descriptor) != VerifyMode::Disabled {
             ctx_builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
 
             let mut store = X509StoreBuilder::new()?;
@@ -423,10 +653,7 @@ impl OpenSSL {
             ctx_builder.set_verify(SslVerifyMode::NONE);
         }
 
-        let mut ssl = Ssl::new(&ctx_builder.build())?;
-        ssl.set_connect_state();
-
-        Ok(ssl)
+        Ok(ctx_builder)
     }
 }
 
@@ -74,16 +74,77 @@ pub fn set_max_protocol_version(
     tls_version: TLSVersion,
 ) -> Result<(), ErrorStack> {
     // Old OpenSSL versions do not have this function
-    #[cfg(any(feature = "openssl111", feature = "libressl"))]
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx", feature = "libressl"))]
     match tls_version {
         TLSVersion::V1_3 => {
-            #[cfg(feature = "openssl111")]
+            #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
             ctx_builder.set_max_proto_version(Some(SslVersion::TLS1_3))?;
             // do nothing as the maximum available TLS version is 1.3
             Ok(())
         }
         TLSVersion::V1_2 => ctx_builder.set_max_proto_version(Some(SslVersion::TLS1_2)),
+        TLSVersion::V1_1 => ctx_builder.set_max_proto_version(Some(SslVersion::TLS1_1)),
+        TLSVersion::V1_0 => ctx_builder.set_max_proto_version(Some(SslVersion::TLS1)),
+        TLSVersion::SSL3 => {
+            // The default minimum version excludes SSLv3 if it is compiled in
+            ctx_builder.set_min_proto_version(Some(SslVersion::SSL3))?;
+            ctx_builder.set_max_proto_version(Some(SslVersion::SSL3))
+        }
+    }?;
+
+    // Instead, the newer versions have to be disabled one by one
+    #[cfg(not(any(feature = "openssl111", feature = "openssl3xx", feature = "libressl")))]
+    {
+        use openssl::ssl::SslOptions;
+
+        let disabled = match tls_version {
+            TLSVersion::V1_3 | TLSVersion::V1_2 => SslOptions::empty(),
+            TLSVersion::V1_1 => SslOptions::NO_TLSV1_2,
+            TLSVersion::V1_0 => SslOptions::NO_TLSV1_2 | SslOptions::NO_TLSV1_1,
+            TLSVersion::SSL3 => {
+                SslOptions::NO_TLSV1_2 | SslOptions::NO_TLSV1_1 | SslOptions::NO_TLSV1
+            }
+        };
+        ctx_builder.set_options(disabled);
+    }
+
+    Ok(())
+}
+
+/// Restricts the minimum protocol version. TLS 1.3 can only be enforced by OpenSSL 1.1.1 and newer.
+#[allow(unused_variables)]
+pub fn set_min_protocol_version(
+    ctx_builder: &mut SslContextBuilder,
+    tls_version: TLSVersion,
+) -> Result<(), ErrorStack> {
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx", feature = "libressl"))]
+    match tls_version {
+        TLSVersion::V1_3 => {
+            #[cfg(any(feature = "openssl111", feature = "openssl3xx"))]
+            ctx_builder.set_min_proto_version(Some(SslVersion::TLS1_3))?;
+            Ok(())
+        }
+        TLSVersion::V1_2 => ctx_builder.set_min_proto_version(Some(SslVersion::TLS1_2)),
+        TLSVersion::V1_1 => ctx_builder.set_min_proto_version(Some(SslVersion::TLS1_1)),
+        TLSVersion::V1_0 => ctx_builder.set_min_proto_version(Some(SslVersion::TLS1)),
+        TLSVersion::SSL3 => ctx_builder.set_min_proto_version(Some(SslVersion::SSL3)),
     }?;
 
+    // Instead, the older versions have to be disabled one by one
+    #[cfg(not(any(feature = "openssl111", feature = "openssl3xx", feature = "libressl")))]
+    {
+        use openssl::ssl::SslOptions;
+
+        let disabled = match tls_version {
+            TLSVersion::V1_3 | TLSVersion::V1_2 => {
+                SslOptions::NO_SSLV3 | SslOptions::NO_TLSV1 | SslOptions::NO_TLSV1_1
+            }
+            TLSVersion::V1_1 => SslOptions::NO_SSLV3 | SslOptions::NO_TLSV1,
+            TLSVersion::V1_0 => SslOptions::NO_SSLV3,
+            TLSVersion::SSL3 => SslOptions::empty(),
+        };
+        ctx_builder.set_options(disabled);
+    }
+
     Ok(())
 }
@@ -1,6 +1,7 @@
 use puffin::{
     algebra::{signature::Signature, Matcher},
     error::Error,
+    oracle::OracleFamily,
     protocol::{
         MessageResult, OpaqueProtocolMessage, ProtocolBehavior, ProtocolMessage,
         ProtocolMessageDeframer,
@@ -16,6 +17,7 @@ use crate::{
     put_registry::TLS_PUT_REGISTRY,
     query::TlsQueryMatcher,
     tls::{
+        oracle::{create_oracle_families, TlsObserver},
         rustls::{
             msgs,
             msgs::{
@@ -117,6 +119,13 @@ impl ProtocolMessage<OpaqueMessage> for Message {
                             // in advance
                             vec![Box::new(self.clone()), Box::new(ecdhe.clone())]
                         }
+                        ServerKeyExchangePayload::DHE(dhe) => {
+                            vec![
+                                Box::new(self.clone()),
+                                Box::new(dhe.clone()),
+                                Box::new(dhe.params.clone()),
+                            ]
+                        }
                         ServerKeyExchangePayload::Unknown(unknown) => {
                             vec![Box::new(self.clone()), Box::new(unknown.0.clone())]
                         }
@@ -193,6 +202,7 @@ impl ProtocolBehavior for TLSProtocolBehavior {
     type ProtocolMessage = Message;
     type OpaqueProtocolMessage = OpaqueMessage;
     type Matcher = TlsQueryMatcher;
+    type OracleObserver = TlsObserver;
 
     fn signature() -> &'static Signature {
         &TLS_SIGNATURE
@@ -205,4 +215,8 @@ impl ProtocolBehavior for TLSProtocolBehavior {
     fn create_corpus() -> Vec<(Trace<Self::Matcher>, &'static str)> {
         create_corpus()
     }
+
+    fn create_oracle_families() -> Vec<OracleFamily<Self::Matcher>> {
+        create_oracle_families()
+    }
 }
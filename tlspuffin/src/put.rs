@@ -1,7 +1,11 @@
 use std::{cell::RefCell, rc::Rc};
 
 use puffin::{
-    agent::AgentDescriptor, algebra::dynamic_function::TypeShape, claims::GlobalClaimList,
+    agent::{AgentDescriptor, AgentType, TLSVersion},
+    algebra::dynamic_function::TypeShape,
+    claims::GlobalClaimList,
+    error::Error,
+    put::PutOptions,
 };
 
 use crate::claims::TlsClaim;
@@ -13,4 +17,526 @@ pub struct TlsPutConfig {
     pub claims: GlobalClaimList<TlsClaim>,
     pub authenticate_peer: bool,
     pub extract_deferred: Rc<RefCell<Option<TypeShape>>>,
+    pub options: TlsPutOptions,
+}
+
+/// How a PUT verifies the certificate of its peer
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VerifyMode {
+    /// Do not request or verify a certificate (`none`)
+    Disabled,
+    /// Verify the certificate of the peer. Servers request a certificate, but continue the
+    /// handshake if the client does not send one (`peer`).
+    Peer,
+    /// Verify the certificate of the peer and abort the handshake if it is missing (`require`)
+    Required,
+}
+
+/// Configuration of the TLS library which replaces the defaults of the in-process PUTs. It is
+/// parsed from the [`PutOptions`] of an agent, which are set for the default PUT through
+/// `--put-option key=value`. Options which are not given keep the default of the PUT.
+///
+/// | Key            | Value                                                                   |
+/// |----------------|-------------------------------------------------------------------------|
+/// | `cipher-list`  | Cipher list for TLS 1.2 and below in OpenSSL syntax                     |
+/// | `ciphersuites` | Colon separated TLS 1.3 ciphersuites, e.g. `TLS_AES_128_GCM_SHA256`     |
+/// | `groups`       | Colon separated supported groups, e.g. `X25519:P-256`                   |
+/// | `sigalgs`      | Colon separated signature algorithms, e.g. `RSA-PSS+SHA256:ECDSA+SHA256` |
+/// | `min-version`  | `1.0`, `1.1`, `1.2` or `1.3`                                            |
+/// | `max-version`  | `1.0`, `1.1`, `1.2` or `1.3`, defaults to the version of the agent      |
+/// | `verify`       | `none`, `peer` or `require`, see [`VerifyMode`]                         |
+/// | `tickets`      | `true` or `false`                                                       |
+/// | `alpn`         | Comma separated protocols in order of preference, e.g. `h2,http/1.1`    |
+/// | `sni`          | Server name which clients send, ignored by servers                      |
+///
+/// Other keys are rejected, so that a misspelled option does not silently fall back to the
+/// default. The options apply to all agents which use the PUT. PUTs which can not honor an option
+/// fail to create the agent instead of silently ignoring it.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TlsPutOptions {
+    pub cipher_list: Option<String>,
+    pub ciphersuites: Option<String>,
+    pub groups: Option<String>,
+    pub sigalgs: Option<String>,
+    pub min_version: Option<TLSVersion>,
+    pub max_version: Option<TLSVersion>,
+    pub verify: Option<VerifyMode>,
+    pub tickets: Option<bool>,
+    pub alpn: Option<Vec<String>>,
+    pub sni: Option<String>,
+}
+
+impl TlsPutOptions {
+    /// The keys of the options in the table above
+    const KEYS: &'static [&'static str] = &[
+        "cipher-list",
+        "ciphersuites",
+        "groups",
+        "sigalgs",
+        "min-version",
+        "max-version",
+        "verify",
+        "tickets",
+        "alpn",
+        "sni",
+    ];
+
+    pub fn parse(options: &PutOptions) -> Result<Self, Error> {
+        options.check_keys(Self::KEYS)?;
+        let string = |key: &str| options.get_option(key).map(|value| value.to_string());
+
+        Ok(Self {
+            cipher_list: string("cipher-list"),
+            ciphersuites: string("ciphersuites"),
+            groups: string("groups"),
+            sigalgs: string("sigalgs"),
+            min_version: options
+                .get_option("min-version")
+                .map(|value| Self::parse_version("min-version", value))
+                .transpose()?,
+            max_version: options
+                .get_option("max-version")
+                .map(|value| Self::parse_version("max-version", value))
+                .transpose()?,
+            verify: options
+                .get_option("verify")
+                .map(|value| match value {
+                    "none" => Ok(VerifyMode::Disabled),
+                    "peer" => Ok(VerifyMode::Peer),
+                    "require" => Ok(VerifyMode::Required),
+                    _ => Err(Self::invalid_value("verify", value)),
+                })
+                .transpose()?,
+            tickets: options
+                .get_option("tickets")
+                .map(|value| {
+                    value
+                        .parse::<bool>()
+                        .map_err(|_| Self::invalid_value("tickets", value))
+                })
+                .transpose()?,
+            alpn: options.get_option("alpn").map(|value| {
+                value
+                    .split(',')
+                    .map(|protocol| protocol.to_string())
+                    .collect()
+            }),
+            sni: string("sni"),
+        })
+    }
+
+    /// Used by PUTs which do not implement any option. Fails if options are given instead of
+    /// silently ignoring them.
+    pub fn unsupported(options: &PutOptions, library: &str) -> Result<Self, Error> {
+        let options = Self::parse(options)?;
+
+        if options == Self::default() {
+            Ok(options)
+        } else {
+            Err(Error::Put(format!(
+                "The {} PUT does not support PUT options",
+                library
+            )))
+        }
+    }
+
+    /// DTLS agents always use the version of their descriptor, because the DTLS PUTs can not
+    /// negotiate the version
+    pub fn check_dtls(&self) -> Result<(), Error> {
+        if self.min_version.is_some() || self.max_version.is_some() {
+            return Err(Error::Put(
+                "The options min-version and max-version are not supported with DTLS".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_version(key: &str, value: &str) -> Result<TLSVersion, Error> {
+        match value {
+            "1.3" => Ok(TLSVersion::V1_3),
+            "1.2" => Ok(TLSVersion::V1_2),
+            "1.1" => Ok(TLSVersion::V1_1),
+            "1.0" => Ok(TLSVersion::V1_0),
+            _ => Err(Self::invalid_value(key, value)),
+        }
+    }
+
+    fn invalid_value(key: &str, value: &str) -> Error {
+        Error::Put(format!(
+            "Invalid value \"{}\" for PUT option {}",
+            value, key
+        ))
+    }
+
+    /// The verification of the peer, which defaults to requiring a certificate if the agent
+    /// authenticates its peer
+    pub fn verify_mode(&self, descriptor: &AgentDescriptor) -> VerifyMode {
+        self.verify.unwrap_or_else(|| {
+            let authenticate_peer = match descriptor.typ {
+                AgentType::Client => descriptor.server_authentication,
+                AgentType::Server => descriptor.client_authentication,
+            };
+
+            if authenticate_peer {
+                VerifyMode::Required
+            } else {
+                VerifyMode::Disabled
+            }
+        })
+    }
+
+    /// Whether the handshake only succeeds if the peer authenticated, see
+    /// [`TlsPutConfig::authenticate_peer`]. Clients always fail the handshake if the certificate
+    /// of the server can not be verified.
+    pub fn authenticate_peer(&self, descriptor: &AgentDescriptor) -> bool {
+        match self.verify_mode(descriptor) {
+            VerifyMode::Disabled => false,
+            VerifyMode::Peer => descriptor.typ == AgentType::Client,
+            VerifyMode::Required => true,
+        }
+    }
+}
+
+/// Fails if the agent requests SSLv3, but the PUT is built without the `ssl3` feature. None of the
+/// vendored libraries can complete an SSLv3 handshake by default.
+pub fn check_tls_version(descriptor: &AgentDescriptor) -> Result<(), Error> {
+    if descriptor.tls_version == TLSVersion::SSL3 && !cfg!(feature = "ssl3") {
+        return Err(Error::Put("The PUT is built without SSLv3".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use puffin::{
+        agent::{AgentDescriptor, AgentName, TLSVersion},
+        put::PutOptions,
+    };
+
+    use crate::put::{TlsPutOptions, VerifyMode};
+
+    #[test]
+    fn test_parse_put_options() {
+        let options = PutOptions::parse([
+            "cipher-list=ECDHE-RSA-AES128-GCM-SHA256",
+            "groups=X25519:P-256",
+            "max-version=1.2",
+            "verify=peer",
+            "tickets=false",
+            "alpn=h2,http/1.1",
+        ])
+        .unwrap();
+
+        let options = TlsPutOptions::parse(&options).unwrap();
+        assert_eq!(
+            options,
+            TlsPutOptions {
+                cipher_list: Some("ECDHE-RSA-AES128-GCM-SHA256".to_string()),
+                groups: Some("X25519:P-256".to_string()),
+                max_version: Some(TLSVersion::V1_2),
+                verify: Some(VerifyMode::Peer),
+                tickets: Some(false),
+                alpn: Some(vec!["h2".to_string(), "http/1.1".to_string()]),
+                ..Default::default()
+            }
+        );
+
+        let server = AgentDescriptor::new_server(AgentName::first(), TLSVersion::V1_3);
+        assert!(!options.authenticate_peer(&server));
+
+        assert!(TlsPutOptions::parse(&PutOptions::new(vec![("verify", "always")])).is_err());
+        assert!(TlsPutOptions::parse(&PutOptions::new(vec![("cipher", "ALL")])).is_err());
+        assert!(TlsPutOptions::parse(&PutOptions::new(vec![("lib", "libputs.so")])).is_err());
+        assert!(TlsPutOptions::unsupported(&PutOptions::default(), "rustls").is_ok());
+        assert!(TlsPutOptions::unsupported(
+            &PutOptions::new(vec![("sni", "example.com")]),
+            "rustls"
+        )
+        .is_err());
+    }
+
+    /// Executes handshakes between agents of the in-process PUTs and checks that the messages
+    /// which they exchanged follow the options
+    #[cfg(any(feature = "openssl-binding", feature = "wolfssl-binding"))]
+    mod execution {
+        use puffin::{
+            agent::AgentName,
+            algebra::dynamic_function::{DescribableFunction, TypeShape},
+            error::Error,
+            put::{PutDescriptor, PutName, PutOptions},
+            trace::{Action, Query, Trace, TraceContext},
+        };
+        use test_log::test;
+
+        #[cfg(feature = "openssl-binding")]
+        use crate::put_registry::OPENSSL111_PUT;
+        #[cfg(feature = "wolfssl-binding")]
+        use crate::put_registry::WOLFSSL520_PUT;
+        use crate::{
+            protocol::TLSProtocolBehavior,
+            put_registry::TLS_PUT_REGISTRY,
+            query::TlsQueryMatcher,
+            tls::{
+                fn_impl::{fn_change_cipher_spec, fn_client_hello},
+                rustls::msgs::{
+                    enums::{CipherSuite, HandshakeType},
+                    handshake::{
+                        ClientHelloPayload, ConvertServerNameList, HandshakePayload,
+                        ServerHelloPayload,
+                    },
+                    message::{Message, MessagePayload},
+                },
+                seeds::seed_successful12,
+            },
+        };
+
+        /// Executes the steps of `seed` which `steps` selects between a client and a server of
+        /// `put`
+        fn execute(
+            put: PutName,
+            seed: fn(AgentName, AgentName) -> Trace<TlsQueryMatcher>,
+            steps: fn(&Trace<TlsQueryMatcher>) -> usize,
+            client_options: &[&str],
+            server_options: &[&str],
+        ) -> Result<TraceContext<TLSProtocolBehavior>, Error> {
+            let client = AgentName::first();
+            let server = client.next();
+            let descriptor = |options: &[&str]| PutDescriptor {
+                name: put,
+                options: PutOptions::parse(options.iter().copied()).unwrap(),
+            };
+
+            let mut trace = seed(client, server);
+            let steps = steps(&trace);
+            trace.steps.truncate(steps);
+
+            let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+            ctx.set_non_default_puts(&[
+                (client, descriptor(client_options)),
+                (server, descriptor(server_options)),
+            ]);
+            trace.execute(&mut ctx)?;
+            Ok(ctx)
+        }
+
+        /// The index of the first step in which `agent_name` receives a message which is created
+        /// by the function `function_name`
+        fn input_step(
+            trace: &Trace<TlsQueryMatcher>,
+            agent_name: AgentName,
+            function_name: &str,
+        ) -> usize {
+            trace
+                .steps
+                .iter()
+                .position(|step| match &step.action {
+                    Action::Input(input) => {
+                        step.agent == agent_name && input.recipe.name() == function_name
+                    }
+                    Action::Output(_) => false,
+                })
+                .unwrap_or_else(|| panic!("{} never receives {}", agent_name, function_name))
+        }
+
+        /// All steps of the trace
+        fn all_steps(trace: &Trace<TlsQueryMatcher>) -> usize {
+            trace.steps.len()
+        }
+
+        /// The steps up to and including the step in which the server receives the ClientHello
+        fn until_client_hello(trace: &Trace<TlsQueryMatcher>) -> usize {
+            input_step(trace, AgentName::first().next(), fn_client_hello.name()) + 1
+        }
+
+        /// The steps before the client receives the ChangeCipherSpec of the server, i.e. up to
+        /// the Finished of the client
+        fn until_server_change_cipher_spec(trace: &Trace<TlsQueryMatcher>) -> usize {
+            input_step(trace, AgentName::first(), fn_change_cipher_spec.name())
+        }
+
+        /// The first handshake message of type `typ` which `agent_name` sent
+        fn find_handshake(
+            ctx: &TraceContext<TLSProtocolBehavior>,
+            agent_name: AgentName,
+            typ: HandshakeType,
+        ) -> Option<HandshakePayload> {
+            ctx.find_variable(
+                TypeShape::of::<Message>(),
+                &Query {
+                    agent_name,
+                    matcher: Some(TlsQueryMatcher::Handshake(Some(typ))),
+                    counter: 0,
+                },
+            )
+            .and_then(|data| data.boxed_any().downcast::<Message>().ok())
+            .and_then(|message| match message.payload {
+                MessagePayload::Handshake(handshake) => Some(handshake.payload),
+                _ => None,
+            })
+        }
+
+        fn client_hello(ctx: &TraceContext<TLSProtocolBehavior>) -> ClientHelloPayload {
+            match find_handshake(ctx, AgentName::first(), HandshakeType::ClientHello) {
+                Some(HandshakePayload::ClientHello(client_hello)) => client_hello,
+                _ => panic!("The client sent no ClientHello"),
+            }
+        }
+
+        fn server_hello(ctx: &TraceContext<TLSProtocolBehavior>) -> Option<ServerHelloPayload> {
+            match find_handshake(ctx, AgentName::first().next(), HandshakeType::ServerHello) {
+                Some(HandshakePayload::ServerHello(server_hello)) => Some(server_hello),
+                _ => None,
+            }
+        }
+
+        fn check_cipher_list(put: PutName) {
+            let options = ["cipher-list=ECDHE-RSA-AES128-SHA"];
+            let ctx = execute(put, seed_successful12, all_steps, &options, &options).unwrap();
+
+            assert!(ctx.agents_successful());
+            assert_eq!(
+                server_hello(&ctx).unwrap().cipher_suite,
+                CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA
+            );
+        }
+
+        fn check_tickets(put: PutName) {
+            // The Finished of the server is expected after a NewSessionTicket
+            let new_session_ticket = |options: &[&str]| {
+                let ctx = execute(
+                    put,
+                    seed_successful12,
+                    until_server_change_cipher_spec,
+                    options,
+                    options,
+                )
+                .unwrap();
+                find_handshake(
+                    &ctx,
+                    AgentName::first().next(),
+                    HandshakeType::NewSessionTicket,
+                )
+                .is_some()
+            };
+
+            assert!(new_session_ticket(&[]));
+            assert!(!new_session_ticket(&["tickets=false"]));
+        }
+
+        #[cfg(not(feature = "openssl101f"))]
+        fn check_alpn(put: PutName) {
+            use crate::tls::rustls::msgs::handshake::{
+                ConvertProtocolNameList, HasServerExtensions,
+            };
+
+            let ctx = execute(
+                put,
+                seed_successful12,
+                until_client_hello,
+                &["alpn=h2,http/1.1"],
+                &["alpn=http/1.1"],
+            )
+            .unwrap();
+
+            assert_eq!(
+                client_hello(&ctx).get_alpn_extension().unwrap().to_slices(),
+                vec![b"h2" as &[u8], b"http/1.1"]
+            );
+            assert_eq!(
+                server_hello(&ctx).unwrap().get_alpn_protocol(),
+                Some(b"http/1.1" as &[u8])
+            );
+        }
+
+        fn check_sni(put: PutName) {
+            let ctx = execute(
+                put,
+                seed_successful12,
+                until_client_hello,
+                &["sni=example.com"],
+                &[],
+            )
+            .unwrap();
+
+            let client_hello = client_hello(&ctx);
+            let hostname: &str = client_hello
+                .get_sni_extension()
+                .and_then(|sni| sni.get_single_hostname())
+                .unwrap()
+                .into();
+            assert_eq!(hostname, "example.com");
+            assert!(server_hello(&ctx).is_some());
+        }
+
+        #[cfg(all(
+            feature = "tls13",
+            any(feature = "openssl-binding", not(feature = "wolfssl430"))
+        ))]
+        fn check_versions(put: PutName) {
+            use crate::tls::{rustls::msgs::enums::ProtocolVersion, seeds::seed_successful};
+
+            let supported_version = |client_options: &[&str], server_options: &[&str]| {
+                let ctx = execute(
+                    put,
+                    seed_successful,
+                    until_client_hello,
+                    client_options,
+                    server_options,
+                )
+                .unwrap();
+                server_hello(&ctx).unwrap().get_supported_versions()
+            };
+
+            assert_eq!(supported_version(&[], &[]), Some(ProtocolVersion::TLSv1_3));
+            // TLS 1.2 is negotiated without the supported_versions extension
+            assert_eq!(
+                supported_version(&["min-version=1.2"], &["max-version=1.2"]),
+                None
+            );
+            // The server rejects the ClientHello
+            assert!(execute(
+                put,
+                seed_successful,
+                until_client_hello,
+                &["max-version=1.2"],
+                &["min-version=1.3"]
+            )
+            .is_err());
+        }
+
+        #[test]
+        #[cfg(feature = "openssl-binding")]
+        fn test_openssl_put_options() {
+            check_cipher_list(OPENSSL111_PUT);
+            check_tickets(OPENSSL111_PUT);
+            #[cfg(not(feature = "openssl101f"))]
+            check_alpn(OPENSSL111_PUT);
+            check_sni(OPENSSL111_PUT);
+        }
+
+        #[test]
+        #[cfg(all(feature = "openssl-binding", feature = "tls13"))]
+        fn test_openssl_version_options() {
+            check_versions(OPENSSL111_PUT);
+        }
+
+        #[test]
+        #[cfg(feature = "wolfssl-binding")]
+        fn test_wolfssl_put_options() {
+            check_cipher_list(WOLFSSL520_PUT);
+            check_tickets(WOLFSSL520_PUT);
+            check_alpn(WOLFSSL520_PUT);
+            check_sni(WOLFSSL520_PUT);
+        }
+
+        #[test]
+        #[cfg(all(
+            feature = "wolfssl-binding",
+            feature = "tls13",
+            not(feature = "wolfssl430")
+        ))]
+        fn test_wolfssl_version_options() {
+            check_versions(WOLFSSL520_PUT);
+        }
+    }
 }
@@ -30,14 +30,25 @@ use crate::{
 pub const OPENSSL111_PUT: PutName = PutName(['O', 'P', 'E', 'N', 'S', 'S', 'L', '1', '1', '1']);
 pub const WOLFSSL520_PUT: PutName = PutName(['W', 'O', 'L', 'F', 'S', 'S', 'L', '5', '2', '0']);
 pub const TCP_PUT: PutName = PutName(['T', 'C', 'P', '_', '_', '_', '_', '_', '_', '_']);
+pub const DYLIB_PUT: PutName = PutName(['D', 'Y', 'L', 'I', 'B', '_', '_', '_', '_', '_']);
+pub const BORINGSSL_PUT: PutName = PutName(['B', 'O', 'R', 'I', 'N', 'G', 'S', 'S', 'L', '_']);
+pub const MBEDTLS_PUT: PutName = PutName(['M', 'B', 'E', 'D', 'T', 'L', 'S', '_', '_', '_']);
+pub const RUSTLS_PUT: PutName = PutName(['R', 'U', 'S', 'T', 'L', 'S', '_', '_', '_', '_']);
 
 pub const TLS_PUT_REGISTRY: PutRegistry<TLSProtocolBehavior> = PutRegistry {
     factories: &[
         crate::tcp::new_tcp_factory,
+        crate::dylib::new_dylib_factory,
         #[cfg(feature = "openssl-binding")]
         crate::openssl::new_openssl_factory,
         #[cfg(feature = "wolfssl-binding")]
         crate::wolfssl::new_wolfssl_factory,
+        #[cfg(feature = "boringssl-binding")]
+        crate::boringssl::new_boringssl_factory,
+        #[cfg(feature = "mbedtls-binding")]
+        crate::mbedtls::new_mbedtls_factory,
+        #[cfg(feature = "rustls-binding")]
+        crate::rustls::new_rustls_factory,
     ],
     default: DEFAULT_PUT_FACTORY,
 };
@@ -48,6 +59,12 @@ pub const DEFAULT_PUT_FACTORY: fn() -> Box<dyn Factory<TLSProtocolBehavior>> = {
             crate::openssl::new_openssl_factory
         } else if #[cfg(feature = "wolfssl-binding")] {
             crate::wolfssl::new_wolfssl_factory
+        } else if #[cfg(feature = "boringssl-binding")] {
+            crate::boringssl::new_boringssl_factory
+        } else if #[cfg(feature = "mbedtls-binding")] {
+            crate::mbedtls::new_mbedtls_factory
+        } else if #[cfg(feature = "rustls-binding")] {
+            crate::rustls::new_rustls_factory
         } else {
              crate::tcp::new_tcp_factory
         }
@@ -15,6 +15,8 @@ pub enum TlsQueryMatcher {
     Handshake(Option<HandshakeType>),
     ApplicationData,
     Heartbeat,
+    /// Only used in DTLS 1.3
+    Ack,
 }
 
 impl Matcher for TlsQueryMatcher {
@@ -30,6 +32,7 @@ impl Matcher for TlsQueryMatcher {
             TlsQueryMatcher::Alert => matches!(self, TlsQueryMatcher::Alert),
             TlsQueryMatcher::Heartbeat => matches!(self, TlsQueryMatcher::Heartbeat),
             TlsQueryMatcher::ApplicationData => matches!(self, TlsQueryMatcher::ApplicationData),
+            TlsQueryMatcher::Ack => matches!(self, TlsQueryMatcher::Ack),
         }
     }
 
@@ -66,7 +69,7 @@ impl TryFrom<&MessageResult<Message, OpaqueMessage>> for TlsQueryMatcher {
             (ContentType::Heartbeat, _) => Ok(TlsQueryMatcher::Heartbeat),
             (ContentType::Alert, _) => Ok(TlsQueryMatcher::Alert),
             (ContentType::ChangeCipherSpec, _) => Ok(TlsQueryMatcher::ChangeCipherSpec),
-            (ContentType::Unknown(_), _) => Err(Error::Extraction()),
+            (ContentType::Ack, _) | (ContentType::Unknown(_), _) => Err(Error::Extraction()),
         }
     }
 }
@@ -0,0 +1,46 @@
+use log::warn;
+
+use crate::rustls::random::RANDOM;
+
+/// The seed of the random number generator
+const SEED: u64 = 42;
+
+/// Makes the randomness which rustls takes from its crypto provider deterministic, including the
+/// ephemeral keys of the key exchange groups in [`crate::rustls::kx`].
+pub fn set_rustls_deterministic() {
+    warn!("rustls is no longer random!");
+    RANDOM.seed(SEED);
+}
+
+#[cfg(test)]
+mod tests {
+    use rustls::crypto::{SecureRandom, SupportedKxGroup};
+
+    use crate::rustls::{
+        deterministic::set_rustls_deterministic, kx::ALL_KX_GROUPS, random::RANDOM,
+    };
+
+    #[test]
+    fn test_rustls_no_randomness() {
+        set_rustls_deterministic();
+        let mut buf1 = [0; 20];
+        RANDOM.fill(&mut buf1).unwrap();
+
+        set_rustls_deterministic();
+        let mut buf2 = [0; 20];
+        RANDOM.fill(&mut buf2).unwrap();
+        assert_eq!(buf1, buf2);
+    }
+
+    #[test]
+    fn test_rustls_deterministic_key_shares() {
+        for group in ALL_KX_GROUPS {
+            set_rustls_deterministic();
+            let key_share1 = group.start().unwrap().pub_key().to_vec();
+
+            set_rustls_deterministic();
+            let key_share2 = group.start().unwrap().pub_key().to_vec();
+            assert_eq!(key_share1, key_share2);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use rustls::{
+    crypto::{ActiveKeyExchange, SecureRandom, SharedSecret, SupportedKxGroup},
+    Error, NamedGroup, PeerMisbehaved,
+};
+use rustls_ring::{
+    agreement::{self, EphemeralPrivateKey, PublicKey, UnparsedPublicKey},
+    rand::SystemRandom,
+    test::rand::FixedSliceRandom,
+};
+
+use crate::rustls::random::RANDOM;
+
+/// The key exchange groups of the ring provider of rustls. Their ephemeral keys are generated from
+/// [`RANDOM`] once it has been seeded, while the groups of the ring provider always use the
+/// randomness of the operating system.
+pub static ALL_KX_GROUPS: &[&dyn SupportedKxGroup] = &[&X25519, &SECP256R1, &SECP384R1];
+
+pub static X25519: KxGroup = KxGroup {
+    name: NamedGroup::X25519,
+    agreement_algorithm: &agreement::X25519,
+    private_key_len: 32,
+};
+
+pub static SECP256R1: KxGroup = KxGroup {
+    name: NamedGroup::secp256r1,
+    agreement_algorithm: &agreement::ECDH_P256,
+    private_key_len: 32,
+};
+
+pub static SECP384R1: KxGroup = KxGroup {
+    name: NamedGroup::secp384r1,
+    agreement_algorithm: &agreement::ECDH_P384,
+    private_key_len: 48,
+};
+
+/// The length of the largest private key of [`ALL_KX_GROUPS`]
+const MAX_PRIVATE_KEY_LEN: usize = 48;
+
+#[derive(Debug)]
+pub struct KxGroup {
+    name: NamedGroup,
+    agreement_algorithm: &'static agreement::Algorithm,
+    /// The number of random bytes which ring draws for a private key
+    private_key_len: usize,
+}
+
+impl KxGroup {
+    fn generate_private_key(&self) -> Result<EphemeralPrivateKey, Error> {
+        let private_key = if RANDOM.is_seeded() {
+            let mut bytes = [0u8; MAX_PRIVATE_KEY_LEN];
+            let bytes = &mut bytes[..self.private_key_len];
+            RANDOM.fill(bytes).map_err(Error::from)?;
+            EphemeralPrivateKey::generate(self.agreement_algorithm, &FixedSliceRandom { bytes })
+        } else {
+            EphemeralPrivateKey::generate(self.agreement_algorithm, &SystemRandom::new())
+        };
+
+        private_key.map_err(|_| Error::FailedToGetRandomBytes)
+    }
+}
+
+impl SupportedKxGroup for KxGroup {
+    fn start(&self) -> Result<Box<dyn ActiveKeyExchange>, Error> {
+        let priv_key = self.generate_private_key()?;
+        let pub_key = priv_key
+            .compute_public_key()
+            .map_err(|_| Error::FailedToGetRandomBytes)?;
+
+        Ok(Box::new(KeyExchange {
+            name: self.name,
+            agreement_algorithm: self.agreement_algorithm,
+            priv_key,
+            pub_key,
+        }))
+    }
+
+    fn name(&self) -> NamedGroup {
+        self.name
+    }
+}
+
+struct KeyExchange {
+    name: NamedGroup,
+    agreement_algorithm: &'static agreement::Algorithm,
+    priv_key: EphemeralPrivateKey,
+    pub_key: PublicKey,
+}
+
+impl ActiveKeyExchange for KeyExchange {
+    fn complete(self: Box<Self>, peer_pub_key: &[u8]) -> Result<SharedSecret, Error> {
+        let peer_key = UnparsedPublicKey::new(self.agreement_algorithm, peer_pub_key);
+        agreement::agree_ephemeral(self.priv_key, &peer_key, |secret| {
+            SharedSecret::from(secret)
+        })
+        .map_err(|_| PeerMisbehaved::InvalidKeyShare.into())
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        self.pub_key.as_ref()
+    }
+
+    fn group(&self) -> NamedGroup {
+        self.name
+    }
+}
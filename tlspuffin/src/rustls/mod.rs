@@ -0,0 +1,510 @@
+//! A PUT for the upstream rustls crate, which is not to be confused with the modified copy of
+//! rustls in [`crate::tls::rustls`]. The connection reads records from and writes records to the
+//! [`MemoryStream`].
+
+use std::{
+    cell::RefCell,
+    io::{ErrorKind, Read},
+    rc::Rc,
+    sync::Arc,
+};
+
+use puffin::{
+    agent::{AgentDescriptor, AgentName, AgentType, TLSVersion},
+    error::Error,
+    protocol::MessageResult,
+    put::{Put, PutName},
+    put_registry::Factory,
+    stream::{MemoryStream, Stream},
+    trace::TraceContext,
+};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{
+        ring::default_provider, verify_tls12_signature, verify_tls13_signature, CryptoProvider,
+        WebPkiSupportedAlgorithms,
+    },
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs1KeyDer, ServerName, UnixTime},
+    server::danger::{ClientCertVerified, ClientCertVerifier},
+    version::{TLS12, TLS13},
+    CertificateError, ClientConfig, ClientConnection, Connection, DigitallySignedStruct,
+    DistinguishedName, ServerConfig, ServerConnection, SignatureScheme, SupportedProtocolVersion,
+};
+#[cfg(feature = "claims")]
+use smallvec::SmallVec;
+
+#[cfg(feature = "claims")]
+use crate::claims::{ClaimData, ClaimDataMessage, Finished, TlsClaim};
+use crate::{
+    protocol::TLSProtocolBehavior,
+    put::{TlsPutConfig, TlsPutOptions},
+    put_registry::RUSTLS_PUT,
+    static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT},
+    tls::rustls::msgs::{
+        deframer::MessageDeframer,
+        message::{Message, OpaqueMessage},
+    },
+};
+
+#[cfg(feature = "deterministic")]
+mod deterministic;
+mod kx;
+mod random;
+mod ticketer;
+
+/// The version of the rustls crate which this PUT is built against
+const RUSTLS_VERSION: &str = "rustls 0.22";
+
+pub fn new_rustls_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
+    struct RustlsFactory;
+    impl Factory<TLSProtocolBehavior> for RustlsFactory {
+        fn create(
+            &self,
+            context: &TraceContext<TLSProtocolBehavior>,
+            agent_descriptor: &AgentDescriptor,
+        ) -> Result<Box<dyn Put<TLSProtocolBehavior>>, Error> {
+            let options = TlsPutOptions::unsupported(
+                &context.put_descriptor(agent_descriptor).options,
+                "rustls",
+            )?;
+
+            let config = TlsPutConfig {
+                descriptor: agent_descriptor.clone(),
+                claims: context.claims().clone(),
+                authenticate_peer: agent_descriptor.typ == AgentType::Client
+                    && agent_descriptor.server_authentication
+                    || agent_descriptor.typ == AgentType::Server
+                        && agent_descriptor.client_authentication,
+                extract_deferred: Rc::new(RefCell::new(None)),
+                options,
+            };
+            Ok(Box::new(Rustls::new(config)?))
+        }
+
+        fn name(&self) -> PutName {
+            RUSTLS_PUT
+        }
+
+        fn version(&self) -> String {
+            Rustls::version()
+        }
+    }
+
+    Box::new(RustlsFactory)
+}
+
+/// The configuration of a connection. It is kept for resetting the connection, because it holds
+/// the sessions of a client.
+enum RustlsConfig {
+    Client(Arc<ClientConfig>),
+    Server(Arc<ServerConfig>),
+}
+
+pub struct Rustls {
+    connection: Connection,
+    rustls_config: RustlsConfig,
+    stream: MemoryStream<MessageDeframer>,
+    config: TlsPutConfig,
+    /// The agent for which claims are made
+    #[cfg(feature = "claims")]
+    claimer: Option<AgentName>,
+    /// Whether the completed handshake of the connection has already been claimed
+    #[cfg(feature = "claims")]
+    claimed_finished: bool,
+}
+
+impl Stream<Message, OpaqueMessage> for Rustls {
+    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) -> Result<(), Error> {
+        <MemoryStream<MessageDeframer> as Stream<Message, OpaqueMessage>>::add_to_inbound(
+            &mut self.stream,
+            opaque_message,
+        )
+    }
+
+    fn take_message_from_outbound(
+        &mut self,
+    ) -> Result<Option<MessageResult<Message, OpaqueMessage>>, Error> {
+        MemoryStream::take_message_from_outbound(&mut self.stream)
+    }
+}
+
+impl Put<TLSProtocolBehavior> for Rustls {
+    fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
+        let result = self.process_inbound();
+        // rustls queues an Alert message if processing failed
+        self.write_outbound()?;
+
+        #[cfg(feature = "claims")]
+        if result.is_ok() && !self.claimed_finished && self.is_state_successful() {
+            self.claim_finished();
+        }
+
+        result
+    }
+
+    fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
+        self.connection = Self::create_connection(&self.rustls_config)?;
+
+        #[cfg(feature = "claims")]
+        {
+            self.claimed_finished = false;
+        }
+
+        Ok(())
+    }
+
+    fn descriptor(&self) -> &AgentDescriptor {
+        &self.config.descriptor
+    }
+
+    #[cfg(feature = "claims")]
+    fn register_claimer(&mut self, agent_name: AgentName) {
+        self.claimer = Some(agent_name);
+    }
+
+    #[cfg(feature = "claims")]
+    fn deregister_claimer(&mut self) {
+        self.claimer = None;
+    }
+
+    #[allow(unused_variables)]
+    fn rename_agent(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        {
+            self.deregister_claimer();
+            self.register_claimer(agent_name);
+        }
+        Ok(())
+    }
+
+    fn describe_state(&self) -> &str {
+        // The state machine of rustls is private
+        if self.connection.is_handshaking() {
+            "Handshake in progress"
+        } else {
+            "Handshake finished"
+        }
+    }
+
+    fn is_state_successful(&self) -> bool {
+        !self.connection.is_handshaking()
+    }
+
+    fn set_deterministic(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "deterministic")]
+        {
+            deterministic::set_rustls_deterministic();
+            // The ClientHello of a client has been created with random values when creating the
+            // connection
+            self.reset(self.config.descriptor.name)
+        }
+        #[cfg(not(feature = "deterministic"))]
+        {
+            Err(Error::Agent(
+                "Unable to make rustls deterministic!".to_string(),
+            ))
+        }
+    }
+
+    fn shutdown(&mut self) -> String {
+        panic!("Unsupported with rustls PUT")
+    }
+
+    fn version() -> String {
+        RUSTLS_VERSION.to_string()
+    }
+}
+
+impl Rustls {
+    fn new(config: TlsPutConfig) -> Result<Rustls, Error> {
+        let rustls_config = Self::create_config(&config.descriptor)?;
+
+        Ok(Rustls {
+            connection: Self::create_connection(&rustls_config)?,
+            rustls_config,
+            stream: MemoryStream::new(MessageDeframer::new()),
+            #[cfg(feature = "claims")]
+            claimer: Some(config.descriptor.name),
+            #[cfg(feature = "claims")]
+            claimed_finished: false,
+            config,
+        })
+    }
+
+    fn create_connection(rustls_config: &RustlsConfig) -> Result<Connection, Error> {
+        Ok(match rustls_config {
+            RustlsConfig::Client(config) => {
+                // Our certificates are not issued for a hostname
+                let server_name =
+                    ServerName::try_from("localhost").map_err(|err| Error::Put(err.to_string()))?;
+                ClientConnection::new(config.clone(), server_name)
+                    .map_err(put_error)?
+                    .into()
+            }
+            RustlsConfig::Server(config) => ServerConnection::new(config.clone())
+                .map_err(put_error)?
+                .into(),
+        })
+    }
+
+    /// Creates the configuration of a client or server with the same certificates as the other
+    /// PUTs
+    fn create_config(descriptor: &AgentDescriptor) -> Result<RustlsConfig, Error> {
+        let versions: Vec<&'static SupportedProtocolVersion> = match descriptor.tls_version {
+            TLSVersion::V1_3 => vec![&TLS13, &TLS12],
+            TLSVersion::V1_2 => vec![&TLS12],
+            version => {
+                return Err(Error::Put(format!(
+                    "{:?} is not supported by rustls",
+                    version
+                )))
+            }
+        };
+
+        let provider = CryptoProvider {
+            kx_groups: kx::ALL_KX_GROUPS.to_vec(),
+            secure_random: &random::RANDOM,
+            ..default_provider()
+        };
+        let algorithms = provider.signature_verification_algorithms;
+        let provider = Arc::new(provider);
+
+        Ok(match descriptor.typ {
+            AgentType::Server => {
+                let builder = ServerConfig::builder_with_provider(provider)
+                    .with_protocol_versions(&versions)
+                    .map_err(put_error)?;
+                let builder = if descriptor.client_authentication {
+                    builder.with_client_cert_verifier(Arc::new(PinnedCertVerifier {
+                        trusted_certs: [BOB_CERT.1, EVE_CERT.1],
+                        authenticate_peer: true,
+                        algorithms,
+                    }))
+                } else {
+                    builder.with_no_client_auth()
+                };
+
+                let mut config = builder
+                    .with_single_cert(
+                        vec![CertificateDer::from(ALICE_CERT.1)],
+                        PrivateKeyDer::Pkcs1(PrivatePkcs1KeyDer::from(ALICE_PRIVATE_KEY.1)),
+                    )
+                    .map_err(put_error)?;
+                // Without a ticketer, sessions are only resumed through session IDs in TLS 1.2
+                config.ticketer = ticketer::shared_ticketer();
+
+                RustlsConfig::Server(Arc::new(config))
+            }
+            AgentType::Client => {
+                let builder = ClientConfig::builder_with_provider(provider)
+                    .with_protocol_versions(&versions)
+                    .map_err(put_error)?
+                    .dangerous()
+                    .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier {
+                        trusted_certs: [ALICE_CERT.1, EVE_CERT.1],
+                        authenticate_peer: descriptor.server_authentication,
+                        algorithms,
+                    }));
+
+                let config = if descriptor.client_authentication {
+                    builder
+                        .with_client_auth_cert(
+                            vec![CertificateDer::from(BOB_CERT.1)],
+                            PrivateKeyDer::Pkcs1(PrivatePkcs1KeyDer::from(BOB_PRIVATE_KEY.1)),
+                        )
+                        .map_err(put_error)?
+                } else {
+                    builder.with_no_client_auth()
+                };
+
+                RustlsConfig::Client(Arc::new(config))
+            }
+        })
+    }
+
+    /// Reads and processes the records of the inbound channel
+    fn process_inbound(&mut self) -> Result<(), Error> {
+        loop {
+            match self.connection.read_tls(&mut self.stream) {
+                Ok(0) => return Ok(()),
+                Ok(_) => {
+                    self.connection.process_new_packets().map_err(put_error)?;
+                    // Application data is discarded, else the buffer of rustls fills up
+                    let _ = self.connection.reader().read_to_end(&mut Vec::new());
+                }
+                // Not actually an error, we just reached the end of the inbound channel
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(Error::IO(err.to_string())),
+            }
+        }
+    }
+
+    /// Writes the records which rustls queued to the outbound channel
+    fn write_outbound(&mut self) -> Result<(), Error> {
+        while self.connection.wants_write() {
+            self.connection
+                .write_tls(&mut self.stream)
+                .map_err(|err| Error::IO(err.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Claims the completed handshake. rustls exposes neither the randoms, the session ID and the
+    /// master secret nor the offered ciphers and the signature algorithms, so they are claimed as
+    /// unknown.
+    #[cfg(feature = "claims")]
+    fn claim_finished(&mut self) {
+        self.claimed_finished = true;
+
+        let agent_name = match self.claimer {
+            Some(agent_name) => agent_name,
+            None => return,
+        };
+
+        let protocol_version = match self.connection.protocol_version() {
+            Some(rustls::ProtocolVersion::TLSv1_3) => TLSVersion::V1_3,
+            Some(rustls::ProtocolVersion::TLSv1_2) => TLSVersion::V1_2,
+            _ => self.config.descriptor.tls_version,
+        };
+
+        let peer_certificate = self
+            .connection
+            .peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .map(|certificate| SmallVec::from_slice(certificate.as_ref()))
+            .unwrap_or_default();
+
+        let chosen_cipher = self
+            .connection
+            .negotiated_cipher_suite()
+            .map(|cipher_suite| cipher_suite.suite().get_u16())
+            .unwrap_or_default();
+
+        self.config.claims.deref_borrow_mut().claim_sized(TlsClaim {
+            agent_name,
+            origin: self.config.descriptor.typ,
+            protocol_version,
+            data: ClaimData::Message(ClaimDataMessage::Finished(Finished {
+                outbound: false,
+                client_random: None,
+                server_random: None,
+                session_id: None,
+                authenticate_peer: self.config.authenticate_peer,
+                peer_certificate,
+                master_secret: None,
+                chosen_cipher,
+                available_ciphers: None,
+                signature_algorithm: None,
+                peer_signature_algorithm: None,
+            })),
+        });
+    }
+}
+
+/// The static certificates are self-signed CA certificates without a hostname, which webpki
+/// rejects as certificates of a peer. Therefore, peers are authenticated by comparing their
+/// certificate with the trusted ones. The handshake signatures are still verified.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    /// DER encoded certificates which are accepted from the peer
+    trusted_certs: [&'static [u8]; 2],
+    /// Whether the certificate of the peer is checked
+    authenticate_peer: bool,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl PinnedCertVerifier {
+    fn verify_certificate(&self, end_entity: &CertificateDer<'_>) -> Result<(), rustls::Error> {
+        if !self.authenticate_peer
+            || self
+                .trusted_certs
+                .iter()
+                .any(|trusted_cert| *trusted_cert == end_entity.as_ref())
+        {
+            Ok(())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::UnknownIssuer,
+            ))
+        }
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.verify_certificate(end_entity)
+            .map(|_| ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+impl ClientCertVerifier for PinnedCertVerifier {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        self.verify_certificate(end_entity)
+            .map(|_| ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+fn put_error(err: rustls::Error) -> Error {
+    Error::Put(err.to_string())
+}
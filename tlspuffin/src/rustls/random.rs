@@ -0,0 +1,53 @@
+use std::sync::Mutex;
+
+use ring::rand::SecureRandom as _;
+use rustls::crypto::{GetRandomFailed, SecureRandom};
+
+/// The source of randomness of all rustls agents. It is shared because the [`SecureRandom`] of a
+/// [`CryptoProvider`](rustls::crypto::CryptoProvider) must be `'static`.
+pub static RANDOM: PuffinRandom = PuffinRandom {
+    state: Mutex::new(None),
+};
+
+/// Fills buffers with the randomness of the operating system, or with the output of a SplitMix64
+/// generator once it has been seeded
+#[derive(Debug)]
+pub struct PuffinRandom {
+    state: Mutex<Option<u64>>,
+}
+
+impl PuffinRandom {
+    /// Resets the generator to `seed`. All following output is derived from it.
+    #[cfg(feature = "deterministic")]
+    pub fn seed(&self, seed: u64) {
+        *self.state.lock().unwrap() = Some(seed);
+    }
+
+    /// Whether the output is derived from a seed instead of the randomness of the operating system
+    pub fn is_seeded(&self) -> bool {
+        self.state.lock().map_or(false, |state| state.is_some())
+    }
+}
+
+impl SecureRandom for PuffinRandom {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), GetRandomFailed> {
+        let mut state = self.state.lock().map_err(|_| GetRandomFailed)?;
+
+        match state.as_mut() {
+            Some(state) => {
+                for chunk in buf.chunks_mut(8) {
+                    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+                    let mut z = *state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+                    z ^= z >> 31;
+                    chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+                }
+                Ok(())
+            }
+            None => ring::rand::SystemRandom::new()
+                .fill(buf)
+                .map_err(|_| GetRandomFailed),
+        }
+    }
+}
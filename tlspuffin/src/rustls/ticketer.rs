@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    hmac,
+};
+use rustls::server::ProducesTickets;
+
+/// The key from which the ticket encryption and nonce keys are derived
+const TICKET_KEY: [u8; 32] = [0x42; 32];
+
+/// The lifetime of tickets in seconds, like the ticketer of rustls
+const TICKET_LIFETIME: u32 = 6 * 60 * 60;
+
+thread_local! {
+    /// The ticketer of all rustls servers. Sharing it allows every server of a trace to resume
+    /// the sessions of another one.
+    static TICKETER: Arc<dyn ProducesTickets> = Arc::new(PuffinTicketer::new());
+}
+
+/// Returns the ticketer which is shared by all rustls servers
+pub fn shared_ticketer() -> Arc<dyn ProducesTickets> {
+    TICKETER.with(Arc::clone)
+}
+
+/// Encrypts tickets with ChaCha20-Poly1305 under a fixed key. In contrast to the ticketer of rustls,
+/// the key is not random and the nonce is derived from the plaintext, such that the same session
+/// always results in the same ticket.
+struct PuffinTicketer {
+    key: LessSafeKey,
+    nonce_key: hmac::Key,
+}
+
+impl PuffinTicketer {
+    fn new() -> Self {
+        let encryption_key = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, &TICKET_KEY),
+            b"encryption",
+        );
+        let nonce_key = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &TICKET_KEY), b"nonce");
+
+        Self {
+            key: LessSafeKey::new(
+                UnboundKey::new(&CHACHA20_POLY1305, encryption_key.as_ref()).unwrap(),
+            ),
+            nonce_key: hmac::Key::new(hmac::HMAC_SHA256, nonce_key.as_ref()),
+        }
+    }
+}
+
+impl std::fmt::Debug for PuffinTicketer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PuffinTicketer").finish()
+    }
+}
+
+impl ProducesTickets for PuffinTicketer {
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn lifetime(&self) -> u32 {
+        TICKET_LIFETIME
+    }
+
+    /// Returns the nonce followed by the encrypted plaintext and its tag
+    fn encrypt(&self, plain: &[u8]) -> Option<Vec<u8>> {
+        let tag = hmac::sign(&self.nonce_key, plain);
+        let nonce = &tag.as_ref()[..NONCE_LEN];
+
+        let mut ticket = nonce.to_vec();
+        let mut in_out = plain.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::try_assume_unique_for_key(nonce).ok()?,
+                Aad::empty(),
+                &mut in_out,
+            )
+            .ok()?;
+        ticket.extend_from_slice(&in_out);
+        Some(ticket)
+    }
+
+    fn decrypt(&self, cipher: &[u8]) -> Option<Vec<u8>> {
+        if cipher.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, cipher) = cipher.split_at(NONCE_LEN);
+
+        let mut in_out = cipher.to_vec();
+        let plain_len = self
+            .key
+            .open_in_place(
+                Nonce::try_assume_unique_for_key(nonce).ok()?,
+                Aad::empty(),
+                &mut in_out,
+            )
+            .ok()?
+            .len();
+        in_out.truncate(plain_len);
+        Some(in_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rustls::ticketer::shared_ticketer;
+
+    #[test]
+    fn test_shared_ticketer() {
+        let ticket = shared_ticketer().encrypt(b"session").unwrap();
+
+        assert_eq!(shared_ticketer().encrypt(b"session").unwrap(), ticket);
+        assert_eq!(
+            shared_ticketer().decrypt(&ticket).unwrap(),
+            b"session".to_vec()
+        );
+        assert_eq!(shared_ticketer().decrypt(&ticket[1..]), None);
+    }
+}
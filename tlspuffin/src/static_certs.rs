@@ -10,6 +10,9 @@
 //! openssl ecparam -genkey -name prime256v1 -noout -out random-key.pem
 //! openssl req -new -key random_ec_key.pem -x509 -nodes -days 365 -out random.pem
 //! openssl pkcs8 -topk8 -in random-key.pem -out random-key.pkcs8 -nocrypt
+//!
+//! openssl genpkey -genparam -algorithm DH -pkeyopt group:ffdhe2048 -out ffdhe2048.pem
+//! openssl dhparam -in ffdhe2048.pem -outform DER -out ffdhe2048.der
 //! ```
 
 pub type PEMDER = (&'static str, &'static [u8]);
@@ -62,3 +65,9 @@ pub const RANDOM_EC_CERT: PEMDER = (
     include_str!("../assets/random-ec.pem"),
     include_bytes!("../assets/random-ec.der"),
 );
+
+/// The ffdhe2048 group of RFC 7919, which servers use for ephemeral finite-field Diffie-Hellman
+pub const FFDHE2048_PARAMS: PEMDER = (
+    include_str!("../assets/ffdhe2048.pem"),
+    include_bytes!("../assets/ffdhe2048.der"),
+);
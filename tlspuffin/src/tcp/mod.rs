@@ -1,22 +1,25 @@
 use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
     ffi::OsStr,
-    io,
+    fmt, io,
     io::{ErrorKind, Read, Write},
-    net::{AddrParseError, IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
-    path::Path,
-    process::{Child, Command, Stdio},
-    str::FromStr,
-    sync::{mpsc, mpsc::channel},
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    process::{Child, ChildStderr, ChildStdout, Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
     thread,
-    time::Duration,
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
 use log::error;
 use puffin::{
     agent::{AgentDescriptor, AgentName, AgentType, TLSVersion},
     error::Error,
+    fuzzer::external_coverage,
     protocol::MessageResult,
-    put::{Put, PutDescriptor, PutName},
+    put::{Put, PutName},
     put_registry::Factory,
     stream::Stream,
     trace::TraceContext,
@@ -25,12 +28,15 @@ use puffin::{
 use crate::{
     protocol::TLSProtocolBehavior,
     put_registry::TCP_PUT,
+    tcp::transport::{Connection, Listener, StdioConnection, Transport},
     tls::rustls::msgs::{
         deframer::MessageDeframer,
         message::{Message, OpaqueMessage},
     },
 };
 
+pub mod transport;
+
 pub fn new_tcp_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
     struct TCPFactory;
     impl Factory<TLSProtocolBehavior> for TCPFactory {
@@ -42,6 +48,7 @@ pub fn new_tcp_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
             let put_descriptor = context.put_descriptor(agent_descriptor);
 
             let options = &put_descriptor.options;
+            options.check_keys(OPTION_KEYS)?;
 
             let args = options
                 .get_option("args")
@@ -55,15 +62,29 @@ pub fn new_tcp_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
                 .get_option("cwd")
                 .map(|cwd| Some(cwd.to_owned()))
                 .unwrap_or_default();
-
-            if agent_descriptor.typ == AgentType::Client {
-                let mut server = TcpServerPut::new(agent_descriptor, &put_descriptor)?;
-                server.set_process(TLSProcess::new(&prog, &args, cwd.as_ref()));
+            let transport = Transport::from_options(options)?;
+            // A process which talks through its stdio can only be used once
+            let persistent = transport != Transport::Stdio
+                && options
+                    .get_option("persistent")
+                    .map_or(false, |persistent| persistent == "true");
+            let accept_timeout = match options.get_option("accept-timeout") {
+                Some(millis) => Duration::from_millis(millis.parse().map_err(|err| {
+                    Error::Put(format!("Invalid accept-timeout {}: {}", millis, err))
+                })?),
+                None => ACCEPT_TIMEOUT,
+            };
+
+            if agent_descriptor.typ == AgentType::Client && transport != Transport::Stdio {
+                // We are listening before the client process is started
+                let mut server =
+                    TcpServerPut::new(agent_descriptor, transport, persistent, accept_timeout)?;
+                server.set_process(new_process(&prog, &args, cwd, persistent, false)?);
                 Ok(Box::new(server))
             } else {
-                let process = TLSProcess::new(&prog, &args, cwd);
-                let mut client = TcpClientPut::new(agent_descriptor, &put_descriptor)?;
-                client.set_process(process);
+                let stdio = transport == Transport::Stdio;
+                let process = new_process(&prog, &args, cwd, persistent, stdio)?;
+                let client = TcpClientPut::new(agent_descriptor, transport, process, persistent)?;
                 Ok(Box::new(client))
             }
         }
@@ -80,25 +101,84 @@ pub fn new_tcp_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
     Box::new(TCPFactory)
 }
 
+type SharedProcess = Arc<Mutex<TLSProcess>>;
+
+thread_local! {
+    /// Persistent processes by their program, arguments and working directory. They are
+    /// thread-local, such that every fuzzer client and every test launches its own processes.
+    static PERSISTENT_PROCESSES: RefCell<HashMap<(String, String, Option<String>), SharedProcess>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Spawns the process behind a PUT. If `persistent` is set, then the process is shared with all
+/// other persistent PUTs with the same program, arguments and working directory. It is only
+/// restarted if it exited, or stopped after each execution if external coverage is collected. If `stdio` is set, then the PUT talks to the process through its stdio.
+fn new_process(
+    prog: &str,
+    args: &str,
+    cwd: Option<String>,
+    persistent: bool,
+    stdio: bool,
+) -> io::Result<SharedProcess> {
+    if stdio {
+        return Ok(Arc::new(Mutex::new(TLSProcess::new_stdio(
+            prog, args, cwd,
+        )?)));
+    }
+    if !persistent {
+        return Ok(Arc::new(Mutex::new(TLSProcess::new(prog, args, cwd)?)));
+    }
+
+    PERSISTENT_PROCESSES.with(|processes| {
+        let process =
+            match processes
+                .borrow_mut()
+                .entry((prog.to_owned(), args.to_owned(), cwd.clone()))
+            {
+                Entry::Occupied(entry) => entry.get().clone(),
+                Entry::Vacant(entry) => entry
+                    .insert(Arc::new(Mutex::new(TLSProcess::new(prog, args, cwd)?)))
+                    .clone(),
+            };
+        process.lock().unwrap().restart_if_exited()?;
+
+        // The coverage of a running process is partial, so it is stopped after the execution and
+        // started again by the next one
+        if external_coverage::shm_id().is_some() {
+            let process = process.clone();
+            external_coverage::stop_before_merge(Box::new(move || {
+                process.lock().unwrap().shutdown();
+            }));
+        }
+        Ok(process)
+    })
+}
+
 trait TcpPut {
     fn deframer_mut(&mut self) -> &mut MessageDeframer;
 
+    fn process(&self) -> Option<&SharedProcess>;
+
     fn write_to_stream(&mut self, buf: &[u8]) -> io::Result<()>;
 
     fn read_to_deframer(&mut self) -> io::Result<usize>;
 }
 
-/// A PUT which is backed by a TCP stream to a server.
+/// A PUT which is backed by a connection to a server, see [`Transport`].
 /// In order to use this start an OpenSSL server like this:
 ///
 /// ```bash
 /// openssl s_server -key key.pem -cert cert.pem -accept 44330 -msg -debug -state
 /// ```
+///
+/// With the stdio transport, the process is either a client or a server.
 pub struct TcpClientPut {
-    stream: TcpStream,
+    stream: Box<dyn Connection>,
+    transport: Transport,
     deframer: MessageDeframer,
     agent_descriptor: AgentDescriptor,
-    process: Option<TLSProcess>,
+    process: SharedProcess,
+    persistent: bool,
 }
 
 impl TcpPut for TcpClientPut {
@@ -106,6 +186,10 @@ impl TcpPut for TcpClientPut {
         &mut self.deframer
     }
 
+    fn process(&self) -> Option<&SharedProcess> {
+        Some(&self.process)
+    }
+
     fn write_to_stream(&mut self, buf: &[u8]) -> io::Result<()> {
         self.stream.write_all(buf)?;
         self.stream.flush()
@@ -119,106 +203,137 @@ impl TcpPut for TcpClientPut {
 impl TcpClientPut {
     fn new(
         agent_descriptor: &AgentDescriptor,
-        put_descriptor: &PutDescriptor,
+        transport: Transport,
+        process: SharedProcess,
+        persistent: bool,
     ) -> Result<Self, Error> {
-        let addr = addr_from_config(put_descriptor).map_err(|err| Error::Put(err.to_string()))?;
-        let stream = Self::new_stream(addr)?;
+        let stream = Self::new_stream(&transport, &process)?;
 
         Ok(Self {
             stream,
+            transport,
             deframer: Default::default(),
             agent_descriptor: agent_descriptor.clone(),
-            process: None,
+            process,
+            persistent,
         })
     }
 
-    fn new_stream<A: ToSocketAddrs>(addr: A) -> io::Result<TcpStream> {
-        let mut tries = 10;
-        let stream = loop {
-            if let Ok(stream) = TcpStream::connect(&addr) {
-                // We are waiting 500ms for a response of the PUT behind the TCP socket.
-                // If we are expecting data from it and this timeout is reached, then we assume that
-                // no more will follow.
-                stream.set_read_timeout(Some(Duration::from_millis(500)))?;
-                stream.set_nodelay(true)?;
-                break Some(stream);
-            }
-
-            tries -= 1;
+    /// Connects to the server process. The transport is polled until the server accepts
+    /// connections. With the stdio transport we take over the stdio of the process.
+    fn new_stream(
+        transport: &Transport,
+        process: &SharedProcess,
+    ) -> Result<Box<dyn Connection>, Error> {
+        if *transport == Transport::Stdio {
+            return process
+                .lock()
+                .unwrap()
+                .take_stdio()
+                .map(|stdio| Box::new(stdio) as Box<dyn Connection>)
+                .ok_or_else(|| {
+                    Error::Put("The stdio of the process is already taken".to_string())
+                });
+        }
 
-            if tries == 0 {
-                break None;
+        let start = Instant::now();
+        loop {
+            if let Ok(stream) = transport.connect() {
+                return Ok(stream);
             }
 
-            thread::sleep(Duration::from_millis(500));
-        };
+            // A server which crashed during startup never accepts connections
+            process.lock().unwrap().check_crash(Duration::ZERO)?;
 
-        stream.ok_or(io::Error::new(
-            ErrorKind::NotConnected,
-            "TcpClientPut failed to connect",
-        ))
-    }
+            if start.elapsed() > CONNECT_TIMEOUT {
+                return Err(io::Error::new(
+                    ErrorKind::NotConnected,
+                    "TcpClientPut failed to connect",
+                )
+                .into());
+            }
 
-    pub fn set_process(&mut self, process: TLSProcess) {
-        self.process = Some(process)
+            thread::sleep(POLL_INTERVAL);
+        }
     }
 }
 
+/// A PUT which listens for a connection of a client process, see [`Transport`].
 pub struct TcpServerPut {
-    stream: Option<(TcpStream, TcpListener)>,
-    stream_receiver: mpsc::Receiver<(TcpStream, TcpListener)>,
+    stream: Option<Box<dyn Connection>>,
+    /// The listener is kept for all executions, because binding to the address again fails while
+    /// it is open
+    listener: Listener,
     deframer: MessageDeframer,
     agent_descriptor: AgentDescriptor,
-    process: Option<TLSProcess>,
+    process: Option<SharedProcess>,
+    persistent: bool,
+    /// How long we wait for the client process to connect, configured by the `accept-timeout`
+    /// option in milliseconds
+    accept_timeout: Duration,
+    /// Whether a persistent client process kept running after the previous execution and is
+    /// expected to connect again
+    awaiting_reconnect: bool,
 }
 
 impl TcpServerPut {
     fn new(
         agent_descriptor: &AgentDescriptor,
-        put_descriptor: &PutDescriptor,
+        transport: Transport,
+        persistent: bool,
+        accept_timeout: Duration,
     ) -> Result<Self, Error> {
-        let (sender, stream_receiver) = channel();
-        let addr = addr_from_config(put_descriptor).map_err(|err| Error::Put(err.to_string()))?;
-
-        thread::spawn(move || {
-            let listener = TcpListener::bind(&addr).unwrap();
-
-            if let Some(new_stream) = listener.incoming().next() {
-                let stream = new_stream.unwrap();
-                // We are waiting 500ms for a response of the PUT behind the TCP socket.
-                // If we are expecting data from it and this timeout is reached, then we assume that
-                // no more will follow.
-                stream
-                    .set_read_timeout(Some(Duration::from_millis(500)))
-                    .unwrap();
-                stream.set_nodelay(true).unwrap();
-                sender.send((stream, listener)).unwrap();
-            }
-        });
+        let listener = transport.listen()?;
 
         Ok(Self {
             stream: None,
-            stream_receiver,
+            listener,
             deframer: Default::default(),
             agent_descriptor: agent_descriptor.clone(),
             process: None,
+            persistent,
+            accept_timeout,
+            awaiting_reconnect: false,
         })
     }
 
-    pub fn set_process(&mut self, process: TLSProcess) {
+    fn set_process(&mut self, process: SharedProcess) {
         self.process = Some(process)
     }
 
-    pub fn receive_stream(&mut self) {
+    pub fn receive_stream(&mut self) -> io::Result<()> {
         if self.stream.is_some() {
-            return;
+            return Ok(());
         }
 
-        if let Ok(tuple) = self.stream_receiver.recv_timeout(Duration::from_secs(10)) {
-            self.stream = Some(tuple);
-        } else {
-            panic!("Unable to get stream to client!")
+        let start = Instant::now();
+        while start.elapsed() < self.accept_timeout {
+            if let Some(connection) = self.listener.try_accept()? {
+                self.stream = Some(connection);
+                self.awaiting_reconnect = false;
+                return Ok(());
+            }
+
+            // A persistent client process which exited after its previous connection is
+            // restarted, unless it crashed. One which keeps running without connecting again
+            // is restarted as well, instead of waiting for the whole timeout in every execution.
+            if let (true, Some(process)) = (self.persistent, &self.process) {
+                let mut process = process.lock().unwrap();
+                if !process.is_running() && process.check_crash(Duration::ZERO).is_ok() {
+                    process.restart()?;
+                } else if self.awaiting_reconnect && start.elapsed() >= RECONNECT_TIMEOUT {
+                    process.restart()?;
+                    self.awaiting_reconnect = false;
+                }
+            }
+
+            thread::sleep(POLL_INTERVAL);
         }
+
+        Err(io::Error::new(
+            ErrorKind::TimedOut,
+            "Unable to get stream to client",
+        ))
     }
 }
 
@@ -227,25 +342,28 @@ impl TcpPut for TcpServerPut {
         &mut self.deframer
     }
 
+    fn process(&self) -> Option<&SharedProcess> {
+        self.process.as_ref()
+    }
+
     fn write_to_stream(&mut self, buf: &[u8]) -> io::Result<()> {
-        self.receive_stream();
-        let stream = &mut self.stream.as_mut().unwrap().0;
+        self.receive_stream()?;
+        let stream = self.stream.as_mut().unwrap();
         stream.write_all(buf)?;
         stream.flush()?;
         Ok(())
     }
 
     fn read_to_deframer(&mut self) -> io::Result<usize> {
-        self.receive_stream();
-        let stream = &mut self.stream.as_mut().unwrap().0;
+        self.receive_stream()?;
+        let stream = self.stream.as_mut().unwrap();
         self.deframer.read(stream)
     }
 }
 
 impl Stream<Message, OpaqueMessage> for TcpServerPut {
-    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) {
-        self.write_to_stream(&mut opaque_message.clone().encode())
-            .unwrap();
+    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) -> Result<(), Error> {
+        add_to_inbound(self, opaque_message)
     }
 
     fn take_message_from_outbound(
@@ -256,9 +374,8 @@ impl Stream<Message, OpaqueMessage> for TcpServerPut {
 }
 
 impl Stream<Message, OpaqueMessage> for TcpClientPut {
-    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) {
-        self.write_to_stream(&mut opaque_message.clone().encode())
-            .unwrap();
+    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) -> Result<(), Error> {
+        add_to_inbound(self, opaque_message)
     }
 
     fn take_message_from_outbound(
@@ -268,6 +385,33 @@ impl Stream<Message, OpaqueMessage> for TcpClientPut {
     }
 }
 
+/// Reports a crash of the process behind the TCP stream, see [`TLSProcess::check_crash`].
+fn check_crash<P: TcpPut>(put: &mut P, timeout: Duration) -> Result<(), Error> {
+    put.process().map_or(Ok(()), |process| {
+        process.lock().unwrap().check_crash(timeout)
+    })
+}
+
+/// Stops the process and returns its output. Persistent processes are kept running for other PUTs
+/// and only return the output since the previous shutdown.
+fn shutdown(process: &SharedProcess, persistent: bool) -> String {
+    let mut process = process.lock().unwrap();
+    if persistent {
+        process.take_output()
+    } else {
+        process.shutdown()
+    }
+}
+
+fn add_to_inbound<P: TcpPut>(put: &mut P, opaque_message: &OpaqueMessage) -> Result<(), Error> {
+    put.write_to_stream(&opaque_message.clone().encode())
+        .or_else(|err| {
+            // The connection breaks if the process crashed
+            check_crash(put, Duration::ZERO)?;
+            Err(err.into())
+        })
+}
+
 fn take_message_from_outbound<P: TcpPut>(
     put: &mut P,
 ) -> Result<Option<MessageResult<Message, OpaqueMessage>>, Error> {
@@ -279,16 +423,21 @@ fn take_message_from_outbound<P: TcpPut>(
             match put.read_to_deframer() {
                 Ok(v) => {
                     if v == 0 {
+                        // The stream was closed, which happens if the process crashed
+                        check_crash(put, Duration::ZERO)?;
                         break None;
                     }
                 }
                 Err(err) => match err.kind() {
                     ErrorKind::WouldBlock => {
                         // This is not a hard error. It just means we will should read again from
-                        // the TCPStream in the next steps.
+                        // the connection in the next steps.
                         break None;
                     }
-                    _ => return Err(err.into()),
+                    _ => {
+                        check_crash(put, Duration::ZERO)?;
+                        return Err(err.into());
+                    }
                 },
             }
         }
@@ -310,24 +459,29 @@ fn take_message_from_outbound<P: TcpPut>(
     }
 }
 
-fn addr_from_config(put_descriptor: &PutDescriptor) -> Result<SocketAddr, AddrParseError> {
-    let options = &put_descriptor.options;
-    let host = options.get_option("host").unwrap_or("127.0.0.1");
-    let port = options
-        .get_option("port")
-        .and_then(|value| u16::from_str(value).ok())
-        .expect("Failed to parse port option");
-
-    Ok(SocketAddr::new(IpAddr::from_str(host)?, port))
-}
-
 impl Put<TLSProtocolBehavior> for TcpServerPut {
     fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
-        Ok(())
+        check_crash(self, Duration::ZERO)
     }
 
     fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
-        panic!("Not supported")
+        self.stream = None;
+        self.deframer = Default::default();
+
+        // Clients only connect once, so the process has to connect again. Persistent processes
+        // are only restarted if they exited, else we wait for them to reconnect.
+        self.awaiting_reconnect = false;
+        if let Some(process) = &self.process {
+            let mut process = process.lock().unwrap();
+            if !self.persistent || !process.is_running() {
+                // Pending connections belong to the previous process
+                while self.listener.try_accept()?.is_some() {}
+                process.restart()?;
+            } else {
+                self.awaiting_reconnect = true;
+            }
+        }
+        Ok(())
     }
 
     fn descriptor(&self) -> &AgentDescriptor {
@@ -363,7 +517,7 @@ impl Put<TLSProtocolBehavior> for TcpServerPut {
     }
 
     fn shutdown(&mut self) -> String {
-        self.process.as_mut().unwrap().shutdown().unwrap()
+        shutdown(self.process.as_ref().unwrap(), self.persistent)
     }
 
     fn version() -> String
@@ -376,12 +530,18 @@ impl Put<TLSProtocolBehavior> for TcpServerPut {
 
 impl Put<TLSProtocolBehavior> for TcpClientPut {
     fn progress(&mut self, _agent_name: &AgentName) -> Result<(), Error> {
-        Ok(())
+        check_crash(self, Duration::ZERO)
     }
 
     fn reset(&mut self, _agent_name: AgentName) -> Result<(), Error> {
-        let address = self.stream.peer_addr()?;
-        self.stream = Self::new_stream(address)?;
+        if self.transport == Transport::Stdio {
+            // The process reads from its stdin until the end, so it has to start again
+            self.process.lock().unwrap().restart()?;
+        } else if self.persistent {
+            self.process.lock().unwrap().restart_if_exited()?;
+        }
+        self.stream = Self::new_stream(&self.transport, &self.process)?;
+        self.deframer = Default::default();
         Ok(())
     }
 
@@ -418,7 +578,7 @@ impl Put<TLSProtocolBehavior> for TcpClientPut {
     }
 
     fn shutdown(&mut self) -> String {
-        self.process.as_mut().unwrap().shutdown().unwrap()
+        shutdown(&self.process, self.persistent)
     }
 
     fn version() -> String
@@ -429,50 +589,274 @@ impl Put<TLSProtocolBehavior> for TcpClientPut {
     }
 }
 
+/// The options of this PUT, including those of the [`Transport`]
+const OPTION_KEYS: &[&str] = &[
+    "args",
+    "prog",
+    "cwd",
+    "persistent",
+    "accept-timeout",
+    "transport",
+    "host",
+    "port",
+    "path",
+];
+
+/// Substrings of stderr which indicate that a sanitizer detected an error
+const SANITIZER_REPORTS: &[&str] = &[
+    "ERROR: AddressSanitizer",
+    "ERROR: LeakSanitizer",
+    "WARNING: MemorySanitizer",
+    "WARNING: ThreadSanitizer",
+    "ERROR: UndefinedBehaviorSanitizer",
+    "runtime error:",
+];
+
+/// How long we wait for a server process to accept connections
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long we wait for a client process to connect, unless the `accept-timeout` option is set
+const ACCEPT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a persistent client process which is still running gets to connect again before it is
+/// restarted
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 pub struct TLSProcess {
+    prog: String,
+    args: String,
+    cwd: Option<PathBuf>,
     child: Option<Child>,
-    output: Option<String>,
+    stdout: CapturedOutput,
+    stderr: CapturedOutput,
+    /// Whether the stdio of the process is used as transport instead of being captured
+    stdio: bool,
+    stdio_connection: Option<StdioConnection>,
+    /// The report of a detected crash
+    crash: Option<String>,
 }
 
 impl TLSProcess {
-    pub fn new<P: AsRef<Path>>(prog: &str, args: &str, cwd: Option<P>) -> Self {
-        Self {
-            child: Some(execute_command(prog, args.split(' '), cwd)),
-            output: None,
+    pub fn new<P: AsRef<Path>>(prog: &str, args: &str, cwd: Option<P>) -> io::Result<Self> {
+        Self::spawn(prog, args, cwd, false)
+    }
+
+    /// Starts a process which exchanges TLS records through its stdin and stdout, see
+    /// [`TLSProcess::take_stdio`]. Only the stderr of the process is captured.
+    pub fn new_stdio<P: AsRef<Path>>(prog: &str, args: &str, cwd: Option<P>) -> io::Result<Self> {
+        Self::spawn(prog, args, cwd, true)
+    }
+
+    fn spawn<P: AsRef<Path>>(
+        prog: &str,
+        args: &str,
+        cwd: Option<P>,
+        stdio: bool,
+    ) -> io::Result<Self> {
+        let mut process = Self {
+            prog: prog.to_owned(),
+            args: args.to_owned(),
+            cwd: cwd.map(|cwd| cwd.as_ref().to_path_buf()),
+            child: None,
+            stdout: CapturedOutput::new::<ChildStdout>(None),
+            stderr: CapturedOutput::new::<ChildStderr>(None),
+            stdio,
+            stdio_connection: None,
+            crash: None,
+        };
+        process.restart()?;
+        Ok(process)
+    }
+
+    /// Stops the process if it is running and starts it again. The previous output is discarded.
+    pub fn restart(&mut self) -> io::Result<()> {
+        self.shutdown_status();
+
+        let mut child = execute_command(&self.prog, self.args.split(' '), self.cwd.as_ref())?;
+        if self.stdio {
+            self.stdout = CapturedOutput::new::<ChildStdout>(None);
+            self.stdio_connection = child
+                .stdin
+                .take()
+                .zip(child.stdout.take())
+                .map(|(stdin, stdout)| StdioConnection::new(stdin, stdout));
+        } else {
+            self.stdout = CapturedOutput::new(child.stdout.take());
         }
+        self.stderr = CapturedOutput::new(child.stderr.take());
+        self.child = Some(child);
+        self.crash = None;
+        Ok(())
     }
 
-    pub fn shutdown(&mut self) -> Option<String> {
-        if let Some(mut child) = self.child.take() {
-            child.kill().expect("failed to stop process");
+    /// Takes the stdio of the process, if the process was started with [`TLSProcess::new_stdio`]
+    /// and its stdio was not taken since the last restart.
+    pub fn take_stdio(&mut self) -> Option<StdioConnection> {
+        self.stdio_connection.take()
+    }
 
-            Some(collect_output(child))
+    pub fn restart_if_exited(&mut self) -> io::Result<()> {
+        if !self.is_running() {
+            self.restart()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        self.child
+            .as_mut()
+            .map_or(false, |child| matches!(child.try_wait(), Ok(None)))
+    }
+
+    /// Returns an [`Error::Crash`] if the process was terminated by a signal or a sanitizer
+    /// reported an error. If the process is still running, then we wait up to `timeout` for it
+    /// to exit. Once a crash is detected, it is reported by every following call.
+    pub fn check_crash(&mut self, timeout: Duration) -> Result<(), Error> {
+        if let Some(crash) = &self.crash {
+            return Err(Error::Crash(crash.clone()));
+        }
+
+        let child = match self.child.as_mut() {
+            Some(child) => child,
+            None => return Ok(()),
+        };
+
+        let mut status = child.try_wait()?;
+        let mut waited = Duration::ZERO;
+        while status.is_none() && waited < timeout {
+            thread::sleep(Duration::from_millis(10));
+            waited += Duration::from_millis(10);
+            status = child.try_wait()?;
+        }
+
+        let crashed = if let Some(status) = status {
+            // The pipes are closed once the process exited, so we can read until the end
+            self.stdout.join();
+            self.stderr.join();
+            is_crash(&status) || self.has_sanitizer_report()
+        } else {
+            self.has_sanitizer_report()
+        };
+
+        if crashed {
+            let status = self.shutdown_status();
+            let crash = format!("--- exit status: {}\n{}", status, self.output());
+            self.crash = Some(crash.clone());
+            Err(Error::Crash(crash))
         } else {
-            None
+            Ok(())
         }
     }
+
+    fn has_sanitizer_report(&self) -> bool {
+        let stderr = self.stderr.to_string();
+        SANITIZER_REPORTS
+            .iter()
+            .any(|report| stderr.contains(report))
+    }
+
+    /// Kills the process if it is still running and describes how it terminated.
+    fn shutdown_status(&mut self) -> String {
+        if let Some(mut child) = self.child.take() {
+            // Killing fails if the process already exited
+            let _ = child.kill();
+            let status = child
+                .wait()
+                .map_or_else(|err| err.to_string(), |status| status.to_string());
+            self.stdout.join();
+            self.stderr.join();
+            status
+        } else {
+            "unknown".to_string()
+        }
+    }
+
+    fn output(&self) -> String {
+        format_output(&self.stderr.to_string(), &self.stdout.to_string())
+    }
+
+    /// Like [`TLSProcess::output`], but the output is cleared, such that it is reported once.
+    pub fn take_output(&mut self) -> String {
+        format_output(&self.stderr.take(), &self.stdout.take())
+    }
+
+    /// Stops the process and returns its output
+    pub fn shutdown(&mut self) -> String {
+        self.shutdown_status();
+        self.output()
+    }
 }
 
 impl Drop for TLSProcess {
     fn drop(&mut self) {
-        self.shutdown();
+        self.shutdown_status();
     }
 }
 
-fn collect_output(child: Child) -> String {
-    let output = child.wait_with_output().expect("failed to wait on child");
+fn format_output(stderr: &str, stdout: &str) -> String {
     let mut complete = "--- start stderr\n".to_string();
 
-    complete.push_str(std::str::from_utf8(&output.stderr).unwrap());
+    complete.push_str(stderr);
     complete.push_str("\n--- end stderr\n");
     complete.push_str("--- start stdout\n");
-    complete.push_str(std::str::from_utf8(&output.stdout).unwrap());
+    complete.push_str(stdout);
     complete.push_str("\n--- end stdout\n");
 
     complete
 }
 
-fn execute_command<I, S, P: AsRef<Path>>(prog: &str, args: I, cwd: Option<P>) -> Child
+fn is_crash(status: &ExitStatus) -> bool {
+    // We only kill processes after checking for crashes, so every signal is unexpected
+    status.signal().is_some()
+}
+
+/// Reads a pipe of the process in the background. Else the process blocks once the pipe is full,
+/// and we could not look for sanitizer reports while the process is running.
+struct CapturedOutput {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl CapturedOutput {
+    fn new<R: Read + Send + 'static>(pipe: Option<R>) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+
+        let reader = pipe.map(|mut pipe| {
+            let buffer = buffer.clone();
+            thread::spawn(move || {
+                let mut chunk = [0u8; 4096];
+                while let Ok(n) = pipe.read(&mut chunk) {
+                    if n == 0 {
+                        break;
+                    }
+                    buffer.lock().unwrap().extend_from_slice(&chunk[..n]);
+                }
+            })
+        });
+
+        Self { buffer, reader }
+    }
+
+    /// Removes and returns the output which was read so far
+    fn take(&self) -> String {
+        let buffer = std::mem::take(&mut *self.buffer.lock().unwrap());
+        String::from_utf8_lossy(&buffer).into_owned()
+    }
+
+    /// Waits until the pipe is closed
+    fn join(&mut self) {
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+impl fmt::Display for CapturedOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.buffer.lock().unwrap()))
+    }
+}
+
+fn execute_command<I, S, P: AsRef<Path>>(prog: &str, args: I, cwd: Option<P>) -> io::Result<Child>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
@@ -483,20 +867,31 @@ where
         cmd.current_dir(cwd);
     }
 
+    // Instrumented processes write their coverage to the shared map of the fuzzer
+    if let Some(shm_id) = external_coverage::shm_id() {
+        cmd.env(external_coverage::SHM_ENV_VAR, shm_id);
+    }
+
     cmd.args(args)
         .stdin(Stdio::piped()) // This line is super important! Else the OpenSSL server immediately stops
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
-        .expect("failed to execute process")
+        .map_err(|err| io::Error::new(err.kind(), format!("failed to execute {}: {}", prog, err)))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{
+        io::{Read, Write},
+        process::Child,
+        time::Duration,
+    };
 
     use log::info;
     use puffin::{
         agent::{AgentName, TLSVersion},
+        error::Error,
         put::{PutDescriptor, PutOptions},
     };
     use tempfile::{tempdir, TempDir};
@@ -504,7 +899,7 @@ mod tests {
 
     use crate::{
         put_registry::{TCP_PUT, TLS_PUT_REGISTRY},
-        tcp::{collect_output, execute_command},
+        tcp::{execute_command, new_process, TLSProcess},
         tls::seeds::{
             seed_client_attacker_full, seed_session_resumption_dhe_full,
             seed_successful12_with_tickets, SeedHelper,
@@ -513,6 +908,15 @@ mod tests {
 
     const OPENSSL_PROG: &str = "openssl";
 
+    fn collect_output(child: Child) -> String {
+        let output = child.wait_with_output().expect("failed to wait on child");
+        format!(
+            "--- start stderr\n{}\n--- end stderr\n--- start stdout\n{}\n--- end stdout\n",
+            String::from_utf8_lossy(&output.stderr),
+            String::from_utf8_lossy(&output.stdout)
+        )
+    }
+
     /// In case `temp_dir` is set this acts as a guard. Dropping it makes it invalid.
     struct ParametersGuard {
         port: u16,
@@ -524,9 +928,20 @@ mod tests {
 
     impl ParametersGuard {
         fn build_options(&self) -> PutOptions {
+            self.build_options_with(vec![])
+        }
+
+        fn build_persistent_options(&self) -> PutOptions {
+            self.build_options_with(vec![("persistent", "true")])
+        }
+
+        fn build_options_with(&self, mut options: Vec<(&str, &str)>) -> PutOptions {
             let port = self.port.to_string();
-            let mut options: Vec<(&str, &str)> =
-                vec![("port", &port), ("prog", &self.prog), ("args", &self.args)];
+            options.extend([
+                ("port", port.as_str()),
+                ("prog", &self.prog),
+                ("args", &self.args),
+            ]);
             if let Some(cwd) = &self.cwd {
                 options.push(("cwd", cwd));
             }
@@ -560,11 +975,9 @@ mod tests {
 
         info!(
             "{}",
-            collect_output(execute_command::<_, _, &str>(
-                OPENSSL_PROG,
-                openssl_gen_cert_args,
-                None,
-            ))
+            collect_output(
+                execute_command::<_, _, &str>(OPENSSL_PROG, openssl_gen_cert_args, None).unwrap()
+            )
         );
 
         (key_path.to_owned(), cert_path.to_owned(), temp_dir)
@@ -587,6 +1000,18 @@ mod tests {
                 args.push("-v");
                 args.push("3");
             }
+            TLSVersion::V1_1 => {
+                args.push("-v");
+                args.push("2");
+            }
+            TLSVersion::V1_0 => {
+                args.push("-v");
+                args.push("1");
+            }
+            TLSVersion::SSL3 => {
+                args.push("-v");
+                args.push("0");
+            }
         }
 
         ParametersGuard {
@@ -616,20 +1041,21 @@ mod tests {
     }
 
     fn openssl_server(port: u16, version: TLSVersion) -> ParametersGuard {
+        let port_string = port.to_string();
+        openssl_server_on(port, &["-accept", &port_string], version)
+    }
+
+    /// Starts an OpenSSL server which accepts connections on the Unix domain socket `path`
+    fn openssl_unix_server(path: &str, version: TLSVersion) -> ParametersGuard {
+        openssl_server_on(0, &["-unix", path, "-unlink"], version)
+    }
+
+    fn openssl_server_on(port: u16, accept: &[&str], version: TLSVersion) -> ParametersGuard {
         let (key, cert, temp_dir) = gen_certificate();
 
-        let port_string = port.to_string();
-        let mut args = vec![
-            "s_server",
-            "-accept",
-            &port_string,
-            "-msg",
-            "-state",
-            "-key",
-            &key,
-            "-cert",
-            &cert,
-        ];
+        let mut args = vec!["s_server"];
+        args.extend(accept);
+        args.extend(["-msg", "-state", "-key", &key, "-cert", &cert]);
 
         match version {
             TLSVersion::V1_3 => {
@@ -638,6 +1064,15 @@ mod tests {
             TLSVersion::V1_2 => {
                 args.push("-tls1_2");
             }
+            TLSVersion::V1_1 => {
+                args.push("-tls1_1");
+            }
+            TLSVersion::V1_0 => {
+                args.push("-tls1");
+            }
+            TLSVersion::SSL3 => {
+                args.push("-ssl3");
+            }
         }
 
         ParametersGuard {
@@ -660,6 +1095,15 @@ mod tests {
             TLSVersion::V1_2 => {
                 args.push("-tls1_2");
             }
+            TLSVersion::V1_1 => {
+                args.push("-tls1_1");
+            }
+            TLSVersion::V1_0 => {
+                args.push("-tls1");
+            }
+            TLSVersion::SSL3 => {
+                args.push("-ssl3");
+            }
         }
 
         ParametersGuard {
@@ -671,6 +1115,72 @@ mod tests {
         }
     }
 
+    /// Starts `sh` with a script which consists of `commands`
+    fn shell_process(commands: &str) -> (TLSProcess, TempDir) {
+        let temp_dir = tempdir().unwrap();
+        let script = temp_dir.path().join("script.sh");
+        std::fs::write(&script, commands).unwrap();
+
+        let process = TLSProcess::new::<&str>("sh", script.to_str().unwrap(), None).unwrap();
+        (process, temp_dir)
+    }
+
+    #[test]
+    fn test_check_crash_signal() {
+        let (mut process, _temp_dir) = shell_process("echo before crash; kill -SEGV $$");
+
+        let result = process.check_crash(Duration::from_secs(5));
+        match result {
+            Err(Error::Crash(output)) => assert!(output.contains("before crash")),
+            _ => panic!("Expected a crash but got {:?}", result),
+        }
+        // The crash is reported again and the output is still available
+        assert!(matches!(
+            process.check_crash(Duration::ZERO),
+            Err(Error::Crash(_))
+        ));
+        assert!(process.shutdown().contains("before crash"));
+    }
+
+    #[test]
+    fn test_check_crash_sanitizer() {
+        let (mut process, _temp_dir) =
+            shell_process("echo '==1==ERROR: AddressSanitizer: heap-buffer-overflow' >&2; exit 1");
+
+        let result = process.check_crash(Duration::from_secs(5));
+        match result {
+            Err(Error::Crash(output)) => assert!(output.contains("heap-buffer-overflow")),
+            _ => panic!("Expected a crash but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_check_crash_exit() {
+        let (mut process, _temp_dir) = shell_process("exit 1");
+        assert!(process.check_crash(Duration::from_secs(5)).is_ok());
+
+        let (mut process, _temp_dir) = shell_process("sleep 10");
+        assert!(process.check_crash(Duration::ZERO).is_ok());
+        process.shutdown();
+    }
+
+    #[test]
+    fn test_stdio_process() {
+        let mut process = TLSProcess::new_stdio::<&str>("cat", "-", None).unwrap();
+
+        let mut stdio = process.take_stdio().unwrap();
+        assert!(process.take_stdio().is_none());
+        stdio.write_all(b"record").unwrap();
+        let mut buf = [0u8; 6];
+        stdio.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"record");
+
+        // Every restart provides new stdio
+        process.restart().unwrap();
+        assert!(process.take_stdio().is_some());
+        process.shutdown();
+    }
+
     #[test]
     fn test_openssl_session_resumption_dhe_full() {
         let port = 44330;
@@ -715,6 +1225,61 @@ mod tests {
         assert!(!shutdown.contains("Reused session-id"));
     }
 
+    #[test]
+    fn test_openssl_unix_seed_client_attacker_full() {
+        let socket_dir = tempdir().unwrap();
+        let socket = socket_dir.path().join("server.sock");
+        let socket = socket.to_str().unwrap();
+
+        let guard = openssl_unix_server(socket, TLSVersion::V1_3);
+        let put = PutDescriptor {
+            name: TCP_PUT,
+            options: guard.build_options_with(vec![("transport", "unix"), ("path", socket)]),
+        };
+
+        let trace = seed_client_attacker_full.build_trace();
+        let server = trace.descriptors[0].name;
+        let mut context = trace.execute_with_puts(&TLS_PUT_REGISTRY, &[(server, put)]);
+
+        let server = AgentName::first();
+        let shutdown = context.find_agent_mut(server).unwrap().put_mut().shutdown();
+        info!("{}", shutdown);
+        assert!(shutdown.contains("BEGIN SSL SESSION PARAMETERS"));
+    }
+
+    #[test]
+    fn test_openssl_persistent_seed_client_attacker_full() {
+        let port = 44334;
+
+        let guard = openssl_server(port, TLSVersion::V1_3);
+        let put = PutDescriptor {
+            name: TCP_PUT,
+            options: guard.build_persistent_options(),
+        };
+
+        let trace = seed_client_attacker_full.build_trace();
+        let server = trace.descriptors[0].name;
+        for _ in 0..2 {
+            let mut context = trace.execute_with_puts(&TLS_PUT_REGISTRY, &[(server, put.clone())]);
+            let shutdown = context
+                .find_agent_mut(AgentName::first())
+                .unwrap()
+                .put_mut()
+                .shutdown();
+            info!("{}", shutdown);
+            // Only the output of this execution is reported
+            assert_eq!(shutdown.matches("BEGIN SSL SESSION PARAMETERS").count(), 1);
+        }
+
+        // Both traces were executed against the same server process, which is still running
+        let process =
+            new_process(&guard.prog, &guard.args, guard.cwd.clone(), true, false).unwrap();
+        let mut process = process.lock().unwrap();
+        assert!(process.is_running());
+        let shutdown = process.shutdown();
+        assert_eq!(shutdown.matches("BEGIN SSL SESSION PARAMETERS").count(), 0);
+    }
+
     #[test]
     fn test_openssl_openssl_seed_successful12() {
         let port = 44332;
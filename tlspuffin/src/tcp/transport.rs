@@ -0,0 +1,348 @@
+//! Transports over which the PUTs of this module exchange TLS records with external processes.
+//! The transport is selected through the `transport` option:
+//!
+//! * `tcp` (default): The `host` and `port` options define the address.
+//! * `unix`: The `path` option defines the Unix domain socket. Paths which start with `@` are
+//!   names in the abstract namespace.
+//! * `stdio`: The process reads TLS records from its stdin and writes them to its stdout.
+
+use std::{
+    fs, io,
+    io::{ErrorKind, Read, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{SocketAddr as UnixSocketAddr, UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    process::{ChildStdin, ChildStdout},
+    str::FromStr,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use puffin::{error::Error, put::PutOptions};
+
+/// We are waiting 500ms for a response of the PUT behind the transport. If we are expecting data
+/// from it and this timeout is reached, then we assume that no more will follow.
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    Tcp(SocketAddr),
+    /// A Unix domain socket in the file system
+    Unix(PathBuf),
+    /// A Unix domain socket in the abstract namespace
+    UnixAbstract(Vec<u8>),
+    /// The stdin and stdout of the process
+    Stdio,
+}
+
+impl Transport {
+    pub fn from_options(options: &PutOptions) -> Result<Self, Error> {
+        match options.get_option("transport").unwrap_or("tcp") {
+            "tcp" => {
+                let host = options.get_option("host").unwrap_or("127.0.0.1");
+                let port = options
+                    .get_option("port")
+                    .and_then(|value| u16::from_str(value).ok())
+                    .ok_or_else(|| Error::Agent("Unable to find port".to_string()))?;
+                let ip = IpAddr::from_str(host).map_err(|err| Error::Put(err.to_string()))?;
+                Ok(Transport::Tcp(SocketAddr::new(ip, port)))
+            }
+            "unix" => {
+                let path = options
+                    .get_option("path")
+                    .ok_or_else(|| Error::Agent("Unable to find path".to_string()))?;
+                Ok(match path.strip_prefix('@') {
+                    Some(name) => Transport::UnixAbstract(name.as_bytes().to_vec()),
+                    None => Transport::Unix(PathBuf::from(path)),
+                })
+            }
+            "stdio" => Ok(Transport::Stdio),
+            transport => Err(Error::Agent(format!("Unknown transport {}", transport))),
+        }
+    }
+
+    /// Connects to a process which listens on the transport. Returns [`ErrorKind::NotConnected`]
+    /// for the stdio transport, whose connection is owned by the process.
+    pub fn connect(&self) -> io::Result<Box<dyn Connection>> {
+        match self {
+            Transport::Tcp(addr) => {
+                let stream = TcpStream::connect(addr)?;
+                stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                stream.set_nodelay(true)?;
+                Ok(Box::new(stream))
+            }
+            Transport::Unix(path) => unix_connection(UnixStream::connect(path)?),
+            Transport::UnixAbstract(name) => unix_connection(UnixStream::connect_addr(
+                &UnixSocketAddr::from_abstract_namespace(name)?,
+            )?),
+            Transport::Stdio => Err(io::Error::new(
+                ErrorKind::NotConnected,
+                "The stdio transport can not be connected",
+            )),
+        }
+    }
+
+    /// Binds to the transport. Connections are accepted with [`Listener::try_accept`].
+    pub fn listen(&self) -> io::Result<Listener> {
+        let listener = match self {
+            Transport::Tcp(addr) => {
+                let listener = TcpListener::bind(addr)?;
+                listener.set_nonblocking(true)?;
+                Listener::Tcp(listener)
+            }
+            Transport::Unix(path) => {
+                remove_socket(path)?;
+                Listener::Unix(unix_listener(UnixListener::bind(path)?)?)
+            }
+            Transport::UnixAbstract(name) => Listener::Unix(unix_listener(
+                UnixListener::bind_addr(&UnixSocketAddr::from_abstract_namespace(name)?)?,
+            )?),
+            Transport::Stdio => {
+                return Err(io::Error::new(
+                    ErrorKind::Unsupported,
+                    "The stdio transport can not listen",
+                ))
+            }
+        };
+
+        Ok(listener)
+    }
+}
+
+/// Removes the socket of a previous listener. Fails if `path` is not a socket, such that other
+/// files are never removed.
+fn remove_socket(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path),
+        Ok(_) => Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        )),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn unix_listener(listener: UnixListener) -> io::Result<UnixListener> {
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+fn unix_connection(stream: UnixStream) -> io::Result<Box<dyn Connection>> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    Ok(Box::new(stream))
+}
+
+/// A bidirectional byte stream to the process. Reads fail with [`ErrorKind::WouldBlock`] if
+/// the process did not send anything within 500ms.
+pub trait Connection: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Connection for T {}
+
+/// A non-blocking listener. The socket is closed once the listener is dropped.
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl Listener {
+    /// Accepts a pending connection without blocking. Returns `None` if no process connected yet.
+    pub fn try_accept(&self) -> io::Result<Option<Box<dyn Connection>>> {
+        let connection = match self {
+            Listener::Tcp(listener) => listener.accept().and_then(|(stream, _)| {
+                stream.set_nonblocking(false)?;
+                stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                stream.set_nodelay(true)?;
+                Ok(Box::new(stream) as Box<dyn Connection>)
+            }),
+            Listener::Unix(listener) => listener
+                .accept()
+                .and_then(|(stream, _)| unix_connection(stream)),
+        };
+
+        match connection {
+            Ok(connection) => Ok(Some(connection)),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// The stdin and stdout of a process. The stdout is read in the background, such that reads can
+/// time out like the ones of sockets.
+pub struct StdioConnection {
+    stdin: ChildStdin,
+    receiver: mpsc::Receiver<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl StdioConnection {
+    pub fn new(stdin: ChildStdin, mut stdout: ChildStdout) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut chunk = [0u8; 4096];
+            while let Ok(n) = stdout.read(&mut chunk) {
+                if n == 0 || sender.send(chunk[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            stdin,
+            receiver,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Read for StdioConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.receiver.recv_timeout(READ_TIMEOUT) {
+                Ok(data) => self.pending = data,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(
+                        ErrorKind::WouldBlock,
+                        "The process did not write to stdout",
+                    ))
+                }
+                // The process closed its stdout
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for StdioConnection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdin.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdin.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        process::{Command, Stdio},
+    };
+
+    use puffin::put::PutOptions;
+
+    use super::*;
+
+    #[test]
+    fn test_from_options() {
+        let transport = |options| Transport::from_options(&PutOptions::new(options)).unwrap();
+
+        assert_eq!(
+            transport(vec![("port", "44330")]),
+            Transport::Tcp("127.0.0.1:44330".parse().unwrap())
+        );
+        assert_eq!(
+            transport(vec![("transport", "unix"), ("path", "/tmp/put.sock")]),
+            Transport::Unix(PathBuf::from("/tmp/put.sock"))
+        );
+        assert_eq!(
+            transport(vec![("transport", "unix"), ("path", "@put")]),
+            Transport::UnixAbstract(b"put".to_vec())
+        );
+        assert_eq!(transport(vec![("transport", "stdio")]), Transport::Stdio);
+        assert!(Transport::from_options(&PutOptions::new(vec![("transport", "udp")])).is_err());
+    }
+
+    #[test]
+    fn test_unix_abstract() {
+        let transport = Transport::UnixAbstract(b"tlspuffin-transport-test".to_vec());
+        let listener = transport.listen().unwrap();
+        assert!(listener.try_accept().unwrap().is_none());
+
+        let mut client = transport.connect().unwrap();
+        let mut server = listener.try_accept().unwrap().unwrap();
+
+        client.write_all(b"hello").unwrap();
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+
+        // Nothing was written to the client
+        let err = client.read(&mut buf).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::WouldBlock | ErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    fn test_unix_remove_socket() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // The socket of the previous listener is replaced
+        let path = temp_dir.path().join("put.sock");
+        let transport = Transport::Unix(path.clone());
+        drop(transport.listen().unwrap());
+        assert!(path.exists());
+        let listener = transport.listen().unwrap();
+        let _client = transport.connect().unwrap();
+        assert!(listener.try_accept().unwrap().is_some());
+
+        // Other files are kept
+        let path = temp_dir.path().join("put.txt");
+        fs::write(&path, "data").unwrap();
+        let err = Transport::Unix(path.clone()).listen().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "data");
+    }
+
+    #[test]
+    fn test_drop_listener() {
+        let transport = Transport::Tcp("127.0.0.1:0".parse().unwrap());
+        let listener = transport.listen().unwrap();
+        let addr = match &listener {
+            Listener::Tcp(listener) => listener.local_addr().unwrap(),
+            Listener::Unix(_) => unreachable!(),
+        };
+
+        // Nothing accepts connections once the listener is gone
+        drop(listener);
+        assert!(Transport::Tcp(addr).connect().is_err());
+    }
+
+    #[test]
+    fn test_stdio() {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut connection =
+            StdioConnection::new(child.stdin.take().unwrap(), child.stdout.take().unwrap());
+
+        connection.write_all(b"hello").unwrap();
+        connection.flush().unwrap();
+        let mut buf = [0u8; 5];
+        connection.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello");
+
+        let err = connection.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -1,18 +1,22 @@
 use std::sync::Arc;
 
-use puffin::algebra::error::FnError;
+use puffin::{algebra::error::FnError, codec::Codec};
 use ring::signature::{RsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
 
 use crate::{
     static_certs::{
         ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT, RANDOM_EC_CERT,
+        RANDOM_EC_PRIVATE_KEY_PKCS8,
     },
     tls::rustls::{
         hash_hs::HandshakeHash,
         key::{Certificate, PrivateKey},
         msgs::{
             enums::SignatureScheme,
-            handshake::{CertificateEntry, CertificateExtensions, HandshakePayload},
+            handshake::{
+                CertificateEntry, CertificateExtensions, HandshakePayload, Random, ServerDHParams,
+                ServerECDHParams,
+            },
             message::{Message, MessagePayload},
         },
         sign::{EcdsaSigningKey, RsaSigner, Signer, SigningKey},
@@ -46,6 +50,15 @@ pub fn fn_random_ec_cert() -> Result<Vec<u8>, FnError> {
     Ok(RANDOM_EC_CERT.1.into())
 }
 
+pub fn fn_random_ec_key() -> Result<Vec<u8>, FnError> {
+    Ok(RANDOM_EC_PRIVATE_KEY_PKCS8.1.into())
+}
+
+/// Wraps a DER encoded `cert` for the TLS 1.2 Certificate message.
+pub fn fn_certificate_from_der(cert: &Vec<u8>) -> Result<Certificate, FnError> {
+    Ok(Certificate(cert.clone()))
+}
+
 pub fn fn_certificate_entry(cert: &Vec<u8>) -> Result<CertificateEntry, FnError> {
     Ok(CertificateEntry {
         cert: Certificate(cert.clone()),
@@ -119,7 +132,50 @@ pub fn fn_rsa_sign_server(
     )
 }
 
-fn _fn_rsa_sign(
+/// Signs the finite-field Diffie-Hellman `params` of a TLS 1.2 ServerKeyExchange.
+pub fn fn_rsa_sign_dhe_params(
+    client_random: &Random,
+    server_random: &Random,
+    params: &ServerDHParams,
+    private_key: &Vec<u8>,
+    scheme: &SignatureScheme,
+) -> Result<Vec<u8>, FnError> {
+    _fn_rsa_sign(
+        &construct_tls12_server_key_exchange_message(client_random, server_random, params),
+        private_key,
+        scheme,
+    )
+}
+
+/// Signs the elliptic curve Diffie-Hellman `params` of a TLS 1.2 ServerKeyExchange.
+pub fn fn_rsa_sign_ecdhe_params(
+    client_random: &Random,
+    server_random: &Random,
+    params: &ServerECDHParams,
+    private_key: &Vec<u8>,
+    scheme: &SignatureScheme,
+) -> Result<Vec<u8>, FnError> {
+    _fn_rsa_sign(
+        &construct_tls12_server_key_exchange_message(client_random, server_random, params),
+        private_key,
+        scheme,
+    )
+}
+
+/// The signature of a TLS 1.2 ServerKeyExchange covers both randoms and the parameters.
+fn construct_tls12_server_key_exchange_message(
+    client_random: &Random,
+    server_random: &Random,
+    params: &impl Codec,
+) -> Vec<u8> {
+    let mut message = Vec::new();
+    client_random.encode(&mut message);
+    server_random.encode(&mut message);
+    params.encode(&mut message);
+    message
+}
+
+pub(crate) fn _fn_rsa_sign(
     message: &[u8],
     private_key: &Vec<u8>,
     scheme: &SignatureScheme,
@@ -166,7 +222,7 @@ pub fn fn_ecdsa_sign_server(
     _fn_ecdsa_sign(&message, private_key)
 }
 
-fn _fn_ecdsa_sign(message: &[u8], private_key: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+pub(crate) fn _fn_ecdsa_sign(message: &[u8], private_key: &Vec<u8>) -> Result<Vec<u8>, FnError> {
     let key = EcdsaSigningKey::new(
         &PrivateKey(private_key.to_vec()),
         SignatureScheme::ECDSA_NISTP256_SHA256,
@@ -62,6 +62,17 @@ pub fn fn_seq_16() -> Result<u64, FnError> {
     Ok(16)
 }
 
+/// The byte `high * 16 + low`, which allows terms to express all byte values with [`fn_seq_0`]
+/// to [`fn_seq_15`].
+pub fn fn_byte_from_nibbles(high: &u64, low: &u64) -> Result<u64, FnError> {
+    if *high > 15 || *low > 15 {
+        return Err(FnError::Unknown(
+            "Nibbles must be smaller than 16".to_string(),
+        ));
+    }
+    Ok(high * 16 + low)
+}
+
 /// Used in heartbleed attack
 pub fn fn_large_length() -> Result<u64, FnError> {
     Ok(32702) // chosen by experimenting
@@ -293,7 +293,12 @@ nyi_fn!();
 /// Padding => 0x0015,
 nyi_fn!();
 /// encrypt_then_mac => 0x0016,
-nyi_fn!();
+pub fn fn_encrypt_then_mac_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::EncryptThenMacRequest)
+}
+pub fn fn_encrypt_then_mac_server_extension() -> Result<ServerExtension, FnError> {
+    Ok(ServerExtension::EncryptThenMacAck)
+}
 /// ExtendedMasterSecret => 0x0017,
 pub fn fn_extended_master_secret_extension() -> Result<ClientExtension, FnError> {
     Ok(ClientExtension::ExtendedMasterSecretRequest)
@@ -368,11 +373,28 @@ pub fn fn_append_preshared_keys_identity(
 
 pub fn fn_preshared_keys_extension_empty_binder(
     new_ticket: &Message,
+) -> Result<ClientExtension, FnError> {
+    let ticket_age_millis: u32 = 100; // 100ms since receiving NewSessionTicket
+    preshared_keys_extension_empty_binder(new_ticket, ticket_age_millis)
+}
+
+/// Like [`fn_preshared_keys_extension_empty_binder`], but the ticket age is one minute. Servers
+/// still resume the session, but must reject early data because the age is outside of the
+/// tolerated window (RFC 8446, Section 8.3).
+pub fn fn_preshared_keys_extension_empty_binder_late(
+    new_ticket: &Message,
+) -> Result<ClientExtension, FnError> {
+    let ticket_age_millis: u32 = 60_000;
+    preshared_keys_extension_empty_binder(new_ticket, ticket_age_millis)
+}
+
+fn preshared_keys_extension_empty_binder(
+    new_ticket: &Message,
+    ticket_age_millis: u32,
 ) -> Result<ClientExtension, FnError> {
     let ticket: Vec<u8> = fn_get_ticket(new_ticket)?;
     let age_add: u64 = fn_get_ticket_age_add(new_ticket)?;
 
-    let ticket_age_millis: u32 = 100; // 100ms since receiving NewSessionTicket
     let obfuscated_ticket_age = ticket_age_millis.wrapping_add(age_add as u32);
 
     let resuming_suite = &crate::tls::rustls::tls13::TLS13_AES_128_GCM_SHA256; // todo allow other cipher suites
@@ -466,7 +488,9 @@ pub fn fn_certificate_authorities_extension() -> Result<CertReqExtension, FnErro
 /// OIDFilters => 0x0030,
 nyi_fn!();
 /// PostHandshakeAuth => 0x0031,
-nyi_fn!();
+pub fn fn_post_handshake_auth_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::PostHandshakeAuth)
+}
 /// SignatureAlgorithmsCert => 0x0032,
 pub fn fn_signature_algorithm_cert_extension() -> Result<ClientExtension, FnError> {
     Ok(ClientExtension::SignatureAlgorithmsCert(
@@ -548,6 +572,42 @@ pub fn fn_transport_parameters_server_extension(
 nyi_fn!();
 /// ChannelId => 0x754f,
 nyi_fn!();
+/// EchOuterExtensions => 0xfd00,
+nyi_fn!();
+/// EncryptedClientHello => 0xfe0d,
+pub fn fn_ech_inner_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::EncryptedClientHello(
+        EncryptedClientHello::Inner,
+    ))
+}
+/// An ECH extension for the ClientHelloOuter with an `enc` and a `payload` which are chosen by the
+/// attacker, see [`crate::tls::fn_impl::fn_encrypt_client_hello`] for a valid one.
+pub fn fn_ech_outer_extension(
+    config: &EchConfigPayload,
+    enc: &Vec<u8>,
+    payload: &Vec<u8>,
+) -> Result<ClientExtension, FnError> {
+    let config_id = match config {
+        EchConfigPayload::Fe0d(contents) => contents.key_config.config_id,
+        EchConfigPayload::Unknown { .. } => 0,
+    };
+    Ok(ClientExtension::EncryptedClientHello(
+        EncryptedClientHello::Outer(EncryptedClientHelloOuter {
+            cipher_suite: HpkeSymmetricCipherSuite {
+                kdf_id: HpkeKdf::HKDF_SHA256,
+                aead_id: HpkeAead::AES_128_GCM,
+            },
+            config_id,
+            enc: PayloadU16::new(enc.clone()),
+            payload: PayloadU16::new(payload.clone()),
+        }),
+    ))
+}
+pub fn fn_ech_retry_configs_server_extension(
+    configs: &EchConfigList,
+) -> Result<ServerExtension, FnError> {
+    Ok(ServerExtension::EncryptedClientHello(configs.clone()))
+}
 /// RenegotiationInfo => 0xff01,
 pub fn fn_renegotiation_info_extension(data: &Vec<u8>) -> Result<ClientExtension, FnError> {
     Ok(ClientExtension::RenegotiationInfo(PayloadU8::new(
@@ -559,6 +619,17 @@ pub fn fn_renegotiation_info_server_extension(data: &Vec<u8>) -> Result<ServerEx
         data.clone(),
     )))
 }
+/// The renegotiated_connection of a server during a secure renegotiation is the verify_data of
+/// the previous Finished of the client followed by the one of the server (RFC 5746). The client
+/// sends only its own verify_data.
+pub fn fn_renegotiated_connection(
+    client_verify_data: &Vec<u8>,
+    server_verify_data: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let mut renegotiated_connection = client_verify_data.clone();
+    renegotiated_connection.extend_from_slice(server_verify_data);
+    Ok(renegotiated_connection)
+}
 /// TransportParametersDraft => 0xffa5
 pub fn fn_transport_parameters_draft_extension(
     parameters: &Vec<u8>,
@@ -7,13 +7,17 @@ use puffin::{
 };
 
 use crate::tls::{
-    key_exchange::tls12_new_secrets,
+    key_exchange::{
+        tls12_new_secrets, tls12_new_secrets_from_premaster, tls12_new_secrets_with_client_random,
+    },
     key_schedule::dhe_key_schedule,
     rustls::{
         hash_hs::HandshakeHash,
         key_log::NoKeyLog,
         msgs::{
-            enums::{CipherSuite, Compression, ExtensionType, NamedGroup, ProtocolVersion},
+            enums::{
+                CipherSuite, Compression, ContentType, ExtensionType, NamedGroup, ProtocolVersion,
+            },
             handshake::{ClientExtension, HasServerExtensions, Random, ServerExtension, SessionID},
         },
     },
@@ -27,6 +31,52 @@ pub fn fn_protocol_version12() -> Result<ProtocolVersion, FnError> {
     Ok(ProtocolVersion::TLSv1_2)
 }
 
+pub fn fn_protocol_version11() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::TLSv1_1)
+}
+
+pub fn fn_protocol_version10() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::TLSv1_0)
+}
+
+pub fn fn_protocol_version_ssl3() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::SSLv3)
+}
+
+/// A protocol version of the `value`, which is unknown or not valid as a record version
+pub fn fn_protocol_version_unknown(value: &u64) -> Result<ProtocolVersion, FnError> {
+    let value = u16::try_from(*value)
+        .map_err(|_err| FnError::Unknown("Protocol version must fit into two bytes".to_string()))?;
+    Ok(ProtocolVersion::from(value))
+}
+
+pub fn fn_content_type_change_cipher_spec() -> Result<ContentType, FnError> {
+    Ok(ContentType::ChangeCipherSpec)
+}
+
+pub fn fn_content_type_alert() -> Result<ContentType, FnError> {
+    Ok(ContentType::Alert)
+}
+
+pub fn fn_content_type_handshake() -> Result<ContentType, FnError> {
+    Ok(ContentType::Handshake)
+}
+
+pub fn fn_content_type_application_data() -> Result<ContentType, FnError> {
+    Ok(ContentType::ApplicationData)
+}
+
+pub fn fn_content_type_heartbeat() -> Result<ContentType, FnError> {
+    Ok(ContentType::Heartbeat)
+}
+
+/// A content type of the `value`, which is unknown unless it is one of the above
+pub fn fn_content_type_unknown(value: &u64) -> Result<ContentType, FnError> {
+    let value = u8::try_from(*value)
+        .map_err(|_err| FnError::Unknown("Content type must fit into a byte".to_string()))?;
+    Ok(ContentType::from(value))
+}
+
 pub fn fn_new_session_id() -> Result<SessionID, FnError> {
     let mut id: Vec<u8> = Vec::from([3u8; 32]);
     id.insert(0, 32);
@@ -182,6 +232,40 @@ pub fn fn_sign_transcript(
     Ok(secrets.client_verify_data(&vh))
 }
 
+/// Like [`fn_sign_transcript`], but for a `premaster_secret` which was established by any key
+/// exchange.
+pub fn fn_sign_transcript_premaster(
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_new_secrets_from_premaster(server_random, premaster_secret)?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.client_verify_data(&vh))
+}
+
+/// Like [`fn_sign_transcript`], but for a `client_random` which was not necessarily chosen by the
+/// attacker. Computes the verify_data of the Finished of the client or, if `client` is false, of
+/// the server. The `ecdh_pubkey` is the public key of the peer of the attacker.
+pub fn fn_sign_transcript_with_client_random(
+    client_random: &Random,
+    server_random: &Random,
+    ecdh_pubkey: &Vec<u8>,
+    transcript: &HandshakeHash,
+    group: &NamedGroup,
+    client: &bool,
+) -> Result<Vec<u8>, FnError> {
+    let secrets =
+        tls12_new_secrets_with_client_random(client_random, server_random, ecdh_pubkey, group)?;
+
+    let vh = transcript.get_current_hash();
+    Ok(match *client {
+        true => secrets.client_verify_data(&vh),
+        false => secrets.server_verify_data(&vh),
+    })
+}
+
 // ----
 // Cipher Suites
 // ----
@@ -211,6 +295,26 @@ pub fn fn_cipher_suite12() -> Result<CipherSuite, FnError> {
     )
 }
 
+pub fn fn_cipher_suite12_rsa_aes_128_gcm_sha256() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_RSA_WITH_AES_128_GCM_SHA256)
+}
+
+pub fn fn_cipher_suite12_dhe_rsa_aes_128_gcm_sha256() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_DHE_RSA_WITH_AES_128_GCM_SHA256)
+}
+
+pub fn fn_cipher_suite12_aes_128_cbc_sha() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA)
+}
+
+pub fn fn_cipher_suite12_aes_256_cbc_sha() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA)
+}
+
+pub fn fn_cipher_suite12_aes_128_cbc_sha256() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256)
+}
+
 pub fn fn_cipher_suite13_aes_128_gcm_sha256() -> Result<CipherSuite, FnError> {
     Ok(CipherSuite::TLS13_AES_128_GCM_SHA256)
 }
@@ -21,7 +21,7 @@ use crate::{
             enums::*,
             handshake::{CertificateEntry, CertificateStatus, HelloRetryExtension, *},
             heartbeat::HeartbeatPayload,
-            message::{Message, MessagePayload, OpaqueMessage},
+            message::{Message, MessagePayload, OpaqueMessage, PlainMessage},
         },
     },
 };
@@ -30,11 +30,50 @@ pub fn fn_opaque_message(message: &OpaqueMessage) -> Result<OpaqueMessage, FnErr
     Ok(message.clone())
 }
 
+/// Packs the `message` into an unencrypted record, such that its header can be overridden.
+pub fn fn_plaintext_record(message: &Message) -> Result<OpaqueMessage, FnError> {
+    Ok(PlainMessage::from(message.clone()).into_unencrypted_opaque())
+}
+
+/// Overrides the content type in the header of the `record`, which may be unknown.
+pub fn fn_record_content_type(
+    record: &OpaqueMessage,
+    typ: &ContentType,
+) -> Result<OpaqueMessage, FnError> {
+    let mut record = record.clone();
+    record.typ = *typ;
+    Ok(record)
+}
+
+/// Overrides the legacy version in the header of the `record`.
+pub fn fn_record_version(
+    record: &OpaqueMessage,
+    version: &ProtocolVersion,
+) -> Result<OpaqueMessage, FnError> {
+    let mut record = record.clone();
+    record.version = *version;
+    Ok(record)
+}
+
+/// Overrides the length in the header of the `record`, independently of the length of its
+/// payload.
+pub fn fn_record_fake_length(
+    record: &OpaqueMessage,
+    fake_length: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let fake_length = u16::try_from(*fake_length)
+        .map_err(|_err| FnError::Unknown("Record length must fit into two bytes".to_string()))?;
+    let mut record = record.clone();
+    record.fake_length = Some(fake_length);
+    Ok(record)
+}
+
 pub fn fn_empty_handshake_message() -> Result<OpaqueMessage, FnError> {
     Ok(OpaqueMessage {
         typ: ContentType::Handshake,
         version: ProtocolVersion::TLSv1_2,
         payload: Payload::empty(),
+        fake_length: None,
     })
 }
 
@@ -227,7 +266,15 @@ pub fn fn_new_session_ticket13(
     })
 }
 /// EndOfEarlyData => 0x05,
-nyi_fn!();
+pub fn fn_end_of_early_data() -> Result<Message, FnError> {
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::EndOfEarlyData,
+            payload: HandshakePayload::EndOfEarlyData,
+        }),
+    })
+}
 /// HelloRetryRequest => 0x06,
 pub fn fn_hello_retry_request(
     legacy_version: &ProtocolVersion,
@@ -306,6 +353,42 @@ pub fn fn_server_key_exchange(data: &Vec<u8>) -> Result<Message, FnError> {
         }),
     })
 }
+/// Like [`fn_server_key_exchange`], but with typed finite-field Diffie-Hellman parameters.
+pub fn fn_server_key_exchange_dhe(
+    params: &ServerDHParams,
+    dss: &DigitallySignedStruct,
+) -> Result<Message, FnError> {
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::ServerKeyExchange,
+            payload: HandshakePayload::ServerKeyExchange(ServerKeyExchangePayload::DHE(
+                DHEServerKeyExchange {
+                    params: params.clone(),
+                    dss: dss.clone(),
+                },
+            )),
+        }),
+    })
+}
+/// Like [`fn_server_key_exchange`], but with typed elliptic curve Diffie-Hellman parameters.
+pub fn fn_server_key_exchange_ecdhe(
+    params: &ServerECDHParams,
+    dss: &DigitallySignedStruct,
+) -> Result<Message, FnError> {
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::ServerKeyExchange,
+            payload: HandshakePayload::ServerKeyExchange(ServerKeyExchangePayload::ECDHE(
+                ECDHEServerKeyExchange {
+                    params: params.clone(),
+                    dss: dss.clone(),
+                },
+            )),
+        }),
+    })
+}
 /// CertificateRequest => 0x0d,
 pub fn fn_certificate_request() -> Result<Message, FnError> {
     // todo unclear where the arguments come from here, needs manual trace implementation
@@ -3,29 +3,43 @@
 
 use std::convert::TryFrom;
 
+use num_bigint::BigUint;
 use puffin::{
     algebra::error::FnError,
     codec::{Codec, Reader},
+    protocol::{OpaqueMessageSequence, ProtocolMessage},
 };
 
 use crate::tls::{
-    key_exchange::{tls12_key_exchange, tls12_new_secrets},
+    hpke::{hpke_open, hpke_seal, x25519_public_key},
+    key_exchange::{
+        dhe_public_key, dhe_shared_secret, pkcs1_pad, rsa_encrypt_raw, rsa_modulus_len,
+        tls12_key_exchange, tls12_new_secrets, tls12_new_secrets_from_premaster,
+        tls12_new_secrets_with_client_random, tls_cbc_new_secrets, FFDHE2048_P, OAKLEY_GROUP1_P,
+    },
     key_schedule::*,
     rustls::{
         conn::Side,
         hash_hs::HandshakeHash,
         key::Certificate,
         msgs::{
-            base::PayloadU8,
-            enums::{HandshakeType, NamedGroup},
+            base::{Payload, PayloadU16, PayloadU8},
+            enums::{
+                CipherSuite, ExtensionType, HandshakeType, HpkeAead, HpkeKdf, HpkeKem, NamedGroup,
+                ProtocolVersion, SignatureScheme,
+            },
             handshake::{
-                CertificateEntry, CertificateExtension, CertificateExtensions,
-                HandshakeMessagePayload, HandshakePayload, Random, ServerECDHParams,
+                CertificateEntry, CertificateExtension, CertificateExtensions, ClientExtension,
+                ClientHelloPayload, DigitallySignedStruct, EchConfigContents, EchConfigList,
+                EchConfigPayload, EncryptedClientHello, EncryptedClientHelloOuter,
+                HandshakeMessagePayload, HandshakePayload, HpkeKeyConfig, HpkeSymmetricCipherSuite,
+                HpkeSymmetricCipherSuites, Random, ServerDHParams, ServerECDHParams,
+                ServerExtension, ServerHelloPayload, ServerKeyExchangePayload, SessionID,
             },
             message::{Message, MessagePayload, OpaqueMessage, PlainMessage},
         },
-        tls12,
-        tls13::key_schedule::KeyScheduleEarly,
+        tls12::{self, CbcWriteKeys, Tls12CbcCipherSuite},
+        tls13::key_schedule::{hkdf_expand, KeyScheduleEarly, PayloadU8Len},
     },
 };
 
@@ -171,6 +185,122 @@ pub fn fn_encrypt_application(
     Ok(application_data)
 }
 
+/// Like [`fn_encrypt_application`], but the traffic secret of the `client` or server has been
+/// updated `key_updates` times. The `sequence` restarts at 0 after each KeyUpdate.
+pub fn fn_encrypt_application_updated(
+    some_message: &Message,
+    server_hello_transcript: &HandshakeHash,
+    server_finished_transcript: &HandshakeHash,
+    server_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    group: &NamedGroup,
+    client: &bool,
+    key_updates: &u64,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let (suite, key) = tls13_updated_application_traffic_secret(
+        server_hello_transcript,
+        server_finished_transcript,
+        server_key_share,
+        psk,
+        group,
+        *client,
+        *key_updates,
+    )?;
+    let encrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_encrypter(&key);
+    let application_data = encrypter
+        .encrypt(PlainMessage::from(some_message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt_application_updated".to_string())
+        })?;
+    Ok(application_data)
+}
+
+/// Like [`fn_decrypt_application`], but the traffic secret of the peer has been updated
+/// `key_updates` times.
+pub fn fn_decrypt_application_updated(
+    application_data: &Message,
+    server_hello_transcript: &HandshakeHash,
+    server_finished_transcript: &HandshakeHash,
+    server_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    group: &NamedGroup,
+    client: &bool,
+    key_updates: &u64,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let (suite, key) = tls13_updated_application_traffic_secret(
+        server_hello_transcript,
+        server_finished_transcript,
+        server_key_share,
+        psk,
+        group,
+        !*client,
+        *key_updates,
+    )?;
+    let decrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_decrypter(&key);
+    let message = decrypter
+        .decrypt(
+            PlainMessage::from(application_data.clone()).into_unencrypted_opaque(),
+            *sequence,
+        )
+        .map_err(|_err| {
+            FnError::Crypto("Failed to decrypt it fn_decrypt_application_updated".to_string())
+        })?;
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
+/// Encrypts 0-RTT data with the client early traffic secret. The `client_hello_transcript`
+/// contains only the ClientHello which offered the early data.
+pub fn fn_encrypt_early_data(
+    some_message: &Message,
+    client_hello_transcript: &HandshakeHash,
+    psk: &Vec<u8>,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let (suite, key) = tls13_early_traffic_secret(client_hello_transcript, psk)?;
+    let encrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_encrypter(&key);
+    let early_data = encrypter
+        .encrypt(PlainMessage::from(some_message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt_early_data".to_string())
+        })?;
+    Ok(early_data)
+}
+
+pub fn fn_decrypt_early_data(
+    early_data: &Message,
+    client_hello_transcript: &HandshakeHash,
+    psk: &Vec<u8>,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let (suite, key) = tls13_early_traffic_secret(client_hello_transcript, psk)?;
+    let decrypter = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .derive_decrypter(&key);
+    let message = decrypter
+        .decrypt(
+            PlainMessage::from(early_data.clone()).into_unencrypted_opaque(),
+            *sequence,
+        )
+        .map_err(|_err| {
+            FnError::Crypto("Failed to decrypt it fn_decrypt_early_data".to_string())
+        })?;
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
 pub fn fn_derive_psk(
     server_hello: &HandshakeHash,
     server_finished: &HandshakeHash,
@@ -308,6 +438,44 @@ pub fn fn_encode_ec_pubkey12(pubkey: &Vec<u8>) -> Result<Vec<u8>, FnError> {
     Ok(buf)
 }
 
+/// Inverse of [`fn_encode_ec_pubkey12`], e.g. for the payload of a ClientKeyExchange.
+pub fn fn_decode_ec_pubkey12(data: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut rd = Reader::init(data.as_slice());
+    let ecpoint = PayloadU8::read(&mut rd)
+        .ok_or_else(|| FnError::Unknown("Failed to parse ec public key".to_string()))?;
+    Ok(ecpoint.0)
+}
+
+/// Returns the random of a ClientHello or ServerHello. This is required for messages which were
+/// decrypted by the attacker, as no knowledge is extracted from them.
+pub fn fn_get_random(hello: &Message) -> Result<Random, FnError> {
+    match &hello.payload {
+        MessagePayload::Handshake(payload) => match &payload.payload {
+            HandshakePayload::ClientHello(client_hello) => Some(client_hello.random),
+            HandshakePayload::ServerHello(server_hello) => Some(server_hello.random),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find random in message".to_owned()))
+}
+
+/// Returns the payload of a ClientKeyExchange or of a ServerKeyExchange of an unknown key
+/// exchange algorithm, like the knowledge which is extracted from plaintext messages.
+pub fn fn_get_key_exchange_data(key_exchange: &Message) -> Result<Vec<u8>, FnError> {
+    match &key_exchange.payload {
+        MessagePayload::Handshake(payload) => match &payload.payload {
+            HandshakePayload::ClientKeyExchange(data) => Some(data.0.clone()),
+            HandshakePayload::ServerKeyExchange(ServerKeyExchangePayload::Unknown(data)) => {
+                Some(data.0.clone())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find key exchange data in message".to_owned()))
+}
+
 pub fn fn_encrypt12(
     message: &Message,
     server_random: &Random,
@@ -328,6 +496,768 @@ pub fn fn_encrypt12(
     Ok(encrypted)
 }
 
+/// Like [`fn_encrypt12`], but for a `client_random` which was not necessarily chosen by the
+/// attacker. The `ecdh_pubkey` is the public key of the peer of the attacker. During a
+/// renegotiation, the handshake messages are encrypted with the keys of the previous handshake.
+pub fn fn_encrypt12_with_client_random(
+    message: &Message,
+    client_random: &Random,
+    server_random: &Random,
+    ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    client: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let secrets =
+        tls12_new_secrets_with_client_random(client_random, server_random, ecdh_pubkey, group)?;
+
+    let (_decrypter, encrypter) = secrets.make_cipher_pair(match *client {
+        true => Side::Client,
+        false => Side::Server,
+    });
+    encrypter
+        .encrypt(PlainMessage::from(message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt12_with_client_random".to_string())
+        })
+}
+
+/// Decrypts a `message` which the peer of the attacker sent. If `client` is true, then the
+/// attacker is the client and the message was sent by the server.
+pub fn fn_decrypt12_with_client_random(
+    message: &Message,
+    client_random: &Random,
+    server_random: &Random,
+    ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    client: &bool,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let secrets =
+        tls12_new_secrets_with_client_random(client_random, server_random, ecdh_pubkey, group)?;
+
+    let (decrypter, _encrypter) = secrets.make_cipher_pair(match *client {
+        true => Side::Client,
+        false => Side::Server,
+    });
+    let message = decrypter
+        .decrypt(
+            PlainMessage::from(message.clone()).into_unencrypted_opaque(),
+            *sequence,
+        )
+        .map_err(|_err| {
+            FnError::Crypto("Failed to decrypt it fn_decrypt12_with_client_random".to_string())
+        })?;
+    Message::try_from(message)
+        .map_err(|_err| FnError::Crypto("Failed to create Message from decrypted data".to_string()))
+}
+
+// ----
+// TLS 1.2 CBC record protection
+// ----
+
+/// Derives the keys with which the `client` or the server writes records using the CBC
+/// `cipher_suite`. The master secret is derived like in [`fn_encrypt12`], but with the PRF of the
+/// `version`. The `version` also selects the MAC and whether records have an explicit IV.
+pub fn fn_cbc_write_keys(
+    version: &ProtocolVersion,
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+    cipher_suite: &CipherSuite,
+    client: &bool,
+) -> Result<CbcWriteKeys, FnError> {
+    let suite = tls12_cbc_suite(cipher_suite)?;
+    let (randoms, master_secret) =
+        tls_cbc_new_secrets(server_random, server_ecdh_pubkey, group, suite, *version)?;
+
+    Ok(suite.write_keys(
+        *version,
+        &master_secret,
+        &randoms,
+        match *client {
+            true => Side::Client,
+            false => Side::Server,
+        },
+    ))
+}
+
+fn tls12_cbc_suite(cipher_suite: &CipherSuite) -> Result<&'static Tls12CbcCipherSuite, FnError> {
+    Tls12CbcCipherSuite::lookup(*cipher_suite)
+        .ok_or_else(|| FnError::Crypto(format!("No CBC implementation of {:?}", cipher_suite)))
+}
+
+/// Creates a transcript which keeps all handshake messages. The Finished of versions before
+/// TLS 1.2 hashes them with MD5 and SHA-1, see [`fn_sign_transcript_cbc`].
+pub fn fn_new_transcript_cbc() -> Result<HandshakeHash, FnError> {
+    let suite = &tls12::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256;
+
+    let transcript = HandshakeHash::new_buffered(suite.hash_algorithm());
+    Ok(transcript)
+}
+
+/// Like [`fn_sign_transcript`](crate::tls::fn_impl::fn_sign_transcript), but for the CBC
+/// `cipher_suite` negotiated with the `version`. The `transcript` must be created by
+/// [`fn_new_transcript_cbc`].
+pub fn fn_sign_transcript_cbc(
+    server_random: &Random,
+    server_ecdh_pubkey: &Vec<u8>,
+    transcript: &HandshakeHash,
+    group: &NamedGroup,
+    cipher_suite: &CipherSuite,
+    version: &ProtocolVersion,
+) -> Result<Vec<u8>, FnError> {
+    let suite = tls12_cbc_suite(cipher_suite)?;
+    let (_randoms, master_secret) =
+        tls_cbc_new_secrets(server_random, server_ecdh_pubkey, group, suite, *version)?;
+    let handshake = transcript.get_handshake_buf().ok_or_else(|| {
+        FnError::Unknown("Transcript does not keep the handshake messages".to_string())
+    })?;
+
+    Ok(suite.verify_data(*version, &master_secret, handshake, Side::Client))
+}
+
+/// Encrypts the `message` with the CBC `keys`. If `encrypt_then_mac` is set, then the MAC is
+/// computed over the ciphertext as negotiated by the encrypt_then_mac extension.
+pub fn fn_encrypt12_cbc(
+    message: &Message,
+    keys: &CbcWriteKeys,
+    encrypt_then_mac: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let encrypted = keys
+        .encrypter(*encrypt_then_mac)
+        .encrypt(PlainMessage::from(message.clone()).borrow(), *sequence)
+        .map_err(|_err| FnError::Crypto("Failed to encrypt it fn_encrypt12_cbc".to_string()))?;
+    Ok(encrypted)
+}
+
+/// Computes the MAC which is encrypted along with the `message` in a MAC-then-encrypt record.
+pub fn fn_cbc_mac12(
+    message: &Message,
+    keys: &CbcWriteKeys,
+    sequence: &u64,
+) -> Result<Vec<u8>, FnError> {
+    let plain = PlainMessage::from(message.clone());
+    Ok(keys.mac(*sequence, plain.typ, &plain.payload.0))
+}
+
+/// Appends the MAC over the ciphertext of the `record` as negotiated by the encrypt_then_mac
+/// extension. Together with [`fn_encrypt12_cbc_raw`] and an empty MAC, this creates an
+/// encrypt-then-MAC record with a padding chosen by the attacker.
+pub fn fn_cbc_append_mac12(
+    record: &OpaqueMessage,
+    keys: &CbcWriteKeys,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let mut record = record.clone();
+    let mac = keys.mac(*sequence, record.typ, &record.payload.0);
+    record.payload.0.extend_from_slice(&mac);
+    Ok(record)
+}
+
+/// Computes the shortest valid padding of a record which holds the `message` and the `mac`.
+/// The padding includes the padding length byte.
+pub fn fn_cbc_padding(message: &Message, mac: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    const CBC_BLOCK_LEN: usize = 16;
+
+    let len = PlainMessage::from(message.clone()).payload.0.len() + mac.len();
+    let padding_len = CBC_BLOCK_LEN - 1 - len % CBC_BLOCK_LEN;
+    let padding_value = u8::try_from(padding_len)
+        .map_err(|_err| FnError::Unknown("CBC padding can not be longer than 255".to_string()))?;
+    Ok(vec![padding_value; padding_len + 1])
+}
+
+/// Like [`fn_cbc_padding`], but all bytes of the padding are set to `value`. The padding is valid
+/// if `value` is at most the length of the padding.
+pub fn fn_cbc_padding_value(
+    message: &Message,
+    mac: &Vec<u8>,
+    value: &u64,
+) -> Result<Vec<u8>, FnError> {
+    let value = u8::try_from(*value)
+        .map_err(|_err| FnError::Unknown("CBC padding byte must fit into a byte".to_string()))?;
+    let padding = fn_cbc_padding(message, mac)?;
+    Ok(vec![value; padding.len()])
+}
+
+/// Creates a padding of `length` bytes and the padding length byte, which is valid if the record
+/// is a multiple of the block size. Padding can be up to 255 bytes long.
+pub fn fn_cbc_padding_length(length: &u64) -> Result<Vec<u8>, FnError> {
+    let padding_len = u8::try_from(*length)
+        .map_err(|_err| FnError::Unknown("CBC padding can not be longer than 255".to_string()))?;
+    Ok(vec![padding_len; padding_len as usize + 1])
+}
+
+/// Encrypts a MAC-then-encrypt record which consists of the `message`, the `mac` and the
+/// `padding`. In contrast to [`fn_encrypt12_cbc`], the MAC and the padding are not computed, but
+/// chosen by the attacker. Their total length must be a multiple of the block size.
+pub fn fn_encrypt12_cbc_raw(
+    message: &Message,
+    mac: &Vec<u8>,
+    padding: &Vec<u8>,
+    keys: &CbcWriteKeys,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let plain = PlainMessage::from(message.clone());
+    let mut fragment = plain.payload.0;
+    fragment.extend_from_slice(mac);
+    fragment.extend_from_slice(padding);
+
+    let payload = keys
+        .encrypt_fragment(*sequence, &fragment)
+        .map_err(|_err| FnError::Crypto("Failed to encrypt it fn_encrypt12_cbc_raw".to_string()))?;
+    Ok(OpaqueMessage {
+        typ: plain.typ,
+        version: plain.version,
+        payload: Payload::new(payload),
+        fake_length: None,
+    })
+}
+
+// ----
+// TLS 1.2 DHE and RSA key exchange
+// ----
+
+pub fn fn_dhe_params(
+    p: &Vec<u8>,
+    g: &Vec<u8>,
+    pubkey: &Vec<u8>,
+) -> Result<ServerDHParams, FnError> {
+    Ok(ServerDHParams::new(p, g, pubkey))
+}
+
+pub fn fn_ecdhe_params(group: &NamedGroup, pubkey: &Vec<u8>) -> Result<ServerECDHParams, FnError> {
+    Ok(ServerECDHParams::new(*group, pubkey))
+}
+
+pub fn fn_digitally_signed(
+    scheme: &SignatureScheme,
+    signature: &Vec<u8>,
+) -> Result<DigitallySignedStruct, FnError> {
+    Ok(DigitallySignedStruct::new(*scheme, signature.clone()))
+}
+
+/// Parses the finite-field Diffie-Hellman parameters of the payload of a ServerKeyExchange.
+pub fn fn_decode_dhe_params(data: &Vec<u8>) -> Result<ServerDHParams, FnError> {
+    let mut rd = Reader::init(data.as_slice());
+    ServerDHParams::read(&mut rd)
+        .ok_or_else(|| FnError::Unknown("Failed to parse dhe parameters".to_string()))
+}
+
+/// Parses the elliptic curve Diffie-Hellman parameters of the payload of a ServerKeyExchange.
+pub fn fn_decode_ecdhe_params(data: &Vec<u8>) -> Result<ServerECDHParams, FnError> {
+    let mut rd = Reader::init(data.as_slice());
+    ServerECDHParams::read(&mut rd)
+        .ok_or_else(|| FnError::Unknown("Failed to parse ecdhe parameters".to_string()))
+}
+
+pub fn fn_get_dhe_p(params: &ServerDHParams) -> Result<Vec<u8>, FnError> {
+    Ok(params.dh_p.0.clone())
+}
+
+pub fn fn_get_dhe_g(params: &ServerDHParams) -> Result<Vec<u8>, FnError> {
+    Ok(params.dh_g.0.clone())
+}
+
+pub fn fn_get_dhe_pubkey(params: &ServerDHParams) -> Result<Vec<u8>, FnError> {
+    Ok(params.dh_Ys.0.clone())
+}
+
+pub fn fn_get_ecdhe_group(params: &ServerECDHParams) -> Result<NamedGroup, FnError> {
+    Ok(params.curve_params.named_group)
+}
+
+pub fn fn_get_ecdhe_pubkey(params: &ServerECDHParams) -> Result<Vec<u8>, FnError> {
+    Ok(params.public.0.clone())
+}
+
+pub fn fn_dh_ffdhe2048_p() -> Result<Vec<u8>, FnError> {
+    Ok(FFDHE2048_P.to_vec())
+}
+
+pub fn fn_dh_oakley_group1_p() -> Result<Vec<u8>, FnError> {
+    Ok(OAKLEY_GROUP1_P.to_vec())
+}
+
+pub fn fn_dh_generator() -> Result<Vec<u8>, FnError> {
+    Ok(vec![2])
+}
+
+/// The value one, which is an invalid generator or public key
+pub fn fn_dh_one() -> Result<Vec<u8>, FnError> {
+    Ok(vec![1])
+}
+
+/// The value `p - 1`, which is an invalid generator or public key as it generates a subgroup of
+/// order two
+pub fn fn_dh_minus_one(p: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let p = BigUint::from_bytes_be(p);
+    if p == BigUint::from(0u8) {
+        return Err(FnError::Crypto("DH modulus must not be zero".to_string()));
+    }
+    Ok((p - 1u8).to_bytes_be())
+}
+
+/// Computes the public key of the attacker in the group of the prime `p` and the generator `g`.
+/// The group is not validated.
+pub fn fn_new_dhe_pubkey(p: &Vec<u8>, g: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    dhe_public_key(p, g)
+}
+
+/// Encodes a finite-field Diffie-Hellman public key for a ClientKeyExchange.
+pub fn fn_encode_dh_pubkey(pubkey: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut buf = Vec::new();
+    PayloadU16::new(pubkey.clone()).encode(&mut buf);
+    Ok(buf)
+}
+
+/// Computes the premaster secret from the public key of the server and the key of the attacker
+/// from [`fn_new_dhe_pubkey`].
+pub fn fn_dhe_premaster_secret(p: &Vec<u8>, server_pubkey: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    dhe_shared_secret(p, server_pubkey)
+}
+
+/// Computes the premaster secret from the public key of the server and the key of the attacker
+/// from [`fn_new_pubkey12`].
+pub fn fn_ecdhe_premaster_secret(
+    server_ecdh_pubkey: &Vec<u8>,
+    group: &NamedGroup,
+) -> Result<Vec<u8>, FnError> {
+    let kx = tls12_key_exchange(group)?;
+    kx.complete(server_ecdh_pubkey, |secret| Ok(secret.to_vec()))
+        .map_err(|_err| FnError::Crypto("Failed to compute ecdhe premaster secret".to_string()))
+}
+
+/// Creates the premaster secret of RSA key transport which starts with the `version` which the
+/// client offered. The remaining 46 bytes are fixed such that traces are deterministic.
+pub fn fn_new_premaster_secret(version: &ProtocolVersion) -> Result<Vec<u8>, FnError> {
+    let mut premaster_secret = Vec::with_capacity(48);
+    version.encode(&mut premaster_secret);
+    premaster_secret.resize(48, 42);
+    Ok(premaster_secret)
+}
+
+/// The certificate of the peer is the first of the chain.
+pub fn fn_get_first_certificate(certs: &Vec<Certificate>) -> Result<Certificate, FnError> {
+    certs
+        .first()
+        .cloned()
+        .ok_or_else(|| FnError::Unknown("Certificate chain is empty".to_string()))
+}
+
+/// The header of PKCS#1 v1.5 encryption paddings
+pub fn fn_pkcs1_encryption_header() -> Result<Vec<u8>, FnError> {
+    Ok(vec![0, 2])
+}
+
+/// A PKCS#1 v1.5 header with the `block_type`. Only the block type 2 is valid for encryption.
+pub fn fn_pkcs1_header(block_type: &u64) -> Result<Vec<u8>, FnError> {
+    let block_type = u8::try_from(*block_type)
+        .map_err(|_err| FnError::Unknown("PKCS#1 block type must fit into a byte".to_string()))?;
+    Ok(vec![0, block_type])
+}
+
+/// The header of PKCS#1 v1.5 signature paddings, which is invalid for encryption
+pub fn fn_pkcs1_signature_header() -> Result<Vec<u8>, FnError> {
+    Ok(vec![0, 1])
+}
+
+/// Pads the `message` for an RSA encryption to the key of the `cert` using PKCS#1 v1.5.
+pub fn fn_pkcs1_pad(message: &Vec<u8>, cert: &Certificate) -> Result<Vec<u8>, FnError> {
+    pkcs1_pad(message, rsa_modulus_len(cert)?, &[0, 2], true)
+}
+
+/// Like [`fn_pkcs1_pad`], but the `header` and whether the padding string is terminated by a zero
+/// `separator` is chosen by the attacker. This yields malformed paddings.
+pub fn fn_pkcs1_pad_raw(
+    message: &Vec<u8>,
+    cert: &Certificate,
+    header: &Vec<u8>,
+    separator: &bool,
+) -> Result<Vec<u8>, FnError> {
+    pkcs1_pad(message, rsa_modulus_len(cert)?, header, *separator)
+}
+
+/// Encrypts the padded `message` to the RSA key of the `cert`.
+pub fn fn_rsa_encrypt(message: &Vec<u8>, cert: &Certificate) -> Result<Vec<u8>, FnError> {
+    rsa_encrypt_raw(message, cert)
+}
+
+/// Encodes an encrypted premaster secret for a ClientKeyExchange.
+pub fn fn_encode_rsa_premaster_secret(encrypted: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut buf = Vec::new();
+    PayloadU16::new(encrypted.clone()).encode(&mut buf);
+    Ok(buf)
+}
+
+/// Like [`fn_encrypt12`], but for a `premaster_secret` which was established by any key exchange.
+pub fn fn_encrypt12_premaster(
+    message: &Message,
+    server_random: &Random,
+    premaster_secret: &Vec<u8>,
+    client: &bool,
+    sequence: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let secrets = tls12_new_secrets_from_premaster(server_random, premaster_secret)?;
+
+    let (_decrypter, encrypter) = secrets.make_cipher_pair(match *client {
+        true => Side::Client,
+        false => Side::Server,
+    });
+    let encrypted = encrypter
+        .encrypt(PlainMessage::from(message.clone()).borrow(), *sequence)
+        .map_err(|_err| {
+            FnError::Crypto("Failed to encrypt it fn_encrypt12_premaster".to_string())
+        })?;
+    Ok(encrypted)
+}
+
+// ----
+// Record fragmentation and coalescing
+// ----
+
+pub fn fn_new_records() -> Result<OpaqueMessageSequence<OpaqueMessage>, FnError> {
+    Ok(OpaqueMessageSequence(vec![]))
+}
+
+/// Appends the `message` unencrypted as a single record.
+pub fn fn_append_record(
+    records: &OpaqueMessageSequence<OpaqueMessage>,
+    message: &Message,
+) -> Result<OpaqueMessageSequence<OpaqueMessage>, FnError> {
+    fn_append_opaque_record(records, &message.create_opaque())
+}
+
+/// Appends a `record` as it is, e.g. an encrypted one.
+pub fn fn_append_opaque_record(
+    records: &OpaqueMessageSequence<OpaqueMessage>,
+    record: &OpaqueMessage,
+) -> Result<OpaqueMessageSequence<OpaqueMessage>, FnError> {
+    let mut new_records = records.clone();
+    new_records.0.push(record.clone());
+    Ok(new_records)
+}
+
+/// Splits the `message` into unencrypted records of at most `fragment_len` bytes like the
+/// [`MessageFragmenter`](crate::tls::rustls::msgs::fragmenter::MessageFragmenter). In contrast to
+/// it, arbitrarily small fragments are allowed. A message without payload results in a single empty
+/// record.
+pub fn fn_fragment_message(
+    message: &Message,
+    fragment_len: &u64,
+) -> Result<OpaqueMessageSequence<OpaqueMessage>, FnError> {
+    if *fragment_len == 0 {
+        return Err(FnError::Unknown(
+            "Fragments must be at least one byte long".to_string(),
+        ));
+    }
+
+    let plain = PlainMessage::from(message.clone());
+    let mut fragments: Vec<&[u8]> = plain.payload.0.chunks(*fragment_len as usize).collect();
+    if fragments.is_empty() {
+        fragments.push(&[]);
+    }
+
+    Ok(OpaqueMessageSequence(
+        fragments
+            .into_iter()
+            .map(|fragment| OpaqueMessage {
+                typ: plain.typ,
+                version: plain.version,
+                payload: Payload::new(fragment),
+                fake_length: None,
+            })
+            .collect(),
+    ))
+}
+
+/// Splits the last of the `records` into two records at the `offset`. If the offset is at or
+/// beyond the end of the record, then the second record is empty.
+pub fn fn_split_last_record(
+    records: &OpaqueMessageSequence<OpaqueMessage>,
+    offset: &u64,
+) -> Result<OpaqueMessageSequence<OpaqueMessage>, FnError> {
+    let mut new_records = records.clone();
+    let last = new_records
+        .0
+        .pop()
+        .ok_or_else(|| FnError::Unknown("No record to split".to_string()))?;
+
+    let offset = (*offset as usize).min(last.payload.0.len());
+    let (first, second) = last.payload.0.split_at(offset);
+
+    for fragment in [first, second] {
+        new_records.0.push(OpaqueMessage {
+            typ: last.typ,
+            version: last.version,
+            payload: Payload::new(fragment),
+            fake_length: None,
+        });
+    }
+    Ok(new_records)
+}
+
+/// Packs the `first` and the `second` message into a single unencrypted record. The content type
+/// and the version of the record are the ones of the `first` message, therefore the messages can
+/// have different content types.
+pub fn fn_coalesce_messages(first: &Message, second: &Message) -> Result<OpaqueMessage, FnError> {
+    let mut record = PlainMessage::from(first.clone());
+    record
+        .payload
+        .0
+        .extend_from_slice(&PlainMessage::from(second.clone()).payload.0);
+    Ok(record.into_unencrypted_opaque())
+}
+
+/// Packs all of the `records` into a single record with the content type and the version of the
+/// first record.
+pub fn fn_coalesce_records(
+    records: &OpaqueMessageSequence<OpaqueMessage>,
+) -> Result<OpaqueMessage, FnError> {
+    let (first, rest) = records
+        .0
+        .split_first()
+        .ok_or_else(|| FnError::Unknown("No records to coalesce".to_string()))?;
+
+    let mut record = first.clone();
+    for next in rest {
+        record.payload.0.extend_from_slice(&next.payload.0);
+    }
+    Ok(record)
+}
+
+// ----
+// Encrypted Client Hello
+// ----
+
+/// The private key of the ECH key pair of the attacker. A PUT which is configured with it, like
+/// the BoringSSL servers, accepts the ClientHelloInners of the attacker.
+pub const ECH_PRIVATE_KEY: [u8; 32] = [43; 32];
+/// The ephemeral private key with which the attacker encrypts ClientHelloInners
+const ECH_EPHEMERAL_PRIVATE_KEY: [u8; 32] = [42; 32];
+const ECH_CONFIG_ID: u8 = 42;
+const ECH_PUBLIC_NAME: &[u8] = b"tlspuffin";
+const ECH_INFO_PREFIX: &[u8] = b"tls ech\x00";
+const AES_128_GCM_TAG_LEN: usize = 16;
+const ECH_ACCEPT_CONFIRMATION_LEN: usize = 8;
+
+/// The only cipher suite which is implemented by [`crate::tls::hpke`]
+fn ech_cipher_suite() -> HpkeSymmetricCipherSuite {
+    HpkeSymmetricCipherSuite {
+        kdf_id: HpkeKdf::HKDF_SHA256,
+        aead_id: HpkeAead::AES_128_GCM,
+    }
+}
+
+fn ech_config_contents(config: &EchConfigPayload) -> Result<&EchConfigContents, FnError> {
+    match config {
+        EchConfigPayload::Fe0d(contents)
+            if contents.key_config.kem_id == HpkeKem::DHKEM_X25519_HKDF_SHA256 =>
+        {
+            Ok(contents)
+        }
+        _ => Err(FnError::Unknown(
+            "Only ECHConfigs of version 0xfe0d with X25519 are supported".to_string(),
+        )),
+    }
+}
+
+fn client_hello_payload(message: &Message) -> Result<ClientHelloPayload, FnError> {
+    match message.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::ClientHello(payload) => Some(payload),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Message is not a ClientHello".to_owned()))
+}
+
+fn client_hello_message(version: ProtocolVersion, payload: ClientHelloPayload) -> Message {
+    Message {
+        version,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::ClientHello,
+            payload: HandshakePayload::ClientHello(payload),
+        }),
+    }
+}
+
+/// The ECHConfig of the ECH key pair of the attacker
+pub fn fn_new_ech_config() -> Result<EchConfigPayload, FnError> {
+    Ok(EchConfigPayload::Fe0d(EchConfigContents {
+        key_config: HpkeKeyConfig {
+            config_id: ECH_CONFIG_ID,
+            kem_id: HpkeKem::DHKEM_X25519_HKDF_SHA256,
+            public_key: PayloadU16::new(x25519_public_key(&ECH_PRIVATE_KEY)?),
+            symmetric_cipher_suites: HpkeSymmetricCipherSuites(vec![ech_cipher_suite()]),
+        },
+        maximum_name_length: 0,
+        public_name: PayloadU8::new(ECH_PUBLIC_NAME.to_vec()),
+        extensions: PayloadU16::empty(),
+    }))
+}
+
+pub fn fn_new_ech_config_list() -> Result<EchConfigList, FnError> {
+    Ok(EchConfigList(vec![]))
+}
+
+pub fn fn_append_ech_config(
+    configs: &EchConfigList,
+    config: &EchConfigPayload,
+) -> Result<EchConfigList, FnError> {
+    let mut new_configs = configs.clone();
+    new_configs.0.push(config.clone());
+    Ok(new_configs)
+}
+
+pub fn fn_get_first_ech_config(configs: &EchConfigList) -> Result<EchConfigPayload, FnError> {
+    configs
+        .0
+        .first()
+        .cloned()
+        .ok_or_else(|| FnError::Unknown("ECHConfigList is empty".to_owned()))
+}
+
+/// The retry_configs which a server sends in the EncryptedExtensions if it rejects the ECH.
+pub fn fn_get_ech_retry_configs(encrypted_extensions: &Message) -> Result<EchConfigList, FnError> {
+    match encrypted_extensions.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::EncryptedExtensions(extensions) => {
+                extensions.0.iter().find_map(|extension| match extension {
+                    ServerExtension::EncryptedClientHello(configs) => Some(configs.clone()),
+                    _ => None,
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Could not find retry_configs in message".to_owned()))
+}
+
+/// Encrypts the `inner` ClientHello to the ECH key of the `config` and adds it in an ECH
+/// extension to the `outer` ClientHello. An existing ECH extension of the `outer` ClientHello is
+/// replaced. The `inner` ClientHello should contain an ECH extension of type inner.
+pub fn fn_encrypt_client_hello(
+    outer: &Message,
+    inner: &Message,
+    config: &EchConfigPayload,
+) -> Result<Message, FnError> {
+    let contents = ech_config_contents(config)?;
+
+    // The EncodedClientHelloInner has an empty session ID, which is copied from the outer one
+    let mut inner_payload = client_hello_payload(inner)?;
+    inner_payload.session_id = SessionID::empty();
+    let encoded_inner = inner_payload.get_encoding();
+
+    // The additional data is the ClientHelloOuter with a payload of zeros
+    let mut outer_payload = client_hello_payload(outer)?;
+    outer_payload
+        .extensions
+        .retain(|extension| extension.get_type() != ExtensionType::EncryptedClientHello);
+    outer_payload
+        .extensions
+        .push(ClientExtension::EncryptedClientHello(
+            EncryptedClientHello::Outer(EncryptedClientHelloOuter {
+                cipher_suite: ech_cipher_suite(),
+                config_id: contents.key_config.config_id,
+                enc: PayloadU16::new(x25519_public_key(&ECH_EPHEMERAL_PRIVATE_KEY)?),
+                payload: PayloadU16::new(vec![0; encoded_inner.len() + AES_128_GCM_TAG_LEN]),
+            }),
+        ));
+    let aad = outer_payload.get_encoding();
+
+    let info = [ECH_INFO_PREFIX, &config.get_encoding()].concat();
+    let (_enc, ciphertext) = hpke_seal(
+        &ECH_EPHEMERAL_PRIVATE_KEY,
+        &contents.key_config.public_key.0,
+        &info,
+        &aad,
+        &encoded_inner,
+    )?;
+
+    if let Some(ClientExtension::EncryptedClientHello(EncryptedClientHello::Outer(ech))) =
+        outer_payload.extensions.last_mut()
+    {
+        ech.payload = PayloadU16::new(ciphertext);
+    }
+
+    Ok(client_hello_message(outer.version, outer_payload))
+}
+
+/// Decrypts the ClientHelloInner of the `outer` ClientHello, which is encrypted to the ECH key
+/// of the attacker, e.g. if the attacker is a server which published [`fn_new_ech_config`].
+/// Compressed outer extensions are not restored.
+pub fn fn_decrypt_client_hello(outer: &Message) -> Result<Message, FnError> {
+    let mut outer_payload = client_hello_payload(outer)?;
+    let ech = outer_payload
+        .extensions
+        .iter_mut()
+        .find_map(|extension| match extension {
+            ClientExtension::EncryptedClientHello(EncryptedClientHello::Outer(ech)) => Some(ech),
+            _ => None,
+        })
+        .ok_or_else(|| FnError::Unknown("Could not find ECH extension in message".to_owned()))?;
+
+    let enc = ech.enc.0.clone();
+    let ciphertext = std::mem::take(&mut ech.payload.0);
+    ech.payload.0 = vec![0; ciphertext.len()];
+    let aad = outer_payload.get_encoding();
+
+    let info = [ECH_INFO_PREFIX, &fn_new_ech_config()?.get_encoding()].concat();
+    let encoded_inner = hpke_open(&ECH_PRIVATE_KEY, &enc, &info, &aad, &ciphertext)?;
+
+    // The EncodedClientHelloInner may be followed by padding
+    let mut inner_payload = ClientHelloPayload::read(&mut Reader::init(&encoded_inner))
+        .ok_or_else(|| FnError::Unknown("Failed to decode ClientHelloInner".to_owned()))?;
+    inner_payload.session_id = outer_payload.session_id;
+
+    Ok(client_hello_message(outer.version, inner_payload))
+}
+
+/// Whether the server accepted the ECH, i.e. whether the last bytes of the random of the
+/// `server_hello` confirm the `inner` ClientHello. The confirmation is computed with SHA-256.
+pub fn fn_is_ech_accepted(inner: &Message, server_hello: &Message) -> Result<bool, FnError> {
+    let inner_random = client_hello_payload(inner)?.random;
+
+    let mut payload: ServerHelloPayload = match server_hello.payload.clone() {
+        MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::ServerHello(payload) => Some(payload),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| FnError::Unknown("Message is not a ServerHello".to_owned()))?;
+
+    // The confirmation is computed over the ServerHello in which it is replaced with zeros
+    let confirmation_offset = payload.random.0.len() - ECH_ACCEPT_CONFIRMATION_LEN;
+    let confirmation = payload.random.0[confirmation_offset..].to_vec();
+    payload.random.0[confirmation_offset..].fill(0);
+
+    let transcript = fn_append_transcript(
+        &fn_append_transcript(&fn_new_transcript()?, inner)?,
+        &Message {
+            version: server_hello.version,
+            payload: MessagePayload::Handshake(HandshakeMessagePayload {
+                typ: HandshakeType::ServerHello,
+                payload: HandshakePayload::ServerHello(payload),
+            }),
+        },
+    )?;
+
+    let secret = ring::hkdf::Salt::new(ring::hkdf::HKDF_SHA256, &[]).extract(&inner_random.0);
+    let expected: PayloadU8 = hkdf_expand(
+        &secret,
+        PayloadU8Len(ECH_ACCEPT_CONFIRMATION_LEN),
+        b"ech accept confirmation",
+        transcript.get_current_hash().as_ref(),
+    );
+
+    Ok(expected.0 == confirmation)
+}
+
 pub fn fn_new_certificate() -> Result<Certificate, FnError> {
     let der_cert = hex::decode(
         "308203473082022fa003020102021406f7fb1d20\
@@ -394,3 +1324,119 @@ pub fn fn_named_group_secp384r1() -> Result<NamedGroup, FnError> {
 pub fn fn_named_group_x25519() -> Result<NamedGroup, FnError> {
     Ok(NamedGroup::X25519)
 }
+
+#[cfg(test)]
+mod tests {
+    use puffin::protocol::OpaqueMessageSequence;
+
+    use crate::tls::{
+        fn_impl::{
+            fn_application_data, fn_change_cipher_spec, fn_coalesce_messages, fn_coalesce_records,
+            fn_fragment_message, fn_split_last_record,
+        },
+        rustls::msgs::{enums::ContentType, message::OpaqueMessage},
+    };
+
+    fn payloads(records: &OpaqueMessageSequence<OpaqueMessage>) -> Vec<Vec<u8>> {
+        records
+            .0
+            .iter()
+            .map(|record| record.payload.0.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_fragment_message() {
+        let message = fn_application_data(&vec![1, 2, 3, 4, 5]).unwrap();
+
+        let records = fn_fragment_message(&message, &2).unwrap();
+        assert_eq!(payloads(&records), vec![vec![1, 2], vec![3, 4], vec![5]]);
+        assert!(records
+            .0
+            .iter()
+            .all(|record| record.typ == ContentType::ApplicationData));
+
+        let records = fn_fragment_message(&message, &5).unwrap();
+        assert_eq!(payloads(&records), vec![vec![1, 2, 3, 4, 5]]);
+        assert!(fn_fragment_message(&message, &0).is_err());
+    }
+
+    #[test]
+    fn test_fragment_empty_message() {
+        let message = fn_application_data(&vec![]).unwrap();
+
+        let records = fn_fragment_message(&message, &16).unwrap();
+        assert_eq!(payloads(&records), vec![Vec::<u8>::new()]);
+    }
+
+    #[test]
+    fn test_split_last_record() {
+        let message = fn_application_data(&vec![1, 2, 3, 4, 5]).unwrap();
+        let records = fn_fragment_message(&message, &3).unwrap();
+
+        let split = fn_split_last_record(&records, &1).unwrap();
+        assert_eq!(payloads(&split), vec![vec![1, 2, 3], vec![4], vec![5]]);
+
+        // Splits at the boundaries of the record result in an empty record
+        let split = fn_split_last_record(&records, &0).unwrap();
+        assert_eq!(payloads(&split), vec![vec![1, 2, 3], vec![], vec![4, 5]]);
+        let split = fn_split_last_record(&records, &2).unwrap();
+        assert_eq!(payloads(&split), vec![vec![1, 2, 3], vec![4, 5], vec![]]);
+        let split = fn_split_last_record(&records, &u64::MAX).unwrap();
+        assert_eq!(payloads(&split), vec![vec![1, 2, 3], vec![4, 5], vec![]]);
+    }
+
+    #[test]
+    fn test_split_empty_records() {
+        assert!(fn_split_last_record(&OpaqueMessageSequence(vec![]), &0).is_err());
+
+        let message = fn_application_data(&vec![]).unwrap();
+        let records = fn_fragment_message(&message, &1).unwrap();
+        let split = fn_split_last_record(&records, &1).unwrap();
+        assert_eq!(payloads(&split), vec![Vec::<u8>::new(), vec![]]);
+    }
+
+    #[test]
+    fn test_coalesce_messages_of_different_types() {
+        let change_cipher_spec = fn_change_cipher_spec().unwrap();
+        let application_data = fn_application_data(&vec![2, 3]).unwrap();
+
+        let record = fn_coalesce_messages(&change_cipher_spec, &application_data).unwrap();
+        assert_eq!(record.typ, ContentType::ChangeCipherSpec);
+        assert_eq!(record.payload.0, vec![1, 2, 3]);
+
+        let record = fn_coalesce_messages(&application_data, &change_cipher_spec).unwrap();
+        assert_eq!(record.typ, ContentType::ApplicationData);
+        assert_eq!(record.payload.0, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_coalesce_records() {
+        assert!(fn_coalesce_records(&OpaqueMessageSequence(vec![])).is_err());
+
+        let change_cipher_spec = fn_change_cipher_spec().unwrap();
+        let application_data = fn_application_data(&vec![2, 3]).unwrap();
+        let records = OpaqueMessageSequence(
+            [
+                fn_fragment_message(&change_cipher_spec, &1).unwrap().0,
+                fn_split_last_record(&fn_fragment_message(&application_data, &2).unwrap(), &0)
+                    .unwrap()
+                    .0,
+            ]
+            .concat(),
+        );
+        assert_eq!(payloads(&records), vec![vec![1], vec![], vec![2, 3]]);
+
+        // Fragmenting and coalescing are inverse, also across content types and empty records
+        let record = fn_coalesce_records(&records).unwrap();
+        assert_eq!(record.typ, ContentType::ChangeCipherSpec);
+        assert_eq!(record.payload.0, vec![1, 2, 3]);
+        assert_eq!(
+            record.payload.0,
+            fn_coalesce_messages(&change_cipher_spec, &application_data)
+                .unwrap()
+                .payload
+                .0
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! Function symbols which decompose, modify and re-sign X.509 certificates. This allows to build
+//! certificate chains which are malformed but still carry valid signatures of the in-repo keys.
+
+use puffin::algebra::error::FnError;
+
+use crate::tls::{
+    fn_impl::fn_cert::{_fn_ecdsa_sign, _fn_rsa_sign},
+    rustls::msgs::enums::SignatureScheme,
+    x509::{
+        encode_tlv, AlgorithmIdentifier, AttributeTypeAndValue, Extension, Name, TbsCertificate,
+        Time, X509Certificate, OID_COMMON_NAME, OID_ECDSA_WITH_SHA256, OID_ORGANIZATION_NAME,
+        OID_SHA256_WITH_RSA, OID_SHA384_WITH_RSA, OID_SHA512_WITH_RSA, OID_UNKNOWN_EXTENSION,
+        TAG_NULL, TAG_UTF8_STRING,
+    },
+};
+
+// certificates
+
+pub fn fn_x509_parse(der: &Vec<u8>) -> Result<X509Certificate, FnError> {
+    X509Certificate::parse(der)
+}
+
+pub fn fn_x509_encode(certificate: &X509Certificate) -> Result<Vec<u8>, FnError> {
+    Ok(certificate.encode())
+}
+
+pub fn fn_x509_tbs_certificate(certificate: &X509Certificate) -> Result<TbsCertificate, FnError> {
+    Ok(certificate.tbs_certificate.clone())
+}
+
+pub fn fn_x509_signature_value(certificate: &X509Certificate) -> Result<Vec<u8>, FnError> {
+    Ok(certificate.signature_value.clone())
+}
+
+/// Replaces the signature of `certificate` without touching the signed data.
+pub fn fn_x509_set_signature_value(
+    certificate: &X509Certificate,
+    signature_value: &Vec<u8>,
+) -> Result<X509Certificate, FnError> {
+    let mut certificate = certificate.clone();
+    certificate.signature_value = signature_value.clone();
+    Ok(certificate)
+}
+
+// signing
+
+/// Signs `tbs` with the RSA `private_key` using PKCS#1 v1.5. The signature algorithm inside and
+/// outside of the TBSCertificate is set according to `scheme`.
+pub fn fn_x509_sign_rsa(
+    tbs: &TbsCertificate,
+    private_key: &Vec<u8>,
+    scheme: &SignatureScheme,
+) -> Result<X509Certificate, FnError> {
+    let algorithm = match scheme {
+        SignatureScheme::RSA_PKCS1_SHA256 => OID_SHA256_WITH_RSA,
+        SignatureScheme::RSA_PKCS1_SHA384 => OID_SHA384_WITH_RSA,
+        SignatureScheme::RSA_PKCS1_SHA512 => OID_SHA512_WITH_RSA,
+        _ => {
+            return Err(FnError::Crypto(
+                "Only RSA PKCS#1 signatures are supported for certificates".to_string(),
+            ))
+        }
+    };
+    let signature_algorithm = AlgorithmIdentifier::new(algorithm, Some(encode_tlv(TAG_NULL, &[])));
+
+    let mut tbs = tbs.clone();
+    tbs.signature = signature_algorithm.clone();
+    let signature_value = _fn_rsa_sign(&tbs.encode(), private_key, scheme)?;
+
+    Ok(X509Certificate {
+        tbs_certificate: tbs,
+        signature_algorithm,
+        signature_value,
+    })
+}
+
+/// Signs `tbs` with the P-256 `private_key`, which is encoded using PKCS8.
+pub fn fn_x509_sign_ecdsa(
+    tbs: &TbsCertificate,
+    private_key: &Vec<u8>,
+) -> Result<X509Certificate, FnError> {
+    let signature_algorithm = AlgorithmIdentifier::new(OID_ECDSA_WITH_SHA256, None);
+
+    let mut tbs = tbs.clone();
+    tbs.signature = signature_algorithm.clone();
+    let signature_value = _fn_ecdsa_sign(&tbs.encode(), private_key)?;
+
+    Ok(X509Certificate {
+        tbs_certificate: tbs,
+        signature_algorithm,
+        signature_value,
+    })
+}
+
+// TBSCertificate fields
+
+pub fn fn_x509_version_v1() -> Result<u64, FnError> {
+    Ok(0)
+}
+
+pub fn fn_x509_version_v3() -> Result<u64, FnError> {
+    Ok(2)
+}
+
+pub fn fn_x509_set_version(tbs: &TbsCertificate, version: &u64) -> Result<TbsCertificate, FnError> {
+    let mut tbs = tbs.clone();
+    tbs.version = *version;
+    Ok(tbs)
+}
+
+pub fn fn_x509_serial_number(tbs: &TbsCertificate) -> Result<Vec<u8>, FnError> {
+    Ok(tbs.serial_number.clone())
+}
+
+/// Sets the content octets of the serial number. An empty or negative serial is malformed.
+pub fn fn_x509_set_serial_number(
+    tbs: &TbsCertificate,
+    serial_number: &Vec<u8>,
+) -> Result<TbsCertificate, FnError> {
+    let mut tbs = tbs.clone();
+    tbs.serial_number = serial_number.clone();
+    Ok(tbs)
+}
+
+pub fn fn_x509_issuer(tbs: &TbsCertificate) -> Result<Name, FnError> {
+    Ok(tbs.issuer.clone())
+}
+
+pub fn fn_x509_set_issuer(tbs: &TbsCertificate, issuer: &Name) -> Result<TbsCertificate, FnError> {
+    let mut tbs = tbs.clone();
+    tbs.issuer = issuer.clone();
+    Ok(tbs)
+}
+
+pub fn fn_x509_subject(tbs: &TbsCertificate) -> Result<Name, FnError> {
+    Ok(tbs.subject.clone())
+}
+
+pub fn fn_x509_set_subject(
+    tbs: &TbsCertificate,
+    subject: &Name,
+) -> Result<TbsCertificate, FnError> {
+    let mut tbs = tbs.clone();
+    tbs.subject = subject.clone();
+    Ok(tbs)
+}
+
+/// Sets the validity period. Times of 13 bytes are encoded as UTCTime, all others as
+/// GeneralizedTime.
+pub fn fn_x509_set_validity(
+    tbs: &TbsCertificate,
+    not_before: &Vec<u8>,
+    not_after: &Vec<u8>,
+) -> Result<TbsCertificate, FnError> {
+    let mut tbs = tbs.clone();
+    tbs.validity.not_before = Time::new(not_before);
+    tbs.validity.not_after = Time::new(not_after);
+    Ok(tbs)
+}
+
+pub fn fn_x509_time_past() -> Result<Vec<u8>, FnError> {
+    Ok(b"200101000000Z".to_vec())
+}
+
+pub fn fn_x509_time_future() -> Result<Vec<u8>, FnError> {
+    Ok(b"20991231235959Z".to_vec())
+}
+
+pub fn fn_x509_public_key_info(tbs: &TbsCertificate) -> Result<Vec<u8>, FnError> {
+    Ok(tbs.subject_public_key_info.clone())
+}
+
+/// Sets the DER encoded SubjectPublicKeyInfo, e.g. the one of another certificate.
+pub fn fn_x509_set_public_key_info(
+    tbs: &TbsCertificate,
+    subject_public_key_info: &Vec<u8>,
+) -> Result<TbsCertificate, FnError> {
+    let mut tbs = tbs.clone();
+    tbs.subject_public_key_info = subject_public_key_info.clone();
+    Ok(tbs)
+}
+
+pub fn fn_x509_extensions(tbs: &TbsCertificate) -> Result<Vec<Extension>, FnError> {
+    Ok(tbs.extensions.clone())
+}
+
+pub fn fn_x509_set_extensions(
+    tbs: &TbsCertificate,
+    extensions: &Vec<Extension>,
+) -> Result<TbsCertificate, FnError> {
+    let mut tbs = tbs.clone();
+    tbs.extensions = extensions.clone();
+    Ok(tbs)
+}
+
+// names
+
+pub fn fn_x509_name_new() -> Result<Name, FnError> {
+    Ok(Name::default())
+}
+
+pub fn fn_x509_name_append_common_name(
+    name: &Name,
+    common_name: &Vec<u8>,
+) -> Result<Name, FnError> {
+    _fn_x509_name_append(name, OID_COMMON_NAME, common_name)
+}
+
+pub fn fn_x509_name_append_organization(
+    name: &Name,
+    organization: &Vec<u8>,
+) -> Result<Name, FnError> {
+    _fn_x509_name_append(name, OID_ORGANIZATION_NAME, organization)
+}
+
+fn _fn_x509_name_append(name: &Name, attribute_type: &[u8], value: &[u8]) -> Result<Name, FnError> {
+    let mut name = name.clone();
+    name.0.push(AttributeTypeAndValue {
+        attribute_type: attribute_type.to_vec(),
+        tag: TAG_UTF8_STRING,
+        value: value.to_vec(),
+    });
+    Ok(name)
+}
+
+pub fn fn_x509_localhost() -> Result<Vec<u8>, FnError> {
+    Ok(b"localhost".to_vec())
+}
+
+// extensions
+
+pub fn fn_x509_extensions_new() -> Result<Vec<Extension>, FnError> {
+    Ok(vec![])
+}
+
+pub fn fn_x509_extensions_append(
+    extensions: &Vec<Extension>,
+    extension: &Extension,
+) -> Result<Vec<Extension>, FnError> {
+    let mut extensions = extensions.clone();
+    extensions.push(extension.clone());
+    Ok(extensions)
+}
+
+pub fn fn_x509_basic_constraints_extension(ca: &bool) -> Result<Extension, FnError> {
+    Ok(Extension::basic_constraints(*ca))
+}
+
+/// keyCertSign and cRLSign
+pub fn fn_x509_key_usage_ca_extension() -> Result<Extension, FnError> {
+    Ok(Extension::key_usage(0x06))
+}
+
+/// digitalSignature and keyEncipherment
+pub fn fn_x509_key_usage_leaf_extension() -> Result<Extension, FnError> {
+    Ok(Extension::key_usage(0xa0))
+}
+
+pub fn fn_x509_subject_alt_name_extension(dns_name: &Vec<u8>) -> Result<Extension, FnError> {
+    Ok(Extension::subject_alt_name(dns_name))
+}
+
+/// An extension with an OID which no implementation knows. Certificates with a critical unknown
+/// extension must be rejected.
+pub fn fn_x509_unknown_extension(
+    critical: &bool,
+    extn_value: &Vec<u8>,
+) -> Result<Extension, FnError> {
+    Ok(Extension::new(
+        OID_UNKNOWN_EXTENSION,
+        *critical,
+        extn_value.clone(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, RSA_PKCS1_2048_8192_SHA256};
+
+    use super::*;
+    use crate::{
+        static_certs::{
+            ALICE_CERT, ALICE_PRIVATE_KEY, RANDOM_EC_CERT, RANDOM_EC_PRIVATE_KEY_PKCS8,
+        },
+        tls::x509::TAG_BIT_STRING,
+    };
+
+    fn header_length(der: &[u8]) -> usize {
+        if der[1] < 0x80 {
+            2
+        } else {
+            2 + (der[1] & 0x7f) as usize
+        }
+    }
+
+    /// Extracts the raw public key from the BIT STRING of a SubjectPublicKeyInfo
+    fn public_key(subject_public_key_info: &[u8]) -> Vec<u8> {
+        let content = &subject_public_key_info[header_length(subject_public_key_info)..];
+        let bit_string = &content[header_length(content) + content[1] as usize..];
+        assert_eq!(bit_string[0], TAG_BIT_STRING);
+        bit_string[header_length(bit_string) + 1..].to_vec()
+    }
+
+    #[test]
+    fn test_resign_rsa() {
+        let alice = fn_x509_parse(&ALICE_CERT.1.to_vec()).unwrap();
+        let tbs = fn_x509_tbs_certificate(&alice).unwrap();
+        let tbs = fn_x509_set_serial_number(&tbs, &vec![0x01]).unwrap();
+        let extensions = fn_x509_extensions_append(
+            &fn_x509_extensions(&tbs).unwrap(),
+            &fn_x509_unknown_extension(&true, &vec![0x05, 0x00]).unwrap(),
+        )
+        .unwrap();
+        let tbs = fn_x509_set_extensions(&tbs, &extensions).unwrap();
+
+        let certificate = fn_x509_sign_rsa(
+            &tbs,
+            &ALICE_PRIVATE_KEY.1.to_vec(),
+            &SignatureScheme::RSA_PKCS1_SHA256,
+        )
+        .unwrap();
+        let der = fn_x509_encode(&certificate).unwrap();
+
+        let parsed = fn_x509_parse(&der).unwrap();
+        assert_eq!(parsed, certificate);
+        assert_eq!(parsed.tbs_certificate.extensions.len(), extensions.len());
+
+        UnparsedPublicKey::new(
+            &RSA_PKCS1_2048_8192_SHA256,
+            public_key(&tbs.subject_public_key_info),
+        )
+        .verify(
+            &certificate.tbs_certificate.encode(),
+            &certificate.signature_value,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_resign_ecdsa() {
+        let random = fn_x509_parse(&RANDOM_EC_CERT.1.to_vec()).unwrap();
+        let tbs = fn_x509_tbs_certificate(&random).unwrap();
+        let tbs = fn_x509_set_validity(
+            &tbs,
+            &fn_x509_time_past().unwrap(),
+            &fn_x509_time_future().unwrap(),
+        )
+        .unwrap();
+
+        let certificate =
+            fn_x509_sign_ecdsa(&tbs, &RANDOM_EC_PRIVATE_KEY_PKCS8.1.to_vec()).unwrap();
+
+        UnparsedPublicKey::new(
+            &ECDSA_P256_SHA256_ASN1,
+            public_key(&tbs.subject_public_key_info),
+        )
+        .verify(
+            &certificate.tbs_certificate.encode(),
+            &certificate.signature_value,
+        )
+        .unwrap();
+    }
+}
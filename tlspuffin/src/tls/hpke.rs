@@ -0,0 +1,268 @@
+//! HPKE (RFC 9180) in the base mode with DHKEM(X25519, HKDF-SHA256), HKDF-SHA256 and
+//! AES-128-GCM. This is the cipher suite which all ECH implementations support. Only a single
+//! message is sealed per context, which is all that ECH needs.
+
+use puffin::algebra::error::FnError;
+use ring::{aead, agreement, hmac, test::rand::FixedSliceRandom};
+
+const KEM_SUITE_ID: &[u8] = b"KEM\x00\x20";
+const HPKE_SUITE_ID: &[u8] = b"HPKE\x00\x20\x00\x01\x00\x01";
+const MODE_BASE: u8 = 0x00;
+
+const X25519_KEY_LEN: usize = 32;
+const SHARED_SECRET_LEN: usize = 32;
+const AES_128_GCM_KEY_LEN: usize = 16;
+const AES_128_GCM_NONCE_LEN: usize = 12;
+
+fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> Vec<u8> {
+    // An empty salt is equivalent to a salt of zeros, as HMAC pads the key with zeros
+    let mut context = hmac::Context::with_key(&hmac::Key::new(hmac::HMAC_SHA256, salt));
+    context.update(b"HPKE-v1");
+    context.update(suite_id);
+    context.update(label);
+    context.update(ikm);
+    context.sign().as_ref().to_vec()
+}
+
+fn labeled_expand(suite_id: &[u8], prk: &[u8], label: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, prk);
+    let mut okm = Vec::with_capacity(len);
+    let mut previous: Vec<u8> = vec![];
+    let mut counter = 1u8;
+
+    while okm.len() < len {
+        let mut context = hmac::Context::with_key(&key);
+        context.update(&previous);
+        context.update(&(len as u16).to_be_bytes());
+        context.update(b"HPKE-v1");
+        context.update(suite_id);
+        context.update(label);
+        context.update(info);
+        context.update(&[counter]);
+        previous = context.sign().as_ref().to_vec();
+        okm.extend_from_slice(&previous);
+        counter += 1;
+    }
+
+    okm.truncate(len);
+    okm
+}
+
+fn x25519_private_key(private_key: &[u8]) -> Result<agreement::EphemeralPrivateKey, FnError> {
+    if private_key.len() != X25519_KEY_LEN {
+        return Err(FnError::Crypto(
+            "X25519 private keys are 32 bytes long".to_string(),
+        ));
+    }
+
+    agreement::EphemeralPrivateKey::generate(
+        &agreement::X25519,
+        &FixedSliceRandom { bytes: private_key },
+    )
+    .map_err(|_err| FnError::Crypto("Failed to create X25519 private key".to_string()))
+}
+
+pub fn x25519_public_key(private_key: &[u8]) -> Result<Vec<u8>, FnError> {
+    Ok(x25519_private_key(private_key)?
+        .compute_public_key()
+        .map_err(|_err| FnError::Crypto("Failed to compute X25519 public key".to_string()))?
+        .as_ref()
+        .to_vec())
+}
+
+fn x25519(private_key: &[u8], public_key: &[u8]) -> Result<Vec<u8>, FnError> {
+    agreement::agree_ephemeral(
+        x25519_private_key(private_key)?,
+        &agreement::UnparsedPublicKey::new(&agreement::X25519, public_key),
+        FnError::Crypto("Failed to compute X25519 shared secret".to_string()),
+        |dh| Ok(dh.to_vec()),
+    )
+}
+
+/// `ExtractAndExpand` of DHKEM, where `enc` is the public key of the sender and `public_key` the
+/// one of the recipient.
+fn kem_shared_secret(dh: &[u8], enc: &[u8], public_key: &[u8]) -> Vec<u8> {
+    let kem_context = [enc, public_key].concat();
+    let eae_prk = labeled_extract(KEM_SUITE_ID, b"", b"eae_prk", dh);
+    labeled_expand(
+        KEM_SUITE_ID,
+        &eae_prk,
+        b"shared_secret",
+        &kem_context,
+        SHARED_SECRET_LEN,
+    )
+}
+
+/// Derives the key and the base nonce of the AEAD in the base mode.
+fn key_schedule(shared_secret: &[u8], info: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let psk_id_hash = labeled_extract(HPKE_SUITE_ID, b"", b"psk_id_hash", b"");
+    let info_hash = labeled_extract(HPKE_SUITE_ID, b"", b"info_hash", info);
+    let key_schedule_context = [&[MODE_BASE][..], &psk_id_hash, &info_hash].concat();
+
+    let secret = labeled_extract(HPKE_SUITE_ID, shared_secret, b"secret", b"");
+    let key = labeled_expand(
+        HPKE_SUITE_ID,
+        &secret,
+        b"key",
+        &key_schedule_context,
+        AES_128_GCM_KEY_LEN,
+    );
+    let base_nonce = labeled_expand(
+        HPKE_SUITE_ID,
+        &secret,
+        b"base_nonce",
+        &key_schedule_context,
+        AES_128_GCM_NONCE_LEN,
+    );
+    (key, base_nonce)
+}
+
+fn aead_key(key: &[u8]) -> Result<aead::LessSafeKey, FnError> {
+    Ok(aead::LessSafeKey::new(
+        aead::UnboundKey::new(&aead::AES_128_GCM, key)
+            .map_err(|_err| FnError::Crypto("Failed to create AES-128-GCM key".to_string()))?,
+    ))
+}
+
+/// Encrypts the `plaintext` to the `public_key` of the recipient and returns the encapsulated key
+/// and the ciphertext. The `ephemeral_private_key` of the sender is chosen by the caller such
+/// that the encryption is deterministic.
+pub fn hpke_seal(
+    ephemeral_private_key: &[u8],
+    public_key: &[u8],
+    info: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), FnError> {
+    let enc = x25519_public_key(ephemeral_private_key)?;
+    let dh = x25519(ephemeral_private_key, public_key)?;
+    let (key, base_nonce) = key_schedule(&kem_shared_secret(&dh, &enc, public_key), info);
+
+    let nonce = aead::Nonce::try_assume_unique_for_key(&base_nonce)
+        .map_err(|_err| FnError::Crypto("Invalid HPKE nonce".to_string()))?;
+    let mut ciphertext = plaintext.to_vec();
+    aead_key(&key)?
+        .seal_in_place_append_tag(nonce, aead::Aad::from(aad), &mut ciphertext)
+        .map_err(|_err| FnError::Crypto("Failed to seal HPKE ciphertext".to_string()))?;
+
+    Ok((enc, ciphertext))
+}
+
+/// Decrypts a ciphertext of [`hpke_seal`] with the `private_key` of the recipient.
+pub fn hpke_open(
+    private_key: &[u8],
+    enc: &[u8],
+    info: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, FnError> {
+    let public_key = x25519_public_key(private_key)?;
+    let dh = x25519(private_key, enc)?;
+    let (key, base_nonce) = key_schedule(&kem_shared_secret(&dh, enc, &public_key), info);
+
+    let nonce = aead::Nonce::try_assume_unique_for_key(&base_nonce)
+        .map_err(|_err| FnError::Crypto("Invalid HPKE nonce".to_string()))?;
+    let mut plaintext = ciphertext.to_vec();
+    let len = aead_key(&key)?
+        .open_in_place(nonce, aead::Aad::from(aad), &mut plaintext)
+        .map_err(|_err| FnError::Crypto("Failed to open HPKE ciphertext".to_string()))?
+        .len();
+    plaintext.truncate(len);
+
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tls::hpke::{
+        hpke_open, hpke_seal, kem_shared_secret, key_schedule, x25519, x25519_public_key,
+    };
+
+    /// The test vector of RFC 9180, Appendix A.1.1 for the base mode with
+    /// DHKEM(X25519, HKDF-SHA256), HKDF-SHA256 and AES-128-GCM
+    mod rfc9180 {
+        pub const INFO: &str = "4f6465206f6e2061204772656369616e2055726e";
+        pub const SK_EM: &str = "52c4a758a802cd8b936eceea314432798d5baf2d7e9235dc084ab1b9cfa2f736";
+        pub const PK_EM: &str = "37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431";
+        pub const SK_RM: &str = "4612c550263fc8ad58375df3f557aac531d26850903e55a9f23f21d8534e8ac8";
+        pub const PK_RM: &str = "3948cfe0ad1ddb695d780e59077195da6c56506b027329794ab02bca80815c4d";
+        pub const SHARED_SECRET: &str =
+            "fe0e18c9f024ce43799ae393c7e8fe8fce9d218875e8227b0187c04e7d2ea1fc";
+        pub const KEY: &str = "4531685d41d65f03dc48f6b8302c05b0";
+        pub const BASE_NONCE: &str = "56d890e5accaaf011cff4b7d";
+        /// The first encryption of the vector, which uses the base nonce
+        pub const AAD: &str = "436f756e742d30";
+        pub const PT: &str = "4265617574792069732074727574682c20747275746820626561757479";
+        pub const CT: &str = "f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a9\
+            6d8770ac83d07bea87e13c512a";
+    }
+
+    fn unhex(data: &str) -> Vec<u8> {
+        hex::decode(data).unwrap()
+    }
+
+    #[test]
+    fn test_hpke_key_schedule_rfc9180() {
+        use rfc9180::*;
+
+        assert_eq!(x25519_public_key(&unhex(SK_EM)).unwrap(), unhex(PK_EM));
+        assert_eq!(x25519_public_key(&unhex(SK_RM)).unwrap(), unhex(PK_RM));
+
+        let dh = x25519(&unhex(SK_EM), &unhex(PK_RM)).unwrap();
+        let shared_secret = kem_shared_secret(&dh, &unhex(PK_EM), &unhex(PK_RM));
+        assert_eq!(shared_secret, unhex(SHARED_SECRET));
+
+        let (key, base_nonce) = key_schedule(&shared_secret, &unhex(INFO));
+        assert_eq!(key, unhex(KEY));
+        assert_eq!(base_nonce, unhex(BASE_NONCE));
+    }
+
+    #[test]
+    fn test_hpke_seal_open_rfc9180() {
+        use rfc9180::*;
+
+        let (enc, ciphertext) = hpke_seal(
+            &unhex(SK_EM),
+            &unhex(PK_RM),
+            &unhex(INFO),
+            &unhex(AAD),
+            &unhex(PT),
+        )
+        .unwrap();
+        assert_eq!(enc, unhex(PK_EM));
+        assert_eq!(ciphertext, unhex(CT));
+
+        let plaintext = hpke_open(
+            &unhex(SK_RM),
+            &unhex(PK_EM),
+            &unhex(INFO),
+            &unhex(AAD),
+            &unhex(CT),
+        )
+        .unwrap();
+        assert_eq!(plaintext, unhex(PT));
+    }
+
+    #[test]
+    fn test_hpke_roundtrip() {
+        let private_key = [43; 32];
+        let public_key = x25519_public_key(&private_key).unwrap();
+
+        let (enc, ciphertext) =
+            hpke_seal(&[42; 32], &public_key, b"info", b"aad", b"plaintext").unwrap();
+        assert_eq!(enc, x25519_public_key(&[42; 32]).unwrap());
+        assert_eq!(ciphertext.len(), b"plaintext".len() + 16);
+
+        let plaintext = hpke_open(&private_key, &enc, b"info", b"aad", &ciphertext).unwrap();
+        assert_eq!(plaintext, b"plaintext");
+
+        assert!(hpke_open(&private_key, &enc, b"info", b"other aad", &ciphertext).is_err());
+        assert!(hpke_open(&private_key, &enc, b"other info", b"aad", &ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_hpke_rejects_malformed_keys() {
+        assert!(hpke_seal(&[42; 31], &[1; 32], b"", b"", b"").is_err());
+        assert!(x25519_public_key(&[]).is_err());
+    }
+}
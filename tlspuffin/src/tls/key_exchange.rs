@@ -1,14 +1,19 @@
 use std::convert::TryInto;
 
+use num_bigint::BigUint;
 use puffin::algebra::error::FnError;
-use ring::test::rand::FixedByteRandom;
+use ring::{io::der::Tag, test::rand::FixedByteRandom};
 
 use crate::tls::rustls::{
     conn::ConnectionRandoms,
+    key::Certificate,
     kx::{KeyExchange, SupportedKxGroup, ALL_KX_GROUPS},
-    msgs::{enums::NamedGroup, handshake::Random},
+    msgs::{
+        enums::{NamedGroup, ProtocolVersion},
+        handshake::Random,
+    },
     tls12,
-    tls12::ConnectionSecrets,
+    tls12::{ConnectionSecrets, Tls12CbcCipherSuite, Tls12CipherSuite},
 };
 
 fn deterministic_key_exchange(skxg: &'static SupportedKxGroup) -> Result<KeyExchange, FnError> {
@@ -66,35 +71,302 @@ pub fn tls12_new_secrets(
     server_ecdh_pubkey: &Vec<u8>,
     group: &NamedGroup,
 ) -> Result<ConnectionSecrets, FnError> {
+    let client_random = Random::from([1; 32]); // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    let secrets = tls12_new_secrets_with_client_random(
+        &client_random,
+        server_random,
+        server_ecdh_pubkey,
+        group,
+    )?;
+    // master_secret is: 01 40 26 dd 53 3c 0a...
+    Ok(secrets)
+}
+
+/// Like [`tls12_new_secrets`], but for a `client_random` which was not necessarily chosen by the
+/// attacker. The key exchange is symmetric, therefore the `ecdh_pubkey` is the public key of
+/// the peer of the attacker, which can be either the client or the server.
+pub fn tls12_new_secrets_with_client_random(
+    client_random: &Random,
+    server_random: &Random,
+    ecdh_pubkey: &[u8],
+    group: &NamedGroup,
+) -> Result<ConnectionSecrets, FnError> {
+    let suite = tls12_suite()?;
+    let randoms = tls12_randoms(client_random, server_random)?;
+    let kx = tls12_key_exchange(group)?;
+    ConnectionSecrets::from_key_exchange(kx, ecdh_pubkey, None, randoms, suite)
+        .map_err(|_err| FnError::Crypto("Failed to shared secrets for TLS 1.2".to_string()))
+}
+
+/// Like [`tls12_new_secrets`], but for a `premaster_secret` which was established by any key
+/// exchange.
+pub fn tls12_new_secrets_from_premaster(
+    server_random: &Random,
+    premaster_secret: &[u8],
+) -> Result<ConnectionSecrets, FnError> {
+    let suite = tls12_suite()?;
+    let client_random = Random::from([1; 32]); // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    let randoms = tls12_randoms(&client_random, server_random)?;
+    Ok(ConnectionSecrets::from_premaster_secret(
+        premaster_secret,
+        None,
+        randoms,
+        suite,
+    ))
+}
+
+/// Like [`tls12_new_secrets`], but derives the master secret of the CBC `suite` with the PRF of
+/// the `version`. Returns the randoms along with the master secret.
+pub fn tls_cbc_new_secrets(
+    server_random: &Random,
+    server_ecdh_pubkey: &[u8],
+    group: &NamedGroup,
+    suite: &Tls12CbcCipherSuite,
+    version: ProtocolVersion,
+) -> Result<(ConnectionRandoms, [u8; 48]), FnError> {
+    let client_random = Random::from([1; 32]); // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    let randoms = tls12_randoms(&client_random, server_random)?;
+    let kx = tls12_key_exchange(group)?;
+    let master_secret = kx
+        .complete(server_ecdh_pubkey, |premaster_secret| {
+            Ok(suite.master_secret(version, premaster_secret, &randoms))
+        })
+        .map_err(|_err| FnError::Crypto("Failed to shared secrets for CBC".to_string()))?;
+    Ok((randoms, master_secret))
+}
+
+fn tls12_suite() -> Result<&'static Tls12CipherSuite, FnError> {
     let suite = &tls12::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    suite
+        .tls12()
+        .ok_or_else(|| FnError::Unknown("VersionNotCompatibleError".to_string()))
+}
 
+fn tls12_randoms(
+    client_random: &Random,
+    server_random: &Random,
+) -> Result<ConnectionRandoms, FnError> {
+    let mut client_random_bytes = vec![0; 32];
     let mut server_random_bytes = vec![0; 32];
 
+    client_random.write_slice(&mut client_random_bytes);
     server_random.write_slice(&mut server_random_bytes);
 
+    let client_random = client_random_bytes
+        .try_into()
+        .map_err(|_| FnError::Unknown("Client random did not have length of 32".to_string()))?;
     let server_random = server_random_bytes
         .try_into()
         .map_err(|_| FnError::Unknown("Server random did not have length of 32".to_string()))?;
-    let randoms = ConnectionRandoms {
-        client: [1; 32], // todo https://github.com/tlspuffin/tlspuffin/issues/129
+    Ok(ConnectionRandoms {
+        client: client_random,
         server: server_random,
+    })
+}
+
+/// The prime of the ffdhe2048 group of RFC 7919, which has the generator 2.
+pub const FFDHE2048_P: [u8; 256] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xad, 0xf8, 0x54, 0x58, 0xa2, 0xbb, 0x4a, 0x9a,
+    0xaf, 0xdc, 0x56, 0x20, 0x27, 0x3d, 0x3c, 0xf1, 0xd8, 0xb9, 0xc5, 0x83, 0xce, 0x2d, 0x36, 0x95,
+    0xa9, 0xe1, 0x36, 0x41, 0x14, 0x64, 0x33, 0xfb, 0xcc, 0x93, 0x9d, 0xce, 0x24, 0x9b, 0x3e, 0xf9,
+    0x7d, 0x2f, 0xe3, 0x63, 0x63, 0x0c, 0x75, 0xd8, 0xf6, 0x81, 0xb2, 0x02, 0xae, 0xc4, 0x61, 0x7a,
+    0xd3, 0xdf, 0x1e, 0xd5, 0xd5, 0xfd, 0x65, 0x61, 0x24, 0x33, 0xf5, 0x1f, 0x5f, 0x06, 0x6e, 0xd0,
+    0x85, 0x63, 0x65, 0x55, 0x3d, 0xed, 0x1a, 0xf3, 0xb5, 0x57, 0x13, 0x5e, 0x7f, 0x57, 0xc9, 0x35,
+    0x98, 0x4f, 0x0c, 0x70, 0xe0, 0xe6, 0x8b, 0x77, 0xe2, 0xa6, 0x89, 0xda, 0xf3, 0xef, 0xe8, 0x72,
+    0x1d, 0xf1, 0x58, 0xa1, 0x36, 0xad, 0xe7, 0x35, 0x30, 0xac, 0xca, 0x4f, 0x48, 0x3a, 0x79, 0x7a,
+    0xbc, 0x0a, 0xb1, 0x82, 0xb3, 0x24, 0xfb, 0x61, 0xd1, 0x08, 0xa9, 0x4b, 0xb2, 0xc8, 0xe3, 0xfb,
+    0xb9, 0x6a, 0xda, 0xb7, 0x60, 0xd7, 0xf4, 0x68, 0x1d, 0x4f, 0x42, 0xa3, 0xde, 0x39, 0x4d, 0xf4,
+    0xae, 0x56, 0xed, 0xe7, 0x63, 0x72, 0xbb, 0x19, 0x0b, 0x07, 0xa7, 0xc8, 0xee, 0x0a, 0x6d, 0x70,
+    0x9e, 0x02, 0xfc, 0xe1, 0xcd, 0xf7, 0xe2, 0xec, 0xc0, 0x34, 0x04, 0xcd, 0x28, 0x34, 0x2f, 0x61,
+    0x91, 0x72, 0xfe, 0x9c, 0xe9, 0x85, 0x83, 0xff, 0x8e, 0x4f, 0x12, 0x32, 0xee, 0xf2, 0x81, 0x83,
+    0xc3, 0xfe, 0x3b, 0x1b, 0x4c, 0x6f, 0xad, 0x73, 0x3b, 0xb5, 0xfc, 0xbc, 0x2e, 0xc2, 0x20, 0x05,
+    0xc5, 0x8e, 0xf1, 0x83, 0x7d, 0x16, 0x83, 0xb2, 0xc6, 0xf3, 0x4a, 0x26, 0xc1, 0xb2, 0xef, 0xfa,
+    0x88, 0x6b, 0x42, 0x38, 0x61, 0x28, 0x5c, 0x97, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+/// The prime of the 768-bit MODP group of RFC 2409, which has the generator 2. Such small groups
+/// are considered broken.
+pub const OAKLEY_GROUP1_P: [u8; 96] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xc9, 0x0f, 0xda, 0xa2, 0x21, 0x68, 0xc2, 0x34,
+    0xc4, 0xc6, 0x62, 0x8b, 0x80, 0xdc, 0x1c, 0xd1, 0x29, 0x02, 0x4e, 0x08, 0x8a, 0x67, 0xcc, 0x74,
+    0x02, 0x0b, 0xbe, 0xa6, 0x3b, 0x13, 0x9b, 0x22, 0x51, 0x4a, 0x08, 0x79, 0x8e, 0x34, 0x04, 0xdd,
+    0xef, 0x95, 0x19, 0xb3, 0xcd, 0x3a, 0x43, 0x1b, 0x30, 0x2b, 0x0a, 0x6d, 0xf2, 0x5f, 0x14, 0x37,
+    0x4f, 0xe1, 0x35, 0x6d, 0x6d, 0x51, 0xc2, 0x45, 0xe4, 0x85, 0xb5, 0x76, 0x62, 0x5e, 0x7e, 0xc6,
+    0xf4, 0x4c, 0x42, 0xe9, 0xa6, 0x3a, 0x36, 0x20, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+/// The private exponent of the attacker in finite-field Diffie-Hellman. Like the private keys of
+/// ECDHE, it is fixed such that traces are deterministic.
+const DHE_PRIVATE_EXPONENT: [u8; 32] = [42; 32];
+
+fn dhe_modulus(p: &[u8]) -> Result<BigUint, FnError> {
+    let p = BigUint::from_bytes_be(p);
+    if p == BigUint::from(0u8) {
+        return Err(FnError::Crypto("DH modulus must not be zero".to_string()));
+    }
+    Ok(p)
+}
+
+/// Computes the public value `g^x mod p` of the attacker. The group is not validated, such that
+/// the attacker can choose small or invalid groups.
+pub fn dhe_public_key(p: &[u8], g: &[u8]) -> Result<Vec<u8>, FnError> {
+    let x = BigUint::from_bytes_be(&DHE_PRIVATE_EXPONENT);
+    let public_key = BigUint::from_bytes_be(g).modpow(&x, &dhe_modulus(p)?);
+    Ok(public_key.to_bytes_be())
+}
+
+/// Computes the shared secret `peer_public_key^x mod p`, which is used as premaster secret. As
+/// specified in RFC 5246, leading zero bytes are stripped.
+pub fn dhe_shared_secret(p: &[u8], peer_public_key: &[u8]) -> Result<Vec<u8>, FnError> {
+    let x = BigUint::from_bytes_be(&DHE_PRIVATE_EXPONENT);
+    let secret = BigUint::from_bytes_be(peer_public_key).modpow(&x, &dhe_modulus(p)?);
+    Ok(secret.to_bytes_be())
+}
+
+/// The OID 1.2.840.113549.1.1.1 of rsaEncryption
+const RSA_ENCRYPTION_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+/// Reads the next DER element. Returns its tag, its contents and the remaining input.
+pub(crate) fn der_read_any(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (tag, data) = data.split_first()?;
+
+    let (first, mut data) = data.split_first()?;
+    let len = if *first < 0x80 {
+        *first as usize
+    } else {
+        let num_bytes = (*first & 0x7f) as usize;
+        if num_bytes == 0 || num_bytes > 4 || data.len() < num_bytes {
+            return None;
+        }
+        let (len_bytes, rest) = data.split_at(num_bytes);
+        data = rest;
+        len_bytes
+            .iter()
+            .fold(0usize, |len, byte| len << 8 | *byte as usize)
     };
-    let kx = tls12_key_exchange(group)?;
-    let suite = suite
-        .tls12()
-        .ok_or_else(|| FnError::Unknown("VersionNotCompatibleError".to_string()))?;
-    let secrets =
-        ConnectionSecrets::from_key_exchange(kx, server_ecdh_pubkey, None, randoms, suite)
-            .map_err(|_err| FnError::Crypto("Failed to shared secrets for TLS 1.2".to_string()))?;
-    // master_secret is: 01 40 26 dd 53 3c 0a...
-    Ok(secrets)
+
+    if data.len() < len {
+        return None;
+    }
+    let (content, rest) = data.split_at(len);
+    Some((*tag, content, rest))
+}
+
+/// Reads a DER element with the expected `tag`. Returns its contents and the remaining input.
+pub(crate) fn der_read(data: &[u8], tag: Tag) -> Option<(&[u8], &[u8])> {
+    match der_read_any(data)? {
+        (actual_tag, content, rest) if actual_tag == tag as u8 => Some((content, rest)),
+        _ => None,
+    }
+}
+
+fn parse_rsa_public_key(cert: &[u8]) -> Option<(BigUint, BigUint)> {
+    let (certificate, _) = der_read(cert, Tag::Sequence)?;
+    let (tbs_certificate, _) = der_read(certificate, Tag::Sequence)?;
+    // The version is optional
+    let rest = der_read(tbs_certificate, Tag::ContextSpecificConstructed0)
+        .map_or(tbs_certificate, |(_version, rest)| rest);
+    let (_serial_number, rest) = der_read(rest, Tag::Integer)?;
+    let (_signature, rest) = der_read(rest, Tag::Sequence)?;
+    let (_issuer, rest) = der_read(rest, Tag::Sequence)?;
+    let (_validity, rest) = der_read(rest, Tag::Sequence)?;
+    let (_subject, rest) = der_read(rest, Tag::Sequence)?;
+    let (subject_public_key_info, _) = der_read(rest, Tag::Sequence)?;
+
+    let (algorithm, rest) = der_read(subject_public_key_info, Tag::Sequence)?;
+    let (oid, _) = der_read(algorithm, Tag::OID)?;
+    if oid != RSA_ENCRYPTION_OID {
+        return None;
+    }
+
+    let (subject_public_key, _) = der_read(rest, Tag::BitString)?;
+    let (unused_bits, subject_public_key) = subject_public_key.split_first()?;
+    if *unused_bits != 0 {
+        return None;
+    }
+    let (rsa_public_key, _) = der_read(subject_public_key, Tag::Sequence)?;
+    let (n, rest) = der_read(rsa_public_key, Tag::Integer)?;
+    let (e, _) = der_read(rest, Tag::Integer)?;
+
+    Some((BigUint::from_bytes_be(n), BigUint::from_bytes_be(e)))
+}
+
+/// Extracts the modulus and the public exponent from an X.509 certificate with an RSA key.
+fn rsa_public_key(cert: &Certificate) -> Result<(BigUint, BigUint), FnError> {
+    let (n, e) = parse_rsa_public_key(&cert.0).ok_or_else(|| {
+        FnError::Crypto("Failed to parse RSA public key of certificate".to_string())
+    })?;
+    if n == BigUint::from(0u8) {
+        return Err(FnError::Crypto("RSA modulus must not be zero".to_string()));
+    }
+    Ok((n, e))
+}
+
+/// The length of the RSA modulus of the `cert` in bytes.
+pub fn rsa_modulus_len(cert: &Certificate) -> Result<usize, FnError> {
+    let (n, _e) = rsa_public_key(cert)?;
+    Ok(((n.bits() + 7) / 8) as usize)
+}
+
+/// Encrypts the already padded `message` using textbook RSA with the public key of the `cert`.
+/// The ciphertext has the length of the modulus.
+pub fn rsa_encrypt_raw(message: &[u8], cert: &Certificate) -> Result<Vec<u8>, FnError> {
+    let (n, e) = rsa_public_key(cert)?;
+    let modulus_len = ((n.bits() + 7) / 8) as usize;
+    if message.len() > modulus_len {
+        return Err(FnError::Crypto(
+            "RSA message is longer than the modulus".to_string(),
+        ));
+    }
+
+    let ciphertext = BigUint::from_bytes_be(message).modpow(&e, &n).to_bytes_be();
+    let mut padded = vec![0u8; modulus_len - ciphertext.len()];
+    padded.extend_from_slice(&ciphertext);
+    Ok(padded)
+}
+
+/// The non-zero byte which fills the padding string of PKCS#1 v1.5. It is fixed such that traces
+/// are deterministic.
+const PKCS1_PADDING_BYTE: u8 = 42;
+
+/// Pads `message` to `modulus_len` bytes like PKCS#1 v1.5: `header || PS || 0x00 || message`.
+/// Correct encryption paddings use the `header` `00 02` and include the zero byte which separates
+/// the padding string PS from the `message`. Other headers, omitting the separator or too long
+/// messages yield malformed paddings.
+pub fn pkcs1_pad(
+    message: &[u8],
+    modulus_len: usize,
+    header: &[u8],
+    separator: bool,
+) -> Result<Vec<u8>, FnError> {
+    let separator: &[u8] = if separator { &[0] } else { &[] };
+    let padding_len = modulus_len
+        .checked_sub(header.len() + separator.len() + message.len())
+        .ok_or_else(|| FnError::Crypto("PKCS#1 message is too long".to_string()))?;
+
+    let mut padded = Vec::with_capacity(modulus_len);
+    padded.extend_from_slice(header);
+    padded.resize(header.len() + padding_len, PKCS1_PADDING_BYTE);
+    padded.extend_from_slice(separator);
+    padded.extend_from_slice(message);
+    Ok(padded)
 }
 
 #[cfg(test)]
 mod tests {
+    use num_bigint::BigUint;
     use test_log::test;
 
-    use crate::tls::{key_exchange::deterministic_key_exchange, rustls::kx::SECP384R1};
+    use crate::{
+        static_certs::ALICE_CERT,
+        tls::{
+            key_exchange::{
+                deterministic_key_exchange, dhe_public_key, dhe_shared_secret,
+                parse_rsa_public_key, pkcs1_pad, rsa_encrypt_raw, rsa_modulus_len,
+            },
+            rustls::{key::Certificate, kx::SECP384R1},
+        },
+    };
 
     #[test]
     fn test_deterministic_key() {
@@ -103,4 +375,62 @@ mod tests {
 
         assert_eq!(a.pubkey.as_ref(), b.pubkey.as_ref())
     }
+
+    #[test]
+    fn test_dhe_shared_secret() {
+        let p = BigUint::from(23u8);
+        let g = BigUint::from(5u8);
+        let peer_private = BigUint::from(6u8);
+        let peer_public = g.modpow(&peer_private, &p);
+
+        let public_key = dhe_public_key(&p.to_bytes_be(), &g.to_bytes_be()).unwrap();
+        let secret = dhe_shared_secret(&p.to_bytes_be(), &peer_public.to_bytes_be()).unwrap();
+
+        let expected = BigUint::from_bytes_be(&public_key).modpow(&peer_private, &p);
+        assert_eq!(secret, expected.to_bytes_be());
+        assert!(dhe_public_key(&[0], &[2]).is_err());
+    }
+
+    #[test]
+    fn test_pkcs1_pad() {
+        assert_eq!(
+            pkcs1_pad(&[1, 2], 8, &[0, 2], true).unwrap(),
+            vec![0, 2, 42, 42, 42, 0, 1, 2]
+        );
+        assert_eq!(
+            pkcs1_pad(&[1, 2], 8, &[0, 1], false).unwrap(),
+            vec![0, 1, 42, 42, 42, 42, 1, 2]
+        );
+        assert!(pkcs1_pad(&[1; 8], 8, &[0, 2], true).is_err());
+    }
+
+    #[test]
+    fn test_rsa_encrypt_raw() {
+        let cert = Certificate(ALICE_CERT.1.to_vec());
+        assert_eq!(rsa_modulus_len(&cert).unwrap(), 256);
+
+        // One is a fixed point of textbook RSA
+        let mut expected = vec![0; 256];
+        expected[255] = 1;
+        assert_eq!(rsa_encrypt_raw(&[1], &cert).unwrap(), expected);
+
+        assert!(rsa_encrypt_raw(&[1; 257], &cert).is_err());
+        assert!(rsa_modulus_len(&Certificate(vec![0x30, 0x00])).is_err());
+    }
+
+    #[test]
+    fn test_rsa_encrypt_raw_zero_modulus() {
+        let mut der = ALICE_CERT.1.to_vec();
+        let (n, _e) = parse_rsa_public_key(&der).unwrap();
+        let n = n.to_bytes_be();
+        let start = der
+            .windows(n.len())
+            .position(|window| window == n.as_slice())
+            .unwrap();
+        der[start..start + n.len()].fill(0);
+
+        let cert = Certificate(der);
+        assert!(rsa_modulus_len(&cert).is_err());
+        assert!(rsa_encrypt_raw(&[1], &cert).is_err());
+    }
 }
@@ -9,12 +9,35 @@ use crate::tls::{
         msgs::enums::NamedGroup,
         suites::SupportedCipherSuite,
         tls13::key_schedule::{
-            KeyScheduleEarly, KeyScheduleHandshake, KeyScheduleHandshakeStart,
+            hkdf_expand, KeyScheduleEarly, KeyScheduleHandshake, KeyScheduleHandshakeStart,
             KeySchedulePreHandshake, KeyScheduleTrafficWithClientFinishedPending,
         },
     },
 };
 
+/// Derives the client early traffic secret which protects 0-RTT data. The `client_hello` transcript
+/// contains only the ClientHello which offered the `psk`.
+pub fn tls13_early_traffic_secret(
+    client_hello: &HandshakeHash,
+    psk: &[u8],
+) -> Result<(&'static SupportedCipherSuite, Prk), FnError> {
+    let client_random = &[1u8; 32]; // todo see op_random() https://github.com/tlspuffin/tlspuffin/issues/129
+    let suite = &crate::tls::rustls::tls13::TLS13_AES_128_GCM_SHA256; // todo see op_cipher_suites() https://github.com/tlspuffin/tlspuffin/issues/129
+    let hkdf_algorithm = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .hkdf_algorithm;
+
+    let early = KeyScheduleEarly::new(hkdf_algorithm, psk);
+    let client_early_traffic_secret = early.client_early_traffic_secret(
+        &client_hello.get_current_hash(),
+        &NoKeyLog {},
+        client_random,
+    );
+
+    Ok((suite, client_early_traffic_secret))
+}
+
 pub fn tls13_handshake_traffic_secret(
     server_hello: &HandshakeHash,
     server_key_share: &Option<Vec<u8>>,
@@ -71,6 +94,47 @@ pub fn tls13_application_traffic_secret(
     ))
 }
 
+/// Limits the number of KeyUpdates such that the ratcheting of the secrets stays cheap.
+const MAX_KEY_UPDATES: u64 = 255;
+
+/// Ratchets the application traffic secret forward `key_updates` times, as it happens after each
+/// KeyUpdate (RFC 8446, Section 7.2).
+pub fn tls13_updated_application_traffic_secret(
+    server_hello: &HandshakeHash,
+    server_finished: &HandshakeHash,
+    server_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    group: &NamedGroup,
+    client: bool,
+    key_updates: u64,
+) -> Result<(&'static SupportedCipherSuite, Prk), FnError> {
+    if key_updates > MAX_KEY_UPDATES {
+        return Err(FnError::Unknown(format!(
+            "At most {} KeyUpdates are supported",
+            MAX_KEY_UPDATES
+        )));
+    }
+
+    let (suite, mut secret, _) = tls13_application_traffic_secret(
+        server_hello,
+        server_finished,
+        server_key_share,
+        psk,
+        group,
+        client,
+    )?;
+    let hkdf_algorithm = suite
+        .tls13()
+        .ok_or_else(|| FnError::Crypto("No tls 1.3 suite".to_owned()))?
+        .hkdf_algorithm;
+
+    for _ in 0..key_updates {
+        secret = hkdf_expand(&secret, hkdf_algorithm, b"traffic upd", &[]);
+    }
+
+    Ok((suite, secret))
+}
+
 pub fn tls13_derive_psk(
     server_hello: &HandshakeHash,
     server_finished: &HandshakeHash,
@@ -4,9 +4,12 @@
 use fn_impl::*;
 use puffin::{algebra::error::FnError, define_signature, error::Error};
 
+mod hpke;
 mod key_exchange;
 mod key_schedule;
+mod x509;
 
+pub mod oracle;
 pub mod rustls;
 pub mod seeds;
 pub mod violation;
@@ -21,6 +24,7 @@ pub mod fn_impl {
     pub mod fn_messages;
     pub mod fn_transcript;
     pub mod fn_utils;
+    pub mod fn_x509;
 
     pub use fn_cert::*;
     pub use fn_constants::*;
@@ -29,6 +33,7 @@ pub mod fn_impl {
     pub use fn_messages::*;
     pub use fn_transcript::*;
     pub use fn_utils::*;
+    pub use fn_x509::*;
 }
 
 impl From<rustls::error::Error> for Error {
@@ -74,6 +79,7 @@ define_signature!(
     fn_seq_14
     fn_seq_15
     fn_seq_16
+    fn_byte_from_nibbles
     fn_large_length
     fn_empty_bytes_vec
     // messages
@@ -90,6 +96,7 @@ define_signature!(
     fn_client_key_exchange
     fn_empty_handshake_message
     fn_encrypted_extensions
+    fn_end_of_early_data
     fn_finished
     fn_heartbeat
     fn_heartbeat_fake_length
@@ -101,9 +108,15 @@ define_signature!(
     fn_new_session_ticket
     fn_new_session_ticket13
     fn_opaque_message
+    fn_plaintext_record
+    fn_record_content_type
+    fn_record_version
+    fn_record_fake_length
     fn_server_hello
     fn_server_hello_done
     fn_server_key_exchange
+    fn_server_key_exchange_dhe
+    fn_server_key_exchange_ecdhe
     // extensions
     fn_client_extensions_new
     fn_client_extensions_append
@@ -136,6 +149,8 @@ define_signature!(
     fn_signed_certificate_timestamp_certificate_extension
     fn_extended_master_secret_extension
     fn_extended_master_secret_server_extension
+    fn_encrypt_then_mac_extension
+    fn_encrypt_then_mac_server_extension
     fn_session_ticket_request_extension
     fn_session_ticket_offer_extension
     fn_session_ticket_server_extension
@@ -143,6 +158,7 @@ define_signature!(
     fn_empty_preshared_keys_identity_vec
     fn_append_preshared_keys_identity
     fn_preshared_keys_extension_empty_binder
+    fn_preshared_keys_extension_empty_binder_late
     fn_preshared_keys_server_extension
     fn_early_data_extension
     fn_early_data_new_session_ticket_extension
@@ -159,6 +175,7 @@ define_signature!(
     fn_psk_exchange_mode_ke_extension
     fn_certificate_authorities_extension
     fn_signature_algorithm_cert_extension
+    fn_post_handshake_auth_extension
     fn_key_share_deterministic_extension
     fn_key_share_extension
     fn_key_share_deterministic_server_extension
@@ -166,8 +183,12 @@ define_signature!(
     fn_key_share_hello_retry_extension
     fn_transport_parameters_extension
     fn_transport_parameters_server_extension
+    fn_ech_inner_extension
+    fn_ech_outer_extension
+    fn_ech_retry_configs_server_extension
     fn_renegotiation_info_extension
     fn_renegotiation_info_server_extension
+    fn_renegotiated_connection
     fn_transport_parameters_draft_extension
     fn_transport_parameters_draft_server_extension
     fn_unknown_client_extension
@@ -179,6 +200,16 @@ define_signature!(
     // fields
     fn_protocol_version13
     fn_protocol_version12
+    fn_protocol_version11
+    fn_protocol_version10
+    fn_protocol_version_ssl3
+    fn_protocol_version_unknown
+    fn_content_type_change_cipher_spec
+    fn_content_type_alert
+    fn_content_type_handshake
+    fn_content_type_application_data
+    fn_content_type_heartbeat
+    fn_content_type_unknown
     fn_new_session_id
     fn_empty_session_id
     fn_new_random
@@ -191,9 +222,16 @@ define_signature!(
     fn_verify_data
     fn_verify_data_server
     fn_sign_transcript
+    fn_sign_transcript_premaster
+    fn_sign_transcript_with_client_random
     fn_new_cipher_suites
     fn_append_cipher_suite
     fn_cipher_suite12
+    fn_cipher_suite12_rsa_aes_128_gcm_sha256
+    fn_cipher_suite12_dhe_rsa_aes_128_gcm_sha256
+    fn_cipher_suite12_aes_128_cbc_sha
+    fn_cipher_suite12_aes_256_cbc_sha
+    fn_cipher_suite12_aes_128_cbc_sha256
     fn_cipher_suite13_aes_128_gcm_sha256
     fn_cipher_suite13_aes_256_gcm_sha384
     fn_cipher_suite13_aes_128_ccm_sha256
@@ -208,6 +246,10 @@ define_signature!(
     fn_decrypt_application
     fn_encrypt_handshake
     fn_encrypt_application
+    fn_encrypt_application_updated
+    fn_decrypt_application_updated
+    fn_encrypt_early_data
+    fn_decrypt_early_data
     fn_derive_psk
     fn_derive_binder
     fn_fill_binder
@@ -217,8 +259,67 @@ define_signature!(
     fn_new_transcript12
     fn_decode_ecdh_pubkey
     fn_encode_ec_pubkey12
+    fn_decode_ec_pubkey12
+    fn_get_random
+    fn_get_key_exchange_data
     fn_new_pubkey12
     fn_encrypt12
+    fn_encrypt12_with_client_random
+    fn_decrypt12_with_client_random
+    fn_new_transcript_cbc
+    fn_sign_transcript_cbc
+    fn_cbc_write_keys
+    fn_encrypt12_cbc
+    fn_cbc_mac12
+    fn_cbc_append_mac12
+    fn_cbc_padding
+    fn_cbc_padding_length
+    fn_cbc_padding_value
+    fn_encrypt12_cbc_raw
+    fn_dhe_params
+    fn_ecdhe_params
+    fn_digitally_signed
+    fn_decode_dhe_params
+    fn_decode_ecdhe_params
+    fn_get_dhe_p
+    fn_get_dhe_g
+    fn_get_dhe_pubkey
+    fn_get_ecdhe_group
+    fn_get_ecdhe_pubkey
+    fn_dh_ffdhe2048_p
+    fn_dh_oakley_group1_p
+    fn_dh_generator
+    fn_dh_one
+    fn_dh_minus_one
+    fn_new_dhe_pubkey
+    fn_encode_dh_pubkey
+    fn_dhe_premaster_secret
+    fn_ecdhe_premaster_secret
+    fn_new_premaster_secret
+    fn_get_first_certificate
+    fn_pkcs1_encryption_header
+    fn_pkcs1_signature_header
+    fn_pkcs1_header
+    fn_pkcs1_pad
+    fn_pkcs1_pad_raw
+    fn_rsa_encrypt
+    fn_encode_rsa_premaster_secret
+    fn_encrypt12_premaster
+    fn_new_records
+    fn_append_record
+    fn_append_opaque_record
+    fn_fragment_message
+    fn_split_last_record
+    fn_coalesce_messages
+    fn_coalesce_records
+    fn_new_ech_config
+    fn_new_ech_config_list
+    fn_append_ech_config
+    fn_get_first_ech_config
+    fn_get_ech_retry_configs
+    fn_encrypt_client_hello
+    fn_decrypt_client_hello
+    fn_is_ech_accepted
     fn_new_certificate
     fn_new_certificates
     fn_append_certificate
@@ -238,6 +339,8 @@ define_signature!(
     fn_alice_key
     fn_eve_cert
     fn_random_ec_cert
+    fn_random_ec_key
+    fn_certificate_from_der
     fn_certificate_entry
     fn_empty_certificate_chain
     fn_append_certificate_entry
@@ -245,10 +348,47 @@ define_signature!(
     fn_eve_pkcs1_signature
     fn_rsa_sign_client
     fn_rsa_sign_server
+    fn_rsa_sign_dhe_params
+    fn_rsa_sign_ecdhe_params
     fn_ecdsa_sign_client
     fn_ecdsa_sign_server
     fn_rsa_pss_signature_algorithm
     fn_rsa_pkcs1_signature_algorithm
     fn_invalid_signature_algorithm
     fn_ecdsa_signature_algorithm
+    // x509 functions
+    fn_x509_parse
+    fn_x509_encode
+    fn_x509_tbs_certificate
+    fn_x509_signature_value
+    fn_x509_set_signature_value
+    fn_x509_sign_rsa
+    fn_x509_sign_ecdsa
+    fn_x509_version_v1
+    fn_x509_version_v3
+    fn_x509_set_version
+    fn_x509_serial_number
+    fn_x509_set_serial_number
+    fn_x509_issuer
+    fn_x509_set_issuer
+    fn_x509_subject
+    fn_x509_set_subject
+    fn_x509_set_validity
+    fn_x509_time_past
+    fn_x509_time_future
+    fn_x509_public_key_info
+    fn_x509_set_public_key_info
+    fn_x509_extensions
+    fn_x509_set_extensions
+    fn_x509_name_new
+    fn_x509_name_append_common_name
+    fn_x509_name_append_organization
+    fn_x509_localhost
+    fn_x509_extensions_new
+    fn_x509_extensions_append
+    fn_x509_basic_constraints_extension
+    fn_x509_key_usage_ca_extension
+    fn_x509_key_usage_leaf_extension
+    fn_x509_subject_alt_name_extension
+    fn_x509_unknown_extension
 );
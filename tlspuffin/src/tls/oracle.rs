@@ -0,0 +1,243 @@
+//! Oracle families for TLS, see [`puffin::oracle`]. The families check whether a server reacts
+//! differently to malformed CBC paddings or malformed PKCS#1 paddings of RSA ciphertexts.
+
+use puffin::{
+    agent::AgentName,
+    algebra::{dynamic_function::TypeShape, Term},
+    codec::{Codec, Reader},
+    oracle::{Observer, OracleFamily},
+    term,
+    trace::{Query, TraceContext},
+};
+
+use crate::{
+    protocol::TLSProtocolBehavior,
+    query::TlsQueryMatcher,
+    tls::{
+        fn_impl::*,
+        rustls::msgs::{
+            alert::AlertMessagePayload,
+            enums::{AlertDescription, ContentType},
+            message::OpaqueMessage,
+        },
+        seeds::{_seed_client_attacker12_cbc, _seed_client_attacker12_rsa},
+    },
+};
+
+/// All TLS oracle families which the PUTs of this build can be checked against
+pub fn create_oracle_families() -> Vec<OracleFamily<TlsQueryMatcher>> {
+    #[allow(unused_mut)]
+    let mut families = vec![];
+    #[cfg(feature = "tls12")]
+    families.push(padding_oracle_family12(AgentName::first()));
+    #[cfg(all(feature = "tls12", feature = "openssl-binding"))]
+    families.push(bleichenbacher_family12(AgentName::first()));
+    families
+}
+
+/// What an attacker observes from a TLS agent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsObservation {
+    /// The alerts which the agent sent. Encrypted alerts can not be decoded and are `None`.
+    pub alerts: Vec<Option<AlertDescription>>,
+    /// The number of records which the agent sent
+    pub messages: usize,
+    /// The state of the agent, see [`puffin::put::Put::describe_state`]
+    pub state: String,
+}
+
+pub struct TlsObserver;
+
+impl Observer<TLSProtocolBehavior> for TlsObserver {
+    type Observation = TlsObservation;
+
+    fn observe(ctx: &TraceContext<TLSProtocolBehavior>, agent: AgentName) -> TlsObservation {
+        let mut alerts = Vec::new();
+        let mut messages = 0;
+
+        // Each record which the agent sent is part of the knowledge as OpaqueMessage
+        while let Some(variable) = ctx.find_variable(
+            TypeShape::of::<OpaqueMessage>(),
+            &Query {
+                agent_name: agent,
+                matcher: None,
+                counter: messages,
+            },
+        ) {
+            messages += 1;
+
+            if let Ok(opaque) = variable.boxed_any().downcast::<OpaqueMessage>() {
+                if opaque.typ == ContentType::Alert {
+                    let mut reader = Reader::init(&opaque.payload.0);
+                    alerts.push(
+                        AlertMessagePayload::read(&mut reader)
+                            .filter(|_| !reader.any_left())
+                            .map(|alert| alert.description),
+                    );
+                }
+            }
+        }
+
+        let state = ctx
+            .find_agent(agent)
+            .map(|agent| agent.put().describe_state().to_string())
+            .unwrap_or_default();
+
+        TlsObservation {
+            alerts,
+            messages: messages as usize,
+            state,
+        }
+    }
+}
+
+/// All byte values, which are used for byte-valued subterms
+fn byte_values() -> Vec<(u8, Term<TlsQueryMatcher>)> {
+    let nibbles: [Term<TlsQueryMatcher>; 16] = [
+        term! { fn_seq_0 },
+        term! { fn_seq_1 },
+        term! { fn_seq_2 },
+        term! { fn_seq_3 },
+        term! { fn_seq_4 },
+        term! { fn_seq_5 },
+        term! { fn_seq_6 },
+        term! { fn_seq_7 },
+        term! { fn_seq_8 },
+        term! { fn_seq_9 },
+        term! { fn_seq_10 },
+        term! { fn_seq_11 },
+        term! { fn_seq_12 },
+        term! { fn_seq_13 },
+        term! { fn_seq_14 },
+        term! { fn_seq_15 },
+    ];
+
+    (0..=u8::MAX)
+        .map(|value| {
+            let high = nibbles[usize::from(value >> 4)].clone();
+            let low = nibbles[usize::from(value & 0x0f)].clone();
+            (value, term! { fn_byte_from_nibbles((@high), (@low)) })
+        })
+        .collect()
+}
+
+/// The Finished of 16 bytes and its HMAC-SHA1 of 20 bytes are padded with 12 bytes in a record
+/// which is protected by TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA.
+const FINISHED_CBC_PADDING_LEN: u8 = 12;
+
+/// Variants of [`crate::tls::seeds::seed_client_attacker12_cbc`] with an invalid MAC, in which all
+/// bytes of the padding are set to the same value. The server must not distinguish valid from
+/// invalid paddings, else it is a padding oracle.
+pub fn padding_oracle_family12(server: AgentName) -> OracleFamily<TlsQueryMatcher> {
+    OracleFamily::from_subterms(
+        "padding_oracle_family12",
+        server,
+        |padding_value| {
+            _seed_client_attacker12_cbc(server, term! { fn_seq_1 }, Some(padding_value))
+        },
+        byte_values()
+            .into_iter()
+            .map(|(value, term)| {
+                let class = if value < FINISHED_CBC_PADDING_LEN {
+                    "valid padding"
+                } else {
+                    "invalid padding"
+                };
+                (class.to_string(), term)
+            })
+            .collect(),
+    )
+}
+
+/// Variants of [`crate::tls::seeds::seed_client_attacker12_rsa`] with different PKCS#1 block
+/// types. The Finished is computed from a different premaster secret, therefore the handshake
+/// fails for all variants. The server must not distinguish conforming from non-conforming
+/// paddings, else it is a Bleichenbacher oracle.
+pub fn bleichenbacher_family12(server: AgentName) -> OracleFamily<TlsQueryMatcher> {
+    OracleFamily::from_subterms(
+        "bleichenbacher_family12",
+        server,
+        |block_type| {
+            _seed_client_attacker12_rsa(
+                server,
+                term! { fn_pkcs1_header((@block_type)) },
+                term! { fn_protocol_version11 },
+            )
+        },
+        byte_values()
+            .into_iter()
+            .map(|(value, term)| {
+                let class = if value == 2 {
+                    "conforming padding"
+                } else {
+                    "non-conforming padding"
+                };
+                (class.to_string(), term)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use puffin::{
+        agent::AgentName,
+        oracle::{analyze_family, OracleConfig},
+    };
+    use test_log::test;
+
+    use crate::{
+        put_registry::TLS_PUT_REGISTRY,
+        tls::oracle::{bleichenbacher_family12, padding_oracle_family12, TlsObserver},
+    };
+
+    #[test]
+    fn test_families_have_both_classes() {
+        let server = AgentName::first();
+
+        let family = padding_oracle_family12(server);
+        assert_eq!(family.variants.len(), 256);
+        assert!(family.variants.iter().any(|v| v.class == "valid padding"));
+        assert!(family.variants.iter().any(|v| v.class == "invalid padding"));
+
+        let family = bleichenbacher_family12(server);
+        assert_eq!(
+            family
+                .variants
+                .iter()
+                .filter(|v| v.class == "conforming padding")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_padding_oracle_family12() {
+        let family = padding_oracle_family12(AgentName::first());
+        let report =
+            analyze_family::<_, TlsObserver>(&family, &TLS_PUT_REGISTRY, &OracleConfig::default());
+
+        assert!(report.skipped.is_empty());
+        // The server rejects all variants
+        for (outcome, _classes) in &report.outcomes {
+            assert!(!outcome.observation.alerts.is_empty());
+        }
+        assert!(!report.is_oracle(), "{}", report);
+    }
+
+    #[test]
+    #[cfg(all(feature = "tls12", feature = "openssl-binding"))]
+    fn test_bleichenbacher_family12() {
+        let family = bleichenbacher_family12(AgentName::first());
+        let report =
+            analyze_family::<_, TlsObserver>(&family, &TLS_PUT_REGISTRY, &OracleConfig::default());
+
+        assert!(report.skipped.is_empty());
+        // The server rejects all variants
+        for (outcome, _classes) in &report.outcomes {
+            assert!(!outcome.observation.alerts.is_empty());
+        }
+        assert!(!report.is_oracle(), "{}", report);
+    }
+}
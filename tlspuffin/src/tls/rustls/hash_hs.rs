@@ -107,6 +107,17 @@ impl HandshakeHash {
         }
     }
 
+    /// Creates a handshake hash which also keeps all messages, like for client auth. This allows
+    /// computing hashes with other algorithms, e.g. the MD5 and SHA-1 of the legacy Finished.
+    pub fn new_buffered(alg: &'static digest::Algorithm) -> HandshakeHash {
+        let ctx = digest::Context::new(alg);
+        HandshakeHash {
+            ctx,
+            client_auth: Some(Vec::new()),
+            override_buffer: None,
+        }
+    }
+
     /// We decided not to do client auth after all, so discard
     /// the transcript.
     pub fn abandon_client_auth(&mut self) {
@@ -186,6 +197,11 @@ impl HandshakeHash {
         self.client_auth.take()
     }
 
+    /// All handshake messages so far, if they are kept.
+    pub fn get_handshake_buf(&self) -> Option<&[u8]> {
+        self.client_auth.as_deref()
+    }
+
     /// The digest algorithm
     pub fn algorithm(&self) -> &'static digest::Algorithm {
         self.ctx.algorithm()
@@ -15,7 +15,8 @@ enum_builder! {
         TLSv1_2 => 0x0303,
         TLSv1_3 => 0x0304,
         DTLSv1_0 => 0xFEFF,
-        DTLSv1_2 => 0xFEFD
+        DTLSv1_2 => 0xFEFD,
+        DTLSv1_3 => 0xFEFC
     }
 }
 
@@ -96,7 +97,8 @@ enum_builder! {
         Alert => 0x15,
         Handshake => 0x16,
         ApplicationData => 0x17,
-        Heartbeat => 0x18
+        Heartbeat => 0x18,
+        Ack => 0x1a
     }
 }
 
@@ -223,6 +225,7 @@ enum_builder! {
         ALProtocolNegotiation => 0x0010,
         SCT => 0x0012,
         Padding => 0x0015,
+        EncryptThenMac => 0x0016,
         ExtendedMasterSecret => 0x0017,
         SessionTicket => 0x0023,
         PreSharedKey => 0x0029,
@@ -239,6 +242,8 @@ enum_builder! {
         TransportParameters => 0x0039,
         NextProtocolNegotiation => 0x3374,
         ChannelId => 0x754f,
+        EchOuterExtensions => 0xfd00,
+        EncryptedClientHello => 0xfe0d,
         RenegotiationInfo => 0xff01,
         TransportParametersDraft => 0xffa5
     }
@@ -798,3 +803,53 @@ enum_builder! {
         OCSP => 0x01
     }
 }
+
+enum_builder! {
+    /// The `EchClientHelloType` enum of the Encrypted Client Hello extension, see
+    /// draft-ietf-tls-esni. The `Unknown` item is used when processing unrecognised ordinals.
+    @U8
+    EnumName: EchClientHelloType;
+    EnumVal{
+        ClientHelloOuter => 0x00,
+        ClientHelloInner => 0x01
+    }
+}
+
+enum_builder! {
+    /// The `HpkeKem` enum, see RFC 9180. Values in this enum are listed by IANA.
+    /// The `Unknown` item is used when processing unrecognised ordinals.
+    @U16
+    EnumName: HpkeKem;
+    EnumVal{
+        DHKEM_P256_HKDF_SHA256 => 0x0010,
+        DHKEM_P384_HKDF_SHA384 => 0x0011,
+        DHKEM_P521_HKDF_SHA512 => 0x0012,
+        DHKEM_X25519_HKDF_SHA256 => 0x0020,
+        DHKEM_X448_HKDF_SHA512 => 0x0021
+    }
+}
+
+enum_builder! {
+    /// The `HpkeKdf` enum, see RFC 9180. Values in this enum are listed by IANA.
+    /// The `Unknown` item is used when processing unrecognised ordinals.
+    @U16
+    EnumName: HpkeKdf;
+    EnumVal{
+        HKDF_SHA256 => 0x0001,
+        HKDF_SHA384 => 0x0002,
+        HKDF_SHA512 => 0x0003
+    }
+}
+
+enum_builder! {
+    /// The `HpkeAead` enum, see RFC 9180. Values in this enum are listed by IANA.
+    /// The `Unknown` item is used when processing unrecognised ordinals.
+    @U16
+    EnumName: HpkeAead;
+    EnumVal{
+        AES_128_GCM => 0x0001,
+        AES_256_GCM => 0x0002,
+        CHACHA20_POLY_1305 => 0x0003,
+        EXPORT_ONLY => 0xffff
+    }
+}
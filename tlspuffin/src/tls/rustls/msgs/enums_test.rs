@@ -92,6 +92,16 @@ fn test_enums() {
         KeyUpdateRequest::UpdateRequested,
     );
     test_enum8::<CertificateStatusType>(CertificateStatusType::OCSP, CertificateStatusType::OCSP);
+    test_enum8::<EchClientHelloType>(
+        EchClientHelloType::ClientHelloOuter,
+        EchClientHelloType::ClientHelloInner,
+    );
+    test_enum16::<HpkeKem>(
+        HpkeKem::DHKEM_P256_HKDF_SHA256,
+        HpkeKem::DHKEM_X448_HKDF_SHA512,
+    );
+    test_enum16::<HpkeKdf>(HpkeKdf::HKDF_SHA256, HpkeKdf::HKDF_SHA512);
+    test_enum16::<HpkeAead>(HpkeAead::AES_128_GCM, HpkeAead::CHACHA20_POLY_1305);
 }
 
 #[test]
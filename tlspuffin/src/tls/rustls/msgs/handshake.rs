@@ -11,9 +11,9 @@ use crate::tls::rustls::{
         base::{Payload, PayloadU16, PayloadU24, PayloadU8},
         enums::{
             CertificateStatusType, CipherSuite, ClientCertificateType, Compression, ECCurveType,
-            ECPointFormat, ExtensionType, HandshakeType, HashAlgorithm, KeyUpdateRequest,
-            NamedGroup, PSKKeyExchangeMode, ProtocolVersion, ServerNameType, SignatureAlgorithm,
-            SignatureScheme,
+            ECPointFormat, EchClientHelloType, ExtensionType, HandshakeType, HashAlgorithm,
+            HpkeAead, HpkeKdf, HpkeKem, KeyUpdateRequest, NamedGroup, PSKKeyExchangeMode,
+            ProtocolVersion, ServerNameType, SignatureAlgorithm, SignatureScheme,
         },
     },
     rand,
@@ -188,6 +188,16 @@ impl UnknownExtension {
         let payload = Payload::read(r);
         Self { typ, payload }
     }
+
+    /// Reads the whole `payload` of an extension as `T`, or keeps it as an unknown extension if it
+    /// is malformed. Attackers send malformed ECH extensions, which must not make the whole
+    /// message unreadable.
+    fn read_or_unknown<T: Codec>(typ: ExtensionType, payload: &[u8]) -> Result<T, Self> {
+        let mut r = Reader::init(payload);
+        T::read(&mut r)
+            .filter(|_| !r.any_left())
+            .ok_or_else(|| Self::read(typ, &mut Reader::init(payload)))
+    }
 }
 
 declare_u8_vec!(ECPointFormatList, ECPointFormat);
@@ -551,6 +561,184 @@ declare_u8_vec!(PSKKeyExchangeModes, PSKKeyExchangeMode);
 declare_u16_vec!(KeyShareEntries, KeyShareEntry);
 declare_u8_vec!(ProtocolVersions, ProtocolVersion);
 
+#[derive(Clone, Debug)]
+pub struct HpkeSymmetricCipherSuite {
+    pub kdf_id: HpkeKdf,
+    pub aead_id: HpkeAead,
+}
+
+impl Codec for HpkeSymmetricCipherSuite {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.kdf_id.encode(bytes);
+        self.aead_id.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            kdf_id: HpkeKdf::read(r)?,
+            aead_id: HpkeAead::read(r)?,
+        })
+    }
+}
+
+declare_u16_vec!(HpkeSymmetricCipherSuites, HpkeSymmetricCipherSuite);
+
+#[derive(Clone, Debug)]
+pub struct HpkeKeyConfig {
+    pub config_id: u8,
+    pub kem_id: HpkeKem,
+    pub public_key: PayloadU16,
+    pub symmetric_cipher_suites: HpkeSymmetricCipherSuites,
+}
+
+impl Codec for HpkeKeyConfig {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.config_id.encode(bytes);
+        self.kem_id.encode(bytes);
+        self.public_key.encode(bytes);
+        self.symmetric_cipher_suites.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            config_id: u8::read(r)?,
+            kem_id: HpkeKem::read(r)?,
+            public_key: PayloadU16::read(r)?,
+            symmetric_cipher_suites: HpkeSymmetricCipherSuites::read(r)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EchConfigContents {
+    pub key_config: HpkeKeyConfig,
+    pub maximum_name_length: u8,
+    pub public_name: PayloadU8,
+    /// The encoded extensions of the ECHConfig. No extensions are defined yet.
+    pub extensions: PayloadU16,
+}
+
+impl Codec for EchConfigContents {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.key_config.encode(bytes);
+        self.maximum_name_length.encode(bytes);
+        self.public_name.encode(bytes);
+        self.extensions.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        Some(Self {
+            key_config: HpkeKeyConfig::read(r)?,
+            maximum_name_length: u8::read(r)?,
+            public_name: PayloadU8::read(r)?,
+            extensions: PayloadU16::read(r)?,
+        })
+    }
+}
+
+/// The version 0xfe0d of ECHConfig, which is used since draft-ietf-tls-esni-13
+pub const ECH_CONFIG_VERSION: u16 = 0xfe0d;
+
+#[derive(Clone, Debug)]
+pub enum EchConfigPayload {
+    /// An ECHConfig of the version [`ECH_CONFIG_VERSION`]
+    Fe0d(EchConfigContents),
+    Unknown {
+        version: u16,
+        contents: Payload,
+    },
+}
+
+impl Codec for EchConfigPayload {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let mut sub: Vec<u8> = Vec::new();
+        let version = match self {
+            Self::Fe0d(contents) => {
+                contents.encode(&mut sub);
+                ECH_CONFIG_VERSION
+            }
+            Self::Unknown { version, contents } => {
+                contents.encode(&mut sub);
+                *version
+            }
+        };
+
+        version.encode(bytes);
+        (sub.len() as u16).encode(bytes);
+        bytes.append(&mut sub);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let version = u16::read(r)?;
+        let len = u16::read(r)? as usize;
+        let mut sub = r.sub(len)?;
+
+        let config = match version {
+            ECH_CONFIG_VERSION => Self::Fe0d(EchConfigContents::read(&mut sub)?),
+            _ => Self::Unknown {
+                version,
+                contents: Payload::read(&mut sub),
+            },
+        };
+
+        if sub.any_left() {
+            None
+        } else {
+            Some(config)
+        }
+    }
+}
+
+declare_u16_vec!(EchConfigList, EchConfigPayload);
+
+#[derive(Clone, Debug)]
+pub struct EncryptedClientHelloOuter {
+    pub cipher_suite: HpkeSymmetricCipherSuite,
+    pub config_id: u8,
+    /// The encapsulated key of HPKE
+    pub enc: PayloadU16,
+    /// The encrypted EncodedClientHelloInner
+    pub payload: PayloadU16,
+}
+
+/// The Encrypted Client Hello extension. The ClientHelloOuter carries the encrypted
+/// ClientHelloInner, which carries an empty extension of type inner.
+#[derive(Clone, Debug)]
+pub enum EncryptedClientHello {
+    Outer(EncryptedClientHelloOuter),
+    Inner,
+}
+
+impl Codec for EncryptedClientHello {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Outer(outer) => {
+                EchClientHelloType::ClientHelloOuter.encode(bytes);
+                outer.cipher_suite.encode(bytes);
+                outer.config_id.encode(bytes);
+                outer.enc.encode(bytes);
+                outer.payload.encode(bytes);
+            }
+            Self::Inner => EchClientHelloType::ClientHelloInner.encode(bytes),
+        }
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        match EchClientHelloType::read(r)? {
+            EchClientHelloType::ClientHelloOuter => Some(Self::Outer(EncryptedClientHelloOuter {
+                cipher_suite: HpkeSymmetricCipherSuite::read(r)?,
+                config_id: u8::read(r)?,
+                enc: PayloadU16::read(r)?,
+                payload: PayloadU16::read(r)?,
+            })),
+            EchClientHelloType::ClientHelloInner => Some(Self::Inner),
+            EchClientHelloType::Unknown(_) => None,
+        }
+    }
+}
+
+declare_u8_vec!(EchOuterExtensions, ExtensionType);
+
 #[derive(Clone, Debug)]
 pub enum ClientExtension {
     ECPointFormats(ECPointFormatList),
@@ -565,6 +753,7 @@ pub enum ClientExtension {
     PresharedKey(PresharedKeyOffer),
     Cookie(PayloadU16),
     ExtendedMasterSecretRequest,
+    EncryptThenMacRequest,
     CertificateStatusRequest(CertificateStatusRequest),
     SignedCertificateTimestampRequest,
     TransportParameters(Vec<u8>),
@@ -572,6 +761,9 @@ pub enum ClientExtension {
     EarlyData,
     RenegotiationInfo(PayloadU8),
     SignatureAlgorithmsCert(SupportedSignatureSchemes),
+    PostHandshakeAuth,
+    EncryptedClientHello(EncryptedClientHello),
+    EchOuterExtensions(EchOuterExtensions),
     Unknown(UnknownExtension),
 }
 
@@ -590,6 +782,7 @@ impl ClientExtension {
             Self::PresharedKey(_) => ExtensionType::PreSharedKey,
             Self::Cookie(_) => ExtensionType::Cookie,
             Self::ExtendedMasterSecretRequest => ExtensionType::ExtendedMasterSecret,
+            Self::EncryptThenMacRequest => ExtensionType::EncryptThenMac,
             Self::CertificateStatusRequest(_) => ExtensionType::StatusRequest,
             Self::SignedCertificateTimestampRequest => ExtensionType::SCT,
             Self::TransportParameters(_) => ExtensionType::TransportParameters,
@@ -597,6 +790,9 @@ impl ClientExtension {
             Self::EarlyData => ExtensionType::EarlyData,
             ClientExtension::RenegotiationInfo(_) => ExtensionType::RenegotiationInfo,
             Self::SignatureAlgorithmsCert(_) => ExtensionType::SignatureAlgorithmsCert,
+            Self::PostHandshakeAuth => ExtensionType::PostHandshakeAuth,
+            Self::EncryptedClientHello(_) => ExtensionType::EncryptedClientHello,
+            Self::EchOuterExtensions(_) => ExtensionType::EchOuterExtensions,
             Self::Unknown(ref r) => r.typ,
         }
     }
@@ -614,8 +810,10 @@ impl Codec for ClientExtension {
             Self::ServerName(ref r) => r.encode(&mut sub),
             Self::SessionTicket(ClientSessionTicket::Request)
             | Self::ExtendedMasterSecretRequest
+            | Self::EncryptThenMacRequest
             | Self::SignedCertificateTimestampRequest
-            | Self::EarlyData => {}
+            | Self::EarlyData
+            | Self::PostHandshakeAuth => {}
             Self::SessionTicket(ClientSessionTicket::Offer(ref r)) => r.encode(&mut sub),
             Self::Protocols(ref r) => r.encode(&mut sub),
             Self::SupportedVersions(ref r) => r.encode(&mut sub),
@@ -629,6 +827,8 @@ impl Codec for ClientExtension {
             }
             Self::RenegotiationInfo(ref r) => r.encode(&mut sub),
             Self::SignatureAlgorithmsCert(ref r) => r.encode(&mut sub),
+            Self::EncryptedClientHello(ref r) => r.encode(&mut sub),
+            Self::EchOuterExtensions(ref r) => r.encode(&mut sub),
             Self::Unknown(ref r) => r.encode(&mut sub),
         }
 
@@ -674,6 +874,7 @@ impl Codec for ClientExtension {
             ExtensionType::ExtendedMasterSecret if !sub.any_left() => {
                 Self::ExtendedMasterSecretRequest
             }
+            ExtensionType::EncryptThenMac if !sub.any_left() => Self::EncryptThenMacRequest,
             ExtensionType::StatusRequest => {
                 let csr = CertificateStatusRequest::read(&mut sub)?;
                 Self::CertificateStatusRequest(csr)
@@ -691,6 +892,19 @@ impl Codec for ClientExtension {
                 ClientExtension::SignatureAlgorithmsCert(schemes)
             }
             ExtensionType::EarlyData if !sub.any_left() => Self::EarlyData,
+            ExtensionType::PostHandshakeAuth if !sub.any_left() => Self::PostHandshakeAuth,
+            ExtensionType::EncryptedClientHello => {
+                match UnknownExtension::read_or_unknown(typ, sub.rest()) {
+                    Ok(ech) => Self::EncryptedClientHello(ech),
+                    Err(unknown) => Self::Unknown(unknown),
+                }
+            }
+            ExtensionType::EchOuterExtensions => {
+                match UnknownExtension::read_or_unknown(typ, sub.rest()) {
+                    Ok(outer_extensions) => Self::EchOuterExtensions(outer_extensions),
+                    Err(unknown) => Self::Unknown(unknown),
+                }
+            }
             _ => Self::Unknown(UnknownExtension::read(typ, &mut sub)),
         };
 
@@ -745,12 +959,15 @@ pub enum ServerExtension {
     KeyShare(KeyShareEntry),
     PresharedKey(u16),
     ExtendedMasterSecretAck,
+    EncryptThenMacAck,
     CertificateStatusAck,
     SignedCertificateTimestamp(SCTList),
     SupportedVersions(ProtocolVersion),
     TransportParameters(Vec<u8>),
     TransportParametersDraft(Vec<u8>),
     EarlyData,
+    /// The retry_configs of a server which rejected the Encrypted Client Hello
+    EncryptedClientHello(EchConfigList),
     Unknown(UnknownExtension),
 }
 
@@ -765,12 +982,14 @@ impl ServerExtension {
             Self::KeyShare(_) => ExtensionType::KeyShare,
             Self::PresharedKey(_) => ExtensionType::PreSharedKey,
             Self::ExtendedMasterSecretAck => ExtensionType::ExtendedMasterSecret,
+            Self::EncryptThenMacAck => ExtensionType::EncryptThenMac,
             Self::CertificateStatusAck => ExtensionType::StatusRequest,
             Self::SignedCertificateTimestamp(_) => ExtensionType::SCT,
             Self::SupportedVersions(_) => ExtensionType::SupportedVersions,
             Self::TransportParameters(_) => ExtensionType::TransportParameters,
             Self::TransportParametersDraft(_) => ExtensionType::TransportParametersDraft,
             Self::EarlyData => ExtensionType::EarlyData,
+            Self::EncryptedClientHello(_) => ExtensionType::EncryptedClientHello,
             Self::Unknown(ref r) => r.typ,
         }
     }
@@ -786,6 +1005,7 @@ impl Codec for ServerExtension {
             Self::ServerNameAck
             | Self::SessionTicketAck
             | Self::ExtendedMasterSecretAck
+            | Self::EncryptThenMacAck
             | Self::CertificateStatusAck
             | Self::EarlyData => {}
             Self::RenegotiationInfo(ref r) => r.encode(&mut sub),
@@ -797,6 +1017,7 @@ impl Codec for ServerExtension {
             Self::TransportParameters(ref r) | Self::TransportParametersDraft(ref r) => {
                 sub.extend_from_slice(r)
             }
+            Self::EncryptedClientHello(ref r) => r.encode(&mut sub),
             Self::Unknown(ref r) => r.encode(&mut sub),
         }
 
@@ -823,6 +1044,7 @@ impl Codec for ServerExtension {
             ExtensionType::KeyShare => Self::KeyShare(KeyShareEntry::read(&mut sub)?),
             ExtensionType::PreSharedKey => Self::PresharedKey(u16::read(&mut sub)?),
             ExtensionType::ExtendedMasterSecret => Self::ExtendedMasterSecretAck,
+            ExtensionType::EncryptThenMac => Self::EncryptThenMacAck,
             ExtensionType::SCT => {
                 let scts = SCTList::read(&mut sub)?;
                 Self::SignedCertificateTimestamp(scts)
@@ -835,6 +1057,12 @@ impl Codec for ServerExtension {
                 Self::TransportParametersDraft(sub.rest().to_vec())
             }
             ExtensionType::EarlyData => Self::EarlyData,
+            ExtensionType::EncryptedClientHello => {
+                match UnknownExtension::read_or_unknown(typ, sub.rest()) {
+                    Ok(retry_configs) => Self::EncryptedClientHello(retry_configs),
+                    Err(unknown) => Self::Unknown(unknown),
+                }
+            }
             _ => Self::Unknown(UnknownExtension::read(typ, &mut sub)),
         };
 
@@ -1066,6 +1294,8 @@ pub enum HelloRetryExtension {
     KeyShare(NamedGroup),
     Cookie(PayloadU16),
     SupportedVersions(ProtocolVersion),
+    /// The confirmation of a server which accepted the Encrypted Client Hello
+    EncryptedClientHello(Vec<u8>),
     Unknown(UnknownExtension),
 }
 
@@ -1075,6 +1305,7 @@ impl HelloRetryExtension {
             HelloRetryExtension::KeyShare(_) => ExtensionType::KeyShare,
             HelloRetryExtension::Cookie(_) => ExtensionType::Cookie,
             HelloRetryExtension::SupportedVersions(_) => ExtensionType::SupportedVersions,
+            HelloRetryExtension::EncryptedClientHello(_) => ExtensionType::EncryptedClientHello,
             HelloRetryExtension::Unknown(ref r) => r.typ,
         }
     }
@@ -1089,6 +1320,7 @@ impl Codec for HelloRetryExtension {
             HelloRetryExtension::KeyShare(ref r) => r.encode(&mut sub),
             HelloRetryExtension::Cookie(ref r) => r.encode(&mut sub),
             HelloRetryExtension::SupportedVersions(ref r) => r.encode(&mut sub),
+            HelloRetryExtension::EncryptedClientHello(ref r) => sub.extend_from_slice(r),
             HelloRetryExtension::Unknown(ref r) => r.encode(&mut sub),
         }
 
@@ -1107,6 +1339,7 @@ impl Codec for HelloRetryExtension {
             ExtensionType::SupportedVersions => {
                 Self::SupportedVersions(ProtocolVersion::read(&mut sub)?)
             }
+            ExtensionType::EncryptedClientHello => Self::EncryptedClientHello(sub.rest().to_vec()),
             _ => Self::Unknown(UnknownExtension::read(typ, &mut sub)),
         };
 
@@ -1691,9 +1924,68 @@ impl Codec for ECDHEServerKeyExchange {
     }
 }
 
+#[allow(non_snake_case)]
+#[derive(Debug, Clone)]
+pub struct ServerDHParams {
+    pub dh_p: PayloadU16,
+    pub dh_g: PayloadU16,
+    pub dh_Ys: PayloadU16,
+}
+
+impl ServerDHParams {
+    pub fn new(p: &[u8], g: &[u8], ys: &[u8]) -> Self {
+        Self {
+            dh_p: PayloadU16::new(p.to_vec()),
+            dh_g: PayloadU16::new(g.to_vec()),
+            dh_Ys: PayloadU16::new(ys.to_vec()),
+        }
+    }
+}
+
+impl Codec for ServerDHParams {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.dh_p.encode(bytes);
+        self.dh_g.encode(bytes);
+        self.dh_Ys.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let p = PayloadU16::read(r)?;
+        let g = PayloadU16::read(r)?;
+        let ys = PayloadU16::read(r)?;
+
+        Some(Self {
+            dh_p: p,
+            dh_g: g,
+            dh_Ys: ys,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DHEServerKeyExchange {
+    pub params: ServerDHParams,
+    pub dss: DigitallySignedStruct,
+}
+
+impl Codec for DHEServerKeyExchange {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.params.encode(bytes);
+        self.dss.encode(bytes);
+    }
+
+    fn read(r: &mut Reader) -> Option<Self> {
+        let params = ServerDHParams::read(r)?;
+        let dss = DigitallySignedStruct::read(r)?;
+
+        Some(Self { params, dss })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ServerKeyExchangePayload {
     ECDHE(ECDHEServerKeyExchange),
+    DHE(DHEServerKeyExchange),
     Unknown(Payload),
 }
 
@@ -1701,6 +1993,7 @@ impl Codec for ServerKeyExchangePayload {
     fn encode(&self, bytes: &mut Vec<u8>) {
         match *self {
             ServerKeyExchangePayload::ECDHE(ref x) => x.encode(bytes),
+            ServerKeyExchangePayload::DHE(ref x) => x.encode(bytes),
             ServerKeyExchangePayload::Unknown(ref x) => x.encode(bytes),
         }
     }
@@ -1713,12 +2006,18 @@ impl Codec for ServerKeyExchangePayload {
 }
 
 impl ServerKeyExchangePayload {
-    pub fn unwrap_given_kxa(&self, kxa: &KeyExchangeAlgorithm) -> Option<ECDHEServerKeyExchange> {
+    /// Parses an `Unknown` payload as the ServerKeyExchange of the key exchange algorithm `kxa`.
+    pub fn unwrap_given_kxa(&self, kxa: &KeyExchangeAlgorithm) -> Option<ServerKeyExchangePayload> {
         if let ServerKeyExchangePayload::Unknown(ref unk) = *self {
             let mut rd = Reader::init(&unk.0);
 
             let result = match *kxa {
-                KeyExchangeAlgorithm::ECDHE => ECDHEServerKeyExchange::read(&mut rd),
+                KeyExchangeAlgorithm::ECDHE => {
+                    ECDHEServerKeyExchange::read(&mut rd).map(ServerKeyExchangePayload::ECDHE)
+                }
+                KeyExchangeAlgorithm::DHE => {
+                    DHEServerKeyExchange::read(&mut rd).map(ServerKeyExchangePayload::DHE)
+                }
                 _ => None,
             };
 
@@ -390,9 +390,20 @@ fn get_sample_clienthellopayload() -> ClientHelloPayload {
             }),
             ClientExtension::Cookie(PayloadU16(vec![1, 2, 3])),
             ClientExtension::ExtendedMasterSecretRequest,
+            ClientExtension::EncryptThenMacRequest,
+            ClientExtension::PostHandshakeAuth,
             ClientExtension::CertificateStatusRequest(CertificateStatusRequest::build_ocsp()),
             ClientExtension::SignedCertificateTimestampRequest,
             ClientExtension::TransportParameters(vec![1, 2, 3]),
+            ClientExtension::EncryptedClientHello(EncryptedClientHello::Outer(
+                EncryptedClientHelloOuter {
+                    cipher_suite: get_sample_hpkesymmetriccipher_suite(),
+                    config_id: 1,
+                    enc: PayloadU16(vec![1, 2, 3]),
+                    payload: PayloadU16(vec![4, 5, 6]),
+                },
+            )),
+            ClientExtension::EchOuterExtensions(EchOuterExtensions(vec![ExtensionType::KeyShare])),
             ClientExtension::Unknown(UnknownExtension {
                 typ: ExtensionType::Unknown(12345),
                 payload: Payload(vec![1, 2, 3]),
@@ -764,12 +775,14 @@ fn get_sample_serverhellopayload() -> ServerHelloPayload {
             ServerExtension::KeyShare(KeyShareEntry::new(NamedGroup::X25519, &[1, 2, 3])),
             ServerExtension::PresharedKey(3),
             ServerExtension::ExtendedMasterSecretAck,
+            ServerExtension::EncryptThenMacAck,
             ServerExtension::CertificateStatusAck,
             ServerExtension::SignedCertificateTimestamp(VecU16OfPayloadU16(vec![PayloadU16(
                 vec![0],
             )])),
             ServerExtension::SupportedVersions(ProtocolVersion::TLSv1_2),
             ServerExtension::TransportParameters(vec![1, 2, 3]),
+            ServerExtension::EncryptedClientHello(get_sample_echconfiglist()),
             ServerExtension::Unknown(UnknownExtension {
                 typ: ExtensionType::Unknown(12345),
                 payload: Payload(vec![1, 2, 3]),
@@ -788,6 +801,88 @@ fn can_clone_all_serverextensions() {
     let _ = get_sample_serverhellopayload().extensions;
 }
 
+fn get_sample_hpkesymmetriccipher_suite() -> HpkeSymmetricCipherSuite {
+    HpkeSymmetricCipherSuite {
+        kdf_id: HpkeKdf::HKDF_SHA256,
+        aead_id: HpkeAead::AES_128_GCM,
+    }
+}
+
+fn get_sample_echconfiglist() -> EchConfigList {
+    EchConfigList(vec![
+        EchConfigPayload::Fe0d(EchConfigContents {
+            key_config: HpkeKeyConfig {
+                config_id: 1,
+                kem_id: HpkeKem::DHKEM_X25519_HKDF_SHA256,
+                public_key: PayloadU16(vec![1, 2, 3]),
+                symmetric_cipher_suites: HpkeSymmetricCipherSuites(vec![
+                    get_sample_hpkesymmetriccipher_suite(),
+                ]),
+            },
+            maximum_name_length: 0,
+            public_name: PayloadU8(b"example.com".to_vec()),
+            extensions: PayloadU16(vec![]),
+        }),
+        EchConfigPayload::Unknown {
+            version: 0xfe0a,
+            contents: Payload(vec![1, 2, 3]),
+        },
+    ])
+}
+
+#[test]
+fn can_roundtrip_echconfiglist() {
+    let list = get_sample_echconfiglist();
+    let enc = list.get_encoding();
+    let decoded = EchConfigList::read_bytes(&enc).unwrap();
+    assert_eq!(decoded.get_encoding(), enc);
+
+    match &decoded.0[..] {
+        [EchConfigPayload::Fe0d(contents), EchConfigPayload::Unknown { version, .. }] => {
+            assert_eq!(contents.key_config.config_id, 1);
+            assert_eq!(contents.public_name.0, b"example.com");
+            assert_eq!(*version, 0xfe0a);
+        }
+        _ => panic!("unexpected ECHConfigList {:?}", decoded),
+    }
+}
+
+#[test]
+fn can_roundtrip_ech_inner_extension() {
+    let ext = ClientExtension::EncryptedClientHello(EncryptedClientHello::Inner);
+    let enc = ext.get_encoding();
+    assert_eq!(enc, vec![0xfe, 0x0d, 0x00, 0x01, 0x01]);
+    assert!(matches!(
+        ClientExtension::read_bytes(&enc),
+        Some(ClientExtension::EncryptedClientHello(
+            EncryptedClientHello::Inner
+        ))
+    ));
+}
+
+#[test]
+fn keeps_malformed_ech_extension_as_unknown() {
+    // An ECH extension with an unknown ECHClientHelloType and one with a truncated outer payload
+    for enc in [
+        vec![0xfe, 0x0d, 0x00, 0x02, 0x02, 0x00],
+        vec![0xfe, 0x0d, 0x00, 0x03, 0x00, 0x00, 0x01],
+    ] {
+        match ClientExtension::read_bytes(&enc) {
+            Some(ClientExtension::Unknown(unknown)) => {
+                assert_eq!(unknown.typ, ExtensionType::EncryptedClientHello);
+                assert_eq!(unknown.payload.0, enc[4..]);
+            }
+            ext => panic!("unexpected extension {:?}", ext),
+        }
+    }
+
+    let enc = vec![0xfe, 0x0d, 0x00, 0x01, 0xff];
+    assert!(matches!(
+        ServerExtension::read_bytes(&enc),
+        Some(ServerExtension::Unknown(_))
+    ));
+}
+
 fn get_sample_helloretryrequest() -> HelloRetryRequest {
     HelloRetryRequest {
         legacy_version: ProtocolVersion::TLSv1_2,
@@ -826,6 +921,35 @@ fn get_sample_certificatepayloadtls13() -> CertificatePayloadTLS13 {
     }
 }
 
+#[test]
+fn unwraps_server_key_exchange_given_kxa() {
+    for sample in [
+        get_sample_serverkeyexchangepayload_ecdhe(),
+        get_sample_serverkeyexchangepayload_dhe(),
+    ] {
+        let kxa = match sample {
+            ServerKeyExchangePayload::ECDHE(_) => KeyExchangeAlgorithm::ECDHE,
+            _ => KeyExchangeAlgorithm::DHE,
+        };
+        let unknown = ServerKeyExchangePayload::read_bytes(&sample.get_encoding()).unwrap();
+        assert!(matches!(unknown, ServerKeyExchangePayload::Unknown(_)));
+
+        let parsed = unknown.unwrap_given_kxa(&kxa).unwrap();
+        assert_eq!(parsed.get_encoding(), sample.get_encoding());
+        assert_eq!(
+            std::mem::discriminant(&parsed),
+            std::mem::discriminant(&sample)
+        );
+    }
+
+    // The parameters of DHE are not valid ECDHE parameters
+    let dhe = ServerKeyExchangePayload::read_bytes(
+        &get_sample_serverkeyexchangepayload_dhe().get_encoding(),
+    )
+    .unwrap();
+    assert!(dhe.unwrap_given_kxa(&KeyExchangeAlgorithm::ECDHE).is_none());
+}
+
 fn get_sample_serverkeyexchangepayload_ecdhe() -> ServerKeyExchangePayload {
     ServerKeyExchangePayload::ECDHE(ECDHEServerKeyExchange {
         params: ServerECDHParams {
@@ -842,6 +966,16 @@ fn get_sample_serverkeyexchangepayload_ecdhe() -> ServerKeyExchangePayload {
     })
 }
 
+fn get_sample_serverkeyexchangepayload_dhe() -> ServerKeyExchangePayload {
+    ServerKeyExchangePayload::DHE(DHEServerKeyExchange {
+        params: ServerDHParams::new(&[23], &[5], &[8]),
+        dss: DigitallySignedStruct {
+            scheme: SignatureScheme::RSA_PKCS1_SHA256,
+            sig: PayloadU16(vec![1, 2, 3]),
+        },
+    })
+}
+
 fn get_sample_serverkeyexchangepayload_unknown() -> ServerKeyExchangePayload {
     ServerKeyExchangePayload::Unknown(Payload(vec![1, 2, 3]))
 }
@@ -932,6 +1066,10 @@ fn get_all_tls12_handshake_payloads() -> Vec<HandshakeMessagePayload> {
                 get_sample_serverkeyexchangepayload_ecdhe(),
             ),
         },
+        HandshakeMessagePayload {
+            typ: HandshakeType::ServerKeyExchange,
+            payload: HandshakePayload::ServerKeyExchange(get_sample_serverkeyexchangepayload_dhe()),
+        },
         HandshakeMessagePayload {
             typ: HandshakeType::ServerKeyExchange,
             payload: HandshakePayload::ServerKeyExchange(
@@ -981,6 +1119,18 @@ fn get_all_tls12_handshake_payloads() -> Vec<HandshakeMessagePayload> {
     ]
 }
 
+#[test]
+fn can_roundtrip_server_dh_params() {
+    let params = ServerDHParams::new(&[0xff; 256], &[2], &[0x42; 256]);
+    let bytes = params.get_encoding();
+    let mut rd = Reader::init(&bytes);
+    let other = ServerDHParams::read(&mut rd).unwrap();
+    assert!(!rd.any_left());
+    assert_eq!(other.dh_p.0, vec![0xff; 256]);
+    assert_eq!(other.dh_g.0, vec![2]);
+    assert_eq!(other.dh_Ys.0, vec![0x42; 256]);
+}
+
 #[test]
 fn can_roundtrip_all_tls12_handshake_payloads() {
     for hm in get_all_tls12_handshake_payloads().iter() {
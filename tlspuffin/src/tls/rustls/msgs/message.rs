@@ -86,6 +86,9 @@ pub struct OpaqueMessage {
     pub typ: ContentType,
     pub version: ProtocolVersion,
     pub payload: Payload,
+    /// The length which is encoded in the header instead of the length of the payload. This
+    /// allows to send records whose header is inconsistent with their payload.
+    pub fake_length: Option<u16>,
 }
 
 impl Codec for OpaqueMessage {
@@ -118,8 +121,8 @@ impl OpaqueMessage {
             return Err(MessageError::IllegalLength);
         }
 
-        // Don't accept any new content-types.
-        if let ContentType::Unknown(_) = typ {
+        // Don't accept any new content-types. ACKs only exist in DTLS 1.3.
+        if let ContentType::Unknown(_) | ContentType::Ack = typ {
             return Err(MessageError::IllegalContentType);
         }
 
@@ -138,6 +141,7 @@ impl OpaqueMessage {
             typ,
             version,
             payload,
+            fake_length: None,
         })
     }
 
@@ -145,7 +149,9 @@ impl OpaqueMessage {
         let mut buf = Vec::new();
         self.typ.encode(&mut buf);
         self.version.encode(&mut buf);
-        (self.payload.0.len() as u16).encode(&mut buf);
+        self.fake_length
+            .unwrap_or(self.payload.0.len() as u16)
+            .encode(&mut buf);
         self.payload.encode(&mut buf);
         buf
     }
@@ -211,6 +217,7 @@ impl PlainMessage {
             version: self.version,
             typ: self.typ,
             payload: self.payload,
+            fake_length: None,
         }
     }
 
@@ -3,7 +3,8 @@ use std::{convert::TryFrom, io::Read};
 use puffin::codec::Reader;
 
 use super::{
-    enums::{AlertDescription, AlertLevel, HandshakeType},
+    base::Payload,
+    enums::{AlertDescription, AlertLevel, ContentType, HandshakeType, ProtocolVersion},
     message::{Message, OpaqueMessage},
 };
 
@@ -35,3 +36,17 @@ fn construct_all_types() {
         // println!("m' = {:?}", m);
     }
 }
+
+#[test]
+fn opaque_message_fake_length() {
+    let mut m = OpaqueMessage {
+        typ: ContentType::Unknown(0x42),
+        version: ProtocolVersion::Unknown(0x0001),
+        payload: Payload::new(vec![1, 2, 3]),
+        fake_length: None,
+    };
+    assert_eq!(m.clone().encode(), b"\x42\x00\x01\x00\x03\x01\x02\x03");
+
+    m.fake_length = Some(0x1000);
+    assert_eq!(m.encode(), b"\x42\x00\x01\x10\x00\x01\x02\x03");
+}
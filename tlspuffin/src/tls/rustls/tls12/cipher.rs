@@ -1,5 +1,10 @@
+use std::sync::Mutex;
+
+use aes::cipher::{
+    block_padding::NoPadding, BlockCipher, BlockDecryptMut, BlockEncryptMut, KeyInit, KeyIvInit,
+};
 use puffin::codec;
-use ring::aead;
+use ring::{aead, constant_time, digest, hmac};
 
 use crate::tls::rustls::{
     cipher::{make_nonce, Iv, MessageDecrypter, MessageEncrypter},
@@ -164,6 +169,7 @@ impl MessageEncrypter for GcmMessageEncrypter {
             typ: msg.typ,
             version: msg.version,
             payload: Payload::new(payload),
+            fake_length: None,
         })
     }
 }
@@ -234,6 +240,578 @@ impl MessageEncrypter for ChaCha20Poly1305MessageEncrypter {
             typ: msg.typ,
             version: msg.version,
             payload: Payload::new(buf),
+            fake_length: None,
+        })
+    }
+}
+
+/// The block size of AES, which is the only block cipher of the CBC-mode cipher suites.
+const CBC_BLOCK_LEN: usize = 16;
+
+/// Whether records carry their IV explicitly. SSLv3 and TLS 1.0 instead use the last ciphertext
+/// block of the previous record as IV.
+fn has_explicit_iv(version: ProtocolVersion) -> bool {
+    !matches!(version, ProtocolVersion::SSLv3 | ProtocolVersion::TLSv1_0)
+}
+
+/// The explicit IV of a record. It is derived from the sequence number such that the encrypted
+/// records are deterministic.
+fn make_explicit_iv(seq: u64) -> [u8; CBC_BLOCK_LEN] {
+    let mut iv = [0u8; CBC_BLOCK_LEN];
+    codec::put_u64(seq, &mut iv[8..]);
+    iv
+}
+
+/// Computes the MAC over a record. TLS uses HMAC, while SSLv3 uses its own construction which
+/// omits the version from the header. The SSLv3 construction is only implemented for SHA-1.
+pub fn make_cbc_mac(
+    version: ProtocolVersion,
+    mac_algorithm: hmac::Algorithm,
+    mac_key: &[u8],
+    seq: u64,
+    typ: ContentType,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut seq_bytes = [0u8; 8];
+    codec::put_u64(seq, &mut seq_bytes);
+    let mut len_bytes = [0u8; 2];
+    codec::put_u16(payload.len() as u16, &mut len_bytes);
+
+    match version {
+        ProtocolVersion::SSLv3 => {
+            const SSL3_SHA1_PAD_LEN: usize = 40;
+            let digest_algorithm = mac_algorithm.digest_algorithm();
+
+            let mut inner = digest::Context::new(digest_algorithm);
+            inner.update(mac_key);
+            inner.update(&[0x36; SSL3_SHA1_PAD_LEN]);
+            inner.update(&seq_bytes);
+            inner.update(&[typ.get_u8()]);
+            inner.update(&len_bytes);
+            inner.update(payload);
+
+            let mut outer = digest::Context::new(digest_algorithm);
+            outer.update(mac_key);
+            outer.update(&[0x5c; SSL3_SHA1_PAD_LEN]);
+            outer.update(inner.finish().as_ref());
+            outer.finish().as_ref().to_vec()
+        }
+        _ => {
+            let mut version_bytes = [0u8; 2];
+            codec::put_u16(version.get_u16(), &mut version_bytes);
+
+            let mut ctx = hmac::Context::with_key(&hmac::Key::new(mac_algorithm, mac_key));
+            ctx.update(&seq_bytes);
+            ctx.update(&[typ.get_u8()]);
+            ctx.update(&version_bytes);
+            ctx.update(&len_bytes);
+            ctx.update(payload);
+            ctx.sign().as_ref().to_vec()
+        }
+    }
+}
+
+fn cbc_encrypt<C: BlockEncryptMut + BlockCipher + KeyInit>(
+    key: &[u8],
+    iv: &[u8],
+    buf: &mut [u8],
+) -> Result<(), Error> {
+    let len = buf.len();
+    cbc::Encryptor::<C>::new_from_slices(key, iv)
+        .map_err(|_| Error::General("invalid CBC key or IV length".to_string()))?
+        .encrypt_padded_mut::<NoPadding>(buf, len)
+        .map_err(|_| Error::General("encrypt failed".to_string()))?;
+    Ok(())
+}
+
+fn cbc_decrypt<C: BlockDecryptMut + BlockCipher + KeyInit>(
+    key: &[u8],
+    iv: &[u8],
+    buf: &mut [u8],
+) -> Result<(), Error> {
+    cbc::Decryptor::<C>::new_from_slices(key, iv)
+        .map_err(|_| Error::General("invalid CBC key or IV length".to_string()))?
+        .decrypt_padded_mut::<NoPadding>(buf)
+        .map_err(|_| Error::DecryptError)?;
+    Ok(())
+}
+
+/// Encrypts `buf` in place. The length of `key` selects between AES-128 and AES-256.
+fn aes_cbc_encrypt(key: &[u8], iv: &[u8], buf: &mut [u8]) -> Result<(), Error> {
+    match key.len() {
+        16 => cbc_encrypt::<aes::Aes128>(key, iv, buf),
+        32 => cbc_encrypt::<aes::Aes256>(key, iv, buf),
+        _ => Err(Error::General("unsupported AES key length".to_string())),
+    }
+}
+
+/// Decrypts `buf` in place. The length of `key` selects between AES-128 and AES-256.
+fn aes_cbc_decrypt(key: &[u8], iv: &[u8], buf: &mut [u8]) -> Result<(), Error> {
+    match key.len() {
+        16 => cbc_decrypt::<aes::Aes128>(key, iv, buf),
+        32 => cbc_decrypt::<aes::Aes256>(key, iv, buf),
+        _ => Err(Error::General("unsupported AES key length".to_string())),
+    }
+}
+
+/// Encrypts a record `fragment` which already ends with the MAC and the padding. The `version`
+/// selects whether the explicit IV of TLS 1.1 and later is prepended, or whether the `iv` from the
+/// key block is used like by SSLv3 and TLS 1.0. The latter is only correct for the first record,
+/// because the IV of later records is chained. In contrast to [`cbc_encrypter`], neither the MAC
+/// nor the padding are computed, such that both can be chosen freely.
+pub fn cbc_encrypt_fragment(
+    version: ProtocolVersion,
+    enc_key: &[u8],
+    iv: &[u8],
+    seq: u64,
+    fragment: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if fragment.len() % CBC_BLOCK_LEN != 0 {
+        return Err(Error::General(
+            "CBC fragment is not a multiple of the block size".to_string(),
+        ));
+    }
+
+    let mut buf = fragment.to_vec();
+    if !has_explicit_iv(version) {
+        aes_cbc_encrypt(enc_key, iv, &mut buf)?;
+        return Ok(buf);
+    }
+
+    let iv = make_explicit_iv(seq);
+    aes_cbc_encrypt(enc_key, &iv, &mut buf)?;
+
+    let mut payload = Vec::with_capacity(CBC_BLOCK_LEN + buf.len());
+    payload.extend_from_slice(&iv);
+    payload.extend_from_slice(&buf);
+    Ok(payload)
+}
+
+/// Creates a `MessageEncrypter` for AES-CBC cipher suites. The `version` selects the MAC
+/// construction and whether records carry an explicit IV. The `iv` from the key block is only used
+/// by SSLv3 and TLS 1.0. If `encrypt_then_mac` is set, then the MAC is computed over the
+/// ciphertext as specified in RFC 7366. Else, the MAC is encrypted along with the plaintext.
+pub fn cbc_encrypter(
+    version: ProtocolVersion,
+    mac_algorithm: hmac::Algorithm,
+    enc_key: &[u8],
+    mac_key: &[u8],
+    iv: &[u8],
+    encrypt_then_mac: bool,
+) -> Box<dyn MessageEncrypter> {
+    Box::new(CbcMessageEncrypter {
+        version,
+        mac_algorithm,
+        enc_key: enc_key.to_vec(),
+        mac_key: mac_key.to_vec(),
+        encrypt_then_mac,
+        chained_iv: Mutex::new(iv.try_into().unwrap_or([0u8; CBC_BLOCK_LEN])),
+    })
+}
+
+/// Creates a `MessageDecrypter` for AES-CBC cipher suites. See [`cbc_encrypter`].
+pub fn cbc_decrypter(
+    version: ProtocolVersion,
+    mac_algorithm: hmac::Algorithm,
+    dec_key: &[u8],
+    mac_key: &[u8],
+    iv: &[u8],
+    encrypt_then_mac: bool,
+) -> Box<dyn MessageDecrypter> {
+    Box::new(CbcMessageDecrypter {
+        version,
+        mac_algorithm,
+        dec_key: dec_key.to_vec(),
+        mac_key: mac_key.to_vec(),
+        encrypt_then_mac,
+        chained_iv: Mutex::new(iv.try_into().unwrap_or([0u8; CBC_BLOCK_LEN])),
+    })
+}
+
+/// A `MessageEncrypter` for AES-CBC cipher suites of SSLv3 up to TLS 1.2.
+struct CbcMessageEncrypter {
+    version: ProtocolVersion,
+    mac_algorithm: hmac::Algorithm,
+    enc_key: Vec<u8>,
+    mac_key: Vec<u8>,
+    encrypt_then_mac: bool,
+    /// The IV of the next record if the version has no explicit IVs
+    chained_iv: Mutex<[u8; CBC_BLOCK_LEN]>,
+}
+
+/// A `MessageDecrypter` for AES-CBC cipher suites of SSLv3 up to TLS 1.2.
+struct CbcMessageDecrypter {
+    version: ProtocolVersion,
+    mac_algorithm: hmac::Algorithm,
+    dec_key: Vec<u8>,
+    mac_key: Vec<u8>,
+    encrypt_then_mac: bool,
+    /// The IV of the next record if the version has no explicit IVs
+    chained_iv: Mutex<[u8; CBC_BLOCK_LEN]>,
+}
+
+impl CbcMessageEncrypter {
+    fn mac(&self, seq: u64, typ: ContentType, data: &[u8]) -> Vec<u8> {
+        make_cbc_mac(
+            self.version,
+            self.mac_algorithm,
+            &self.mac_key,
+            seq,
+            typ,
+            data,
+        )
+    }
+}
+
+impl MessageEncrypter for CbcMessageEncrypter {
+    fn encrypt(&self, msg: BorrowedPlainMessage, seq: u64) -> Result<OpaqueMessage, Error> {
+        let mut buf = Vec::with_capacity(msg.payload.len() + 2 * CBC_BLOCK_LEN);
+        buf.extend_from_slice(msg.payload);
+        if !self.encrypt_then_mac {
+            buf.extend_from_slice(&self.mac(seq, msg.typ, msg.payload));
+        }
+        // Each padding byte and the padding length byte hold the length of the padding
+        let padding_len = CBC_BLOCK_LEN - 1 - buf.len() % CBC_BLOCK_LEN;
+        buf.resize(buf.len() + padding_len + 1, padding_len as u8);
+
+        let explicit_iv = has_explicit_iv(self.version);
+        let iv = if explicit_iv {
+            make_explicit_iv(seq)
+        } else {
+            *self.chained_iv.lock().unwrap()
+        };
+
+        aes_cbc_encrypt(&self.enc_key, &iv, &mut buf)?;
+
+        let mut payload = if explicit_iv {
+            let mut payload = Vec::with_capacity(CBC_BLOCK_LEN + buf.len());
+            payload.extend_from_slice(&iv);
+            payload.extend_from_slice(&buf);
+            payload
+        } else {
+            self.chained_iv
+                .lock()
+                .unwrap()
+                .copy_from_slice(&buf[buf.len() - CBC_BLOCK_LEN..]);
+            buf
+        };
+
+        if self.encrypt_then_mac {
+            let mac = self.mac(seq, msg.typ, &payload);
+            payload.extend_from_slice(&mac);
+        }
+
+        Ok(OpaqueMessage {
+            typ: msg.typ,
+            version: msg.version,
+            payload: Payload::new(payload),
+            fake_length: None,
         })
     }
 }
+
+impl CbcMessageDecrypter {
+    fn verify_mac(&self, seq: u64, typ: ContentType, data: &[u8], mac: &[u8]) -> Result<(), Error> {
+        let expected_mac = make_cbc_mac(
+            self.version,
+            self.mac_algorithm,
+            &self.mac_key,
+            seq,
+            typ,
+            data,
+        );
+        constant_time::verify_slices_are_equal(mac, &expected_mac).map_err(|_| Error::DecryptError)
+    }
+}
+
+impl MessageDecrypter for CbcMessageDecrypter {
+    fn decrypt(&self, mut msg: OpaqueMessage, seq: u64) -> Result<PlainMessage, Error> {
+        let payload = &mut msg.payload.0;
+        let mac_len = self.mac_algorithm.digest_algorithm().output_len;
+        let explicit_iv = has_explicit_iv(self.version);
+
+        // The MAC is either part of the ciphertext or appended to it
+        let min_len = if explicit_iv { CBC_BLOCK_LEN } else { 0 }
+            + if self.encrypt_then_mac {
+                CBC_BLOCK_LEN + mac_len
+            } else {
+                mac_len + 1
+            };
+        if payload.len() < min_len {
+            return Err(Error::DecryptError);
+        }
+
+        if self.encrypt_then_mac {
+            let mac = payload.split_off(payload.len() - mac_len);
+            self.verify_mac(seq, msg.typ, payload, &mac)?;
+        }
+
+        if payload.len() % CBC_BLOCK_LEN != 0 {
+            return Err(Error::DecryptError);
+        }
+
+        let iv: [u8; CBC_BLOCK_LEN] = if explicit_iv {
+            let iv = payload[..CBC_BLOCK_LEN].try_into().unwrap();
+            payload.drain(..CBC_BLOCK_LEN);
+            iv
+        } else {
+            let mut chained_iv = self.chained_iv.lock().unwrap();
+            let iv = *chained_iv;
+            chained_iv.copy_from_slice(&payload[payload.len() - CBC_BLOCK_LEN..]);
+            iv
+        };
+
+        aes_cbc_decrypt(&self.dec_key, &iv, payload)?;
+
+        let padding_len = *payload.last().ok_or(Error::DecryptError)? as usize;
+        let inner_mac_len = if self.encrypt_then_mac { 0 } else { mac_len };
+        if padding_len + 1 + inner_mac_len > payload.len() {
+            return Err(Error::DecryptError);
+        }
+        let padding_start = payload.len() - padding_len - 1;
+        let valid_padding = match self.version {
+            // SSLv3 does not specify the content of the padding
+            ProtocolVersion::SSLv3 => padding_len < CBC_BLOCK_LEN,
+            _ => payload[padding_start..]
+                .iter()
+                .all(|byte| *byte as usize == padding_len),
+        };
+        if !valid_padding {
+            return Err(Error::DecryptError);
+        }
+        payload.truncate(padding_start);
+
+        if !self.encrypt_then_mac {
+            let mac = payload.split_off(payload.len() - mac_len);
+            self.verify_mac(seq, msg.typ, payload, &mac)?;
+        }
+
+        if payload.len() > MAX_FRAGMENT_LEN {
+            return Err(Error::PeerSentOversizedRecord);
+        }
+
+        Ok(msg.into_plain_message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::hmac;
+
+    use super::{cbc_decrypter, cbc_encrypt_fragment, cbc_encrypter, make_cbc_mac};
+    use crate::tls::rustls::msgs::{
+        base::Payload,
+        enums::{ContentType, ProtocolVersion},
+        message::{OpaqueMessage, PlainMessage},
+    };
+
+    fn application_data(version: ProtocolVersion, payload: &[u8]) -> PlainMessage {
+        PlainMessage {
+            typ: ContentType::ApplicationData,
+            version,
+            payload: Payload::new(payload.to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_cbc_roundtrip() {
+        for version in [
+            ProtocolVersion::SSLv3,
+            ProtocolVersion::TLSv1_0,
+            ProtocolVersion::TLSv1_1,
+            ProtocolVersion::TLSv1_2,
+        ] {
+            let alg = hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY;
+            let encrypter = cbc_encrypter(version, alg, &[1; 16], &[2; 20], &[3; 16], false);
+            let decrypter = cbc_decrypter(version, alg, &[1; 16], &[2; 20], &[3; 16], false);
+
+            // The records of SSLv3 and TLS 1.0 are chained, therefore encrypt several
+            for (seq, payload) in [&b"hello"[..], &[0; 16], &[]].iter().enumerate() {
+                let plain = application_data(version, payload);
+                let encrypted = encrypter.encrypt(plain.borrow(), seq as u64).unwrap();
+                assert_eq!(encrypted.payload.0.len() % 16, 0);
+
+                let decrypted = decrypter.decrypt(encrypted, seq as u64).unwrap();
+                assert_eq!(decrypted.payload.0, payload.to_vec());
+            }
+        }
+    }
+
+    #[test]
+    fn test_cbc_encrypt_then_mac_roundtrip() {
+        for version in [ProtocolVersion::TLSv1_0, ProtocolVersion::TLSv1_2] {
+            let alg = hmac::HMAC_SHA256;
+            let encrypter = cbc_encrypter(version, alg, &[1; 16], &[2; 32], &[3; 16], true);
+            let decrypter = cbc_decrypter(version, alg, &[1; 16], &[2; 32], &[3; 16], true);
+
+            for (seq, payload) in [&b"hello"[..], &[0; 16], &[]].iter().enumerate() {
+                let plain = application_data(version, payload);
+                let encrypted = encrypter.encrypt(plain.borrow(), seq as u64).unwrap();
+                // The MAC follows the ciphertext
+                assert_eq!((encrypted.payload.0.len() - 32) % 16, 0);
+
+                let decrypted = decrypter.decrypt(encrypted, seq as u64).unwrap();
+                assert_eq!(decrypted.payload.0, payload.to_vec());
+            }
+        }
+
+        // A MAC-then-encrypt peer rejects the records
+        let encrypter = cbc_encrypter(
+            ProtocolVersion::TLSv1_2,
+            hmac::HMAC_SHA256,
+            &[1; 16],
+            &[2; 32],
+            &[],
+            true,
+        );
+        let decrypter = cbc_decrypter(
+            ProtocolVersion::TLSv1_2,
+            hmac::HMAC_SHA256,
+            &[1; 16],
+            &[2; 32],
+            &[],
+            false,
+        );
+        let plain = application_data(ProtocolVersion::TLSv1_2, b"hello");
+        let encrypted = encrypter.encrypt(plain.borrow(), 0).unwrap();
+        assert!(decrypter.decrypt(encrypted, 0).is_err());
+    }
+
+    #[test]
+    fn test_cbc_encrypt_fragment() {
+        let version = ProtocolVersion::TLSv1_2;
+        let alg = hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY;
+        let decrypter = cbc_decrypter(version, alg, &[1; 16], &[2; 20], &[], false);
+
+        let payload = b"hello";
+        let mut fragment = payload.to_vec();
+        fragment.extend(make_cbc_mac(
+            version,
+            alg,
+            &[2; 20],
+            3,
+            ContentType::ApplicationData,
+            payload,
+        ));
+        fragment.extend([6u8; 7]);
+
+        let encrypted = OpaqueMessage {
+            typ: ContentType::ApplicationData,
+            version,
+            payload: Payload::new(
+                cbc_encrypt_fragment(version, &[1; 16], &[], 3, &fragment).unwrap(),
+            ),
+            fake_length: None,
+        };
+        let decrypted = decrypter.decrypt(encrypted.clone(), 3).unwrap();
+        assert_eq!(decrypted.payload.0, payload.to_vec());
+
+        // The sequence number is part of the MAC
+        assert!(decrypter.decrypt(encrypted, 4).is_err());
+        // The fragment must be a multiple of the block size
+        assert!(cbc_encrypt_fragment(version, &[1; 16], &[], 3, &fragment[1..]).is_err());
+    }
+
+    #[test]
+    fn test_cbc_encrypt_fragment_implicit_iv() {
+        let alg = hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY;
+
+        for version in [ProtocolVersion::SSLv3, ProtocolVersion::TLSv1_0] {
+            let decrypter = cbc_decrypter(version, alg, &[1; 16], &[2; 20], &[3; 16], false);
+
+            let payload = b"hello";
+            let mut fragment = payload.to_vec();
+            fragment.extend(make_cbc_mac(
+                version,
+                alg,
+                &[2; 20],
+                0,
+                ContentType::ApplicationData,
+                payload,
+            ));
+            fragment.extend([6u8; 7]);
+
+            // The record holds no IV, the one from the key block is used instead
+            let encrypted =
+                cbc_encrypt_fragment(version, &[1; 16], &[3; 16], 0, &fragment).unwrap();
+            assert_eq!(encrypted.len(), fragment.len());
+
+            let decrypted = decrypter
+                .decrypt(
+                    OpaqueMessage {
+                        typ: ContentType::ApplicationData,
+                        version,
+                        payload: Payload::new(encrypted),
+                        fake_length: None,
+                    },
+                    0,
+                )
+                .unwrap();
+            assert_eq!(decrypted.payload.0, payload.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_cbc_encrypt_fragment_encrypt_then_mac() {
+        let version = ProtocolVersion::TLSv1_2;
+        let alg = hmac::HMAC_SHA256;
+        let decrypter = cbc_decrypter(version, alg, &[1; 16], &[2; 32], &[], true);
+
+        let payload = b"hello";
+        let mut fragment = payload.to_vec();
+        fragment.extend([10u8; 11]);
+
+        // The MAC is computed over the IV and the ciphertext and appended to them
+        let mut encrypted = cbc_encrypt_fragment(version, &[1; 16], &[], 0, &fragment).unwrap();
+        encrypted.extend(make_cbc_mac(
+            version,
+            alg,
+            &[2; 32],
+            0,
+            ContentType::ApplicationData,
+            &encrypted,
+        ));
+
+        let record = OpaqueMessage {
+            typ: ContentType::ApplicationData,
+            version,
+            payload: Payload::new(encrypted),
+            fake_length: None,
+        };
+        let decrypted = decrypter.decrypt(record.clone(), 0).unwrap();
+        assert_eq!(decrypted.payload.0, payload.to_vec());
+
+        // A modified ciphertext is rejected by the MAC before the padding is checked
+        let mut modified = record;
+        modified.payload.0[16] ^= 1;
+        assert!(decrypter.decrypt(modified, 0).is_err());
+    }
+
+    #[test]
+    fn test_cbc_rejects_modified_record() {
+        let alg = hmac::HMAC_SHA256;
+        let encrypter = cbc_encrypter(
+            ProtocolVersion::TLSv1_1,
+            alg,
+            &[1; 32],
+            &[2; 32],
+            &[],
+            false,
+        );
+        let decrypter = cbc_decrypter(
+            ProtocolVersion::TLSv1_1,
+            alg,
+            &[1; 32],
+            &[2; 32],
+            &[],
+            false,
+        );
+
+        let plain = application_data(ProtocolVersion::TLSv1_1, b"hello");
+        let mut encrypted = encrypter.encrypt(plain.borrow(), 0).unwrap();
+        // Flip a bit of the plaintext by modifying the explicit IV
+        encrypted.payload.0[0] ^= 1;
+
+        assert!(decrypter.decrypt(encrypted, 0).is_err());
+    }
+}
@@ -1,20 +1,23 @@
 use std::fmt;
 
-use ring::{aead, digest::Digest};
+use ring::{aead, digest, digest::Digest};
 
 use crate::tls::rustls::{
     cipher::{MessageDecrypter, MessageEncrypter},
     conn::ConnectionRandoms,
     kx,
     msgs::{
-        enums::{CipherSuite, SignatureScheme},
+        enums::{CipherSuite, ContentType, ProtocolVersion, SignatureScheme},
         handshake::KeyExchangeAlgorithm,
     },
     suites::{BulkAlgorithm, CipherSuiteCommon, SupportedCipherSuite},
 };
 
 mod cipher;
-pub use cipher::{AesGcm, ChaCha20Poly1305, Tls12AeadAlgorithm};
+pub use cipher::{
+    cbc_decrypter, cbc_encrypt_fragment, cbc_encrypter, make_cbc_mac, AesGcm, ChaCha20Poly1305,
+    Tls12AeadAlgorithm,
+};
 use puffin::codec::{Codec, Reader};
 
 use crate::tls::rustls::{conn::Side, error::Error};
@@ -117,6 +120,40 @@ pub static TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384: SupportedCipherSuite =
         hmac_algorithm: ring::hmac::HMAC_SHA384,
     });
 
+/// The TLS1.2 ciphersuite TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA
+pub static TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA: Tls12CbcCipherSuite = Tls12CbcCipherSuite {
+    suite: CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+    kx: KeyExchangeAlgorithm::ECDHE,
+    enc_key_len: 16,
+    mac_algorithm: ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+    hmac_algorithm: ring::hmac::HMAC_SHA256,
+};
+
+/// The TLS1.2 ciphersuite TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA
+pub static TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA: Tls12CbcCipherSuite = Tls12CbcCipherSuite {
+    suite: CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+    kx: KeyExchangeAlgorithm::ECDHE,
+    enc_key_len: 32,
+    mac_algorithm: ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+    hmac_algorithm: ring::hmac::HMAC_SHA256,
+};
+
+/// The TLS1.2 ciphersuite TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256
+pub static TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256: Tls12CbcCipherSuite = Tls12CbcCipherSuite {
+    suite: CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256,
+    kx: KeyExchangeAlgorithm::ECDHE,
+    enc_key_len: 16,
+    mac_algorithm: ring::hmac::HMAC_SHA256,
+    hmac_algorithm: ring::hmac::HMAC_SHA256,
+};
+
+/// All AES-CBC cipher suites which can be used to protect records.
+pub static ALL_CBC_CIPHER_SUITES: &[&Tls12CbcCipherSuite] = &[
+    &TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA,
+    &TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA,
+    &TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256,
+];
+
 static TLS12_ECDSA_SCHEMES: &[SignatureScheme] = &[
     SignatureScheme::ED25519,
     SignatureScheme::ECDSA_NISTP521_SHA512,
@@ -198,6 +235,212 @@ impl fmt::Debug for Tls12CipherSuite {
     }
 }
 
+/// An AES-CBC cipher suite which MACs records with HMAC. In contrast to [`Tls12CipherSuite`],
+/// these suites are never negotiated by rustls. They are only used to protect records of SSLv3 up
+/// to TLS 1.2, therefore the `version` is passed explicitly when deriving keys.
+pub struct Tls12CbcCipherSuite {
+    pub suite: CipherSuite,
+    /// How to exchange/agree keys.
+    pub kx: KeyExchangeAlgorithm,
+    /// The length of the AES key, which selects between AES-128 and AES-256
+    pub enc_key_len: usize,
+    /// The HMAC which authenticates the records
+    pub mac_algorithm: ring::hmac::Algorithm,
+    /// The HMAC of the PRF. Only TLS 1.2 uses it, older versions have a fixed PRF.
+    pub hmac_algorithm: ring::hmac::Algorithm,
+}
+
+/// The keys with which one side protects the records it writes.
+#[derive(Clone, Debug)]
+pub struct CbcWriteKeys {
+    /// The version for which the keys are derived. It selects the MAC construction and whether
+    /// records carry an explicit IV.
+    pub version: ProtocolVersion,
+    pub mac_algorithm: ring::hmac::Algorithm,
+    pub mac_key: Vec<u8>,
+    pub enc_key: Vec<u8>,
+    /// Only used by SSLv3 and TLS 1.0, later versions have explicit IVs
+    pub iv: Vec<u8>,
+}
+
+impl CbcWriteKeys {
+    /// Creates a `MessageEncrypter` which protects records with these keys. If
+    /// `encrypt_then_mac` is set, then the records are protected as negotiated by the
+    /// encrypt_then_mac extension.
+    pub fn encrypter(&self, encrypt_then_mac: bool) -> Box<dyn MessageEncrypter> {
+        cbc_encrypter(
+            self.version,
+            self.mac_algorithm,
+            &self.enc_key,
+            &self.mac_key,
+            &self.iv,
+            encrypt_then_mac,
+        )
+    }
+
+    /// Computes the MAC over the `payload` of a record with type `typ`.
+    pub fn mac(&self, seq: u64, typ: ContentType, payload: &[u8]) -> Vec<u8> {
+        make_cbc_mac(
+            self.version,
+            self.mac_algorithm,
+            &self.mac_key,
+            seq,
+            typ,
+            payload,
+        )
+    }
+
+    /// Encrypts a `fragment` which already ends with the padding, see [`cbc_encrypt_fragment`].
+    pub fn encrypt_fragment(&self, seq: u64, fragment: &[u8]) -> Result<Vec<u8>, Error> {
+        cbc_encrypt_fragment(self.version, &self.enc_key, &self.iv, seq, fragment)
+    }
+}
+
+impl Tls12CbcCipherSuite {
+    /// The length of the IVs in the key block, which equals the AES block size.
+    const IV_LEN: usize = 16;
+
+    /// Looks up the implementation of `suite`.
+    pub fn lookup(suite: CipherSuite) -> Option<&'static Tls12CbcCipherSuite> {
+        ALL_CBC_CIPHER_SUITES
+            .iter()
+            .find(|cbc_suite| cbc_suite.suite == suite)
+            .copied()
+    }
+
+    /// Derives the keys with which `side` writes records from the `master_secret`. The key
+    /// derivation depends on the `version`.
+    pub fn write_keys(
+        &self,
+        version: ProtocolVersion,
+        master_secret: &[u8],
+        randoms: &ConnectionRandoms,
+        side: Side,
+    ) -> CbcWriteKeys {
+        let mac_key_len = self.mac_algorithm.digest_algorithm().output_len;
+        let enc_key_len = self.enc_key_len;
+
+        // The IVs are only used by SSLv3 and TLS 1.0. They are at the end of the key block such
+        // that deriving them anyway does not change the other keys.
+        let mut key_block = vec![0u8; (mac_key_len + enc_key_len + Self::IV_LEN) * 2];
+        let randoms = join_randoms(&randoms.server, &randoms.client);
+        match version {
+            ProtocolVersion::SSLv3 => prf::ssl3_prf(&mut key_block, master_secret, &randoms),
+            ProtocolVersion::TLSv1_0 | ProtocolVersion::TLSv1_1 => {
+                prf::prf_legacy(&mut key_block, master_secret, b"key expansion", &randoms)
+            }
+            _ => prf::prf(
+                &mut key_block,
+                self.hmac_algorithm,
+                master_secret,
+                b"key expansion",
+                &randoms,
+            ),
+        }
+
+        let (client_mac_key, rest) = key_block.split_at(mac_key_len);
+        let (server_mac_key, rest) = rest.split_at(mac_key_len);
+        let (client_write_key, rest) = rest.split_at(enc_key_len);
+        let (server_write_key, rest) = rest.split_at(enc_key_len);
+        let (client_write_iv, server_write_iv) = rest.split_at(Self::IV_LEN);
+
+        let (mac_key, enc_key, iv) = match side {
+            Side::Client => (client_mac_key, client_write_key, client_write_iv),
+            Side::Server => (server_mac_key, server_write_key, server_write_iv),
+        };
+
+        CbcWriteKeys {
+            version,
+            mac_algorithm: self.mac_algorithm,
+            mac_key: mac_key.to_vec(),
+            enc_key: enc_key.to_vec(),
+            iv: iv.to_vec(),
+        }
+    }
+
+    /// Derives the master secret from the `premaster_secret`. The PRF depends on the `version`.
+    pub fn master_secret(
+        &self,
+        version: ProtocolVersion,
+        premaster_secret: &[u8],
+        randoms: &ConnectionRandoms,
+    ) -> [u8; 48] {
+        let mut master_secret = [0u8; 48];
+        let randoms = join_randoms(&randoms.client, &randoms.server);
+        match version {
+            ProtocolVersion::SSLv3 => prf::ssl3_prf(&mut master_secret, premaster_secret, &randoms),
+            ProtocolVersion::TLSv1_0 | ProtocolVersion::TLSv1_1 => prf::prf_legacy(
+                &mut master_secret,
+                premaster_secret,
+                b"master secret",
+                &randoms,
+            ),
+            _ => prf::prf(
+                &mut master_secret,
+                self.hmac_algorithm,
+                premaster_secret,
+                b"master secret",
+                &randoms,
+            ),
+        }
+        master_secret
+    }
+
+    /// Computes the verify_data of the Finished of `side`. In contrast to
+    /// [`ConnectionSecrets::client_verify_data`], the `handshake` messages are passed unhashed,
+    /// because versions before TLS 1.2 hash them with both MD5 and SHA-1.
+    pub fn verify_data(
+        &self,
+        version: ProtocolVersion,
+        master_secret: &[u8],
+        handshake: &[u8],
+        side: Side,
+    ) -> Vec<u8> {
+        if version == ProtocolVersion::SSLv3 {
+            let sender: &[u8] = match side {
+                Side::Client => b"CLNT",
+                Side::Server => b"SRVR",
+            };
+            return prf::ssl3_finished(master_secret, handshake, sender);
+        }
+
+        let label: &[u8] = match side {
+            Side::Client => b"client finished",
+            Side::Server => b"server finished",
+        };
+        let mut verify_data = vec![0u8; 12];
+        match version {
+            ProtocolVersion::TLSv1_0 | ProtocolVersion::TLSv1_1 => {
+                let mut seed = md5::compute(handshake).0.to_vec();
+                seed.extend_from_slice(
+                    digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, handshake).as_ref(),
+                );
+                prf::prf_legacy(&mut verify_data, master_secret, label, &seed);
+            }
+            _ => {
+                let hash = digest::digest(self.hmac_algorithm.digest_algorithm(), handshake);
+                prf::prf(
+                    &mut verify_data,
+                    self.hmac_algorithm,
+                    master_secret,
+                    label,
+                    hash.as_ref(),
+                );
+            }
+        }
+        verify_data
+    }
+}
+
+impl fmt::Debug for Tls12CbcCipherSuite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tls12CbcCipherSuite")
+            .field("suite", &self.suite)
+            .field("enc_key_len", &self.enc_key_len)
+            .finish()
+    }
+}
+
 /// TLS1.2 per-connection keying material
 pub struct ConnectionSecrets {
     pub randoms: ConnectionRandoms,
@@ -213,6 +456,21 @@ impl ConnectionSecrets {
         randoms: ConnectionRandoms,
         suite: &'static Tls12CipherSuite,
     ) -> Result<Self, Error> {
+        kx.complete(peer_pub_key, |secret| {
+            Ok(Self::from_premaster_secret(
+                secret, ems_seed, randoms, suite,
+            ))
+        })
+    }
+
+    /// Derives the master secret from a `premaster_secret` which was established by any key
+    /// exchange, e.g. by finite-field Diffie-Hellman or by RSA key transport.
+    pub fn from_premaster_secret(
+        premaster_secret: &[u8],
+        ems_seed: Option<Digest>,
+        randoms: ConnectionRandoms,
+        suite: &'static Tls12CipherSuite,
+    ) -> Self {
         let mut ret = Self {
             randoms,
             suite,
@@ -227,18 +485,15 @@ impl ConnectionSecrets {
             ),
         };
 
-        kx.complete(peer_pub_key, |secret| {
-            prf::prf(
-                &mut ret.master_secret,
-                suite.hmac_algorithm,
-                secret,
-                label.as_bytes(),
-                seed.as_ref(),
-            );
-            Ok(())
-        })?;
-
-        Ok(ret)
+        prf::prf(
+            &mut ret.master_secret,
+            suite.hmac_algorithm,
+            premaster_secret,
+            label.as_bytes(),
+            seed.as_ref(),
+        );
+
+        ret
     }
 
     pub fn new_resume(
@@ -1,4 +1,4 @@
-use ring::hmac;
+use ring::{digest, hmac};
 
 fn concat_sign(key: &hmac::Key, a: &[u8], b: &[u8]) -> hmac::Tag {
     let mut ctx = hmac::Context::with_key(key);
@@ -35,6 +35,112 @@ pub fn prf(out: &mut [u8], alg: hmac::Algorithm, secret: &[u8], label: &[u8], se
     p(out, alg, secret, &joined_seed);
 }
 
+/// HMAC-MD5, which is not implemented by ring.
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    const MD5_BLOCK_LEN: usize = 64;
+
+    let mut block_key = [0u8; MD5_BLOCK_LEN];
+    if key.len() > MD5_BLOCK_LEN {
+        block_key[..16].copy_from_slice(&md5::compute(key).0);
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = md5::Context::new();
+    inner.consume(block_key.map(|byte| byte ^ 0x36));
+    for part in parts {
+        inner.consume(part);
+    }
+
+    let mut outer = md5::Context::new();
+    outer.consume(block_key.map(|byte| byte ^ 0x5c));
+    outer.consume(inner.compute().0);
+    outer.compute().0
+}
+
+fn p_md5(out: &mut [u8], secret: &[u8], seed: &[u8]) {
+    // A(1)
+    let mut current_a = hmac_md5(secret, &[seed]);
+    for chunk in out.chunks_mut(16) {
+        // P_MD5[i] = HMAC_MD5(secret, A(i) + seed)
+        let p_term = hmac_md5(secret, &[&current_a, seed]);
+        chunk.copy_from_slice(&p_term[..chunk.len()]);
+
+        // A(i+1) = HMAC_MD5(secret, A(i))
+        current_a = hmac_md5(secret, &[&current_a]);
+    }
+}
+
+/// The PRF of TLS 1.0 and TLS 1.1, which XORs P_MD5 and P_SHA1 keyed with the two halves of the
+/// secret.
+pub fn prf_legacy(out: &mut [u8], secret: &[u8], label: &[u8], seed: &[u8]) {
+    let joined_seed = concat(label, seed);
+    // The halves overlap by one byte if the length of the secret is odd
+    let half_len = (secret.len() + 1) / 2;
+
+    p_md5(out, &secret[..half_len], &joined_seed);
+
+    let mut sha1_out = vec![0u8; out.len()];
+    p(
+        &mut sha1_out,
+        hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        &secret[secret.len() - half_len..],
+        &joined_seed,
+    );
+    out.iter_mut()
+        .zip(sha1_out)
+        .for_each(|(out, sha1)| *out ^= sha1);
+}
+
+/// The key derivation of SSLv3 which derives the master secret as well as the key block. Each
+/// block of 16 bytes is MD5(secret + SHA1(salt + secret + seed)) with the salts "A", "BB", "CCC", ...
+pub fn ssl3_prf(out: &mut [u8], secret: &[u8], seed: &[u8]) {
+    for (i, chunk) in out.chunks_mut(16).enumerate() {
+        let salt = vec![b'A' + i as u8; i + 1];
+
+        let mut sha1 = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+        sha1.update(&salt);
+        sha1.update(secret);
+        sha1.update(seed);
+
+        let mut md5 = md5::Context::new();
+        md5.consume(secret);
+        md5.consume(sha1.finish());
+        chunk.copy_from_slice(&md5.compute().0[..chunk.len()]);
+    }
+}
+
+/// The verify_data of an SSLv3 Finished, which concatenates an MD5 and a SHA-1 based MAC over the
+/// `handshake` messages and the `sender`.
+pub fn ssl3_finished(master_secret: &[u8], handshake: &[u8], sender: &[u8]) -> Vec<u8> {
+    const MD5_PAD_LEN: usize = 48;
+    const SHA1_PAD_LEN: usize = 40;
+
+    let mut inner_md5 = md5::Context::new();
+    inner_md5.consume(handshake);
+    inner_md5.consume(sender);
+    inner_md5.consume(master_secret);
+    inner_md5.consume([0x36; MD5_PAD_LEN]);
+    let mut outer_md5 = md5::Context::new();
+    outer_md5.consume(master_secret);
+    outer_md5.consume([0x5c; MD5_PAD_LEN]);
+    outer_md5.consume(inner_md5.compute().0);
+
+    let mut inner_sha1 = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+    inner_sha1.update(handshake);
+    inner_sha1.update(sender);
+    inner_sha1.update(master_secret);
+    inner_sha1.update(&[0x36; SHA1_PAD_LEN]);
+    let mut outer_sha1 = digest::Context::new(&digest::SHA1_FOR_LEGACY_USE_ONLY);
+    outer_sha1.update(master_secret);
+    outer_sha1.update(&[0x5c; SHA1_PAD_LEN]);
+    outer_sha1.update(inner_sha1.finish().as_ref());
+
+    let mut verify_data = outer_md5.compute().0.to_vec();
+    verify_data.extend_from_slice(outer_sha1.finish().as_ref());
+    verify_data
+}
+
 #[cfg(test)]
 mod tests {
     use ring::hmac::{HMAC_SHA256, HMAC_SHA512};
@@ -64,4 +170,34 @@ mod tests {
         assert_eq!(expect.len(), output.len());
         assert_eq!(expect.to_vec(), output.to_vec());
     }
+
+    #[test]
+    fn check_legacy() {
+        let secret = b"\x9b\xbe\x43\x6b\xa9\x40\xf0\x17\xb1\x76\x52\x84\x9a\x71\xdb\x35";
+        let seed = b"\xa0\xba\x9f\x93\x6c\xda\x31\x18\x27\xa6\xf7\x96\xff\xd5\x19\x8c";
+        let label = b"test label";
+        let expect = hex::decode(
+            "661740e6f98bc901efd2738502a71c03f76dd2f86298549b1148eff06714cf0f\
+            6b7c532cd8c69f1530e0bb680eec34c4",
+        )
+        .unwrap();
+        let mut output = [0u8; 48];
+
+        super::prf_legacy(&mut output, secret, label, seed);
+        assert_eq!(expect, output.to_vec());
+    }
+
+    #[test]
+    fn check_ssl3() {
+        let secret = b"\x9b\xbe\x43\x6b\xa9\x40\xf0\x17\xb1\x76\x52\x84\x9a\x71\xdb\x35";
+        let seed = b"\xa0\xba\x9f\x93\x6c\xda\x31\x18\x27\xa6\xf7\x96\xff\xd5\x19\x8c";
+        let expect = hex::decode(
+            "808d751749e3199a086430d9a22f92cf9b3d7bc8f3e2bb5a649a006cc0c8671bd4905c378a85312d",
+        )
+        .unwrap();
+        let mut output = [0u8; 40];
+
+        super::ssl3_prf(&mut output, secret, seed);
+        assert_eq!(expect, output.to_vec());
+    }
 }
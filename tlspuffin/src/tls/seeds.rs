@@ -16,9 +16,12 @@ use crate::{
     query::TlsQueryMatcher,
     tls::{
         fn_impl::*,
-        rustls::msgs::{
-            enums::{CipherSuite, Compression, HandshakeType, ProtocolVersion},
-            handshake::{Random, ServerExtension, SessionID},
+        rustls::{
+            key::Certificate,
+            msgs::{
+                enums::{CipherSuite, Compression, HandshakeType, ProtocolVersion},
+                handshake::{Random, ServerExtension, SessionID},
+            },
         },
     },
 };
@@ -579,6 +582,51 @@ pub fn seed_successful12(client: AgentName, server: AgentName) -> Trace<TlsQuery
     }
 }
 
+/// Changes the [`TLSVersion`] of all agents. The full TLS 1.2 handshakes forward the messages
+/// between the agents, therefore they also work for TLS 1.1 and TLS 1.0.
+fn with_tls_version(
+    mut trace: Trace<TlsQueryMatcher>,
+    tls_version: TLSVersion,
+) -> Trace<TlsQueryMatcher> {
+    for descriptor in &mut trace.descriptors {
+        descriptor.tls_version = tls_version;
+    }
+
+    trace
+}
+
+pub fn seed_successful11(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    with_tls_version(seed_successful12(client, server), TLSVersion::V1_1)
+}
+
+pub fn seed_successful11_with_tickets(
+    client: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    with_tls_version(
+        seed_successful12_with_tickets(client, server),
+        TLSVersion::V1_1,
+    )
+}
+
+pub fn seed_successful10(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    with_tls_version(seed_successful12(client, server), TLSVersion::V1_0)
+}
+
+pub fn seed_successful10_with_tickets(
+    client: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    with_tls_version(
+        seed_successful12_with_tickets(client, server),
+        TLSVersion::V1_0,
+    )
+}
+
+pub fn seed_successful_ssl3(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
+    with_tls_version(seed_successful12(client, server), TLSVersion::SSL3)
+}
+
 pub fn seed_successful_with_ccs(client: AgentName, server: AgentName) -> Trace<TlsQueryMatcher> {
     let mut trace = seed_successful(client, server);
 
@@ -645,6 +693,52 @@ pub fn seed_successful_with_tickets(
 }
 
 pub fn seed_server_attacker_full(client: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_server_attacker_full(client).0
+}
+
+/// A TLS 1.3 handshake with a client like [`seed_server_attacker_full`], but the DER of the
+/// certificate of the attacker is built with terms of the X.509 model. The TBSCertificate of
+/// [`ALICE_CERT`] is parsed and signed again, which yields the same self-signed certificate.
+///
+/// [`ALICE_CERT`]: crate::static_certs::ALICE_CERT
+pub fn seed_server_attacker_x509_certificate(client: AgentName) -> Trace<TlsQueryMatcher> {
+    let certificate = term! {
+        fn_x509_encode(
+            (fn_x509_sign_rsa(
+                (fn_x509_tbs_certificate(
+                    (fn_x509_parse(fn_alice_cert))
+                )),
+                fn_alice_key,
+                fn_rsa_pkcs1_signature_algorithm
+            ))
+        )
+    };
+
+    _seed_server_attacker_full_with_certificate(client, certificate).0
+}
+
+fn _seed_server_attacker_full(
+    client: AgentName,
+) -> (
+    Trace<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+) {
+    _seed_server_attacker_full_with_certificate(client, term! { fn_alice_cert })
+}
+
+/// Like [`_seed_server_attacker_full`], but sends the DER encoded `certificate`, which must
+/// belong to the key of Alice.
+fn _seed_server_attacker_full_with_certificate(
+    client: AgentName,
+    certificate: Term<TlsQueryMatcher>,
+) -> (
+    Trace<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+    Term<TlsQueryMatcher>,
+) {
     let curve = term! {
         fn_get_any_client_curve(
             ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))])
@@ -689,7 +783,7 @@ pub fn seed_server_attacker_full(client: AgentName) -> Trace<TlsQueryMatcher> {
             (fn_empty_bytes_vec),
             (fn_append_certificate_entry(
                 (fn_certificate_entry(
-                    fn_alice_cert
+                    (@certificate)
                 )),
               fn_empty_certificate_chain
             ))
@@ -824,9 +918,422 @@ pub fn seed_server_attacker_full(client: AgentName) -> Trace<TlsQueryMatcher> {
         ],
     };
 
+    let server_finished_transcript = term! {
+        fn_append_transcript(
+            (@certificate_verify_transcript),
+            (@server_finished) // plaintext Server Finished
+        )
+    };
+
+    (
+        trace,
+        server_hello_transcript,
+        server_finished_transcript,
+        curve,
+    )
+}
+
+/// Continues [`seed_server_attacker_full`] with a CertificateRequest after the handshake. The
+/// client, which is configured with a certificate, answers with its Certificate, CertificateVerify
+/// and Finished.
+pub fn seed_server_attacker_post_handshake_auth(client: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, server_hello_transcript, server_finished_transcript, curve) =
+        _seed_server_attacker_full(client);
+
+    for descriptor in &mut trace.descriptors {
+        descriptor.client_authentication = true;
+    }
+
+    let certificate_request = term! {
+        fn_certificate_request13(
+            fn_empty_bytes_vec,
+            (fn_cert_req_extensions_append(
+                fn_cert_req_extensions_new,
+                fn_signature_algorithm_cert_req_extension
+            ))
+        )
+    };
+
+    trace.steps.push(InputAction::new_step(
+        client,
+        term! {
+            fn_encrypt_application_updated(
+                (@certificate_request),
+                (@server_hello_transcript),
+                (@server_finished_transcript),
+                (fn_get_client_key_share(((client, 0)), (@curve))),
+                fn_no_psk,
+                (@curve),
+                fn_false,
+                fn_seq_0, // no KeyUpdate
+                fn_seq_0  // sequence 0
+            )
+        },
+    ));
+    trace.steps.push(OutputAction::new_step(client));
+
     trace
 }
 
+/// A TLS 1.2 handshake with a client, which is followed by a secure renegotiation that the attacker
+/// initiates with an encrypted HelloRequest. The renegotiation_info of the second ServerHello is
+/// bound to the verify_data of both previous Finished messages.
+pub fn seed_server_attacker12_renegotiation(client: AgentName) -> Trace<TlsQueryMatcher> {
+    let client_random = term! {
+        ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))]/Random)
+    };
+
+    let server_hello = term! {
+          fn_server_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_empty_session_id,
+            fn_cipher_suite12,
+            fn_compression,
+            (fn_server_extensions_append(
+                fn_server_extensions_new,
+                // Enable Renegotiation
+                (fn_renegotiation_info_server_extension(fn_empty_bytes_vec))
+            ))
+        )
+    };
+
+    let certificate = term! {
+        fn_certificate(
+            (fn_append_certificate(
+                fn_new_certificates,
+                (fn_certificate_from_der(fn_alice_cert))
+            ))
+        )
+    };
+
+    let ecdhe_params = term! {
+        fn_ecdhe_params(
+            fn_named_group_secp384r1,
+            (fn_new_pubkey12(fn_named_group_secp384r1))
+        )
+    };
+
+    let server_key_exchange = term! {
+        fn_server_key_exchange_ecdhe(
+            (@ecdhe_params),
+            (fn_digitally_signed(
+                fn_rsa_pkcs1_signature_algorithm,
+                (fn_rsa_sign_ecdhe_params(
+                    (@client_random),
+                    fn_new_random,
+                    (@ecdhe_params),
+                    fn_alice_key,
+                    fn_rsa_pkcs1_signature_algorithm
+                ))
+            ))
+        )
+    };
+
+    let client_key_exchange = term! {
+        ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))])
+    };
+
+    let client_ecdh_pubkey = term! {
+        fn_decode_ec_pubkey12(
+            ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>)
+        )
+    };
+
+    let client_key_exchange_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                fn_new_transcript12,
+                                ((client, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello)))])
+                            )),
+                            (@server_hello)
+                        )),
+                        (@certificate)
+                    )),
+                    (@server_key_exchange)
+                )),
+                fn_server_hello_done
+            )),
+            (@client_key_exchange)
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_with_client_random(
+            (@client_random),
+            fn_new_random,
+            (@client_ecdh_pubkey),
+            (@client_key_exchange_transcript),
+            fn_named_group_secp384r1,
+            fn_true
+        )
+    };
+
+    let server_verify_data = term! {
+        fn_sign_transcript_with_client_random(
+            (@client_random),
+            fn_new_random,
+            (@client_ecdh_pubkey),
+            (fn_append_transcript(
+                (@client_key_exchange_transcript),
+                (fn_finished((@client_verify_data)))
+            )),
+            fn_named_group_secp384r1,
+            fn_false
+        )
+    };
+
+    // The renegotiation is encrypted with the keys of the previous handshake
+    let renegotiation_client_hello = term! {
+        fn_decrypt12_with_client_random(
+            ((client, 1)[Some(TlsQueryMatcher::Handshake(None))]), // encrypted ClientHello
+            (@client_random),
+            fn_new_random,
+            (@client_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_false,
+            fn_seq_1
+        )
+    };
+
+    let renegotiation_client_random = term! {
+        fn_get_random((@renegotiation_client_hello))
+    };
+
+    let renegotiation_server_hello = term! {
+          fn_server_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_empty_session_id,
+            fn_cipher_suite12,
+            fn_compression,
+            (fn_server_extensions_append(
+                fn_server_extensions_new,
+                // Bind the renegotiation to the previous handshake
+                (fn_renegotiation_info_server_extension(
+                    (fn_renegotiated_connection((@client_verify_data), (@server_verify_data)))
+                ))
+            ))
+        )
+    };
+
+    let renegotiation_server_key_exchange = term! {
+        fn_server_key_exchange_ecdhe(
+            (@ecdhe_params),
+            (fn_digitally_signed(
+                fn_rsa_pkcs1_signature_algorithm,
+                (fn_rsa_sign_ecdhe_params(
+                    (@renegotiation_client_random),
+                    fn_new_random,
+                    (@ecdhe_params),
+                    fn_alice_key,
+                    fn_rsa_pkcs1_signature_algorithm
+                ))
+            ))
+        )
+    };
+
+    let renegotiation_client_key_exchange = term! {
+        fn_decrypt12_with_client_random(
+            ((client, 2)[Some(TlsQueryMatcher::Handshake(None))]), // encrypted ClientKeyExchange
+            (@client_random),
+            fn_new_random,
+            (@client_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_false,
+            fn_seq_2
+        )
+    };
+
+    let renegotiation_client_ecdh_pubkey = term! {
+        fn_decode_ec_pubkey12(
+            (fn_get_key_exchange_data((@renegotiation_client_key_exchange)))
+        )
+    };
+
+    let renegotiation_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                fn_new_transcript12,
+                                (@renegotiation_client_hello)
+                            )),
+                            (@renegotiation_server_hello)
+                        )),
+                        (@certificate)
+                    )),
+                    (@renegotiation_server_key_exchange)
+                )),
+                fn_server_hello_done
+            )),
+            (@renegotiation_client_key_exchange)
+        )
+    };
+
+    let renegotiation_client_verify_data = term! {
+        fn_sign_transcript_with_client_random(
+            (@renegotiation_client_random),
+            fn_new_random,
+            (@renegotiation_client_ecdh_pubkey),
+            (@renegotiation_transcript),
+            fn_named_group_secp384r1,
+            fn_true
+        )
+    };
+
+    let renegotiation_server_verify_data = term! {
+        fn_sign_transcript_with_client_random(
+            (@renegotiation_client_random),
+            fn_new_random,
+            (@renegotiation_client_ecdh_pubkey),
+            (fn_append_transcript(
+                (@renegotiation_transcript),
+                (fn_finished((@renegotiation_client_verify_data)))
+            )),
+            fn_named_group_secp384r1,
+            fn_false
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: client,
+            tls_version: TLSVersion::V1_2,
+            typ: AgentType::Client,
+            renegotiation: true,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
+            OutputAction::new_step(client),
+            InputAction::new_step(client, server_hello),
+            InputAction::new_step(client, certificate.clone()),
+            InputAction::new_step(client, server_key_exchange),
+            InputAction::new_step(client, term! { fn_server_hello_done }),
+            InputAction::new_step(client, term! { fn_change_cipher_spec }),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        (fn_finished((@server_verify_data))),
+                        (@client_random),
+                        fn_new_random,
+                        (@client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_0
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        fn_hello_request,
+                        (@client_random),
+                        fn_new_random,
+                        (@client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_1
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        (@renegotiation_server_hello),
+                        (@client_random),
+                        fn_new_random,
+                        (@client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_2
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        (@certificate),
+                        (@client_random),
+                        fn_new_random,
+                        (@client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_3
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        (@renegotiation_server_key_exchange),
+                        (@client_random),
+                        fn_new_random,
+                        (@client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_4
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        fn_server_hello_done,
+                        (@client_random),
+                        fn_new_random,
+                        (@client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_5
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        fn_change_cipher_spec,
+                        (@client_random),
+                        fn_new_random,
+                        (@client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_6
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_encrypt12_with_client_random(
+                        (fn_finished((@renegotiation_server_verify_data))),
+                        (@renegotiation_client_random),
+                        fn_new_random,
+                        (@renegotiation_client_ecdh_pubkey),
+                        fn_named_group_secp384r1,
+                        fn_false,
+                        fn_seq_0
+                    )
+                },
+            ),
+        ],
+    }
+}
+
 pub fn seed_client_attacker_auth(server: AgentName) -> Trace<TlsQueryMatcher> {
     let client_hello = term! {
           fn_client_hello(
@@ -1416,11 +1923,152 @@ pub fn seed_client_attacker(server: AgentName) -> Trace<TlsQueryMatcher> {
     trace
 }
 
-pub fn seed_client_attacker12(server: AgentName) -> Trace<TlsQueryMatcher> {
-    _seed_client_attacker12(server).0
-}
+/// Continues [`seed_client_attacker`] with repeated KeyUpdates. The first two request an update
+/// from the server, the last one does not. Each KeyUpdate is encrypted under the client
+/// application traffic secret of the previous generation.
+pub fn seed_client_attacker_key_update(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let mut trace = seed_client_attacker(server);
+
+    trace.steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt_application(
+                fn_key_update,
+                (fn_server_hello_transcript(((server, 0)))),
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_get_server_key_share(((server, 0)))),
+                fn_no_psk,
+                fn_named_group_secp384r1,
+                fn_seq_0  // sequence 0
+            )
+        },
+    ));
+    trace.steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt_application_updated(
+                fn_key_update,
+                (fn_server_hello_transcript(((server, 0)))),
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_get_server_key_share(((server, 0)))),
+                fn_no_psk,
+                fn_named_group_secp384r1,
+                fn_true,
+                fn_seq_1, // first KeyUpdate
+                fn_seq_0  // sequence 0
+            )
+        },
+    ));
+    trace.steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt_application_updated(
+                fn_key_update_not_requested,
+                (fn_server_hello_transcript(((server, 0)))),
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_get_server_key_share(((server, 0)))),
+                fn_no_psk,
+                fn_named_group_secp384r1,
+                fn_true,
+                fn_seq_2, // second KeyUpdate
+                fn_seq_0  // sequence 0
+            )
+        },
+    ));
+
+    trace
+}
+
+/// Like [`seed_client_attacker`], but the ClientHello is encrypted using ECH to the ECH key of the
+/// attacker. A server which is configured with [`ECH_PRIVATE_KEY`] accepts the ClientHelloInner,
+/// any other server rejects the ECH and continues with the ClientHelloOuter. In both cases the
+/// handshake succeeds as the transcripts are extracted from the server.
+pub fn seed_client_attacker_ech(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker_ech(server).0
+}
+
+/// Returns the trace of [`seed_client_attacker_ech`] and the ClientHelloInner
+pub fn _seed_client_attacker_ech(
+    server: AgentName,
+) -> (Trace<TlsQueryMatcher>, Term<TlsQueryMatcher>) {
+    let extensions = term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        fn_client_extensions_new,
+                        (fn_support_group_extension(fn_named_group_secp384r1))
+                    )),
+                    fn_signature_algorithm_extension
+                )),
+                (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+            )),
+            fn_supported_versions13_extension
+        )
+    };
+
+    let client_hello_outer = term! {
+        fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (@extensions)
+        )
+    };
+
+    let client_hello_inner = term! {
+        fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (@extensions),
+                fn_ech_inner_extension
+            ))
+        )
+    };
+
+    let mut trace = seed_client_attacker(server);
+    trace.steps[0] = InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt_client_hello(
+                (@client_hello_outer),
+                (@client_hello_inner),
+                fn_new_ech_config
+            )
+        },
+    );
+
+    (trace, client_hello_inner)
+}
+
+pub fn seed_client_attacker12(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker12(server).0
+}
 
 fn _seed_client_attacker12(server: AgentName) -> (Trace<TlsQueryMatcher>, Term<TlsQueryMatcher>) {
+    // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+    _seed_client_attacker12_with_cipher_suite(server, term! { fn_cipher_suite12 })
+}
+
+/// Like [`_seed_client_attacker12`], but offers only the `cipher_suite`. The Finished of the
+/// attacker is encrypted using AES-GCM, therefore the last step has to be replaced for other
+/// cipher suites.
+fn _seed_client_attacker12_with_cipher_suite(
+    server: AgentName,
+    cipher_suite: Term<TlsQueryMatcher>,
+) -> (Trace<TlsQueryMatcher>, Term<TlsQueryMatcher>) {
     let client_hello = term! {
           fn_client_hello(
             fn_protocol_version12,
@@ -1428,8 +2076,7 @@ fn _seed_client_attacker12(server: AgentName) -> (Trace<TlsQueryMatcher>, Term<T
             fn_new_session_id,
             (fn_append_cipher_suite(
                 (fn_new_cipher_suites()),
-                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
-                fn_cipher_suite12
+                (@cipher_suite)
             )),
             fn_compressions,
             (fn_client_extensions_append(
@@ -1529,33 +2176,798 @@ fn _seed_client_attacker12(server: AgentName) -> (Trace<TlsQueryMatcher>, Term<T
                     recipe: client_key_exchange,
                 }),
             },
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! { fn_change_cipher_spec },
-                }),
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! { fn_change_cipher_spec },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt12(
+                            (fn_finished((@client_verify_data))),
+                            ((server, 0)),
+                            (fn_decode_ecdh_pubkey(
+                                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                            )),
+                            fn_named_group_secp384r1,
+                            fn_true,
+                            fn_seq_0
+                        )
+                    },
+                }),
+            },
+        ],
+    };
+
+    (trace, client_verify_data)
+}
+
+/// Like [`seed_client_attacker12`], but the handshake messages of the attacker are fragmented. The
+/// ClientHello is split into records of 16 bytes, of which the last one is split again, and the
+/// ClientKeyExchange is split within its handshake header. This exercises the reassembly of
+/// handshake messages.
+pub fn seed_client_attacker12_fragmented(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, _) = _seed_client_attacker12(server);
+
+    if let Action::Input(input) = &mut trace.steps[0].action {
+        let client_hello = input.recipe.clone();
+        input.recipe = term! {
+            fn_split_last_record(
+                (fn_fragment_message((@client_hello), fn_seq_16)),
+                fn_seq_1
+            )
+        };
+    }
+
+    if let Action::Input(input) = &mut trace.steps[1].action {
+        let client_key_exchange = input.recipe.clone();
+        input.recipe = term! {
+            fn_split_last_record(
+                (fn_append_record(fn_new_records, (@client_key_exchange))),
+                fn_seq_3
+            )
+        };
+    }
+
+    trace
+}
+
+/// Like [`seed_client_attacker12`], but negotiates TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA. The
+/// Finished of the attacker is encrypted with MAC-then-encrypt, where the MAC and the padding are
+/// part of the trace such that they can be mutated individually.
+pub fn seed_client_attacker12_cbc(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker12_cbc(server, term! { fn_seq_0 }, None)
+}
+
+/// The MAC of the Finished is computed for the `mac_sequence`, which is only correct if it is 0.
+/// If a `padding_value` is given, then all bytes of the padding are set to it.
+pub(crate) fn _seed_client_attacker12_cbc(
+    server: AgentName,
+    mac_sequence: Term<TlsQueryMatcher>,
+    padding_value: Option<Term<TlsQueryMatcher>>,
+) -> Trace<TlsQueryMatcher> {
+    let (mut trace, client_verify_data) = _seed_client_attacker12_with_cipher_suite(
+        server,
+        term! { fn_cipher_suite12_aes_128_cbc_sha },
+    );
+
+    let client_finished = term! {
+        fn_finished((@client_verify_data))
+    };
+
+    let server_ecdh_pubkey = term! {
+        fn_decode_ecdh_pubkey(
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+        )
+    };
+
+    let keys = term! {
+        fn_cbc_write_keys(
+            fn_protocol_version12,
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_cipher_suite12_aes_128_cbc_sha,
+            fn_true
+        )
+    };
+
+    let mac = term! {
+        fn_cbc_mac12((@client_finished), (@keys), (@mac_sequence))
+    };
+
+    let padding = match padding_value {
+        Some(padding_value) => term! {
+            fn_cbc_padding_value((@client_finished), (@mac), (@padding_value))
+        },
+        None => term! {
+            fn_cbc_padding((@client_finished), (@mac))
+        },
+    };
+
+    trace.steps[3] = InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt12_cbc_raw(
+                (@client_finished),
+                (@mac),
+                (@padding),
+                (@keys),
+                fn_seq_0
+            )
+        },
+    );
+
+    trace
+}
+
+/// Like [`seed_client_attacker12_cbc`], but negotiates encrypt-then-MAC. The padding of the
+/// Finished is part of the trace, while its MAC is computed over the ciphertext.
+pub fn seed_client_attacker12_cbc_etm(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker_cbc(
+        server,
+        TLSVersion::V1_2,
+        term! { fn_protocol_version12 },
+        true,
+    )
+}
+
+/// Like [`seed_client_attacker12_cbc`], but for TLS 1.1. The Finished of the attacker is protected
+/// with [`fn_encrypt12_cbc`], which uses an explicit IV.
+pub fn seed_client_attacker11_cbc(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker_cbc(
+        server,
+        TLSVersion::V1_1,
+        term! { fn_protocol_version11 },
+        false,
+    )
+}
+
+/// Like [`seed_client_attacker12_cbc`], but for TLS 1.0. The Finished of the attacker is protected
+/// with [`fn_encrypt12_cbc`], which uses the IV of the key block.
+pub fn seed_client_attacker10_cbc(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker_cbc(
+        server,
+        TLSVersion::V1_0,
+        term! { fn_protocol_version10 },
+        false,
+    )
+}
+
+/// Completes a handshake with the `server` using TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA. The servers
+/// only accept records with the negotiated `version` once it is negotiated, therefore the headers
+/// of all records after the ClientHello are overridden. The Finished hashes the transcript
+/// depending on the `version`, see [`fn_sign_transcript_cbc`]. If `encrypt_then_mac` is set, then
+/// the ClientHello offers the encrypt_then_mac extension and the Finished is protected
+/// accordingly.
+fn _seed_client_attacker_cbc(
+    server: AgentName,
+    tls_version: TLSVersion,
+    version: Term<TlsQueryMatcher>,
+    encrypt_then_mac: bool,
+) -> Trace<TlsQueryMatcher> {
+    // The signature algorithms are ignored by versions before TLS 1.2
+    let extensions = term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        fn_client_extensions_new,
+                        (fn_support_group_extension(fn_named_group_secp384r1))
+                    )),
+                    fn_signature_algorithm_extension
+                )),
+                fn_ec_point_formats_extension
+            )),
+            (fn_renegotiation_info_extension(fn_empty_bytes_vec))
+        )
+    };
+    let extensions = if encrypt_then_mac {
+        term! {
+            fn_client_extensions_append(
+                (@extensions),
+                fn_encrypt_then_mac_extension
+            )
+        }
+    } else {
+        extensions
+    };
+
+    let client_hello = term! {
+          fn_client_hello(
+            (@version),
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite12_aes_128_cbc_sha
+            )),
+            fn_compressions,
+            (@extensions)
+        )
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_encode_ec_pubkey12(
+                (fn_new_pubkey12(fn_named_group_secp384r1))
+            ))
+        )
+    };
+
+    let transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                fn_new_transcript_cbc,
+                                (@client_hello)
+                            )),
+                            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))])
+                        )),
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::Certificate)))])
+                    )),
+                    ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))])
+                )),
+                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHelloDone)))])
+            )),
+            (@client_key_exchange)
+        )
+    };
+
+    let server_ecdh_pubkey = term! {
+        fn_decode_ecdh_pubkey(
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_cbc(
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            (@transcript),
+            fn_named_group_secp384r1,
+            fn_cipher_suite12_aes_128_cbc_sha,
+            (@version)
+        )
+    };
+
+    let keys = term! {
+        fn_cbc_write_keys(
+            (@version),
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_cipher_suite12_aes_128_cbc_sha,
+            fn_true
+        )
+    };
+
+    let finished = term! {
+        fn_finished((@client_verify_data))
+    };
+
+    let client_finished = if encrypt_then_mac {
+        term! {
+            fn_cbc_append_mac12(
+                (fn_encrypt12_cbc_raw(
+                    (@finished),
+                    fn_empty_bytes_vec,
+                    (fn_cbc_padding((@finished), fn_empty_bytes_vec)),
+                    (@keys),
+                    fn_seq_0
+                )),
+                (@keys),
+                fn_seq_0
+            )
+        }
+    } else {
+        term! {
+            fn_encrypt12_cbc((@finished), (@keys), fn_false, fn_seq_0)
+        }
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_server(server, tls_version)],
+        steps: vec![
+            InputAction::new_step(server, client_hello),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_record_version((fn_plaintext_record((@client_key_exchange))), (@version))
+                },
+            ),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_record_version((fn_plaintext_record(fn_change_cipher_spec)), (@version))
+                },
+            ),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_record_version((@client_finished), (@version))
+                },
+            ),
+        ],
+    }
+}
+
+/// Like [`seed_client_attacker12`], but negotiates TLS_RSA_WITH_AES_128_GCM_SHA256. The attacker
+/// pads a premaster secret of its choice with PKCS#1 v1.5 and encrypts it to the certificate of
+/// the server. The padding is part of the trace such that it can be mutated into malformed
+/// paddings.
+pub fn seed_client_attacker12_rsa(server: AgentName) -> Trace<TlsQueryMatcher> {
+    _seed_client_attacker12_rsa(
+        server,
+        term! { fn_pkcs1_encryption_header },
+        term! { fn_protocol_version12 },
+    )
+}
+
+/// The encrypted premaster secret is padded with the `pkcs1_header`. The Finished is computed
+/// from a premaster secret with the `finished_version`, which only matches the encrypted premaster
+/// secret for TLS 1.2.
+pub(crate) fn _seed_client_attacker12_rsa(
+    server: AgentName,
+    pkcs1_header: Term<TlsQueryMatcher>,
+    finished_version: Term<TlsQueryMatcher>,
+) -> Trace<TlsQueryMatcher> {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite12_rsa_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    fn_client_extensions_new,
+                    fn_signature_algorithm_extension
+                )),
+                // Enable Renegotiation
+                (fn_renegotiation_info_extension(fn_empty_bytes_vec))
+            ))
+        )
+    };
+
+    let premaster_secret = term! {
+        fn_new_premaster_secret(fn_protocol_version12)
+    };
+
+    let server_certificate = term! {
+        fn_get_first_certificate(
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::Certificate)))]/Vec<Certificate>)
+        )
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_encode_rsa_premaster_secret(
+                (fn_rsa_encrypt(
+                    (fn_pkcs1_pad_raw(
+                        (@premaster_secret),
+                        (@server_certificate),
+                        (@pkcs1_header),
+                        fn_true
+                    )),
+                    (@server_certificate)
+                ))
+            ))
+        )
+    };
+
+    let client_key_exchange_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            fn_new_transcript12,
+                            (@client_hello) // ClientHello
+                        )),
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+                    )),
+                    ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
+                )),
+                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
+            )),
+            (@client_key_exchange)
+        )
+    };
+
+    let finished_premaster_secret = term! {
+        fn_new_premaster_secret((@finished_version))
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_premaster(
+            ((server, 0)),
+            (@finished_premaster_secret),
+            (@client_key_exchange_transcript)
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_2)],
+        steps: vec![
+            InputAction::new_step(server, client_hello),
+            InputAction::new_step(server, client_key_exchange),
+            InputAction::new_step(server, term! { fn_change_cipher_spec }),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_encrypt12_premaster(
+                        (fn_finished((@client_verify_data))),
+                        ((server, 0)),
+                        (@finished_premaster_secret),
+                        fn_true,
+                        fn_seq_0
+                    )
+                },
+            ),
+        ],
+    }
+}
+
+/// Like [`seed_client_attacker12`], but negotiates TLS_DHE_RSA_WITH_AES_128_GCM_SHA256. The
+/// attacker computes its public key and the premaster secret in the finite-field group which the
+/// server chose in its ServerKeyExchange.
+pub fn seed_client_attacker12_dhe(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite12_dhe_rsa_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    fn_client_extensions_new,
+                    fn_signature_algorithm_extension
+                )),
+                // Enable Renegotiation
+                (fn_renegotiation_info_extension(fn_empty_bytes_vec))
+            ))
+        )
+    };
+
+    let server_dhe_params = term! {
+        fn_decode_dhe_params(
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerDHParams
+        )
+    };
+
+    let dh_p = term! {
+        fn_get_dhe_p((@server_dhe_params))
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_encode_dh_pubkey(
+                (fn_new_dhe_pubkey(
+                    (@dh_p),
+                    (fn_get_dhe_g((@server_dhe_params)))
+                ))
+            ))
+        )
+    };
+
+    let premaster_secret = term! {
+        fn_dhe_premaster_secret(
+            (@dh_p),
+            (fn_get_dhe_pubkey((@server_dhe_params)))
+        )
+    };
+
+    let client_key_exchange_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                fn_new_transcript12,
+                                (@client_hello) // ClientHello
+                            )),
+                            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+                        )),
+                        ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
+                    )),
+                    ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]) // ServerKeyExchange
+                )),
+                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
+            )),
+            (@client_key_exchange)
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_premaster(
+            ((server, 0)),
+            (@premaster_secret),
+            (@client_key_exchange_transcript)
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_2)],
+        steps: vec![
+            InputAction::new_step(server, client_hello),
+            InputAction::new_step(server, client_key_exchange),
+            InputAction::new_step(server, term! { fn_change_cipher_spec }),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_encrypt12_premaster(
+                        (fn_finished((@client_verify_data))),
+                        ((server, 0)),
+                        (@premaster_secret),
+                        fn_true,
+                        fn_seq_0
+                    )
+                },
+            ),
+        ],
+    }
+}
+
+/// Continues [`seed_client_attacker12`] with a secure renegotiation which is initiated by the
+/// client. The renegotiation_info of the second ClientHello is bound to the verify_data of the
+/// previous Finished of the client. The handshake messages of the renegotiation are protected by
+/// the keys of the previous handshake until the ChangeCipherSpec.
+pub fn seed_client_attacker12_renegotiation(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, client_verify_data) = _seed_client_attacker12(server);
+    for descriptor in &mut trace.descriptors {
+        descriptor.renegotiation = true;
+    }
+
+    let server_ecdh_pubkey = term! {
+        fn_decode_ecdh_pubkey(
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+        )
+    };
+
+    let extensions = _renegotiation_client_extensions12();
+    let renegotiation_client_hello = _renegotiation_client_hello12(term! {
+        fn_client_extensions_append(
+            (@extensions),
+            // Bind the renegotiation to the previous handshake
+            (fn_renegotiation_info_extension((@client_verify_data)))
+        )
+    });
+
+    // The messages of the server are encrypted with the keys of the previous handshake
+    let renegotiation_server_hello = term! {
+        fn_decrypt12_with_client_random(
+            ((server, 1)[Some(TlsQueryMatcher::Handshake(None))]), // encrypted ServerHello
+            fn_new_random,
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_1
+        )
+    };
+
+    let renegotiation_certificate = term! {
+        fn_decrypt12_with_client_random(
+            ((server, 2)[Some(TlsQueryMatcher::Handshake(None))]), // encrypted Certificate
+            fn_new_random,
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_2
+        )
+    };
+
+    let renegotiation_server_key_exchange = term! {
+        fn_decrypt12_with_client_random(
+            ((server, 3)[Some(TlsQueryMatcher::Handshake(None))]), // encrypted ServerKeyExchange
+            fn_new_random,
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_3
+        )
+    };
+
+    let renegotiation_server_hello_done = term! {
+        fn_decrypt12_with_client_random(
+            ((server, 4)[Some(TlsQueryMatcher::Handshake(None))]), // encrypted ServerHelloDone
+            fn_new_random,
+            ((server, 0)),
+            (@server_ecdh_pubkey),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_4
+        )
+    };
+
+    let renegotiation_server_random = term! {
+        fn_get_random((@renegotiation_server_hello))
+    };
+
+    let renegotiation_server_ecdh_pubkey = term! {
+        fn_decode_ecdh_pubkey(
+            (fn_get_key_exchange_data((@renegotiation_server_key_exchange)))
+        )
+    };
+
+    let renegotiation_client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_encode_ec_pubkey12(
+                (fn_new_pubkey12(fn_named_group_secp384r1))
+            ))
+        )
+    };
+
+    let renegotiation_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                fn_new_transcript12,
+                                (@renegotiation_client_hello)
+                            )),
+                            (@renegotiation_server_hello)
+                        )),
+                        (@renegotiation_certificate)
+                    )),
+                    (@renegotiation_server_key_exchange)
+                )),
+                (@renegotiation_server_hello_done)
+            )),
+            (@renegotiation_client_key_exchange)
+        )
+    };
+
+    let renegotiation_client_verify_data = term! {
+        fn_sign_transcript(
+            (@renegotiation_server_random),
+            (@renegotiation_server_ecdh_pubkey),
+            (@renegotiation_transcript),
+            fn_named_group_secp384r1
+        )
+    };
+
+    trace.steps.append(&mut vec![
+        InputAction::new_step(
+            server,
+            term! {
+                fn_encrypt12(
+                    (@renegotiation_client_hello),
+                    ((server, 0)),
+                    (@server_ecdh_pubkey),
+                    fn_named_group_secp384r1,
+                    fn_true,
+                    fn_seq_1
+                )
+            },
+        ),
+        InputAction::new_step(
+            server,
+            term! {
+                fn_encrypt12(
+                    (@renegotiation_client_key_exchange),
+                    ((server, 0)),
+                    (@server_ecdh_pubkey),
+                    fn_named_group_secp384r1,
+                    fn_true,
+                    fn_seq_2
+                )
+            },
+        ),
+        InputAction::new_step(
+            server,
+            term! {
+                fn_encrypt12(
+                    fn_change_cipher_spec,
+                    ((server, 0)),
+                    (@server_ecdh_pubkey),
+                    fn_named_group_secp384r1,
+                    fn_true,
+                    fn_seq_3
+                )
             },
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_encrypt12(
-                            (fn_finished((@client_verify_data))),
-                            ((server, 0)),
-                            (fn_decode_ecdh_pubkey(
-                                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
-                            )),
-                            fn_named_group_secp384r1,
-                            fn_true,
-                            fn_seq_0
-                        )
-                    },
-                }),
+        ),
+        InputAction::new_step(
+            server,
+            term! {
+                fn_encrypt12(
+                    (fn_finished((@renegotiation_client_verify_data))),
+                    (@renegotiation_server_random),
+                    (@renegotiation_server_ecdh_pubkey),
+                    fn_named_group_secp384r1,
+                    fn_true,
+                    fn_seq_0
+                )
             },
-        ],
-    };
+        ),
+    ]);
 
-    (trace, client_verify_data)
+    trace
+}
+
+/// Like [`seed_client_attacker12_renegotiation`], but the second ClientHello does not contain a
+/// renegotiation_info. Servers must abort such an insecure renegotiation after the previous
+/// handshake negotiated secure renegotiation (RFC 5746, Section 3.7).
+pub fn seed_client_attacker12_renegotiation_insecure(server: AgentName) -> Trace<TlsQueryMatcher> {
+    let (mut trace, _) = _seed_client_attacker12(server);
+    for descriptor in &mut trace.descriptors {
+        descriptor.renegotiation = true;
+    }
+
+    let renegotiation_client_hello =
+        _renegotiation_client_hello12(_renegotiation_client_extensions12());
+
+    trace.steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt12(
+                (@renegotiation_client_hello),
+                ((server, 0)),
+                (fn_decode_ecdh_pubkey(
+                    ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                )),
+                fn_named_group_secp384r1,
+                fn_true,
+                fn_seq_1
+            )
+        },
+    ));
+
+    trace
+}
+
+fn _renegotiation_client_extensions12() -> Term<TlsQueryMatcher> {
+    term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    fn_client_extensions_new,
+                    (fn_support_group_extension(fn_named_group_secp384r1))
+                )),
+                fn_signature_algorithm_extension
+            )),
+            fn_ec_point_formats_extension
+        )
+    }
+}
+
+fn _renegotiation_client_hello12(extensions: Term<TlsQueryMatcher>) -> Term<TlsQueryMatcher> {
+    term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (@extensions)
+        )
+    }
 }
 
 pub fn seed_cve_2021_3449(server: AgentName) -> Trace<TlsQueryMatcher> {
@@ -2375,7 +3787,237 @@ pub fn seed_session_resumption_dhe_full(
     let resumption_client_finished = term! {
         fn_finished(
             (fn_verify_data(
-                (@resumption_server_finished_transcript),
+                (@resumption_server_finished_transcript),
+                (@resumption_server_hello_transcript),
+                (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                (fn_psk((@psk))),
+                fn_named_group_secp384r1
+            ))
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @full_client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_handshake(
+                            (@resumption_client_finished),
+                            (@resumption_server_hello_transcript),
+                            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                            (fn_psk((@psk))),
+                            fn_named_group_secp384r1,
+                            fn_true,
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+            /*Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                         fn_encrypt_application(
+                            fn_alert_close_notify,
+                            (@resumption_server_hello_transcript),
+                            (@resumption_server_finished_transcript),
+                            (fn_get_server_key_share(((server, 0)))),
+                            (fn_psk((@psk))),
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },*/
+        ],
+    };
+
+    trace
+}
+
+/// Resumes the session of [`_seed_client_attacker_full`] and sends 0-RTT data. Depending on
+/// `late_ticket`, the ticket age is either within the tolerated window such that the server
+/// accepts the early data, or it is too old such that the server resumes the session, but rejects
+/// the early data.
+fn _seed_session_resumption_early_data(
+    initial_server: AgentName,
+    server: AgentName,
+    late_ticket: bool,
+) -> Trace<TlsQueryMatcher> {
+    let (
+        initial_handshake,
+        server_hello_transcript,
+        server_finished_transcript,
+        client_finished_transcript,
+    ) = _seed_client_attacker_full(initial_server);
+
+    let new_ticket_message = term! {
+        fn_decrypt_application(
+            ((initial_server, 4)[Some(TlsQueryMatcher::ApplicationData)]), // Ticket?
+            (@server_hello_transcript),
+            (@server_finished_transcript),
+            (fn_get_server_key_share(((initial_server, 0)))),
+            fn_no_psk,
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
+        )
+    };
+
+    let preshared_keys_extension = if late_ticket {
+        term! { fn_preshared_keys_extension_empty_binder_late((@new_ticket_message)) }
+    } else {
+        term! { fn_preshared_keys_extension_empty_binder((@new_ticket_message)) }
+    };
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                (fn_client_extensions_append(
+                                    (fn_client_extensions_append(
+                                        fn_client_extensions_new,
+                                        (fn_support_group_extension(fn_named_group_secp384r1))
+                                    )),
+                                    fn_signature_algorithm_extension
+                                )),
+                                fn_supported_versions13_extension
+                            )),
+                            (fn_key_share_deterministic_extension(fn_named_group_secp384r1))
+                        )),
+                        fn_psk_exchange_mode_dhe_ke_extension
+                    )),
+                    fn_early_data_extension
+                )),
+                // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
+                // must be last in client_hello, and initially empty until filled by fn_fill_binder
+                (@preshared_keys_extension)
+            ))
+        )
+    };
+
+    let psk = term! {
+        fn_derive_psk(
+            (@server_hello_transcript),
+            (@server_finished_transcript),
+            (@client_finished_transcript),
+            (fn_get_server_key_share(((initial_server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_get_ticket_nonce((@new_ticket_message))),
+            fn_named_group_secp384r1
+        )
+    };
+
+    let full_client_hello = term! {
+        fn_fill_binder(
+            (@client_hello),
+            (fn_derive_binder(
+                (@client_hello),
+                (@psk)
+            ))
+        )
+    };
+
+    let early_data = term! {
+        fn_encrypt_early_data(
+            (fn_application_data((fn_get_ticket_nonce((@new_ticket_message))))), // arbitrary data
+            (fn_append_transcript(
+                fn_new_transcript,
+                (@full_client_hello) // ClientHello
+            )),
+            (@psk),
+            fn_seq_0  // sequence 0
+        )
+    };
+
+    let resumption_server_hello_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                fn_new_transcript,
+                (@full_client_hello) // ClientHello
+            )),
+            ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+        )
+    };
+
+    let resumption_encrypted_extensions = term! {
+        fn_decrypt_handshake(
+            ((server, 0)[Some(TlsQueryMatcher::ApplicationData)]), // Encrypted Extensions
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_psk((@psk))),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_0  // sequence 0
+        )
+    };
+
+    let resumption_encrypted_extension_transcript = term! {
+        fn_append_transcript(
+            (@resumption_server_hello_transcript),
+            (@resumption_encrypted_extensions) // plaintext Encrypted Extensions
+        )
+    };
+
+    let resumption_server_finished = term! {
+        fn_decrypt_handshake(
+            ((server, 1)[Some(TlsQueryMatcher::ApplicationData)]), // Server Handshake Finished
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_psk((@psk))),
+            fn_named_group_secp384r1,
+            fn_true,
+            fn_seq_1 // sequence 1
+        )
+    };
+
+    let resumption_server_finished_transcript = term! {
+        fn_append_transcript(
+            (@resumption_encrypted_extension_transcript),
+            (@resumption_server_finished) // plaintext Server Handshake Finished
+        )
+    };
+
+    // The EndOfEarlyData is only sent if the server accepted the early data. It is part of the
+    // transcript of the client Finished.
+    let (client_finished_transcript, client_finished_sequence) = if late_ticket {
+        (resumption_server_finished_transcript, term! { fn_seq_0 })
+    } else {
+        (
+            term! {
+                fn_append_transcript(
+                    (@resumption_server_finished_transcript),
+                    fn_end_of_early_data
+                )
+            },
+            term! { fn_seq_1 },
+        )
+    };
+
+    let resumption_client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (@client_finished_transcript),
                 (@resumption_server_hello_transcript),
                 (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
                 (fn_psk((@psk))),
@@ -2384,53 +4026,88 @@ pub fn seed_session_resumption_dhe_full(
         )
     };
 
-    let trace = Trace {
-        prior_traces: vec![initial_handshake],
-        descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_3)],
-        steps: vec![
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        @full_client_hello
-                    },
-                }),
-            },
-            Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_encrypt_handshake(
-                            (@resumption_client_finished),
-                            (@resumption_server_hello_transcript),
-                            (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
-                            (fn_psk((@psk))),
-                            fn_named_group_secp384r1,
-                            fn_true,
-                            fn_seq_0  // sequence 0
-                        )
-                    },
-                }),
+    let mut steps = vec![
+        InputAction::new_step(server, full_client_hello),
+        InputAction::new_step(server, early_data),
+    ];
+
+    if !late_ticket {
+        steps.push(InputAction::new_step(
+            server,
+            term! {
+                fn_encrypt_handshake(
+                    fn_end_of_early_data,
+                    (@resumption_server_hello_transcript),
+                    (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                    (fn_psk((@psk))),
+                    fn_named_group_secp384r1,
+                    fn_true,
+                    fn_seq_0  // sequence 0
+                )
             },
-            /*Step {
-                agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                         fn_encrypt_application(
-                            fn_alert_close_notify,
-                            (@resumption_server_hello_transcript),
-                            (@resumption_server_finished_transcript),
-                            (fn_get_server_key_share(((server, 0)))),
-                            (fn_psk((@psk))),
-                            fn_seq_0  // sequence 0
-                        )
-                    },
-                }),
-            },*/
-        ],
-    };
+        ));
+    }
 
-    trace
+    steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_encrypt_handshake(
+                (@resumption_client_finished),
+                (@resumption_server_hello_transcript),
+                (fn_get_server_key_share(((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))]))),
+                (fn_psk((@psk))),
+                fn_named_group_secp384r1,
+                fn_true,
+                (@client_finished_sequence)
+            )
+        },
+    ));
+
+    Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor {
+            early_data: true,
+            ..AgentDescriptor::new_server(server, TLSVersion::V1_3)
+        }],
+        steps,
+    }
+}
+
+/// Session resumption with 0-RTT data which the server accepts
+pub fn seed_session_resumption_early_data(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    _seed_session_resumption_early_data(initial_server, server, false)
+}
+
+/// Session resumption with 0-RTT data which the server rejects because the ticket is too old
+pub fn seed_session_resumption_early_data_rejected(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    _seed_session_resumption_early_data(initial_server, server, true)
+}
+
+/// Replays the ClientHello and the 0-RTT data of [`seed_session_resumption_early_data`]. A server
+/// must not accept the early data a second time. The servers of the seed do not protect against
+/// replays, therefore the security policy is violated.
+pub fn seed_session_resumption_early_data_replay(
+    initial_server: AgentName,
+    server: AgentName,
+) -> Trace<TlsQueryMatcher> {
+    let replay_server = server.next();
+    let replay = _seed_session_resumption_early_data(initial_server, replay_server, false);
+
+    let mut steps = replay.steps;
+    // The client Finished can only be computed if the server resumed the session
+    steps.pop();
+
+    Trace {
+        prior_traces: vec![seed_session_resumption_early_data(initial_server, server)],
+        descriptors: replay.descriptors,
+        steps,
+    }
 }
 
 macro_rules! corpus {
@@ -2456,15 +4133,40 @@ pub fn create_corpus() -> Vec<(Trace<TlsQueryMatcher>, &'static str)> {
         seed_successful_with_tickets: cfg(feature = "tls13"),
         seed_successful12: cfg(not(feature = "tls12-session-resumption")),
         seed_successful12_with_tickets: cfg(feature = "tls12-session-resumption"),
+        seed_successful11: cfg(all(feature = "tls11", not(feature = "tls12-session-resumption"))),
+        seed_successful11_with_tickets: cfg(all(feature = "tls11", feature = "tls12-session-resumption")),
+        seed_successful10: cfg(all(feature = "tls10", not(feature = "tls12-session-resumption"))),
+        seed_successful10_with_tickets: cfg(all(feature = "tls10", feature = "tls12-session-resumption")),
+        seed_successful_ssl3: cfg(feature = "ssl3"),
         // Client Attackers
         seed_client_attacker: cfg(feature = "tls13"),
         seed_client_attacker_auth: cfg(all(feature = "tls13", feature = "client-authentication-transcript-extraction")),
+        seed_client_attacker_ech: cfg(feature = "tls13"),
+        seed_client_attacker_key_update: cfg(feature = "tls13"),
         seed_client_attacker12: cfg(feature = "tls13"),
+        seed_client_attacker12_cbc: cfg(feature = "tls12"),
+        seed_client_attacker12_cbc_etm: cfg(feature = "tls12"),
+        seed_client_attacker11_cbc: cfg(feature = "tls11"),
+        seed_client_attacker10_cbc: cfg(feature = "tls10"),
+        seed_client_attacker12_fragmented: cfg(feature = "tls12"),
+        seed_client_attacker12_renegotiation: cfg(feature = "tls12"),
+        seed_client_attacker12_renegotiation_insecure: cfg(feature = "tls12"),
+        // wolfSSL is built without static RSA cipher suites
+        seed_client_attacker12_rsa: cfg(all(feature = "tls12", feature = "openssl-binding")),
+        // Only OpenSSL and wolfSSL are configured with finite-field Diffie-Hellman parameters
+        seed_client_attacker12_dhe: cfg(all(feature = "tls12", any(feature = "openssl-binding", feature = "wolfssl-binding"))),
         // Session resumption
         seed_session_resumption_dhe: cfg(all(feature = "tls13", feature = "tls13-session-resumption")),
         seed_session_resumption_ke: cfg(all(feature = "tls13", feature = "tls13-session-resumption")),
+        seed_session_resumption_early_data: cfg(all(feature = "tls13", feature = "tls13-session-resumption")),
+        seed_session_resumption_early_data_rejected: cfg(all(feature = "tls13", feature = "tls13-session-resumption")),
+        seed_session_resumption_early_data_replay: cfg(all(feature = "tls13", feature = "tls13-session-resumption")),
         // Server Attackers
-        seed_server_attacker_full: cfg(feature = "tls13")
+        seed_server_attacker_full: cfg(feature = "tls13"),
+        seed_server_attacker_x509_certificate: cfg(feature = "tls13"),
+        seed_server_attacker12_renegotiation: cfg(feature = "tls12"),
+        // Post-handshake authentication is only enabled for OpenSSL clients
+        seed_server_attacker_post_handshake_auth: cfg(all(feature = "tls13", any(feature = "openssl111", feature = "openssl3xx")))
     )
 }
 
@@ -2480,11 +4182,21 @@ pub mod tests {
         },
         unistd::{fork, ForkResult},
     };
-    use puffin::{agent::AgentName, trace::Action};
+    use puffin::{agent::AgentName, algebra::error::FnError, trace::Action};
     use test_log::test;
 
     use super::{SeedHelper, *};
-    use crate::put_registry::{DEFAULT_PUT_FACTORY, TLS_PUT_REGISTRY};
+    use crate::{
+        put_registry::{DEFAULT_PUT_FACTORY, TLS_PUT_REGISTRY},
+        tls::rustls::{
+            hash_hs::HandshakeHash,
+            msgs::{
+                codec::Codec,
+                enums::NamedGroup,
+                message::{Message, MessagePayload},
+            },
+        },
+    };
 
     fn expect_crash<R>(mut func: R)
     where
@@ -2514,6 +4226,71 @@ pub mod tests {
         }
     }
 
+    fn evaluate<T: 'static>(
+        ctx: &TraceContext<TLSProtocolBehavior>,
+        term: &Term<TlsQueryMatcher>,
+    ) -> T {
+        *term.evaluate(ctx).unwrap().downcast::<T>().unwrap()
+    }
+
+    #[cfg(all(
+        feature = "claims",
+        any(feature = "openssl-binding", feature = "wolfssl-binding")
+    ))]
+    fn find_renegotiation(
+        ctx: &TraceContext<TLSProtocolBehavior>,
+        agent: AgentName,
+    ) -> Option<crate::claims::Renegotiation> {
+        use puffin::algebra::dynamic_function::TypeShape;
+
+        ctx.find_claim(agent, TypeShape::of::<crate::claims::Renegotiation>())
+            .map(|claim| *claim.downcast::<crate::claims::Renegotiation>().unwrap())
+    }
+
+    #[cfg(all(
+        feature = "claims",
+        any(feature = "openssl-binding", feature = "wolfssl-binding")
+    ))]
+    fn check_violation(ctx: &TraceContext<TLSProtocolBehavior>) -> Option<&'static str> {
+        use puffin::claims::SecurityViolationPolicy;
+
+        use crate::tls::violation::TlsSecurityViolationPolicy;
+
+        TlsSecurityViolationPolicy::check_violation(ctx.claims().deref_borrow().slice())
+    }
+
+    /// Decrypts the application data records which `agent` sent and returns the types of the
+    /// handshake messages in them. Records which fail to decrypt with `decrypt` for all
+    /// sequence numbers, like the ones under the handshake traffic secrets, are skipped.
+    fn decrypt_handshakes<F>(
+        ctx: &TraceContext<TLSProtocolBehavior>,
+        agent: AgentName,
+        decrypt: F,
+    ) -> Vec<HandshakeType>
+    where
+        F: Fn(&Message, u64) -> Result<Message, FnError>,
+    {
+        let mut handshakes = Vec::new();
+
+        for counter in 0.. {
+            let record = term! {
+                ((agent, counter)[Some(TlsQueryMatcher::ApplicationData)]/Message)
+            };
+            let record = match record.evaluate(ctx) {
+                Ok(record) => *record.downcast::<Message>().unwrap(),
+                Err(_) => break,
+            };
+
+            if let Some(message) = (0..8).find_map(|sequence| decrypt(&record, sequence).ok()) {
+                if let MessagePayload::Handshake(handshake) = message.payload {
+                    handshakes.push(handshake.typ);
+                }
+            }
+        }
+
+        handshakes
+    }
+
     #[test]
     fn test_version() {
         TLS_PUT_REGISTRY.version_strings();
@@ -2550,6 +4327,127 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_cbc() {
+        let ctx = seed_client_attacker12_cbc.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_cbc_etm() {
+        // The MAC follows the ciphertext, therefore the record is only accepted if the server
+        // negotiated encrypt-then-MAC
+        let ctx = seed_client_attacker12_cbc_etm.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls11")]
+    fn test_seed_client_attacker11_cbc() {
+        let ctx = seed_client_attacker11_cbc.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls10")]
+    fn test_seed_client_attacker10_cbc() {
+        let ctx = seed_client_attacker10_cbc.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_fragmented() {
+        let ctx = seed_client_attacker12_fragmented.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_renegotiation() {
+        let ctx = seed_client_attacker12_renegotiation.execute_trace();
+        assert!(ctx.agents_successful());
+
+        #[cfg(all(
+            feature = "claims",
+            any(feature = "openssl-binding", feature = "wolfssl-binding")
+        ))]
+        {
+            let server = AgentName::first();
+            let (_, client_verify_data) = _seed_client_attacker12(server);
+            let client_verify_data = evaluate::<Vec<u8>>(&ctx, &client_verify_data);
+
+            // The server binds the renegotiation to the Finished of the attacker
+            let renegotiation = find_renegotiation(&ctx, server).unwrap();
+            assert_eq!(
+                renegotiation.previous_client_verify_data.as_slice(),
+                client_verify_data.as_slice()
+            );
+            assert_eq!(check_violation(&ctx), None);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_client_attacker12_renegotiation_insecure() {
+        let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+        ctx.set_deterministic(true);
+        let result = seed_client_attacker12_renegotiation_insecure
+            .build_trace()
+            .execute(&mut ctx);
+
+        // The server aborts the renegotiation without renegotiation_info
+        assert!(matches!(result, Err(puffin::error::Error::Put(..))));
+
+        #[cfg(all(
+            feature = "claims",
+            any(feature = "openssl-binding", feature = "wolfssl-binding")
+        ))]
+        {
+            let server = AgentName::first();
+            assert!(find_renegotiation(&ctx, server).is_none());
+            assert_eq!(check_violation(&ctx), None);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tls12")]
+    fn test_seed_server_attacker12_renegotiation() {
+        let ctx = seed_server_attacker12_renegotiation.execute_trace();
+        assert!(ctx.agents_successful());
+
+        #[cfg(all(
+            feature = "claims",
+            any(feature = "openssl-binding", feature = "wolfssl-binding")
+        ))]
+        {
+            let client = AgentName::first();
+            let renegotiation = find_renegotiation(&ctx, client).unwrap();
+            assert!(!renegotiation.previous_client_verify_data.is_empty());
+            assert!(!renegotiation.previous_server_verify_data.is_empty());
+            assert_eq!(check_violation(&ctx), None);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "tls12", feature = "openssl-binding"))]
+    fn test_seed_client_attacker12_rsa() {
+        let ctx = seed_client_attacker12_rsa.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "tls12",
+        any(feature = "openssl-binding", feature = "wolfssl-binding")
+    ))]
+    fn test_seed_client_attacker12_dhe() {
+        let ctx = seed_client_attacker12_dhe.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[cfg(feature = "transcript-extraction")] // this depends on extracted transcripts -> claims are required
     #[test]
@@ -2558,6 +4456,113 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[cfg(feature = "transcript-extraction")] // this depends on extracted transcripts -> claims are required
+    #[test]
+    fn test_seed_client_attacker_key_update() {
+        let server = AgentName::first();
+        let ctx = seed_client_attacker_key_update.execute_trace();
+        assert!(ctx.agents_successful());
+
+        let server_hello_transcript = term! { fn_server_hello_transcript(((server, 0))) };
+        let server_finished_transcript = term! { fn_server_finished_transcript(((server, 0))) };
+        let server_key_share = term! { fn_get_server_key_share(((server, 0))) };
+        let server_hello_transcript = evaluate::<HandshakeHash>(&ctx, &server_hello_transcript);
+        let server_finished_transcript =
+            evaluate::<HandshakeHash>(&ctx, &server_finished_transcript);
+        let server_key_share = evaluate::<Option<Vec<u8>>>(&ctx, &server_key_share);
+
+        // The server answers each requested KeyUpdate with a KeyUpdate, which is the last message
+        // under the traffic secret of the previous generation
+        for key_updates in 0..2 {
+            let handshakes = decrypt_handshakes(&ctx, server, |application_data, sequence| {
+                fn_decrypt_application_updated(
+                    application_data,
+                    &server_hello_transcript,
+                    &server_finished_transcript,
+                    &server_key_share,
+                    &None,
+                    &NamedGroup::secp384r1,
+                    &true,
+                    &key_updates,
+                    &sequence,
+                )
+            });
+            assert_eq!(handshakes.last(), Some(&HandshakeType::KeyUpdate));
+        }
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[cfg(feature = "transcript-extraction")] // this depends on extracted transcripts -> claims are required
+    #[test]
+    fn test_seed_client_attacker_ech() {
+        // None of the PUTs which extract transcripts supports ECH, therefore this covers the
+        // rejection of the ECH
+        let ctx = seed_client_attacker_ech.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[cfg(feature = "boringssl-binding")]
+    #[test]
+    fn test_seed_client_attacker_ech_accepted() {
+        use puffin::put::PutOptions;
+
+        use crate::put_registry::BORINGSSL_PUT;
+
+        let server = AgentName::first();
+        let (mut trace, client_hello_inner) = _seed_client_attacker_ech(server);
+        // BoringSSL is configured with the ECH key of the attacker, but does not extract the
+        // transcripts which are needed for the Finished
+        trace.steps.truncate(1);
+
+        let ctx = trace.execute_with_puts(
+            &TLS_PUT_REGISTRY,
+            &[(
+                server,
+                PutDescriptor {
+                    name: BORINGSSL_PUT,
+                    options: PutOptions::default(),
+                },
+            )],
+        );
+
+        let accepted = term! {
+            fn_is_ech_accepted(
+                (@client_hello_inner),
+                ((server, 0)[Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello)))])
+            )
+        };
+        assert!(*accepted.evaluate(&ctx).unwrap().downcast::<bool>().unwrap());
+    }
+
+    #[test]
+    fn test_encrypt_client_hello() {
+        let client_hello = |extensions: &Vec<_>| {
+            fn_client_hello(
+                &fn_protocol_version12().unwrap(),
+                &fn_new_random().unwrap(),
+                &fn_new_session_id().unwrap(),
+                &vec![CipherSuite::TLS13_AES_128_GCM_SHA256],
+                &fn_compressions().unwrap(),
+                extensions,
+            )
+            .unwrap()
+        };
+        let outer = client_hello(&vec![]);
+        let inner = client_hello(&vec![fn_ech_inner_extension().unwrap()]);
+
+        let encrypted =
+            fn_encrypt_client_hello(&outer, &inner, &fn_new_ech_config().unwrap()).unwrap();
+        let decrypted = fn_decrypt_client_hello(&encrypted).unwrap();
+        assert_eq!(decrypted.get_encoding(), inner.get_encoding());
+
+        // Re-encrypting replaces the ECH extension
+        let encrypted_twice =
+            fn_encrypt_client_hello(&encrypted, &inner, &fn_new_ech_config().unwrap()).unwrap();
+        assert_eq!(encrypted_twice.get_encoding(), encrypted.get_encoding());
+    }
+
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[cfg(feature = "client-authentication-transcript-extraction")]
     #[test]
@@ -2619,6 +4624,52 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_server_attacker_x509_certificate() {
+        let ctx = seed_server_attacker_x509_certificate.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))] // requires a client which allows post-handshake authentication
+    #[test]
+    fn test_seed_server_attacker_post_handshake_auth() {
+        let client = AgentName::first();
+        let ctx = seed_server_attacker_post_handshake_auth.execute_trace();
+        assert!(ctx.agents_successful());
+
+        let (_, server_hello_transcript, server_finished_transcript, curve) =
+            _seed_server_attacker_full(client);
+        let client_key_share = term! { fn_get_client_key_share(((client, 0)), (@curve)) };
+        let server_hello_transcript = evaluate::<HandshakeHash>(&ctx, &server_hello_transcript);
+        let server_finished_transcript =
+            evaluate::<HandshakeHash>(&ctx, &server_finished_transcript);
+        let client_key_share = evaluate::<Option<Vec<u8>>>(&ctx, &client_key_share);
+        let curve = evaluate::<NamedGroup>(&ctx, &curve);
+
+        let handshakes = decrypt_handshakes(&ctx, client, |application_data, sequence| {
+            fn_decrypt_application(
+                application_data,
+                &server_hello_transcript,
+                &server_finished_transcript,
+                &client_key_share,
+                &None,
+                &curve,
+                &false,
+                &sequence,
+            )
+        });
+        assert_eq!(
+            handshakes,
+            vec![
+                HandshakeType::Certificate,
+                HandshakeType::CertificateVerify,
+                HandshakeType::Finished
+            ]
+        );
+    }
+
     #[cfg(all(feature = "tls13", feature = "tls13-session-resumption"))]
     #[test]
     fn test_seed_session_resumption_dhe() {
@@ -2640,6 +4691,42 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[cfg(all(feature = "tls13", feature = "tls13-session-resumption"))]
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))] // requires a PUT which is configured to accept early data
+    #[test]
+    fn test_seed_session_resumption_early_data() {
+        let ctx = seed_session_resumption_early_data.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(all(feature = "tls13", feature = "tls13-session-resumption"))]
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))] // requires a PUT which is configured to accept early data
+    #[test]
+    fn test_seed_session_resumption_early_data_rejected() {
+        let ctx = seed_session_resumption_early_data_rejected.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[cfg(all(feature = "tls13", feature = "tls13-session-resumption"))]
+    #[cfg(any(feature = "openssl111", feature = "openssl3xx"))] // requires a PUT which is configured to accept early data
+    #[cfg(feature = "claims")] // the replay is detected using EndOfEarlyData claims
+    #[test]
+    fn test_seed_session_resumption_early_data_replay() {
+        let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+        ctx.set_deterministic(true);
+        let result = seed_session_resumption_early_data_replay
+            .build_trace()
+            .execute(&mut ctx);
+
+        // The replay server accepts the early data a second time
+        match result {
+            Err(puffin::error::Error::SecurityClaim(msg, _)) => {
+                assert_eq!(msg, "Replayed early data accepted twice")
+            }
+            result => panic!("Replay was not detected: {:?}", result),
+        }
+    }
+
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
     fn test_seed_successful() {
@@ -2647,6 +4734,58 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_successful_check_violations_per_step() {
+        let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+        ctx.set_deterministic(true);
+        ctx.set_check_violations_per_step(true);
+        seed_successful.build_trace().execute(&mut ctx).unwrap();
+        assert!(ctx.agents_successful());
+    }
+
+    /// Finishes two unrelated handshakes with the attacker, which results in a client and a server
+    /// which disagree on the master secret. The trace continues after the violation.
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_violation_in_middle_step() {
+        let client = AgentName::first();
+        let server = client.next();
+
+        let server_attacker = seed_server_attacker_full(client);
+        let client_attacker = seed_client_attacker(server);
+        let mut trace = Trace {
+            descriptors: [server_attacker.descriptors, client_attacker.descriptors].concat(),
+            steps: [server_attacker.steps, client_attacker.steps].concat(),
+            prior_traces: vec![],
+        };
+        trace.steps.push(OutputAction::new_step(server));
+
+        let execute = |steps: usize| {
+            let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+            ctx.set_deterministic(true);
+            ctx.set_check_violations_per_step(true);
+            Trace {
+                steps: trace.steps[..steps].to_vec(),
+                ..trace.clone()
+            }
+            .execute(&mut ctx)
+        };
+
+        let step = match execute(trace.steps.len()) {
+            Err(puffin::error::Error::SecurityClaim(_, Some(step))) => step,
+            result => panic!("Expected a violation after a step, got {:?}", result.err()),
+        };
+        assert!(step < trace.steps.len() - 1);
+
+        // The violation is attributed to the first step after which the policy fails
+        assert!(execute(step).is_ok());
+        assert!(matches!(
+            execute(step + 1),
+            Err(puffin::error::Error::SecurityClaim(_, Some(violation_step))) if violation_step == step
+        ));
+    }
+
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
     fn test_seed_successful_client_auth() {
@@ -2691,6 +4830,44 @@ pub mod tests {
         assert!(ctx.agents_successful());
     }
 
+    #[test]
+    #[cfg(feature = "tls11")]
+    fn test_seed_successful11() {
+        #[cfg(feature = "tls12-session-resumption")]
+        let ctx = seed_successful11_with_tickets.execute_trace();
+        #[cfg(not(feature = "tls12-session-resumption"))]
+        let ctx = seed_successful11.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "tls10")]
+    fn test_seed_successful10() {
+        #[cfg(feature = "tls12-session-resumption")]
+        let ctx = seed_successful10_with_tickets.execute_trace();
+        #[cfg(not(feature = "tls12-session-resumption"))]
+        let ctx = seed_successful10.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(feature = "ssl3")]
+    fn test_seed_successful_ssl3() {
+        let ctx = seed_successful_ssl3.execute_trace();
+        assert!(ctx.agents_successful());
+    }
+
+    #[test]
+    #[cfg(not(feature = "ssl3"))]
+    fn test_seed_successful_ssl3_unsupported() {
+        let trace = seed_successful_ssl3.build_trace();
+        let mut ctx = TraceContext::new(&TLS_PUT_REGISTRY);
+        assert!(matches!(
+            trace.execute(&mut ctx),
+            Err(puffin::error::Error::Put(_))
+        ));
+    }
+
     // Vulnerable up until OpenSSL 1.0.1j
     #[cfg(all(feature = "openssl101f", feature = "asan"))]
     #[cfg(feature = "tls12")]
@@ -3017,6 +5194,7 @@ pub mod tests {
                 typ: ContentType::Handshake,
                 version: ProtocolVersion::TLSv1_2,
                 payload: Payload::new(vec![1, 2, 3]),
+                fake_length: None,
             };
 
             create_message(opaque_message);
@@ -1,11 +1,12 @@
 use itertools::Itertools;
 use puffin::{
-    agent::{AgentType, TLSVersion},
+    agent::{AgentName, AgentType, TLSVersion},
     claims::SecurityViolationPolicy,
 };
+use smallvec::SmallVec;
 
 use crate::{
-    claims::{ClaimData, ClaimDataMessage, Finished, TlsClaim},
+    claims::{ClaimData, ClaimDataMessage, Finished, Renegotiation, TlsClaim},
     static_certs::{ALICE_CERT, BOB_CERT},
 };
 
@@ -13,6 +14,14 @@ pub struct TlsSecurityViolationPolicy;
 
 impl SecurityViolationPolicy<TlsClaim> for TlsSecurityViolationPolicy {
     fn check_violation(claims: &[TlsClaim]) -> Option<&'static str> {
+        if find_replayed_early_data(claims) {
+            return Some("Replayed early data accepted twice");
+        }
+
+        if find_unbound_renegotiation(claims) {
+            return Some("Renegotiation not bound to previous handshake");
+        }
+
         if let Some((claim_a, claim_b)) = find_two_finished_messages(claims) {
             if let Some(((client_claim, client), (server_claim, server))) =
                 get_client_server(claim_a, claim_b)
@@ -21,14 +30,14 @@ impl SecurityViolationPolicy<TlsClaim> for TlsSecurityViolationPolicy {
                     return Some("Mismatching versions");
                 }
 
-                if client.master_secret != server.master_secret {
+                if mismatching(&client.master_secret, &server.master_secret) {
                     return Some("Mismatching master secrets");
                 }
 
-                if client.server_random != server.server_random {
+                if mismatching(&client.server_random, &server.server_random) {
                     return Some("Mismatching server random");
                 }
-                if client.client_random != server.client_random {
+                if mismatching(&client.client_random, &server.client_random) {
                     return Some("Mismatching client random");
                 }
 
@@ -36,9 +45,13 @@ impl SecurityViolationPolicy<TlsClaim> for TlsSecurityViolationPolicy {
                     return Some("Mismatching ciphers");
                 }
 
-                if client.signature_algorithm != server.peer_signature_algorithm
-                    || server.signature_algorithm != client.peer_signature_algorithm
-                {
+                if mismatching(
+                    &client.signature_algorithm,
+                    &server.peer_signature_algorithm,
+                ) || mismatching(
+                    &server.signature_algorithm,
+                    &client.peer_signature_algorithm,
+                ) {
                     return Some("mismatching signature algorithms");
                 }
 
@@ -51,27 +64,33 @@ impl SecurityViolationPolicy<TlsClaim> for TlsSecurityViolationPolicy {
                 }
 
                 match client_claim.protocol_version {
-                    TLSVersion::V1_2 => {
-                        // TLS 1.2 Checks
+                    TLSVersion::V1_2 | TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => {
+                        // TLS 1.2 Checks, which also apply to older versions
 
                         // https://datatracker.ietf.org/doc/html/rfc5077#section-3.4
-                        if !server.session_id.is_empty() && client.session_id != server.session_id {
-                            return Some("Mismatching session ids");
+                        if let (Some(client_session_id), Some(server_session_id)) =
+                            (&client.session_id, &server.session_id)
+                        {
+                            if !server_session_id.is_empty()
+                                && client_session_id != server_session_id
+                            {
+                                return Some("Mismatching session ids");
+                            }
                         }
                     }
                     TLSVersion::V1_3 => {
                         // TLS 1.3 Checks
-                        if client.session_id != server.session_id {
+                        if mismatching(&client.session_id, &server.session_id) {
                             return Some("Mismatching session ids");
                         }
 
-                        if !client.available_ciphers.is_empty()
-                            && !server.available_ciphers.is_empty()
+                        if let (Some(client_ciphers), Some(server_ciphers)) =
+                            (&client.available_ciphers, &server.available_ciphers)
                         {
                             let best_cipher = {
                                 let mut cipher = None;
-                                for server_cipher in &server.available_ciphers {
-                                    if client.available_ciphers.contains(server_cipher) {
+                                for server_cipher in server_ciphers {
+                                    if client_ciphers.contains(server_cipher) {
                                         cipher = Some(*server_cipher);
                                         break;
                                     }
@@ -152,6 +171,82 @@ pub fn find_two_finished_messages(
     two_finishes
 }
 
+/// Whether a server accepted early data twice with the same PSK. Each PSK must be usable only once
+/// for 0-RTT (RFC 8446, Section 8.1).
+pub fn find_replayed_early_data(claims: &[TlsClaim]) -> bool {
+    let accepted_early_secrets = claims
+        .iter()
+        .filter_map(|claim| match &claim.data {
+            ClaimData::Message(ClaimDataMessage::EndOfEarlyData(data))
+                if claim.origin == AgentType::Server && !data.outbound =>
+            {
+                Some(&data.early_secret)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    accepted_early_secrets
+        .iter()
+        .enumerate()
+        .any(|(i, secret)| accepted_early_secrets[..i].contains(secret))
+}
+
+/// Whether a renegotiation is not bound to the handshake which precedes it (RFC 5746). If both
+/// peers renegotiated, then they must agree on the verify_data of the previous handshake. If only
+/// one of them renegotiated, but both share the same master secret, then the other peer believes
+/// that this is its first handshake, like in the renegotiation attack of CVE-2009-3555.
+pub fn find_unbound_renegotiation(claims: &[TlsClaim]) -> bool {
+    let renegotiations = claims
+        .iter()
+        .filter_map(|claim| match &claim.data {
+            ClaimData::Message(ClaimDataMessage::Renegotiation(data)) => Some((claim, data)),
+            _ => None,
+        })
+        .collect::<Vec<(&TlsClaim, &Renegotiation)>>();
+
+    renegotiations.iter().any(|(claim, renegotiation)| {
+        let peer_renegotiations = renegotiations
+            .iter()
+            .filter(|(peer_claim, _)| peer_claim.origin != claim.origin)
+            .collect::<Vec<_>>();
+
+        if peer_renegotiations.is_empty() {
+            let master_secret = last_master_secret(claims, claim.agent_name);
+            claims.iter().any(|peer_claim| {
+                peer_claim.origin != claim.origin
+                    && master_secret.is_some()
+                    && last_master_secret(claims, peer_claim.agent_name) == master_secret
+            })
+        } else {
+            peer_renegotiations.iter().any(|(_, peer_renegotiation)| {
+                peer_renegotiation.previous_client_verify_data
+                    != renegotiation.previous_client_verify_data
+                    || peer_renegotiation.previous_server_verify_data
+                        != renegotiation.previous_server_verify_data
+            })
+        }
+    })
+}
+
+/// The master secret of the latest handshake of the agent
+fn last_master_secret(claims: &[TlsClaim], agent_name: AgentName) -> Option<&SmallVec<[u8; 32]>> {
+    claims
+        .iter()
+        .filter(|claim| claim.agent_name == agent_name)
+        .filter_map(|claim| match &claim.data {
+            ClaimData::Message(ClaimDataMessage::Finished(data)) => Some(&data.master_secret),
+            _ => None,
+        })
+        .last()
+        .and_then(Option::as_ref)
+}
+
+/// Whether both fields are known and differ. Fields which a PUT can not supply are unknown.
+fn mismatching<T: PartialEq>(a: &Option<T>, b: &Option<T>) -> bool {
+    matches!((a, b), (Some(a), Some(b)) if a != b)
+}
+
 pub fn get_client_server<'a, T>(
     a: (&'a TlsClaim, &'a T),
     b: (&'a TlsClaim, &'a T),
@@ -167,3 +262,139 @@ pub fn get_client_server<'a, T>(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use puffin::{
+        agent::{AgentName, AgentType, TLSVersion},
+        claims::SecurityViolationPolicy,
+    };
+    use smallvec::SmallVec;
+
+    use crate::{
+        claims::{ClaimData, ClaimDataMessage, EndOfEarlyData, Finished, Renegotiation, TlsClaim},
+        tls::violation::{
+            find_replayed_early_data, find_unbound_renegotiation, TlsSecurityViolationPolicy,
+        },
+    };
+
+    fn end_of_early_data(origin: AgentType, early_secret: u8) -> TlsClaim {
+        TlsClaim {
+            agent_name: AgentName::first(),
+            origin,
+            protocol_version: TLSVersion::V1_3,
+            data: ClaimData::Message(ClaimDataMessage::EndOfEarlyData(EndOfEarlyData {
+                outbound: false,
+                early_secret: SmallVec::from_slice(&[early_secret; 32]),
+            })),
+        }
+    }
+
+    fn finished(agent_name: AgentName, origin: AgentType, master_secret: u8) -> TlsClaim {
+        TlsClaim {
+            agent_name,
+            origin,
+            protocol_version: TLSVersion::V1_2,
+            data: ClaimData::Message(ClaimDataMessage::Finished(Finished {
+                outbound: false,
+                client_random: None,
+                server_random: None,
+                session_id: None,
+                authenticate_peer: false,
+                peer_certificate: SmallVec::new(),
+                master_secret: Some(SmallVec::from_slice(&[master_secret; 48])),
+                chosen_cipher: 0,
+                available_ciphers: None,
+                signature_algorithm: None,
+                peer_signature_algorithm: None,
+            })),
+        }
+    }
+
+    fn renegotiation(agent_name: AgentName, origin: AgentType, verify_data: u8) -> TlsClaim {
+        TlsClaim {
+            agent_name,
+            origin,
+            protocol_version: TLSVersion::V1_2,
+            data: ClaimData::Message(ClaimDataMessage::Renegotiation(Renegotiation {
+                previous_client_verify_data: SmallVec::from_slice(&[verify_data; 12]),
+                previous_server_verify_data: SmallVec::from_slice(&[verify_data; 12]),
+            })),
+        }
+    }
+
+    #[test]
+    fn test_find_unbound_renegotiation() {
+        let client = AgentName::first();
+        let server = client.next();
+
+        assert!(!find_unbound_renegotiation(&[
+            finished(client, AgentType::Client, 1),
+            finished(server, AgentType::Server, 1),
+            renegotiation(client, AgentType::Client, 1),
+            renegotiation(server, AgentType::Server, 1),
+            finished(client, AgentType::Client, 2),
+            finished(server, AgentType::Server, 2),
+        ]));
+        // The server renegotiated a session of the attacker, which the client continues
+        assert!(find_unbound_renegotiation(&[
+            finished(server, AgentType::Server, 1),
+            renegotiation(server, AgentType::Server, 1),
+            finished(client, AgentType::Client, 2),
+            finished(server, AgentType::Server, 2),
+        ]));
+        // Only the server renegotiated with the attacker
+        assert!(!find_unbound_renegotiation(&[
+            finished(server, AgentType::Server, 1),
+            renegotiation(server, AgentType::Server, 1),
+            finished(server, AgentType::Server, 2),
+        ]));
+        assert!(find_unbound_renegotiation(&[
+            renegotiation(client, AgentType::Client, 1),
+            renegotiation(server, AgentType::Server, 2),
+        ]));
+    }
+
+    #[test]
+    fn test_unknown_fields_are_not_compared() {
+        let client = AgentName::first();
+        let server = client.next();
+
+        assert_eq!(
+            TlsSecurityViolationPolicy::check_violation(&[
+                finished(client, AgentType::Client, 1),
+                finished(server, AgentType::Server, 2),
+            ]),
+            Some("Mismatching master secrets")
+        );
+
+        let mut unknown = finished(server, AgentType::Server, 2);
+        if let ClaimData::Message(ClaimDataMessage::Finished(finished)) = &mut unknown.data {
+            finished.master_secret = None;
+        }
+        assert_eq!(
+            TlsSecurityViolationPolicy::check_violation(&[
+                finished(client, AgentType::Client, 1),
+                unknown,
+            ]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_replayed_early_data() {
+        assert!(!find_replayed_early_data(&[
+            end_of_early_data(AgentType::Server, 1),
+            end_of_early_data(AgentType::Server, 2),
+        ]));
+        assert!(!find_replayed_early_data(&[
+            end_of_early_data(AgentType::Client, 1),
+            end_of_early_data(AgentType::Server, 1),
+        ]));
+        assert!(find_replayed_early_data(&[
+            end_of_early_data(AgentType::Server, 1),
+            end_of_early_data(AgentType::Server, 2),
+            end_of_early_data(AgentType::Server, 1),
+        ]));
+    }
+}
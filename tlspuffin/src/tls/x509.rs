@@ -0,0 +1,482 @@
+//! A small DER model of X.509 certificates (RFC 5280). Only the parts which are interesting for
+//! mutations are decoded. Everything else, like the subject public key info, stays opaque DER.
+//! Unlike a certificate parser of a TLS library, encoding never fails, such that terms can build
+//! malformed certificates.
+
+use puffin::algebra::error::FnError;
+
+use crate::tls::key_exchange::der_read_any;
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_BIT_STRING: u8 = 0x03;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_UTF8_STRING: u8 = 0x0c;
+pub const TAG_UTC_TIME: u8 = 0x17;
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+const TAG_VERSION: u8 = 0xa0;
+const TAG_EXTENSIONS: u8 = 0xa3;
+const TAG_DNS_NAME: u8 = 0x82;
+
+/// 1.2.840.113549.1.1.11
+pub const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+/// 1.2.840.113549.1.1.12
+pub const OID_SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+/// 1.2.840.113549.1.1.13
+pub const OID_SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+/// 1.2.840.10045.4.3.2
+pub const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+/// 2.5.4.3
+pub const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+/// 2.5.4.10
+pub const OID_ORGANIZATION_NAME: &[u8] = &[0x55, 0x04, 0x0a];
+/// 2.5.29.15
+pub const OID_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+/// 2.5.29.17
+pub const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+/// 2.5.29.19
+pub const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+/// 1.3.6.1.4.1.44363.44 is a private OID which no implementation knows.
+pub const OID_UNKNOWN_EXTENSION: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0xda, 0x4b, 0x2c];
+
+/// Encodes a DER type-length-value. Lengths are always encoded in the shortest form.
+pub fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![tag];
+    let length = content.len();
+    if length < 0x80 {
+        bytes.push(length as u8);
+    } else {
+        let length_bytes = length.to_be_bytes();
+        let skip = length_bytes.iter().take_while(|b| **b == 0).count();
+        bytes.push(0x80 | (length_bytes.len() - skip) as u8);
+        bytes.extend_from_slice(&length_bytes[skip..]);
+    }
+    bytes.extend_from_slice(content);
+    bytes
+}
+
+/// Encodes `value` as the content of a DER INTEGER, i.e. big-endian in the minimal number of
+/// bytes with a leading zero if the highest bit is set.
+pub fn encode_unsigned_integer(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count().min(7);
+    let mut content = bytes[skip..].to_vec();
+    if content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    content
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.bytes.first().copied()
+    }
+
+    /// Reads the next TLV and returns its tag and content.
+    fn read_any(&mut self) -> Result<(u8, &'a [u8]), FnError> {
+        let (tag, content, rest) = der_read_any(self.bytes)
+            .ok_or_else(|| FnError::Unknown("Truncated or unsupported DER value".to_string()))?;
+        self.bytes = rest;
+        Ok((tag, content))
+    }
+
+    fn read(&mut self, expected: u8) -> Result<&'a [u8], FnError> {
+        let (tag, content) = self.read_any()?;
+        if tag != expected {
+            return Err(FnError::Unknown(format!(
+                "Expected DER tag {:#04x} but found {:#04x}",
+                expected, tag
+            )));
+        }
+        Ok(content)
+    }
+
+    /// Reads the next TLV and returns it including the header.
+    fn read_raw(&mut self) -> Result<&'a [u8], FnError> {
+        let before = self.bytes;
+        self.read_any()?;
+        Ok(&before[..before.len() - self.bytes.len()])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlgorithmIdentifier {
+    /// The content octets of the OBJECT IDENTIFIER
+    pub algorithm: Vec<u8>,
+    /// The DER encoded parameters, if present
+    pub parameters: Option<Vec<u8>>,
+}
+
+impl AlgorithmIdentifier {
+    pub fn new(algorithm: &[u8], parameters: Option<Vec<u8>>) -> Self {
+        Self {
+            algorithm: algorithm.to_vec(),
+            parameters,
+        }
+    }
+
+    fn parse(content: &[u8]) -> Result<Self, FnError> {
+        let mut reader = Reader::new(content);
+        let algorithm = reader.read(TAG_OID)?.to_vec();
+        let parameters = if reader.is_empty() {
+            None
+        } else {
+            Some(reader.read_raw()?.to_vec())
+        };
+        Ok(Self {
+            algorithm,
+            parameters,
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut content = encode_tlv(TAG_OID, &self.algorithm);
+        if let Some(parameters) = &self.parameters {
+            content.extend_from_slice(parameters);
+        }
+        encode_tlv(TAG_SEQUENCE, &content)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeTypeAndValue {
+    pub attribute_type: Vec<u8>,
+    /// The string type of the value, e.g. [`TAG_UTF8_STRING`]
+    pub tag: u8,
+    pub value: Vec<u8>,
+}
+
+/// A distinguished name. Every attribute is encoded in its own relative distinguished name, which
+/// is what virtually all certificates do. Multi-valued RDNs are flattened during parsing.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Name(pub Vec<AttributeTypeAndValue>);
+
+impl Name {
+    fn parse(content: &[u8]) -> Result<Self, FnError> {
+        let mut attributes = Vec::new();
+        let mut reader = Reader::new(content);
+        while !reader.is_empty() {
+            let mut rdn = Reader::new(reader.read(TAG_SET)?);
+            while !rdn.is_empty() {
+                let mut attribute = Reader::new(rdn.read(TAG_SEQUENCE)?);
+                let attribute_type = attribute.read(TAG_OID)?.to_vec();
+                let (tag, value) = attribute.read_any()?;
+                attributes.push(AttributeTypeAndValue {
+                    attribute_type,
+                    tag,
+                    value: value.to_vec(),
+                });
+            }
+        }
+        Ok(Self(attributes))
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let content: Vec<u8> = self
+            .0
+            .iter()
+            .flat_map(|attribute| {
+                let mut content = encode_tlv(TAG_OID, &attribute.attribute_type);
+                content.extend(encode_tlv(attribute.tag, &attribute.value));
+                encode_tlv(TAG_SET, &encode_tlv(TAG_SEQUENCE, &content))
+            })
+            .collect();
+        encode_tlv(TAG_SEQUENCE, &content)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Time {
+    /// Either [`TAG_UTC_TIME`] or [`TAG_GENERALIZED_TIME`]
+    pub tag: u8,
+    pub value: Vec<u8>,
+}
+
+impl Time {
+    /// Chooses the encoding by the length of `value`. UTCTime is used for "YYMMDDHHMMSSZ" and
+    /// GeneralizedTime for everything else.
+    pub fn new(value: &[u8]) -> Self {
+        let tag = if value.len() == 13 {
+            TAG_UTC_TIME
+        } else {
+            TAG_GENERALIZED_TIME
+        };
+        Self {
+            tag,
+            value: value.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validity {
+    pub not_before: Time,
+    pub not_after: Time,
+}
+
+impl Validity {
+    fn parse(content: &[u8]) -> Result<Self, FnError> {
+        let mut reader = Reader::new(content);
+        let mut read_time = || -> Result<Time, FnError> {
+            let (tag, value) = reader.read_any()?;
+            Ok(Time {
+                tag,
+                value: value.to_vec(),
+            })
+        };
+        Ok(Self {
+            not_before: read_time()?,
+            not_after: read_time()?,
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut content = encode_tlv(self.not_before.tag, &self.not_before.value);
+        content.extend(encode_tlv(self.not_after.tag, &self.not_after.value));
+        encode_tlv(TAG_SEQUENCE, &content)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    pub extn_id: Vec<u8>,
+    pub critical: bool,
+    /// The DER encoded value which is wrapped in an OCTET STRING
+    pub extn_value: Vec<u8>,
+}
+
+impl Extension {
+    pub fn new(extn_id: &[u8], critical: bool, extn_value: Vec<u8>) -> Self {
+        Self {
+            extn_id: extn_id.to_vec(),
+            critical,
+            extn_value,
+        }
+    }
+
+    fn parse(content: &[u8]) -> Result<Self, FnError> {
+        let mut reader = Reader::new(content);
+        let extn_id = reader.read(TAG_OID)?.to_vec();
+        let critical = if reader.peek_tag() == Some(TAG_BOOLEAN) {
+            matches!(reader.read(TAG_BOOLEAN)?.first(), Some(b) if *b != 0)
+        } else {
+            false
+        };
+        let extn_value = reader.read(TAG_OCTET_STRING)?.to_vec();
+        Ok(Self {
+            extn_id,
+            critical,
+            extn_value,
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut content = encode_tlv(TAG_OID, &self.extn_id);
+        // DER omits the default value FALSE
+        if self.critical {
+            content.extend(encode_tlv(TAG_BOOLEAN, &[0xff]));
+        }
+        content.extend(encode_tlv(TAG_OCTET_STRING, &self.extn_value));
+        encode_tlv(TAG_SEQUENCE, &content)
+    }
+
+    pub fn basic_constraints(ca: bool) -> Self {
+        let content = if ca {
+            encode_tlv(TAG_BOOLEAN, &[0xff])
+        } else {
+            vec![]
+        };
+        Self::new(
+            OID_BASIC_CONSTRAINTS,
+            true,
+            encode_tlv(TAG_SEQUENCE, &content),
+        )
+    }
+
+    /// `usage` are the first eight bits of the KeyUsage BIT STRING, starting with
+    /// digitalSignature as the most significant bit.
+    pub fn key_usage(usage: u8) -> Self {
+        let unused_bits = usage.trailing_zeros().min(7) as u8;
+        Self::new(
+            OID_KEY_USAGE,
+            true,
+            encode_tlv(TAG_BIT_STRING, &[unused_bits, usage]),
+        )
+    }
+
+    pub fn subject_alt_name(dns_name: &[u8]) -> Self {
+        Self::new(
+            OID_SUBJECT_ALT_NAME,
+            false,
+            encode_tlv(TAG_SEQUENCE, &encode_tlv(TAG_DNS_NAME, dns_name)),
+        )
+    }
+}
+
+/// The TBSCertificate. Issuer and subject unique identifiers are dropped during parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TbsCertificate {
+    /// The version number, i.e. 2 for v3 certificates. The default value 0 is not encoded.
+    pub version: u64,
+    /// The content octets of the serial number INTEGER
+    pub serial_number: Vec<u8>,
+    pub signature: AlgorithmIdentifier,
+    pub issuer: Name,
+    pub validity: Validity,
+    pub subject: Name,
+    /// The DER encoded SubjectPublicKeyInfo
+    pub subject_public_key_info: Vec<u8>,
+    /// Encoded only if non-empty
+    pub extensions: Vec<Extension>,
+}
+
+impl TbsCertificate {
+    fn parse(content: &[u8]) -> Result<Self, FnError> {
+        let mut reader = Reader::new(content);
+
+        let version = if reader.peek_tag() == Some(TAG_VERSION) {
+            let version = Reader::new(reader.read(TAG_VERSION)?).read(TAG_INTEGER)?;
+            if version.len() > 8 {
+                return Err(FnError::Unknown("Version is too large".to_string()));
+            }
+            version.iter().fold(0u64, |v, b| (v << 8) | *b as u64)
+        } else {
+            0
+        };
+        let serial_number = reader.read(TAG_INTEGER)?.to_vec();
+        let signature = AlgorithmIdentifier::parse(reader.read(TAG_SEQUENCE)?)?;
+        let issuer = Name::parse(reader.read(TAG_SEQUENCE)?)?;
+        let validity = Validity::parse(reader.read(TAG_SEQUENCE)?)?;
+        let subject = Name::parse(reader.read(TAG_SEQUENCE)?)?;
+        let subject_public_key_info = reader.read_raw()?.to_vec();
+
+        let mut extensions = Vec::new();
+        while !reader.is_empty() {
+            let (tag, content) = reader.read_any()?;
+            if tag == TAG_EXTENSIONS {
+                let mut list = Reader::new(Reader::new(content).read(TAG_SEQUENCE)?);
+                while !list.is_empty() {
+                    extensions.push(Extension::parse(list.read(TAG_SEQUENCE)?)?);
+                }
+            }
+        }
+
+        Ok(Self {
+            version,
+            serial_number,
+            signature,
+            issuer,
+            validity,
+            subject,
+            subject_public_key_info,
+            extensions,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut content = Vec::new();
+        if self.version != 0 {
+            content.extend(encode_tlv(
+                TAG_VERSION,
+                &encode_tlv(TAG_INTEGER, &encode_unsigned_integer(self.version)),
+            ));
+        }
+        content.extend(encode_tlv(TAG_INTEGER, &self.serial_number));
+        content.extend(self.signature.encode());
+        content.extend(self.issuer.encode());
+        content.extend(self.validity.encode());
+        content.extend(self.subject.encode());
+        content.extend_from_slice(&self.subject_public_key_info);
+        if !self.extensions.is_empty() {
+            let extensions: Vec<u8> = self.extensions.iter().flat_map(Extension::encode).collect();
+            content.extend(encode_tlv(
+                TAG_EXTENSIONS,
+                &encode_tlv(TAG_SEQUENCE, &extensions),
+            ));
+        }
+        encode_tlv(TAG_SEQUENCE, &content)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct X509Certificate {
+    pub tbs_certificate: TbsCertificate,
+    pub signature_algorithm: AlgorithmIdentifier,
+    /// The bytes of the signature BIT STRING, without the count of unused bits
+    pub signature_value: Vec<u8>,
+}
+
+impl X509Certificate {
+    pub fn parse(der: &[u8]) -> Result<Self, FnError> {
+        let mut reader = Reader::new(Reader::new(der).read(TAG_SEQUENCE)?);
+        let tbs_certificate = TbsCertificate::parse(reader.read(TAG_SEQUENCE)?)?;
+        let signature_algorithm = AlgorithmIdentifier::parse(reader.read(TAG_SEQUENCE)?)?;
+        let signature_value = reader
+            .read(TAG_BIT_STRING)?
+            .get(1..)
+            .ok_or_else(|| FnError::Unknown("Empty signature BIT STRING".to_string()))?
+            .to_vec();
+        Ok(Self {
+            tbs_certificate,
+            signature_algorithm,
+            signature_value,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut signature_value = vec![0];
+        signature_value.extend_from_slice(&self.signature_value);
+
+        let mut content = self.tbs_certificate.encode();
+        content.extend(self.signature_algorithm.encode());
+        content.extend(encode_tlv(TAG_BIT_STRING, &signature_value));
+        encode_tlv(TAG_SEQUENCE, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::static_certs::{ALICE_CERT, BOB_CERT, EVE_CERT, RANDOM_EC_CERT};
+
+    #[test]
+    fn test_round_trip() {
+        for der in [ALICE_CERT.1, BOB_CERT.1, EVE_CERT.1, RANDOM_EC_CERT.1] {
+            let certificate = X509Certificate::parse(der).unwrap();
+            assert_eq!(certificate.encode(), der);
+        }
+    }
+
+    #[test]
+    fn test_long_length() {
+        let content = vec![7; 300];
+        let encoded = encode_tlv(TAG_OCTET_STRING, &content);
+        assert_eq!(&encoded[..4], &[TAG_OCTET_STRING, 0x82, 0x01, 0x2c]);
+        assert_eq!(
+            Reader::new(&encoded).read(TAG_OCTET_STRING).unwrap(),
+            content
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        let der = ALICE_CERT.1;
+        assert!(X509Certificate::parse(&der[..der.len() - 1]).is_err());
+    }
+}
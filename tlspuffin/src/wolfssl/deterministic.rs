@@ -0,0 +1,130 @@
+use std::{
+    os::raw::c_int,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use log::warn;
+use puffin::agent::AgentName;
+use wolfssl_sys as wolf;
+
+const SEED: u64 = 42;
+
+/// State of the xorshift generator which feeds the seeds of wolfSSL's DRBGs
+static SEED_STATE: AtomicU64 = AtomicU64::new(SEED);
+
+/// Replaces the OS entropy which is used to seed wolfSSL's Hash DRBGs. The generated bytes must
+/// not repeat in consecutive blocks as wolfSSL runs a continuous test on the seed.
+unsafe extern "C" fn deterministic_seed(
+    _os: *mut wolf::OS_Seed,
+    output: *mut wolf::byte,
+    sz: wolf::word32,
+) -> c_int {
+    let output = std::slice::from_raw_parts_mut(output, sz as usize);
+
+    for byte in output.iter_mut() {
+        let mut x = SEED_STATE.load(Ordering::SeqCst);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        SEED_STATE.store(x, Ordering::SeqCst);
+        *byte = x as u8;
+    }
+
+    0
+}
+
+/// Derives the seed of an agent from [`SEED`] and its name. Agents need distinct seeds, else the
+/// client and the server draw the same random values.
+///
+/// The seed is the 64-bit FNV-1a hash of both, which unlike the hashers of std is stable across
+/// Rust releases. This keeps saved traces replayable.
+fn agent_seed(agent: AgentName) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let seed = SEED
+        .to_le_bytes()
+        .iter()
+        .chain(agent.to_string().as_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        });
+
+    // The state of a xorshift generator must not be zero
+    match seed {
+        0 => SEED,
+        seed => seed,
+    }
+}
+
+/// Makes all DRBGs which are initialized from now on deterministic. Every WOLFSSL object owns its
+/// own DRBG, which means that this needs to be called before the object of `agent` is created.
+pub fn set_wolfssl_deterministic(agent: AgentName) {
+    warn!("WolfSSL is no longer random!");
+    SEED_STATE.store(agent_seed(agent), Ordering::SeqCst);
+    unsafe {
+        wolf::wc_SetSeed_Cb(Some(deterministic_seed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use puffin::{agent::AgentName, algebra::dynamic_function::TypeShape, trace::Query};
+    use test_log::test;
+
+    use super::agent_seed;
+    use crate::{
+        query::TlsQueryMatcher,
+        tls::{
+            rustls::msgs::{enums::HandshakeType, handshake::Random},
+            seeds::{seed_successful, SeedExecutor},
+        },
+    };
+
+    #[test]
+    fn test_agent_seed() {
+        let client = AgentName::first();
+
+        assert_eq!(agent_seed(client), 0x3ae1_1c2f_5f3e_e8ed);
+        assert_ne!(agent_seed(client), agent_seed(client.next()));
+    }
+
+    #[test]
+    #[cfg(feature = "tls13")]
+    fn test_wolfssl_seed_successful_deterministic() {
+        let randoms = || {
+            let ctx = seed_successful.execute_trace();
+            let client = AgentName::first();
+            let server = client.next();
+
+            let client_random = ctx
+                .find_variable(
+                    TypeShape::of::<Random>(),
+                    &Query {
+                        agent_name: client,
+                        matcher: Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ClientHello))),
+                        counter: 0,
+                    },
+                )
+                .and_then(|data| data.boxed_any().downcast::<Random>().ok())
+                .unwrap();
+            let server_random = ctx
+                .find_variable(
+                    TypeShape::of::<Random>(),
+                    &Query {
+                        agent_name: server,
+                        matcher: Some(TlsQueryMatcher::Handshake(Some(HandshakeType::ServerHello))),
+                        counter: 0,
+                    },
+                )
+                .and_then(|data| data.boxed_any().downcast::<Random>().ok())
+                .unwrap();
+
+            (*client_random, *server_random)
+        };
+
+        let (client_random, server_random) = randoms();
+        assert_eq!((client_random, server_random), randoms());
+        assert_ne!(client_random, server_random);
+    }
+}
@@ -0,0 +1,301 @@
+//! DTLS PUT for wolfSSL. DTLS 1.3 is only available if wolfSSL is built with `--enable-dtls13`,
+//! which is the case for wolfSSL 5.4.0.
+
+use std::{cell::RefCell, rc::Rc};
+
+use puffin::{
+    agent::{AgentDescriptor, AgentName, AgentType, TLSVersion},
+    error::Error,
+    protocol::MessageResult,
+    put::{Put, PutName},
+    put_registry::Factory,
+    stream::Stream,
+    trace::TraceContext,
+};
+use wolfssl::{
+    ssl::{Ssl, SslContext, SslContextRef, SslMethod, SslStream},
+    version::version,
+};
+
+use crate::{
+    dtls::{
+        message::{DtlsMessage, DtlsOpaqueMessage},
+        protocol::DtlsProtocolBehavior,
+        stream::DatagramStream,
+    },
+    put::{TlsPutConfig, TlsPutOptions},
+    put_registry::WOLFSSL520_PUT,
+    wolfssl::{check_options, MaybeError, WolfSSL, WolfSSLErrorStack},
+};
+
+/// The MTU which is announced to wolfSSL. The in-memory stream does not limit the size of
+/// datagrams, therefore this only needs to be large enough to fit the flights of the seeds.
+const DTLS_MTU: u16 = 1500;
+
+/// The cookie which is sent in the HelloVerifyRequest. It is static such that traces are
+/// deterministic.
+const DTLS_COOKIE: &[u8] = b"tlspuffin-dtls-cookie";
+
+pub fn new_wolfssl_dtls_factory() -> Box<dyn Factory<DtlsProtocolBehavior>> {
+    struct WolfSSLDtlsFactory;
+    impl Factory<DtlsProtocolBehavior> for WolfSSLDtlsFactory {
+        fn create(
+            &self,
+            context: &TraceContext<DtlsProtocolBehavior>,
+            agent_descriptor: &AgentDescriptor,
+        ) -> Result<Box<dyn Put<DtlsProtocolBehavior>>, Error> {
+            let options = TlsPutOptions::parse(&context.put_descriptor(agent_descriptor).options)?;
+            options.check_dtls()?;
+            check_options(&options)?;
+
+            let config = TlsPutConfig {
+                descriptor: agent_descriptor.clone(),
+                claims: context.claims().clone(),
+                authenticate_peer: options.authenticate_peer(agent_descriptor),
+                extract_deferred: Rc::new(RefCell::new(None)),
+                options,
+            };
+
+            Ok(Box::new(WolfSSLDtls::new(config)?))
+        }
+
+        fn name(&self) -> PutName {
+            WOLFSSL520_PUT
+        }
+
+        fn version(&self) -> String {
+            WolfSSLDtls::version()
+        }
+    }
+
+    Box::new(WolfSSLDtlsFactory)
+}
+
+pub struct WolfSSLDtls {
+    stream: SslStream<DatagramStream>,
+    ctx: SslContext,
+    config: TlsPutConfig,
+}
+
+impl Stream<DtlsMessage, DtlsOpaqueMessage> for WolfSSLDtls {
+    fn add_to_inbound(&mut self, opaque_message: &DtlsOpaqueMessage) -> Result<(), Error> {
+        self.stream.get_mut().add_to_inbound(opaque_message)
+    }
+
+    fn take_message_from_outbound(
+        &mut self,
+    ) -> Result<Option<MessageResult<DtlsMessage, DtlsOpaqueMessage>>, Error> {
+        self.stream.get_mut().take_message_from_outbound()
+    }
+}
+
+impl Drop for WolfSSLDtls {
+    fn drop(&mut self) {
+        #[cfg(feature = "claims")]
+        self.deregister_claimer();
+    }
+}
+
+impl WolfSSLDtls {
+    fn new(config: TlsPutConfig) -> Result<Self, Error> {
+        let agent_descriptor = &config.descriptor;
+        let mut ctx = match agent_descriptor.typ {
+            AgentType::Server => WolfSSL::create_server_ctx_with_method(
+                agent_descriptor,
+                &config.options,
+                Self::server_method(agent_descriptor.tls_version)?,
+            )?,
+            AgentType::Client => WolfSSL::create_client_ctx_with_method(
+                agent_descriptor,
+                &config.options,
+                Self::client_method(agent_descriptor.tls_version)?,
+            )?,
+        };
+
+        // The default cookie callback of wolfSSL requires the address of the peer
+        ctx.set_gen_cookie_callback(|_ssl, cookie| {
+            let length = DTLS_COOKIE.len().min(cookie.len());
+            cookie[..length].copy_from_slice(&DTLS_COOKIE[..length]);
+            length
+        });
+
+        #[cfg(not(feature = "wolfssl430"))]
+        ctx.set_msg_callback(WolfSSL::create_msg_callback(agent_descriptor.name, &config))
+            .map_err(WolfSSLErrorStack::from)?;
+
+        #[allow(unused_mut)]
+        let mut stream = Self::new_stream(&ctx, &config)?;
+
+        #[cfg(feature = "wolfssl430")]
+        stream
+            .ssl_mut()
+            .set_msg_callback(WolfSSL::create_msg_callback(agent_descriptor.name, &config))
+            .map_err(WolfSSLErrorStack::from)?;
+
+        #[allow(unused_mut)]
+        let mut wolfssl = WolfSSLDtls {
+            stream,
+            ctx,
+            config: config.clone(),
+        };
+
+        #[cfg(feature = "claims")]
+        wolfssl.register_claimer(agent_descriptor.name);
+        Ok(wolfssl)
+    }
+
+    fn client_method(tls_version: TLSVersion) -> Result<SslMethod, Error> {
+        match tls_version {
+            TLSVersion::V1_2 => Ok(SslMethod::dtls_client_12()),
+            #[cfg(feature = "dtls13")]
+            TLSVersion::V1_3 => Ok(SslMethod::dtls_client_13()),
+            #[cfg(not(feature = "dtls13"))]
+            TLSVersion::V1_3 => Err(Error::Put("wolfSSL is built without DTLS 1.3".to_string())),
+            TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => Err(Error::Put(format!(
+                "Unsupported DTLS version: {:?}",
+                tls_version
+            ))),
+        }
+    }
+
+    fn server_method(tls_version: TLSVersion) -> Result<SslMethod, Error> {
+        match tls_version {
+            TLSVersion::V1_2 => Ok(SslMethod::dtls_server_12()),
+            #[cfg(feature = "dtls13")]
+            TLSVersion::V1_3 => Ok(SslMethod::dtls_server_13()),
+            #[cfg(not(feature = "dtls13"))]
+            TLSVersion::V1_3 => Err(Error::Put("wolfSSL is built without DTLS 1.3".to_string())),
+            TLSVersion::V1_1 | TLSVersion::V1_0 | TLSVersion::SSL3 => Err(Error::Put(format!(
+                "Unsupported DTLS version: {:?}",
+                tls_version
+            ))),
+        }
+    }
+
+    /// Recreates the WOLFSSL object and moves the claimer of the agent to the new object
+    #[allow(unused_variables)]
+    fn renew_stream(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        self.deregister_claimer();
+
+        self.stream = Self::new_stream(&self.ctx, &self.config)?;
+
+        #[cfg(feature = "claims")]
+        self.register_claimer(agent_name);
+
+        Ok(())
+    }
+
+    fn new_stream(
+        ctx: &SslContextRef,
+        config: &TlsPutConfig,
+    ) -> Result<SslStream<DatagramStream>, WolfSSLErrorStack> {
+        let mut ssl: Ssl = Ssl::new(ctx)?;
+
+        match config.descriptor.typ {
+            AgentType::Server => ssl.set_accept_state(),
+            AgentType::Client => ssl.set_connect_state(),
+        }
+
+        // Reading from the in-memory stream never blocks
+        ssl.dtls_set_using_nonblock(true);
+        ssl.dtls_set_mtu(DTLS_MTU)?;
+
+        WolfSSL::configure_ssl(&mut ssl, config)?;
+
+        Ok(SslStream::new(ssl, DatagramStream::new())?)
+    }
+}
+
+impl Put<DtlsProtocolBehavior> for WolfSSLDtls {
+    fn progress(&mut self, agent_name: &AgentName) -> Result<(), Error> {
+        let result = if self.is_state_successful() {
+            // Trigger another read
+            let mut vec: Vec<u8> = Vec::from([1; 128]);
+            let maybe_error: MaybeError = self.stream.ssl_read(&mut vec).into();
+            maybe_error.into()
+        } else {
+            let maybe_error: MaybeError = self.stream.do_handshake().into();
+            maybe_error.into()
+        };
+
+        WolfSSL::extract_deferred_transcript(self.stream.ssl(), &self.config, agent_name);
+
+        result
+    }
+
+    fn reset(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        self.renew_stream(agent_name)
+    }
+
+    #[cfg(feature = "claims")]
+    fn register_claimer(&mut self, agent_name: AgentName) {
+        super::register_claimer(self.stream.ssl(), &self.config, agent_name);
+    }
+
+    #[cfg(feature = "claims")]
+    fn deregister_claimer(&mut self) {
+        unsafe {
+            use foreign_types::ForeignTypeRef;
+
+            security_claims::deregister_claimer(self.stream.ssl().as_ptr().cast());
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn rename_agent(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        {
+            self.deregister_claimer();
+            self.register_claimer(agent_name);
+        }
+
+        #[cfg(not(feature = "wolfssl430"))]
+        self.ctx
+            .set_msg_callback(WolfSSL::create_msg_callback(agent_name, &self.config))
+            .map_err(WolfSSLErrorStack::from)?;
+
+        #[cfg(feature = "wolfssl430")]
+        self.stream
+            .ssl_mut()
+            .set_msg_callback(WolfSSL::create_msg_callback(agent_name, &self.config))
+            .map_err(WolfSSLErrorStack::from)?;
+
+        Ok(())
+    }
+
+    fn describe_state(&self) -> &'static str {
+        self.stream.state_string_long()
+    }
+
+    fn is_state_successful(&self) -> bool {
+        self.stream.is_handshake_done()
+    }
+
+    fn version() -> String {
+        unsafe { version().to_string() }
+    }
+
+    fn set_deterministic(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "deterministic")]
+        {
+            super::deterministic::set_wolfssl_deterministic(self.config.descriptor.name);
+            // The DRBG of a WOLFSSL object is seeded on creation, therefore recreate it
+            self.renew_stream(self.config.descriptor.name)
+        }
+        #[cfg(not(feature = "deterministic"))]
+        {
+            Err(Error::Agent(
+                "WolfSSL does not support determinism".to_string(),
+            ))
+        }
+    }
+
+    fn shutdown(&mut self) -> String {
+        panic!("Unsupported with WolfSSL PUT")
+    }
+
+    fn descriptor(&self) -> &AgentDescriptor {
+        &self.config.descriptor
+    }
+}
@@ -34,14 +34,16 @@ use wolfssl::{
 
 use crate::{
     claims::{
-        ClaimData, ClaimDataMessage, ClaimDataTranscript, Finished, TlsClaim,
+        ClaimData, ClaimDataMessage, ClaimDataTranscript, Finished, Renegotiation, TlsClaim,
         TranscriptCertificate, TranscriptClientFinished, TranscriptClientHello,
         TranscriptServerFinished, TranscriptServerHello,
     },
     protocol::TLSProtocolBehavior,
-    put::TlsPutConfig,
+    put::{check_tls_version, TlsPutConfig, TlsPutOptions, VerifyMode},
     put_registry::WOLFSSL520_PUT,
-    static_certs::{ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT},
+    static_certs::{
+        ALICE_CERT, ALICE_PRIVATE_KEY, BOB_CERT, BOB_PRIVATE_KEY, EVE_CERT, FFDHE2048_PARAMS,
+    },
     tls,
     tls::rustls::msgs::{
         deframer::MessageDeframer,
@@ -51,6 +53,10 @@ use crate::{
     wolfssl::transcript::extract_current_transcript,
 };
 
+#[cfg(feature = "deterministic")]
+mod deterministic;
+#[cfg(any(feature = "dtls12", feature = "dtls13"))]
+pub mod dtls;
 mod transcript;
 
 pub fn new_wolfssl_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
@@ -61,14 +67,16 @@ pub fn new_wolfssl_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
             context: &TraceContext<TLSProtocolBehavior>,
             agent_descriptor: &AgentDescriptor,
         ) -> Result<Box<dyn Put<TLSProtocolBehavior>>, Error> {
+            check_tls_version(agent_descriptor)?;
+            let options = TlsPutOptions::parse(&context.put_descriptor(agent_descriptor).options)?;
+            check_options(&options)?;
+
             let config = TlsPutConfig {
                 descriptor: agent_descriptor.clone(),
                 claims: context.claims().clone(),
-                authenticate_peer: agent_descriptor.typ == AgentType::Client
-                    && agent_descriptor.server_authentication
-                    || agent_descriptor.typ == AgentType::Server
-                        && agent_descriptor.client_authentication,
+                authenticate_peer: options.authenticate_peer(agent_descriptor),
                 extract_deferred: Rc::new(RefCell::new(None)),
+                options,
             };
 
             Ok(Box::new(WolfSSL::new(config)?))
@@ -86,6 +94,25 @@ pub fn new_wolfssl_factory() -> Box<dyn Factory<TLSProtocolBehavior>> {
     Box::new(WolfSSLFactory)
 }
 
+/// Rejects the options which the linked wolfSSL version can not apply. Shared between the TLS and
+/// DTLS PUTs.
+#[allow(unused_variables)]
+fn check_options(options: &TlsPutOptions) -> Result<(), Error> {
+    #[cfg(feature = "wolfssl430")]
+    if options.groups.is_some()
+        || options.sigalgs.is_some()
+        || options.min_version.is_some()
+        || options.max_version.is_some()
+    {
+        return Err(Error::Put(
+            "The options groups, sigalgs, min-version and max-version are not supported by wolfSSL 4.3.0"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
 pub struct WolfSSLErrorStack(pub ErrorStack);
 
 impl From<ErrorStack> for WolfSSLErrorStack {
@@ -104,10 +131,14 @@ pub struct WolfSSL {
     stream: SslStream<MemoryStream<MessageDeframer>>,
     ctx: SslContext,
     config: TlsPutConfig,
+    /// The verify_data of the Finished messages of the client and server in the latest completed
+    /// handshake
+    #[cfg(feature = "claims")]
+    verify_data: Option<(SmallVec<[u8; 32]>, SmallVec<[u8; 32]>)>,
 }
 
 impl Stream<Message, OpaqueMessage> for WolfSSL {
-    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) {
+    fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) -> Result<(), Error> {
         let raw_stream = self.stream.get_mut();
         //raw_stream.add_to_inbound(opaque_message)
         <MemoryStream<MessageDeframer> as Stream<Message, OpaqueMessage>>::add_to_inbound(
@@ -136,8 +167,8 @@ impl WolfSSL {
     fn new(config: TlsPutConfig) -> Result<Self, Error> {
         let agent_descriptor = &config.descriptor;
         let mut ctx = match agent_descriptor.typ {
-            AgentType::Server => Self::create_server_ctx(agent_descriptor)?,
-            AgentType::Client => Self::create_client_ctx(agent_descriptor)?,
+            AgentType::Server => Self::create_server_ctx(agent_descriptor, &config.options)?,
+            AgentType::Client => Self::create_client_ctx(agent_descriptor, &config.options)?,
         };
 
         #[cfg(not(feature = "wolfssl430"))]
@@ -152,26 +183,52 @@ impl WolfSSL {
             .set_msg_callback(Self::create_msg_callback(agent_descriptor.name, &config))
             .map_err(|err| WolfSSLErrorStack::from(err))?;
 
+        #[allow(unused_mut)]
         let mut wolfssl = WolfSSL {
             ctx,
             stream,
             config: config.clone(),
+            #[cfg(feature = "claims")]
+            verify_data: None,
         };
 
         #[cfg(feature = "claims")]
-        stream.register_claimer(config.claims, config.agent_name);
+        wolfssl.register_claimer(agent_descriptor.name);
         Ok(wolfssl)
     }
 
+    /// Recreates the WOLFSSL object and moves the claimer of the agent to the new object
+    #[allow(unused_variables)]
+    fn renew_stream(&mut self, agent_name: AgentName) -> Result<(), Error> {
+        #[cfg(feature = "claims")]
+        self.deregister_claimer();
+
+        self.stream = Self::new_stream(&self.ctx, &self.config)?;
+
+        #[cfg(feature = "claims")]
+        {
+            self.register_claimer(agent_name);
+            self.verify_data = None;
+        }
+
+        Ok(())
+    }
+
     fn new_stream(
         ctx: &SslContextRef,
         config: &TlsPutConfig,
     ) -> Result<SslStream<MemoryStream<MessageDeframer>>, WolfSSLErrorStack> {
-        let ssl = match config.descriptor.typ {
+        let mut ssl = match config.descriptor.typ {
             AgentType::Server => Self::create_server(ctx)?,
-            AgentType::Client => Self::create_client(ctx)?,
+            AgentType::Client => Self::create_client(ctx, &config.options)?,
         };
 
+        if config.descriptor.renegotiation && config.descriptor.tls_version != TLSVersion::V1_3 {
+            ssl.use_secure_renegotiation()?;
+        }
+
+        Self::configure_ssl(&mut ssl, config)?;
+
         Ok(SslStream::new(
             ssl,
             MemoryStream::new(MessageDeframer::new()),
@@ -179,6 +236,31 @@ impl WolfSSL {
     }
 }
 
+#[cfg(feature = "claims")]
+fn register_claimer(ssl: &SslRef, config: &TlsPutConfig, agent_name: AgentName) {
+    use crate::claims::to_claim_data;
+
+    let claims = config.claims.clone();
+    let protocol_version = config.descriptor.tls_version;
+    let origin = config.descriptor.typ;
+
+    unsafe {
+        security_claims::register_claimer(
+            ssl.as_ptr().cast(),
+            move |claim: security_claims::Claim| {
+                if let Some(data) = to_claim_data(protocol_version, claim) {
+                    claims.deref_borrow_mut().claim_sized(TlsClaim {
+                        agent_name,
+                        origin,
+                        protocol_version,
+                        data,
+                    })
+                }
+            },
+        );
+    }
+}
+
 impl Put<TLSProtocolBehavior> for WolfSSL {
     fn progress(&mut self, agent_name: &AgentName) -> Result<(), Error> {
         let result = if self.is_state_successful() {
@@ -193,25 +275,19 @@ impl Put<TLSProtocolBehavior> for WolfSSL {
 
         self.deferred_transcript_extraction(agent_name);
 
+        #[cfg(feature = "claims")]
+        self.claim_renegotiation(*agent_name);
+
         result
     }
 
     fn reset(&mut self, agent_name: AgentName) -> Result<(), Error> {
-        self.stream = Self::new_stream(&self.ctx, &self.config)?;
-        //self.stream.clear();
-        Ok(())
+        self.renew_stream(agent_name)
     }
 
     #[cfg(feature = "claims")]
     fn register_claimer(&mut self, agent_name: AgentName) {
-        unsafe {
-            security_claims::register_claimer(
-                self.stream.ssl().as_ptr().cast(),
-                move |claim: security_claims::Claim| {
-                    (*claims).borrow_mut().claim(agent_name, claim)
-                },
-            );
-        }
+        register_claimer(self.stream.ssl(), &self.config, agent_name);
     }
 
     #[cfg(feature = "claims")]
@@ -261,9 +337,18 @@ impl Put<TLSProtocolBehavior> for WolfSSL {
     }
 
     fn set_deterministic(&mut self) -> Result<(), puffin::error::Error> {
-        Err(Error::Agent(
-            "WolfSSL does not support determinism".to_string(),
-        ))
+        #[cfg(feature = "deterministic")]
+        {
+            deterministic::set_wolfssl_deterministic(self.config.descriptor.name);
+            // The DRBG of a WOLFSSL object is seeded on creation, therefore recreate it
+            self.renew_stream(self.config.descriptor.name)
+        }
+        #[cfg(not(feature = "deterministic"))]
+        {
+            Err(Error::Agent(
+                "WolfSSL does not support determinism".to_string(),
+            ))
+        }
     }
 
     fn shutdown(&mut self) -> String {
@@ -276,14 +361,164 @@ impl Put<TLSProtocolBehavior> for WolfSSL {
 }
 
 impl WolfSSL {
+    /// Claims a [`Renegotiation`] once a handshake completes which replaced a previous handshake
+    /// on this connection. Like with OpenSSL, only the Finished messages of the latest handshake
+    /// are available.
+    #[cfg(feature = "claims")]
+    fn claim_renegotiation(&mut self, agent_name: AgentName) {
+        if !self.is_state_successful() {
+            return;
+        }
+
+        let ssl = self.stream.ssl();
+        let mut finished = [0u8; 64];
+        let mut peer_finished = [0u8; 64];
+        let finished_len = ssl.finished(&mut finished).min(finished.len());
+        let peer_finished_len = ssl
+            .peer_finished(&mut peer_finished)
+            .min(peer_finished.len());
+
+        let finished = SmallVec::from_slice(&finished[..finished_len]);
+        let peer_finished = SmallVec::from_slice(&peer_finished[..peer_finished_len]);
+        let verify_data = match self.config.descriptor.typ {
+            AgentType::Server => (peer_finished, finished),
+            AgentType::Client => (finished, peer_finished),
+        };
+
+        if let Some(previous_verify_data) = self.verify_data.take() {
+            if previous_verify_data != verify_data {
+                let (previous_client_verify_data, previous_server_verify_data) =
+                    previous_verify_data;
+                self.config.claims.deref_borrow_mut().claim_sized(TlsClaim {
+                    agent_name,
+                    origin: self.config.descriptor.typ,
+                    protocol_version: self.config.descriptor.tls_version,
+                    data: ClaimData::Message(ClaimDataMessage::Renegotiation(Renegotiation {
+                        previous_client_verify_data,
+                        previous_server_verify_data,
+                    })),
+                });
+            }
+        }
+
+        self.verify_data = Some(verify_data);
+    }
+
     pub fn create_client_ctx(
         descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
     ) -> Result<SslContext, WolfSSLErrorStack> {
-        let mut ctx = match descriptor.tls_version {
-            TLSVersion::V1_3 => SslContext::new(SslMethod::tls_client_13())?,
-            TLSVersion::V1_2 => SslContext::new(SslMethod::tls_client_12())?,
+        let method = if Self::negotiates_version(options) {
+            SslMethod::tls_client()
+        } else {
+            match descriptor.tls_version {
+                TLSVersion::V1_3 => SslMethod::tls_client_13(),
+                TLSVersion::V1_2 => SslMethod::tls_client_12(),
+                TLSVersion::V1_1 => SslMethod::tls_client_11(),
+                TLSVersion::V1_0 => SslMethod::tls_client_10(),
+                TLSVersion::SSL3 => SslMethod::ssl_client_3(),
+            }
         };
 
+        let mut ctx = Self::create_client_ctx_with_method(descriptor, options, method)?;
+        Self::set_protocol_versions(&mut ctx, descriptor, options)?;
+        Ok(ctx)
+    }
+
+    /// The methods of wolfSSL either use a single version or negotiate all versions. The latter
+    /// are used if the options restrict the versions.
+    fn negotiates_version(options: &TlsPutOptions) -> bool {
+        options.min_version.is_some() || options.max_version.is_some()
+    }
+
+    /// Limits the negotiated versions to the bounds of the options. The maximum defaults to the
+    /// version of the agent.
+    #[allow(unused_variables)]
+    fn set_protocol_versions(
+        ctx: &mut SslContextRef,
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+    ) -> Result<(), WolfSSLErrorStack> {
+        #[cfg(not(feature = "wolfssl430"))]
+        if Self::negotiates_version(options) {
+            use wolfssl::ssl::SslVersion;
+
+            let ssl_version = |tls_version| match tls_version {
+                TLSVersion::V1_3 => SslVersion::TLS1_3,
+                TLSVersion::V1_2 => SslVersion::TLS1_2,
+                TLSVersion::V1_1 => SslVersion::TLS1_1,
+                TLSVersion::V1_0 => SslVersion::TLS1,
+                TLSVersion::SSL3 => SslVersion::SSL3,
+            };
+
+            ctx.set_max_proto_version(ssl_version(
+                options.max_version.unwrap_or(descriptor.tls_version),
+            ))?;
+
+            if let Some(min_version) = options.min_version {
+                ctx.set_min_proto_version(ssl_version(min_version))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// wolfSSL configures the ciphers of all versions through a single list, therefore the TLS 1.3
+    /// ciphersuites of the options are appended to the cipher list
+    fn cipher_list(options: &TlsPutOptions) -> String {
+        match (&options.cipher_list, &options.ciphersuites) {
+            (Some(cipher_list), Some(ciphersuites)) => format!("{}:{}", cipher_list, ciphersuites),
+            (Some(list), None) | (None, Some(list)) => list.clone(),
+            // Disallow EXPORT in clients and servers
+            (None, None) => "ALL:!EXPORT:!LOW:!aNULL:!eNULL:!SSLv2".to_string(),
+        }
+    }
+
+    /// Applies the options which are the same for clients and servers. Shared between the TLS
+    /// and DTLS PUTs.
+    fn configure_ctx(
+        ctx: &mut SslContextRef,
+        options: &TlsPutOptions,
+    ) -> Result<(), WolfSSLErrorStack> {
+        ctx.set_cipher_list(&Self::cipher_list(options))?;
+
+        #[cfg(not(feature = "wolfssl430"))]
+        {
+            if let Some(groups) = &options.groups {
+                ctx.set_groups_list(groups)?;
+            }
+
+            if let Some(sigalgs) = &options.sigalgs {
+                ctx.set_sigalgs_list(sigalgs)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies the options which wolfSSL configures per connection. Shared between the TLS and
+    /// DTLS PUTs.
+    fn configure_ssl(ssl: &mut SslRef, config: &TlsPutConfig) -> Result<(), WolfSSLErrorStack> {
+        if let Some(alpn) = &config.options.alpn {
+            ssl.use_alpn(&alpn.join(","))?;
+        }
+
+        if let (AgentType::Client, Some(sni)) = (config.descriptor.typ, &config.options.sni) {
+            ssl.use_sni(sni)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the context of a client for an arbitrary method. Shared between the TLS and DTLS
+    /// PUTs.
+    fn create_client_ctx_with_method(
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+        method: SslMethod,
+    ) -> Result<SslContext, WolfSSLErrorStack> {
+        let mut ctx = SslContext::new(method)?;
+
         ctx.disable_session_cache()?;
 
         if descriptor.client_authentication {
@@ -304,7 +539,7 @@ impl WolfSSL {
             }
         }
 
-        if descriptor.server_authentication {
+        if options.verify_mode(descriptor) != VerifyMode::Disabled {
             ctx.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
             ctx.load_verify_buffer(ALICE_CERT.0.as_bytes())?;
             ctx.load_verify_buffer(EVE_CERT.0.as_bytes())?;
@@ -313,35 +548,60 @@ impl WolfSSL {
             ctx.set_verify(SslVerifyMode::NONE);
         }
 
-        // Disallow EXPORT in client
-        ctx.set_cipher_list("ALL:!EXPORT:!LOW:!aNULL:!eNULL:!SSLv2")?;
+        Self::configure_ctx(&mut ctx, options)?;
 
         Ok(ctx)
     }
 
-    pub fn create_client(ctx: &SslContextRef) -> Result<Ssl, WolfSSLErrorStack> {
+    pub fn create_client(
+        ctx: &SslContextRef,
+        options: &TlsPutOptions,
+    ) -> Result<Ssl, WolfSSLErrorStack> {
         let mut ssl: Ssl = Ssl::new(&ctx)?;
         ssl.set_connect_state();
 
         // Force requesting session ticket because `seed_successfull12` expects it.
-        ssl.use_session_ticket();
+        if options.tickets != Some(false) {
+            ssl.use_session_ticket();
+        }
 
         Ok(ssl)
     }
 
     pub fn create_server_ctx(
         descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
     ) -> Result<SslContext, WolfSSLErrorStack> {
-        let mut ctx = match descriptor.tls_version {
-            TLSVersion::V1_3 => SslContext::new(SslMethod::tls_server_13())?,
-            TLSVersion::V1_2 => SslContext::new(SslMethod::tls_server_12())?,
+        let method = if Self::negotiates_version(options) {
+            SslMethod::tls_server()
+        } else {
+            match descriptor.tls_version {
+                TLSVersion::V1_3 => SslMethod::tls_server_13(),
+                TLSVersion::V1_2 => SslMethod::tls_server_12(),
+                TLSVersion::V1_1 => SslMethod::tls_server_11(),
+                TLSVersion::V1_0 => SslMethod::tls_server_10(),
+                TLSVersion::SSL3 => SslMethod::ssl_server_3(),
+            }
         };
 
+        let mut ctx = Self::create_server_ctx_with_method(descriptor, options, method)?;
+        Self::set_protocol_versions(&mut ctx, descriptor, options)?;
+        Ok(ctx)
+    }
+
+    /// Creates the context of a server for an arbitrary method. Shared between the TLS and DTLS
+    /// PUTs.
+    fn create_server_ctx_with_method(
+        descriptor: &AgentDescriptor,
+        options: &TlsPutOptions,
+        method: SslMethod,
+    ) -> Result<SslContext, WolfSSLErrorStack> {
+        let mut ctx = SslContext::new(method)?;
+
         // Mitigates "2. Misuse of sessions of different TLS versions (1.2, 1.3) from the session cache"
         ctx.disable_session_cache()?;
 
-        // Disallow EXPORT in server
-        ctx.set_cipher_list("ALL:!EXPORT:!LOW:!aNULL:!eNULL:!SSLv2")?;
+        Self::configure_ctx(&mut ctx, options)?;
 
         let cert = X509::from_pem(ALICE_CERT.0.as_bytes())?;
         ctx.set_certificate(cert.as_ref())?;
@@ -357,12 +617,20 @@ impl WolfSSL {
             ctx.set_private_key_pem(ALICE_PRIVATE_KEY.0.as_bytes())?;
         }
 
-        if descriptor.client_authentication {
-            ctx.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
-            ctx.load_verify_buffer(BOB_CERT.0.as_bytes())?;
-            ctx.load_verify_buffer(EVE_CERT.0.as_bytes())?;
-        } else {
-            ctx.set_verify(SslVerifyMode::NONE);
+        // Enables the DHE cipher suites
+        ctx.set_tmp_dh_pem(FFDHE2048_PARAMS.0.as_bytes())?;
+
+        match options.verify_mode(descriptor) {
+            VerifyMode::Disabled => ctx.set_verify(SslVerifyMode::NONE),
+            verify_mode => {
+                if verify_mode == VerifyMode::Required {
+                    ctx.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+                } else {
+                    ctx.set_verify(SslVerifyMode::PEER);
+                }
+                ctx.load_verify_buffer(BOB_CERT.0.as_bytes())?;
+                ctx.load_verify_buffer(EVE_CERT.0.as_bytes())?;
+            }
         }
 
         // Callbacks for experiements
@@ -371,9 +639,13 @@ impl WolfSSL {
         //wolf::wolfSSL_CTX_SetTlsFinishedCb(ctx, Some(SSL_finished));
         //wolf::wolfSSL_set_tls13_secret_cb(ssl.as_ptr(), Some(SSL_keylog13), ptr::null_mut());
 
-        // We expect two tickets like in OpenSSL
-        #[cfg(not(feature = "wolfssl430"))]
-        ctx.set_num_tickets(2)?;
+        if options.tickets == Some(false) {
+            ctx.disable_session_tickets()?;
+        } else {
+            // We expect two tickets like in OpenSSL
+            #[cfg(not(feature = "wolfssl430"))]
+            ctx.set_num_tickets(2)?;
+        }
         Ok(ctx)
     }
 
@@ -386,9 +658,14 @@ impl WolfSSL {
     }
 
     fn deferred_transcript_extraction(&self, agent_name: &AgentName) {
-        let config = &self.config;
-        if let Some(type_shape) = self.config.extract_deferred.deref().borrow_mut().take() {
-            if let Some(transcript) = extract_current_transcript(self.stream.ssl()) {
+        Self::extract_deferred_transcript(self.stream.ssl(), &self.config, agent_name)
+    }
+
+    /// Extracts the transcript which has been requested by the message callback. Shared between
+    /// the TLS and DTLS PUTs.
+    fn extract_deferred_transcript(ssl: &SslRef, config: &TlsPutConfig, agent_name: &AgentName) {
+        if let Some(type_shape) = config.extract_deferred.deref().borrow_mut().take() {
+            if let Some(transcript) = extract_current_transcript(ssl) {
                 let CERT_SHAPE: TypeShape = TypeShape::of::<TranscriptCertificate>();
                 let FINISHED_SHAPE: TypeShape = TypeShape::of::<TranscriptServerFinished>();
                 let CLIENT_SHAPE: TypeShape = TypeShape::of::<TranscriptClientFinished>();
@@ -459,19 +736,19 @@ impl WolfSSL {
                             protocol_version,
                             data: ClaimData::Message(ClaimDataMessage::Finished(Finished {
                                 outbound,
-                                client_random: Default::default(), // TODO
-                                server_random: Default::default(), // TODO
-                                session_id: Default::default(),    // TODO
+                                client_random: None, // TODO
+                                server_random: None, // TODO
+                                session_id: None,    // TODO
                                 authenticate_peer,
                                 peer_certificate: context
                                     .get_peer_certificate()
                                     .map(|cert| SmallVec::from_vec(cert))
                                     .unwrap_or_else(|| SmallVec::new()),
-                                master_secret: Default::default(), // TODO
-                                chosen_cipher: 0,                  // TODO
-                                available_ciphers: Default::default(), // TODO
-                                signature_algorithm: 0,            // TODO
-                                peer_signature_algorithm: 0,       // TODO
+                                master_secret: None,            // TODO
+                                chosen_cipher: 0,               // TODO
+                                available_ciphers: None,        // TODO
+                                signature_algorithm: None,      // TODO
+                                peer_signature_algorithm: None, // TODO
                             })),
                         });
 